@@ -0,0 +1,98 @@
+use crate::ipc::{scan, PipeUser, FLAVORS};
+use eframe::egui::{self, Ui};
+use std::path::PathBuf;
+
+/// Picks which running Discord client, and so which account, receives the presence.
+#[derive(Default)]
+pub struct AccountPicker {
+    /// Pipe of the chosen client, empty to use the first one found.
+    pub pipe: String,
+    /// Kind of client to use when no pipe is chosen, like Canary, empty for any.
+    pub flavor: String,
+    pub found: Vec<PipeUser>,
+}
+
+impl AccountPicker {
+    pub fn run(&mut self, ui: &mut Ui, client_id: &str, connected: bool) {
+        let selected = match (self.pipe.is_empty(), self.flavor.is_empty()) {
+            (true, true) => "Any account".to_string(),
+            (true, false) => format!("Any {} client", self.flavor),
+            (false, _) => match self.found.iter().find(|found| self.is_selected(found)) {
+                Some(found) => label(found),
+                None => pipe_name(&self.pipe),
+            },
+        };
+        egui::ComboBox::from_id_source("account")
+            .selected_text(selected)
+            .show_ui(ui, |ui| {
+                if ui
+                    .selectable_label(
+                        self.pipe.is_empty() && self.flavor.is_empty(),
+                        "Any account",
+                    )
+                    .clicked()
+                {
+                    self.pipe = String::new();
+                    self.flavor = String::new();
+                }
+                for flavor in FLAVORS {
+                    let picked = self.pipe.is_empty() && self.flavor == flavor;
+                    if ui
+                        .selectable_label(picked, format!("Any {flavor} client"))
+                        .clicked()
+                    {
+                        self.pipe = String::new();
+                        self.flavor = flavor.to_string();
+                    }
+                }
+                ui.separator();
+                for found in self.found.iter() {
+                    if ui
+                        .selectable_label(self.is_selected(found), label(found))
+                        .clicked()
+                    {
+                        self.pipe = found.pipe.display().to_string();
+                        self.flavor = String::new();
+                    }
+                }
+            });
+        if ui
+            .add_enabled(!connected, egui::Button::new("🔄"))
+            .on_hover_text("Find running Discord clients and their accounts")
+            .clicked()
+        {
+            self.found = scan(client_id);
+        }
+    }
+
+    pub fn selected(&self) -> Option<PathBuf> {
+        match self.pipe.is_empty() {
+            true => None,
+            false => Some(PathBuf::from(&self.pipe)),
+        }
+    }
+
+    pub fn flavor(&self) -> Option<String> {
+        match self.flavor.is_empty() {
+            true => None,
+            false => Some(self.flavor.clone()),
+        }
+    }
+
+    fn is_selected(&self, found: &PipeUser) -> bool {
+        found.pipe.display().to_string() == self.pipe
+    }
+}
+
+fn pipe_name(pipe: &str) -> String {
+    pipe.rsplit(['/', '\\']).next().unwrap_or(pipe).to_string()
+}
+
+fn label(found: &PipeUser) -> String {
+    let pipe = pipe_name(&found.pipe.display().to_string());
+    match (found.user.is_empty(), found.flavor.is_empty()) {
+        (true, _) => format!("{pipe} (didn't answer)"),
+        (false, true) => format!("{} ({pipe})", found.user),
+        (false, false) => format!("{} · {} ({pipe})", found.user, found.flavor),
+    }
+}
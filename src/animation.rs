@@ -0,0 +1,109 @@
+use derivative::Derivative;
+use eframe::egui::{DragValue, RichText, TextEdit, Ui};
+use eframe::epaint::Color32;
+use serde::{Deserialize, Serialize};
+
+/// Shortest time a frame stays up. Discord accepts more, but each frame
+/// should be readable and leave room for other updates.
+pub const MIN_INTERVAL: u8 = 15;
+/// Characters the text moves by per frame when scrolling.
+const SCROLL_STEP: usize = 4;
+
+#[derive(Serialize, Deserialize, PartialEq, Eq, Clone, Copy, Default)]
+pub enum Style {
+    /// One line of the text per frame.
+    #[default]
+    Frames,
+    /// The text appears word by word.
+    Typewriter,
+    /// The text scrolls like a ticker.
+    Scroll,
+}
+
+/// Animates the details text, one frame per interval. The rotation decides
+/// when a frame is due, so the frames go on over the presets it switches to.
+#[derive(Derivative)]
+#[derivative(Default)]
+pub struct Animation {
+    pub running: bool,
+    pub style: Style,
+    pub text: String,
+    #[derivative(Default(value = "MIN_INTERVAL"))]
+    pub interval: u8,
+    pub index: usize,
+}
+
+impl Animation {
+    pub fn run(&mut self, ui: &mut Ui) {
+        ui.label(RichText::new("Animate details").color(Color32::from_rgb(163, 163, 163)));
+        ui.horizontal(|ui| {
+            ui.radio_value(&mut self.style, Style::Frames, "Frames");
+            ui.radio_value(&mut self.style, Style::Typewriter, "Typewriter");
+            ui.radio_value(&mut self.style, Style::Scroll, "Scroll");
+        });
+        let hint = match self.style {
+            Style::Frames => "One frame per line",
+            _ => "Text to animate",
+        };
+        ui.add(
+            TextEdit::multiline(&mut self.text)
+                .hint_text(hint)
+                .desired_rows(3),
+        );
+        ui.horizontal(|ui| {
+            ui.label("Next frame every");
+            ui.add(
+                DragValue::new(&mut self.interval)
+                    .clamp_range(MIN_INTERVAL..=255)
+                    .suffix("s"),
+            );
+        });
+        if ui.checkbox(&mut self.running, "Run animation").changed() {
+            self.index = 0;
+        }
+    }
+
+    /// The details text for the next frame.
+    pub fn next(&mut self) -> Option<String> {
+        if !self.running {
+            return None;
+        }
+        let frames = self.frames();
+        if frames.is_empty() {
+            return None;
+        }
+        let frame = frames[self.index % frames.len()].clone();
+        self.index = (self.index + 1) % frames.len();
+        Some(frame)
+    }
+
+    fn frames(&self) -> Vec<String> {
+        match self.style {
+            Style::Frames => self
+                .text
+                .lines()
+                .map(str::trim)
+                .filter(|line| !line.is_empty())
+                .map(str::to_string)
+                .collect(),
+            Style::Typewriter => {
+                let words: Vec<&str> = self.text.split_whitespace().collect();
+                (1..=words.len())
+                    .map(|len| words[..len].join(" "))
+                    .collect()
+            }
+            Style::Scroll => {
+                let text = self.text.split_whitespace().collect::<Vec<_>>().join(" ");
+                if text.is_empty() {
+                    return Vec::new();
+                }
+                // the gap keeps the end and the start of the text apart
+                let chars: Vec<char> = format!("{text} • ").chars().collect();
+                (0..chars.len())
+                    .step_by(SCROLL_STEP)
+                    .map(|start| chars[start..].iter().chain(&chars[..start]).collect())
+                    .collect()
+            }
+        }
+    }
+}
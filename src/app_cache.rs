@@ -0,0 +1,218 @@
+use crate::storage::data_dir;
+use chrono::Utc;
+use serde::{Deserialize, Serialize};
+use serde_json::Value;
+use std::collections::HashMap;
+use std::fs;
+use std::path::PathBuf;
+use std::sync::mpsc::{channel, Receiver};
+use std::thread;
+use std::time::Duration;
+
+/// How many application IDs are remembered.
+const MAX_ENTRIES: usize = 10;
+/// Seconds before a cached entry is fetched again. Older entries are still
+/// used while the fetch runs or when Discord can't be reached.
+const TTL: i64 = 24 * 60 * 60;
+/// Seconds to wait after a failed fetch, so being offline doesn't mean a request every frame.
+const RETRY_AFTER: i64 = 60;
+/// What fetching an ID Discord doesn't know fails with.
+const NOT_FOUND: &str = "Discord has no application with this ID";
+
+/// Name and asset keys of an application, as Discord reported them.
+#[derive(Serialize, Deserialize, Clone)]
+pub struct CachedApp {
+    pub id: String,
+    pub name: String,
+    pub assets: Vec<String>,
+    /// Asset IDs by key, for the images' URLs.
+    #[serde(default)]
+    pub asset_ids: HashMap<String, String>,
+    pub fetched: i64,
+}
+
+/// The last used application IDs with their names and asset lists, kept on
+/// disk so switching back to an app is instant and works offline.
+#[derive(Default)]
+pub struct AppCache {
+    entries: Vec<CachedApp>,
+    pending: Option<(String, Receiver<Result<CachedApp, String>>)>,
+    retry_at: i64,
+    /// The last ID Discord answered it doesn't know.
+    unknown: Option<String>,
+}
+
+impl AppCache {
+    pub fn load() -> Self {
+        let entries = cache_path()
+            .and_then(|path| fs::read_to_string(path).ok())
+            .and_then(|file| serde_json::from_str(&file).ok())
+            .unwrap_or_default();
+        Self {
+            entries,
+            ..Default::default()
+        }
+    }
+
+    pub fn get(&self, id: &str) -> Option<&CachedApp> {
+        self.entries.iter().find(|entry| entry.id == id)
+    }
+
+    /// Whether the ID is being looked up.
+    pub fn looking_up(&self, id: &str) -> bool {
+        matches!(&self.pending, Some((pending, _)) if pending == id)
+    }
+
+    /// Whether Discord answered that there's no application with the ID.
+    pub fn unknown(&self, id: &str) -> bool {
+        self.unknown.as_deref() == Some(id)
+    }
+
+    /// Most recently used first.
+    pub fn recent(&self) -> &[CachedApp] {
+        &self.entries
+    }
+
+    /// Starts fetching `id` in the background when it's missing or stale.
+    pub fn refresh(&mut self, id: &str) {
+        if check_id(id).is_err() || self.unknown(id) {
+            return;
+        }
+        if let Some((pending, _)) = &self.pending {
+            if pending == id {
+                return;
+            }
+        }
+        self.used(id);
+        if self.retry_at > Utc::now().timestamp() {
+            return;
+        }
+        if let Some(entry) = self.get(id) {
+            // entries cached before the IDs were kept are fetched again
+            let ids_missing = entry.asset_ids.is_empty() && !entry.assets.is_empty();
+            if entry.fetched + TTL > Utc::now().timestamp() && !ids_missing {
+                return;
+            }
+        }
+        let (sender, receiver) = channel();
+        let fetch_id = id.to_string();
+        thread::spawn(move || {
+            let _ = sender.send(fetch(&fetch_id));
+        });
+        self.pending = Some((id.to_string(), receiver));
+    }
+
+    /// Moves a cached ID to the front, the list follows use and not when
+    /// the entries were last fetched.
+    fn used(&mut self, id: &str) {
+        match self.entries.iter().position(|entry| entry.id == id) {
+            Some(0) | None => {}
+            Some(i) => {
+                let entry = self.entries.remove(i);
+                self.entries.insert(0, entry);
+                self.save();
+            }
+        }
+    }
+
+    /// Stores a finished fetch. Returns false while one is still running.
+    pub fn poll(&mut self) -> bool {
+        let (id, result) = match &self.pending {
+            Some((id, receiver)) => match receiver.try_recv() {
+                Ok(result) => (id.clone(), result),
+                Err(_) => return false,
+            },
+            None => return true,
+        };
+        self.pending = None;
+        // failed fetches keep the stale entry, that's the point of the cache
+        match result {
+            Err(reason) if reason == NOT_FOUND => self.unknown = Some(id),
+            Ok(app) => self.insert(app),
+            Err(_) => self.retry_at = Utc::now().timestamp() + RETRY_AFTER,
+        }
+        true
+    }
+
+    /// Stores an application fetched elsewhere, as the most recent one.
+    pub fn insert(&mut self, app: CachedApp) {
+        if self.unknown.as_ref() == Some(&app.id) {
+            self.unknown = None;
+        }
+        self.entries.retain(|entry| entry.id != app.id);
+        self.entries.insert(0, app);
+        self.entries.truncate(MAX_ENTRIES);
+        self.save();
+    }
+
+    fn save(&self) {
+        if let (Some(path), Ok(file)) = (cache_path(), serde_json::to_string(&self.entries)) {
+            if let Some(dir) = path.parent() {
+                let _ = fs::create_dir_all(dir);
+            }
+            let _ = fs::write(path, file);
+        }
+    }
+}
+
+/// Checks that the ID looks like an application ID, a Discord snowflake.
+pub fn check_id(id: &str) -> Result<(), String> {
+    match id.chars().all(|c| c.is_ascii_digit()) {
+        false => Err("ID must only contain digits".to_string()),
+        true if !(17..=20).contains(&id.len()) => {
+            Err("ID must be 17 to 20 digits long, copy it from the Developer Portal".to_string())
+        }
+        true => Ok(()),
+    }
+}
+
+fn cache_path() -> Option<PathBuf> {
+    data_dir().map(|dir| dir.join("app_cache.json"))
+}
+
+pub fn fetch(id: &str) -> Result<CachedApp, String> {
+    let app = get_json(&format!(
+        "https://discord.com/api/v10/applications/{id}/rpc"
+    ))?;
+    let assets = get_json(&format!(
+        "https://discord.com/api/v10/oauth2/applications/{id}/assets"
+    ))?;
+    let asset_ids: HashMap<String, String> = match assets.as_array() {
+        Some(assets) => assets
+            .iter()
+            .filter_map(
+                |asset| match (asset["name"].as_str(), asset["id"].as_str()) {
+                    (Some(name), Some(id)) => Some((name.to_string(), id.to_string())),
+                    _ => None,
+                },
+            )
+            .collect(),
+        None => HashMap::new(),
+    };
+    let assets = match assets.as_array() {
+        Some(assets) => assets
+            .iter()
+            .filter_map(|asset| asset["name"].as_str().map(str::to_string))
+            .collect(),
+        None => Vec::new(),
+    };
+    Ok(CachedApp {
+        id: id.to_string(),
+        name: app["name"].as_str().unwrap_or_default().to_string(),
+        assets,
+        asset_ids,
+        fetched: Utc::now().timestamp(),
+    })
+}
+
+fn get_json(url: &str) -> Result<Value, String> {
+    let response = match ureq::get(url).timeout(Duration::from_secs(10)).call() {
+        Ok(response) => response,
+        Err(ureq::Error::Status(404, _)) => return Err(NOT_FOUND.to_string()),
+        Err(_) => return Err(format!("Failed to fetch {url}")),
+    };
+    match response.into_string() {
+        Ok(body) => serde_json::from_str(&body).map_err(|_| format!("Invalid answer from {url}")),
+        Err(_) => Err(format!("Failed to fetch {url}")),
+    }
+}
@@ -0,0 +1,229 @@
+use crate::app_cache::{self, CachedApp};
+use crate::preset::InAppPreset;
+use eframe::egui::{self, Context, RichText};
+use eframe::epaint::Color32;
+use serde_json::{from_str, to_string};
+use std::collections::HashMap;
+use std::sync::mpsc::{channel, Receiver};
+use std::thread;
+
+/// Most edits between a key and an asset name for it to be suggested.
+const MAX_DISTANCE: usize = 3;
+
+/// An image key of a preset that its application has no asset for.
+pub struct Broken {
+    pub preset: String,
+    /// `true` for the large image, `false` for the small one.
+    pub large: bool,
+    pub key: String,
+    pub problem: String,
+    /// Asset name the key was probably meant to be.
+    pub suggestion: Option<String>,
+    /// Where the missing image can be uploaded.
+    pub upload: Option<String>,
+}
+
+/// Checks the image keys of every in-app preset against the assets of the
+/// preset's application and lists the ones that won't show.
+#[derive(Default)]
+pub struct AssetCheck {
+    pub open: bool,
+    running: Option<Receiver<Vec<Broken>>>,
+    report: Option<Vec<Broken>>,
+}
+
+impl AssetCheck {
+    pub fn start(&mut self, presets: &str) {
+        let presets: Vec<InAppPreset> = from_str(presets).unwrap_or_default();
+        let (sender, receiver) = channel();
+        thread::spawn(move || {
+            let _ = sender.send(check(&presets));
+        });
+        self.running = Some(receiver);
+        self.report = None;
+        self.open = true;
+    }
+
+    /// Shows the report, applies the suggestions that were accepted to the presets.
+    pub fn run(&mut self, ctx: &Context, presets: &mut String) {
+        if let Some(Ok(report)) = self.running.as_ref().map(Receiver::try_recv) {
+            self.report = Some(report);
+            self.running = None;
+        }
+        let mut open = self.open;
+        let mut fix = None;
+        egui::containers::Window::new("Image keys")
+            .open(&mut open)
+            .resizable(false)
+            .show(ctx, |ui| {
+                let report = match &self.report {
+                    Some(report) => report,
+                    None => {
+                        ui.horizontal(|ui| {
+                            ui.spinner();
+                            ui.label("Fetching the applications' assets…");
+                        });
+                        ctx.request_repaint();
+                        return;
+                    }
+                };
+                if report.is_empty() {
+                    ui.label("Every image key of every preset is an asset of its application");
+                }
+                egui::ScrollArea::vertical()
+                    .max_height(300.)
+                    .show(ui, |ui| {
+                        for (i, broken) in report.iter().enumerate() {
+                            let image = match broken.large {
+                                true => "large image",
+                                false => "small image",
+                            };
+                            ui.label(format!("{}, {image} {}", broken.preset, broken.key));
+                            ui.label(
+                                RichText::new(&broken.problem)
+                                    .small()
+                                    .color(Color32::LIGHT_RED),
+                            );
+                            ui.horizontal(|ui| {
+                                if let Some(suggestion) = &broken.suggestion {
+                                    if ui.button(format!("Use {suggestion}")).clicked() {
+                                        fix = Some(i);
+                                    }
+                                }
+                                if let Some(upload) = &broken.upload {
+                                    if ui.button("Upload it").clicked() {
+                                        ui.output().open_url(upload);
+                                    }
+                                }
+                            });
+                            ui.add_space(5.);
+                        }
+                    });
+                if ui.button("Check again").clicked() {
+                    self.start(presets);
+                }
+            });
+        self.open = open;
+        let broken = match (fix, &mut self.report) {
+            (Some(i), Some(report)) => report.remove(i),
+            _ => return,
+        };
+        let mut list: Vec<InAppPreset> = from_str(presets).unwrap_or_default();
+        if let Some(preset) = list.iter_mut().find(|preset| preset.name == broken.preset) {
+            let key = match broken.large {
+                true => &mut preset.LargeKey,
+                false => &mut preset.SmallKey,
+            };
+            *key = broken.suggestion;
+            *presets = to_string(&list).unwrap();
+        }
+    }
+}
+
+fn check(presets: &[InAppPreset]) -> Vec<Broken> {
+    let mut apps: HashMap<String, Result<CachedApp, String>> = HashMap::new();
+    let mut report = Vec::new();
+    for preset in presets {
+        let id = preset.ID.as_deref().unwrap_or_default().trim();
+        for (large, key) in [(true, &preset.LargeKey), (false, &preset.SmallKey)] {
+            let key = key.as_deref().unwrap_or_default().trim();
+            // links and placeholders aren't assets
+            if key.is_empty() || key.contains("://") || key.starts_with("mp:") || key.contains('{')
+            {
+                continue;
+            }
+            let broken = |problem: String, suggestion, upload| Broken {
+                preset: preset.name.clone(),
+                large,
+                key: key.to_string(),
+                problem,
+                suggestion,
+                upload,
+            };
+            if id.is_empty() {
+                report.push(broken(
+                    "The preset has no application ID to check against".to_string(),
+                    None,
+                    None,
+                ));
+                continue;
+            }
+            if let Err(problem) = app_cache::check_id(id) {
+                report.push(broken(
+                    format!("The preset's ID is wrong: {problem}"),
+                    None,
+                    None,
+                ));
+                continue;
+            }
+            let app = apps
+                .entry(id.to_string())
+                .or_insert_with(|| app_cache::fetch(id));
+            let app = match app {
+                Ok(app) => app,
+                Err(error) => {
+                    report.push(broken(error.clone(), None, None));
+                    continue;
+                }
+            };
+            if app.assets.iter().any(|asset| asset == key) {
+                continue;
+            }
+            let suggestion = app
+                .assets
+                .iter()
+                .map(|asset| (distance(&key.to_lowercase(), &asset.to_lowercase()), asset))
+                .filter(|(distance, _)| *distance <= MAX_DISTANCE)
+                .min_by_key(|(distance, _)| *distance)
+                .map(|(_, asset)| asset.clone());
+            let upload =
+                format!("https://discord.com/developers/applications/{id}/rich-presence/assets");
+            report.push(broken(
+                format!("{} has no asset named {key}", app.name),
+                suggestion,
+                Some(upload),
+            ));
+        }
+    }
+    report
+}
+
+/// Edits needed to turn one text into the other.
+fn distance(a: &str, b: &str) -> usize {
+    let b: Vec<char> = b.chars().collect();
+    let mut row: Vec<usize> = (0..=b.len()).collect();
+    for (i, a) in a.chars().enumerate() {
+        let mut diagonal = row[0];
+        row[0] = i + 1;
+        for (j, b) in b.iter().enumerate() {
+            let above = row[j + 1];
+            row[j + 1] = match a == *b {
+                true => diagonal,
+                false => 1 + diagonal.min(above).min(row[j]),
+            };
+            diagonal = above;
+        }
+    }
+    row[b.len()]
+}
+
+#[cfg(test)]
+mod tests {
+    use super::distance;
+
+    #[test]
+    fn counts_edits() {
+        assert_eq!(distance("", ""), 0);
+        assert_eq!(distance("logo", "logo"), 0);
+        assert_eq!(distance("logo", ""), 4);
+        assert_eq!(distance("", "logo"), 4);
+        assert_eq!(distance("logo", "lgo"), 1);
+        assert_eq!(distance("logo", "logos"), 1);
+        assert_eq!(distance("kitten", "sitting"), 3);
+    }
+
+    #[test]
+    fn counts_characters_not_bytes() {
+        assert_eq!(distance("café", "cafe"), 1);
+    }
+}
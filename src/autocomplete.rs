@@ -0,0 +1,26 @@
+use eframe::egui::{self, Response, Ui};
+
+/// Lists the options that contain what was typed below the field, picking
+/// one puts it in the field.
+pub fn suggest(ui: &mut Ui, response: &Response, text: &mut String, options: &[String]) {
+    let typed = text.to_lowercase();
+    let matches: Vec<&String> = options
+        .iter()
+        .filter(|option| option.to_lowercase().contains(&typed) && *option != text)
+        .take(8)
+        .collect();
+    let popup = response.id.with("suggestions");
+    match (response.has_focus(), matches.is_empty()) {
+        (true, false) => ui.memory().open_popup(popup),
+        (_, true) if ui.memory().is_popup_open(popup) => ui.memory().close_popup(),
+        _ => (),
+    }
+    egui::popup_below_widget(ui, popup, response, |ui| {
+        ui.set_min_width(150.);
+        for option in matches {
+            if ui.selectable_label(false, option).clicked() {
+                *text = option.clone();
+            }
+        }
+    });
+}
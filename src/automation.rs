@@ -0,0 +1,278 @@
+use crate::preset::{InAppPreset, Preset};
+use crate::process_rules::ProcessRule;
+use chrono::{DateTime, Datelike, Local, NaiveTime, Utc, Weekday};
+use serde_json::from_str;
+use sysinfo::{ProcessExt, System, SystemExt};
+
+/// Seconds between two checks of the preset triggers.
+const CHECK_INTERVAL: i64 = 5;
+/// Seconds between two checks while the window is minimized.
+pub const LOW_POWER_INTERVAL: i64 = 30;
+
+/// What the automation wants shown after a check.
+pub enum Switch {
+    /// A rule or trigger started matching.
+    Load(Box<InAppPreset>),
+    /// Nothing matches anymore, the presence from before comes back.
+    Restore,
+}
+
+/// A preset with embedded triggers, parsed when the presets change.
+struct Triggered {
+    preset: InAppPreset,
+    processes: String,
+    schedule: Option<Schedule>,
+}
+
+/// Loads presets by process rules, or whose embedded triggers (running
+/// processes, schedule) match.
+pub struct Automation {
+    system: System,
+    next_check: i64,
+    active: Option<String>,
+    /// The presets JSON `presets` and `triggered` were parsed from.
+    parsed: String,
+    presets: Vec<InAppPreset>,
+    triggered: Vec<Triggered>,
+    /// Presence from before the first preset loaded by a trigger.
+    pub previous: Option<Preset>,
+    /// Check less often, set while the window is minimized.
+    pub low_power: bool,
+}
+
+impl Default for Automation {
+    fn default() -> Self {
+        Self {
+            system: System::new(),
+            next_check: 0,
+            active: None,
+            parsed: String::new(),
+            presets: Vec::new(),
+            triggered: Vec::new(),
+            previous: None,
+            low_power: false,
+        }
+    }
+}
+
+impl Automation {
+    /// Returns what to show when a rule or trigger just started or stopped
+    /// matching, `None` while nothing changed.
+    pub fn run(&mut self, presets: &str, rules: &[ProcessRule]) -> Option<Switch> {
+        if self.next_check > Utc::now().timestamp() {
+            return None;
+        }
+        self.next_check = Utc::now().timestamp()
+            + match self.low_power {
+                true => LOW_POWER_INTERVAL,
+                false => CHECK_INTERVAL,
+            };
+
+        if self.parsed != presets {
+            self.parse(presets);
+        }
+        if !rules.is_empty()
+            || self
+                .triggered
+                .iter()
+                .any(|triggered| !triggered.processes.is_empty())
+        {
+            self.system.refresh_processes();
+        }
+        let ruled = rules
+            .iter()
+            .filter(|rule| self.process_running(&rule.process))
+            .find_map(|rule| {
+                self.presets
+                    .iter()
+                    .find(|preset| preset.name == rule.preset)
+            });
+        let now = Local::now();
+        let matching = ruled.or_else(|| {
+            self.triggered
+                .iter()
+                .find(|triggered| {
+                    (triggered.processes.is_empty() || self.process_running(&triggered.processes))
+                        && match &triggered.schedule {
+                            Some(schedule) => schedule.contains(now),
+                            None => true,
+                        }
+                })
+                .map(|triggered| &triggered.preset)
+        });
+
+        let name = matching.map(|preset| preset.name.clone());
+        if name == self.active {
+            return None;
+        }
+        let switch = match matching {
+            Some(preset) => Switch::Load(Box::new(preset.clone())),
+            None => Switch::Restore,
+        };
+        self.active = name;
+        Some(switch)
+    }
+
+    /// Parses the presets and their triggers, skipping triggers with a
+    /// schedule that doesn't parse.
+    fn parse(&mut self, presets: &str) {
+        self.parsed = presets.to_string();
+        self.presets = from_str(presets).unwrap_or_default();
+        self.triggered = self
+            .presets
+            .iter()
+            .filter_map(|preset| {
+                let processes = preset.TriggerProcesses.clone().unwrap_or_default();
+                let schedule = match preset.TriggerSchedule.as_deref().unwrap_or_default() {
+                    "" if processes.is_empty() => return None,
+                    "" => None,
+                    schedule => Some(Schedule::parse(schedule).ok()?),
+                };
+                Some(Triggered {
+                    preset: preset.clone(),
+                    processes,
+                    schedule,
+                })
+            })
+            .collect();
+    }
+
+    fn process_running(&self, processes: &str) -> bool {
+        let wanted: Vec<String> = processes
+            .split(',')
+            .map(|name| name.trim().to_lowercase())
+            .filter(|name| !name.is_empty())
+            .collect();
+        self.system.processes().values().any(|process| {
+            let name = process.name().to_lowercase();
+            let stem = name.trim_end_matches(".exe");
+            wanted
+                .iter()
+                .any(|wanted| *wanted == name || wanted.trim_end_matches(".exe") == stem)
+        })
+    }
+}
+
+/// A schedule like `09:00-17:00` or `Mon-Fri 09:00-17:00` or `Sat,Sun 18:00-23:30`.
+pub struct Schedule {
+    days: Vec<Weekday>,
+    start: NaiveTime,
+    end: NaiveTime,
+}
+
+impl Schedule {
+    /// A range on the given days, every day when empty.
+    pub fn new(days: Vec<Weekday>, start: NaiveTime, end: NaiveTime) -> Self {
+        Self { days, start, end }
+    }
+
+    pub fn parse(schedule: &str) -> Result<Self, String> {
+        let schedule = schedule.trim();
+        let (days, times) = match schedule.rsplit_once(' ') {
+            Some((days, times)) => (parse_days(days.trim())?, times),
+            None => (Vec::new(), schedule),
+        };
+        let (start, end) = match times.split_once('-') {
+            Some(times) => times,
+            None => return Err(format!("\"{times}\" is not a time range like 09:00-17:00")),
+        };
+        let parse_time = |time: &str| {
+            NaiveTime::parse_from_str(time.trim(), "%H:%M")
+                .map_err(|_| format!("\"{}\" is not a time like 09:00", time.trim()))
+        };
+        Ok(Self {
+            days,
+            start: parse_time(start)?,
+            end: parse_time(end)?,
+        })
+    }
+
+    pub fn contains(&self, now: DateTime<Local>) -> bool {
+        let time = now.time();
+        // ranges like 22:00-02:00 wrap around midnight and belong to the day they start on
+        let (in_range, day) = match self.start <= self.end {
+            true => (time >= self.start && time < self.end, now.weekday()),
+            false if time >= self.start => (true, now.weekday()),
+            false => (time < self.end, now.weekday().pred()),
+        };
+        in_range && (self.days.is_empty() || self.days.contains(&day))
+    }
+}
+
+fn parse_days(days: &str) -> Result<Vec<Weekday>, String> {
+    let parse_day = |day: &str| {
+        day.trim()
+            .parse::<Weekday>()
+            .map_err(|_| format!("\"{}\" is not a weekday", day.trim()))
+    };
+    let mut parsed = Vec::new();
+    for part in days.split(',') {
+        match part.split_once('-') {
+            Some((from, to)) => {
+                let (mut day, to) = (parse_day(from)?, parse_day(to)?);
+                parsed.push(day);
+                while day != to {
+                    day = day.succ();
+                    parsed.push(day);
+                }
+            }
+            None => parsed.push(parse_day(part)?),
+        }
+    }
+    Ok(parsed)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use chrono::{NaiveDate, TimeZone};
+
+    /// A time in the first week of 2024, which starts on a Monday.
+    fn at(day: u32, time: &str) -> DateTime<Local> {
+        let time = NaiveTime::parse_from_str(time, "%H:%M").unwrap();
+        let naive = NaiveDate::from_ymd_opt(2024, 1, day)
+            .unwrap()
+            .and_time(time);
+        Local.from_local_datetime(&naive).unwrap()
+    }
+
+    #[test]
+    fn parses_schedules() {
+        assert!(Schedule::parse("09:00-17:00").is_ok());
+        assert!(Schedule::parse(" Mon-Fri 09:00-17:00 ").is_ok());
+        assert!(Schedule::parse("Sat,Sun 18:00-23:30").is_ok());
+        assert!(Schedule::parse("09:00").is_err());
+        assert!(Schedule::parse("9am-5pm").is_err());
+        assert!(Schedule::parse("Someday 09:00-17:00").is_err());
+    }
+
+    #[test]
+    fn matches_times_and_days() {
+        let work = Schedule::parse("Mon-Fri 09:00-17:00").unwrap();
+        assert!(work.contains(at(1, "09:00")));
+        assert!(work.contains(at(5, "16:59")));
+        assert!(!work.contains(at(1, "17:00")));
+        assert!(!work.contains(at(6, "12:00")));
+        let weekend = Schedule::parse("Sat,Sun 18:00-23:30").unwrap();
+        assert!(weekend.contains(at(7, "20:00")));
+        assert!(!weekend.contains(at(5, "20:00")));
+    }
+
+    #[test]
+    fn ranges_past_midnight_belong_to_their_first_day() {
+        let friday_night = Schedule::parse("Fri 22:00-02:00").unwrap();
+        assert!(friday_night.contains(at(5, "23:00")));
+        assert!(friday_night.contains(at(6, "01:00")));
+        assert!(!friday_night.contains(at(6, "23:00")));
+        assert!(!friday_night.contains(at(5, "01:00")));
+    }
+
+    #[test]
+    fn wrapping_day_ranges() {
+        let schedule = Schedule::parse("Sat-Mon 10:00-11:00").unwrap();
+        for day in [6, 7, 1] {
+            assert!(schedule.contains(at(day, "10:30")), "{day}");
+        }
+        assert!(!schedule.contains(at(2, "10:30")));
+    }
+}
@@ -0,0 +1,69 @@
+use crate::redact::redact;
+use eframe::egui::{self, Context, Layout, TextEdit};
+use eframe::emath::Align;
+use std::env;
+
+const NEW_ISSUE_URL: &str = "https://github.com/Tofix26/discord_presence/issues/new";
+
+/// "Report a problem" dialog, composes an issue body with what's needed to
+/// reproduce the problem and opens GitHub with it on the clipboard.
+#[derive(Default)]
+pub struct BugReport {
+    pub open: bool,
+    pub description: String,
+    /// Recent errors, filled in by the app while the dialog is open.
+    pub errors: Vec<String>,
+    /// Activity Discord last rejected, as JSON.
+    pub payload: Option<String>,
+}
+
+impl BugReport {
+    pub fn run(&mut self, ctx: &Context) {
+        let mut open = self.open;
+        egui::containers::Window::new("Report a problem")
+            .open(&mut open)
+            .resizable(false)
+            .show(ctx, |ui| {
+                ui.with_layout(Layout::top_down(Align::Center), |ui| {
+                    ui.label("What happened?");
+                    ui.add(TextEdit::multiline(&mut self.description).desired_rows(4));
+                    ui.add_space(5.);
+                    ui.collapsing("Report", |ui| {
+                        ui.add(
+                            TextEdit::multiline(&mut self.compose())
+                                .code_editor()
+                                .interactive(false),
+                        );
+                    });
+                    ui.add_space(5.);
+                    if ui
+                        .button("Open GitHub")
+                        .on_hover_text("Copies the report, paste it as the issue body")
+                        .clicked()
+                    {
+                        ui.output().copied_text = self.compose();
+                        ui.output().open_url(NEW_ISSUE_URL);
+                    }
+                });
+            });
+        self.open = open;
+    }
+
+    /// Builds the issue body, redacted like every other diagnostic.
+    pub fn compose(&self) -> String {
+        let mut report = format!(
+            "### Description\n\n{}\n\n### Environment\n\n- Version: {}\n- OS: {} ({})\n",
+            self.description,
+            env!("CARGO_PKG_VERSION"),
+            env::consts::OS,
+            env::consts::ARCH,
+        );
+        if !self.errors.is_empty() {
+            report += &format!("\n### Errors\n\n```\n{}\n```\n", self.errors.join("\n"));
+        }
+        if let Some(payload) = &self.payload {
+            report += &format!("\n### Failing payload\n\n```json\n{payload}\n```\n");
+        }
+        redact(&report)
+    }
+}
@@ -0,0 +1,143 @@
+use crate::config::Config;
+use crate::control::{COMMANDS, MIN_TOKEN};
+use crate::preset::{InAppPreset, Preset};
+use crate::preset_manager;
+use crate::rules::Rules;
+use crate::storage::{saved, Storage};
+use serde_json::from_str;
+use std::net::ToSocketAddrs;
+use std::path::Path;
+
+/// Checks the config file, the presets, the validation rules and everything
+/// that refers to presets
+/// without starting the app. Prints every problem and returns the exit code,
+/// 1 when anything is wrong.
+pub fn run() -> i32 {
+    let saved = saved();
+    let (presets, mut problems) = match preset_manager::load() {
+        Some((presets, errors)) => (presets, errors),
+        None => (
+            saved.get("presets").cloned().unwrap_or_default(),
+            Vec::new(),
+        ),
+    };
+    let presets: Vec<InAppPreset> = from_str(&presets).unwrap_or_default();
+    let names: Vec<&str> = presets.iter().map(|preset| preset.name.as_str()).collect();
+
+    for preset in presets.iter() {
+        if let Err(problem) = Preset::from_in_app(preset.clone()).validate() {
+            problems.push(format!("Preset {}: {problem}", preset.name));
+        }
+        if let Some(next) = preset.NextPreset.as_deref() {
+            if !names.contains(&next) {
+                problems.push(format!(
+                    "Preset {}: NextPreset {next} doesn't exist",
+                    preset.name
+                ));
+            }
+        }
+    }
+
+    let storage: Option<Storage> = saved
+        .get("settings")
+        .and_then(|settings| from_str(settings).ok());
+    if let Some(storage) = storage {
+        for (i, step) in storage.rundown.iter().enumerate() {
+            if !names.contains(&step.preset.as_str()) {
+                problems.push(format!(
+                    "Rundown step {}: preset {} doesn't exist",
+                    i + 1,
+                    step.preset
+                ));
+            }
+        }
+        for (i, rule) in storage.process_rules.iter().enumerate() {
+            if rule.process.trim().is_empty() {
+                problems.push(format!("Process rule {}: no process", i + 1));
+            }
+            if !names.contains(&rule.preset.as_str()) {
+                problems.push(format!(
+                    "Process rule {}: preset {} doesn't exist",
+                    i + 1,
+                    rule.preset
+                ));
+            }
+        }
+        for (i, slot) in storage.schedule.iter().enumerate() {
+            if let Err(problem) = slot.schedule() {
+                problems.push(format!("Schedule range {}: {problem}", i + 1));
+            }
+            if !names.contains(&slot.preset.as_str()) {
+                problems.push(format!(
+                    "Schedule range {}: preset {} doesn't exist",
+                    i + 1,
+                    slot.preset
+                ));
+            }
+        }
+        let rules = Rules::new(storage.banned_words, storage.patterns);
+        for (i, problem) in rules.invalid() {
+            problems.push(format!("Validation pattern {i}: {problem}"));
+        }
+    }
+
+    match Config::load() {
+        Ok(config) => check(&config, &names, &mut problems),
+        Err(error) => problems.push(error),
+    }
+
+    for problem in problems.iter() {
+        eprintln!("{problem}");
+    }
+    match problems.is_empty() {
+        true => {
+            println!("No problems found");
+            0
+        }
+        false => 1,
+    }
+}
+
+fn check(config: &Config, names: &[&str], problems: &mut Vec<String>) {
+    if let Some(preset) = config.startup.preset.as_deref() {
+        if !names.contains(&preset) && !Path::new(preset).is_file() {
+            problems.push(format!(
+                "startup.preset {preset} is neither an in-app preset nor a file"
+            ));
+        }
+    }
+    for (key, listen) in [
+        ("metrics.listen", &config.metrics.listen),
+        ("control.listen", &config.control.listen),
+    ] {
+        if let Some(listen) = listen {
+            if listen.to_socket_addrs().is_err() {
+                problems.push(format!(
+                    "{key} {listen} isn't an address like 127.0.0.1:9188"
+                ));
+            }
+        }
+    }
+    if config.control.listen.is_some() && config.control.tokens.is_empty() {
+        problems.push("control.listen needs at least one control.tokens entry".to_string());
+    }
+    if config.control.advertise && config.control.listen.is_none() {
+        problems.push("control.advertise needs control.listen".to_string());
+    }
+    for (i, token) in config.control.tokens.iter().enumerate() {
+        if token.token.len() < MIN_TOKEN {
+            problems.push(format!(
+                "control.tokens {}: token is shorter than {MIN_TOKEN} characters",
+                i + 1
+            ));
+        }
+        for command in token.commands.iter().flatten() {
+            if !COMMANDS.contains(&command.as_str()) {
+                problems.push(format!(
+                    "control.tokens {}: unknown command {command}",
+                    i + 1
+                ));
+            }
+        }
+    }
+}
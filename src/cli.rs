@@ -0,0 +1,351 @@
+use crate::check_config;
+use crate::control::{ask, same, Command, Request};
+use crate::remote::random_hex;
+use crate::storage::data_dir;
+use eframe::egui::Context;
+use serde_json::{from_str, json, Value};
+use std::fs;
+use std::io::{self, BufRead, BufReader, Read, Write};
+use std::path::{Path, PathBuf};
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::mpsc::{channel, Receiver, Sender};
+use std::sync::Arc;
+use std::thread;
+use std::time::Duration;
+
+#[cfg(windows)]
+use std::net::{Ipv4Addr, TcpListener as Listener, TcpStream as Stream};
+#[cfg(unix)]
+use std::os::unix::net::{UnixListener as Listener, UnixStream as Stream};
+
+/// Where the running app tells the command line how to reach it.
+const INFO_FILE: &str = "cli.json";
+#[cfg(unix)]
+const SOCKET: &str = "cli.sock";
+/// Longest request line read.
+const MAX_LINE: u64 = 64 * 1024;
+
+const USAGE: &str = "Usage: discord_presence [command]
+
+Commands for the running app:
+  status                                   Print the current status
+  presets                                  List the in-app presets
+  connect                                  Connect to Discord
+  disconnect                               Disconnect from Discord
+  update                                   Send the presence as it is in the app
+  load-preset <name>                       Apply an in-app preset
+  set [--details <text>] [--state <text>]  Change the texts and update
+  goal [+n | -n | n]                       Change the goal by a step, by n or to n
+  focus start [minutes]                    Show the Focus preset, 25 minutes by default
+  focus end                                Go back to the presence from before
+
+Without the app running:
+  check-config                             Check the config file, presets and rundown
+
+Without a command the app starts, with --headless without a window. With
+--safe-mode it starts without connecting, automation, the webhook or remote
+control, to fix settings that keep it from starting normally. --renderer
+glow, wgpu or software picks how the window is drawn.";
+
+/// Runs a subcommand against the running app and returns the exit code,
+/// `None` when the arguments aren't a subcommand and the app should start.
+pub fn run(args: &[String]) -> Option<i32> {
+    if args.get(1).map(String::as_str) == Some("check-config") {
+        return Some(check_config::run());
+    }
+    let message = match parse(args)? {
+        Ok(message) => message,
+        Err(error) => {
+            eprintln!("{error}\n\n{USAGE}");
+            return Some(2);
+        }
+    };
+    if message.is_null() {
+        println!("{USAGE}");
+        return Some(0);
+    }
+    match send(message) {
+        Ok(reply) => match reply["error"].as_str() {
+            Some(error) => {
+                eprintln!("{error}");
+                Some(1)
+            }
+            None => {
+                println!("{}", serde_json::to_string_pretty(&reply).unwrap());
+                Some(0)
+            }
+        },
+        Err(error) => {
+            eprintln!("{error}");
+            Some(1)
+        }
+    }
+}
+
+/// Turns the arguments into a message for the app, `Null` to show the usage.
+fn parse(args: &[String]) -> Option<Result<Value, String>> {
+    let command = args.get(1)?.as_str();
+    let message = match command {
+        "help" | "--help" | "-h" => Value::Null,
+        "status" | "presets" | "connect" | "disconnect" | "update" => json!({ "type": command }),
+        "load-preset" => match args.get(2) {
+            Some(name) => json!({ "type": "load-preset", "name": name }),
+            None => return Some(Err("load-preset needs the name of a preset".to_string())),
+        },
+        "goal" => json!({ "type": "goal", "change": args.get(2).cloned().unwrap_or_default() }),
+        "focus" => match (args.get(2).map(String::as_str), args.get(3)) {
+            (Some("start"), None) => json!({ "type": "focus-start" }),
+            (Some("start"), Some(minutes)) => match minutes.parse::<u32>() {
+                Ok(minutes @ 1..=1440) => json!({ "type": "focus-start", "minutes": minutes }),
+                _ => return Some(Err(format!("{minutes} isn't a number of minutes"))),
+            },
+            (Some("end"), _) => json!({ "type": "focus-end" }),
+            _ => return Some(Err("focus needs start or end".to_string())),
+        },
+        "set" => {
+            let mut message = json!({ "type": "set" });
+            let mut flags = args[2..].iter();
+            while let Some(flag) = flags.next() {
+                let key = match flag.as_str() {
+                    "--details" => "details",
+                    "--state" => "state",
+                    flag => return Some(Err(format!("Unknown option {flag}"))),
+                };
+                match flags.next() {
+                    Some(text) => message[key] = json!(text),
+                    None => return Some(Err(format!("{flag} needs a text"))),
+                }
+            }
+            if message.as_object().map_or(0, |message| message.len()) == 1 {
+                return Some(Err("set needs --details or --state".to_string()));
+            }
+            message
+        }
+        _ => return None,
+    };
+    Some(Ok(message))
+}
+
+fn send(mut message: Value) -> Result<Value, String> {
+    let not_running = || "Discord Presence isn't running".to_string();
+    let dir = data_dir().ok_or_else(not_running)?;
+    let info: Value = match fs::read_to_string(dir.join(INFO_FILE)) {
+        Ok(info) => from_str(&info).unwrap_or_default(),
+        Err(_) => return Err(not_running()),
+    };
+    message["token"] = info["token"].clone();
+    let port = info["port"].as_u64().unwrap_or_default() as u16;
+    let stream = match connect(&dir, port) {
+        Ok(stream) => stream,
+        Err(_) => return Err(not_running()),
+    };
+    let write = writeln!(&stream, "{message}");
+    let mut reply = String::new();
+    match write.and_then(|_| BufReader::new(&stream).read_line(&mut reply)) {
+        Ok(_) => from_str(&reply).map_err(|_| "The app sent an invalid answer".to_string()),
+        Err(err) => Err(format!("Failed to talk to the app: {err}")),
+    }
+}
+
+/// Local channel the command line reaches the running app on: a unix
+/// socket, or a loopback port on Windows. Requests need the token from the
+/// info file, which only the user running the app can read, and the socket
+/// only lets that user connect.
+pub struct LocalServer {
+    receiver: Receiver<Request>,
+    dir: PathBuf,
+    port: u16,
+    stopped: Arc<AtomicBool>,
+}
+
+impl LocalServer {
+    pub fn start(ctx: Context) -> Result<Self, String> {
+        let dir = match data_dir() {
+            Some(dir) => dir,
+            None => return Err("No data folder for the command line channel".to_string()),
+        };
+        let started = fs::create_dir_all(&dir).and_then(|_| bind(&dir));
+        let (listener, port) = match started {
+            Ok(started) => started,
+            Err(err) => return Err(format!("Command line channel not started: {err}")),
+        };
+        let token = random_hex(32);
+        let info = json!({ "port": port, "token": token }).to_string();
+        if let Err(err) = write_private(&dir.join(INFO_FILE), &info) {
+            return Err(format!("Command line channel not started: {err}"));
+        }
+        let (sender, receiver) = channel();
+        let stopped = Arc::new(AtomicBool::new(false));
+        let stop = stopped.clone();
+        thread::spawn(move || {
+            for stream in listener.incoming().flatten() {
+                if stop.load(Ordering::Relaxed) {
+                    break;
+                }
+                let token = token.clone();
+                let sender = sender.clone();
+                let ctx = ctx.clone();
+                thread::spawn(move || {
+                    let _ = serve(stream, &token, &sender, &ctx);
+                });
+            }
+        });
+        Ok(Self {
+            receiver,
+            dir,
+            port,
+            stopped,
+        })
+    }
+
+    pub fn poll(&self) -> Option<Request> {
+        self.receiver.try_recv().ok()
+    }
+}
+
+/// Stops listening and removes the info file, so the command line says the
+/// app isn't running.
+impl Drop for LocalServer {
+    fn drop(&mut self) {
+        self.stopped.store(true, Ordering::Relaxed);
+        // wakes the listener thread so it sees the flag
+        let _ = connect(&self.dir, self.port);
+        let _ = fs::remove_file(self.dir.join(INFO_FILE));
+        #[cfg(unix)]
+        let _ = fs::remove_file(self.dir.join(SOCKET));
+    }
+}
+
+fn serve(stream: Stream, token: &str, sender: &Sender<Request>, ctx: &Context) -> io::Result<()> {
+    stream.set_read_timeout(Some(Duration::from_secs(5)))?;
+    let mut line = String::new();
+    BufReader::new((&stream).take(MAX_LINE)).read_line(&mut line)?;
+    let message: Value = from_str(&line).unwrap_or_default();
+    let given = message["token"].as_str().unwrap_or_default();
+    let reply = match Command::from_message(&message) {
+        _ if !same(token, given) => json!({ "error": "Wrong token, restart the app" }),
+        Some(command) => match ask(command, sender, ctx) {
+            Ok(status) => status,
+            Err((_, error)) => json!({ "error": error }),
+        },
+        None => json!({ "error": "Unknown command" }),
+    };
+    writeln!(&stream, "{reply}")
+}
+
+#[cfg(unix)]
+fn bind(dir: &Path) -> io::Result<(Listener, u16)> {
+    use std::os::unix::fs::PermissionsExt;
+
+    let path = dir.join(SOCKET);
+    // a socket that answers belongs to another running app, one that doesn't was left by a crash
+    if Stream::connect(&path).is_ok() {
+        return Err(io::Error::new(
+            io::ErrorKind::AddrInUse,
+            "another Discord Presence is running",
+        ));
+    }
+    let _ = fs::remove_file(&path);
+    let listener = Listener::bind(&path)?;
+    // connecting needs write access to the socket, only the owner keeps it.
+    // Someone connecting before this still can't do anything, every request
+    // needs the token that's only written to the private info file after it
+    fs::set_permissions(&path, fs::Permissions::from_mode(0o600))?;
+    Ok((listener, 0))
+}
+
+#[cfg(windows)]
+fn bind(_: &Path) -> io::Result<(Listener, u16)> {
+    let listener = Listener::bind((Ipv4Addr::LOCALHOST, 0))?;
+    let port = listener.local_addr()?.port();
+    Ok((listener, port))
+}
+
+#[cfg(unix)]
+fn connect(dir: &Path, _: u16) -> io::Result<Stream> {
+    Stream::connect(dir.join(SOCKET))
+}
+
+#[cfg(windows)]
+fn connect(_: &Path, port: u16) -> io::Result<Stream> {
+    Stream::connect((Ipv4Addr::LOCALHOST, port))
+}
+
+/// Writes a file only the current user can read.
+pub fn write_private(path: &Path, text: &str) -> io::Result<()> {
+    let mut options = fs::OpenOptions::new();
+    options.write(true).create(true).truncate(true);
+    #[cfg(unix)]
+    {
+        use std::os::unix::fs::OpenOptionsExt;
+        options.mode(0o600);
+    }
+    let mut file = options.open(path)?;
+    // the mode only applies to new files, an old one may be readable by others
+    #[cfg(unix)]
+    {
+        use std::os::unix::fs::PermissionsExt;
+        file.set_permissions(fs::Permissions::from_mode(0o600))?;
+    }
+    file.write_all(text.as_bytes())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn args(line: &str) -> Vec<String> {
+        std::iter::once("discord_presence")
+            .chain(line.split_whitespace())
+            .map(str::to_string)
+            .collect()
+    }
+
+    #[test]
+    fn starts_the_app_without_a_command() {
+        assert!(parse(&args("")).is_none());
+        assert!(parse(&args("--headless")).is_none());
+    }
+
+    #[test]
+    fn parses_commands() {
+        assert_eq!(parse(&args("help")), Some(Ok(Value::Null)));
+        assert_eq!(
+            parse(&args("status")),
+            Some(Ok(json!({ "type": "status" })))
+        );
+        assert_eq!(
+            parse(&args("load-preset Gaming")),
+            Some(Ok(json!({ "type": "load-preset", "name": "Gaming" })))
+        );
+        assert_eq!(
+            parse(&args("goal +2")),
+            Some(Ok(json!({ "type": "goal", "change": "+2" })))
+        );
+        assert_eq!(
+            parse(&args("focus start 50")),
+            Some(Ok(json!({ "type": "focus-start", "minutes": 50 })))
+        );
+        assert_eq!(
+            parse(&args("set --state Coding --details Rust")),
+            Some(Ok(
+                json!({ "type": "set", "state": "Coding", "details": "Rust" })
+            ))
+        );
+    }
+
+    #[test]
+    fn rejects_bad_arguments() {
+        for line in [
+            "load-preset",
+            "focus",
+            "focus start 0",
+            "focus start soon",
+            "set",
+            "set --state",
+            "set --name x",
+        ] {
+            assert!(matches!(parse(&args(line)), Some(Err(_))), "{line}");
+        }
+    }
+}
@@ -0,0 +1,224 @@
+use chrono::{DateTime, Duration, DurationRound, TimeZone, Utc};
+use eframe::egui::Ui;
+use std::net::UdpSocket;
+use std::sync::mpsc::{channel, Receiver};
+use std::thread;
+
+const SERVER: &str = "pool.ntp.org:123";
+/// Seconds between two measurements, clocks drift slowly.
+const REMEASURE: i64 = 6 * 60 * 60;
+/// Seconds to wait after a failed measurement.
+const RETRY_AFTER: i64 = 5 * 60;
+/// Seconds from 1900, where NTP time starts, to 1970.
+const NTP_EPOCH: i64 = 2_208_988_800;
+/// Largest correction applied, in seconds. A clock off by more than a day
+/// needs fixing, not correcting.
+const MAX_OFFSET: i64 = 24 * 60 * 60;
+
+/// Corrects start timestamps taken from a local clock that's off, so the
+/// elapsed time others see doesn't start early or jump once the clock is fixed.
+#[derive(Default)]
+pub struct Clock {
+    pub correct: bool,
+    pub round: bool,
+    /// How far the local clock is behind the NTP time.
+    pub offset: Option<Duration>,
+    pub error: Option<String>,
+    pub pending: Option<Receiver<Result<Duration, String>>>,
+    /// Unix time the next measurement is due.
+    pub next_check: i64,
+}
+
+impl Clock {
+    pub fn run(&mut self, ui: &mut Ui) {
+        if ui
+            .checkbox(&mut self.correct, "Correct the clock with NTP")
+            .changed()
+        {
+            self.next_check = 0;
+        }
+        ui.checkbox(&mut self.round, "Round start times to the minute");
+        match (self.correct, self.offset, &self.error) {
+            (false, _, _) => (),
+            (true, _, Some(error)) => {
+                ui.label(error);
+            }
+            (true, Some(offset), None) => {
+                ui.label(format!(
+                    "Local clock is off by {:.1}s",
+                    offset.num_milliseconds() as f64 / 1000.
+                ));
+            }
+            (true, None, None) => {
+                ui.label("Measuring…");
+            }
+        }
+    }
+
+    /// Starts a measurement when one is due and stores a finished one.
+    /// Returns false while one is still running.
+    pub fn poll(&mut self) -> bool {
+        if let Some(receiver) = &self.pending {
+            let result = match receiver.try_recv() {
+                Ok(result) => result,
+                Err(_) => return false,
+            };
+            self.pending = None;
+            match result {
+                Ok(offset) => {
+                    self.offset = Some(offset);
+                    self.error = None;
+                    self.next_check = Utc::now().timestamp() + REMEASURE;
+                }
+                Err(error) => {
+                    self.error = Some(error);
+                    self.next_check = Utc::now().timestamp() + RETRY_AFTER;
+                }
+            }
+        }
+        if self.correct && self.next_check <= Utc::now().timestamp() {
+            let (sender, receiver) = channel();
+            thread::spawn(move || {
+                let _ = sender.send(measure());
+            });
+            self.pending = Some(receiver);
+            self.next_check = i64::MAX;
+            return false;
+        }
+        true
+    }
+
+    /// A start moment read from the local clock, as the rest of the world
+    /// would have read it.
+    pub fn adjust(&self, start: DateTime<Utc>) -> DateTime<Utc> {
+        let start = match (self.correct, self.offset) {
+            (true, Some(offset)) => start + offset,
+            _ => start,
+        };
+        if !self.round {
+            return start;
+        }
+        // a start rounded past now would show a negative elapsed time
+        match start.duration_round(Duration::minutes(1)) {
+            Ok(rounded) if rounded <= Utc::now() => rounded,
+            _ => start.duration_trunc(Duration::minutes(1)).unwrap_or(start),
+        }
+    }
+}
+
+/// Asks an NTP server for the time and returns how far it is ahead of the
+/// local clock, with the time the answer took taken out.
+fn measure() -> Result<Duration, String> {
+    let failed = |err: std::io::Error| format!("Failed to reach the time server: {err}");
+    let socket = UdpSocket::bind(("0.0.0.0", 0)).map_err(failed)?;
+    socket
+        .set_read_timeout(Some(std::time::Duration::from_secs(5)))
+        .map_err(failed)?;
+    let mut packet = [0; 48];
+    // no leap indicator, version 3, client mode
+    packet[0] = 0x1b;
+    let sent = Utc::now();
+    socket.send_to(&packet, SERVER).map_err(failed)?;
+    let len = socket.recv(&mut packet).map_err(failed)?;
+    let received = Utc::now();
+    if len < 48 {
+        return Err("The time server sent an invalid answer".to_string());
+    }
+    offset(&packet, sent, received)
+}
+
+/// The offset an answer from the server gives, for a request sent and
+/// answered at the local times `sent` and `received`.
+fn offset(
+    packet: &[u8; 48],
+    sent: DateTime<Utc>,
+    received: DateTime<Utc>,
+) -> Result<Duration, String> {
+    let invalid = || "The time server sent an invalid answer".to_string();
+    let (leap, mode, stratum) = (packet[0] >> 6, packet[0] & 0b111, packet[1]);
+    // a leap indicator of 3 means the server's clock isn't set, stratum 0 is a
+    // kiss-o'-death asking to back off
+    if mode != 4 || leap == 3 || !(1..=15).contains(&stratum) || packet[40..48] == [0; 8] {
+        return Err(invalid());
+    }
+    let (server_received, server_sent) =
+        match (ntp_time(&packet[32..40]), ntp_time(&packet[40..48])) {
+            (Some(server_received), Some(server_sent)) => (server_received, server_sent),
+            _ => return Err(invalid()),
+        };
+    let offset = ((server_received - sent) + (server_sent - received)) / 2;
+    let max = Duration::seconds(MAX_OFFSET);
+    Ok(offset.max(-max).min(max))
+}
+
+fn ntp_time(bytes: &[u8]) -> Option<DateTime<Utc>> {
+    let seconds = u32::from_be_bytes(bytes[..4].try_into().ok()?) as i64 - NTP_EPOCH;
+    let fraction = u32::from_be_bytes(bytes[4..].try_into().ok()?) as u64;
+    let nanos = (fraction * 1_000_000_000) >> 32;
+    Utc.timestamp_opt(seconds, nanos as u32).single()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{ntp_time, offset, MAX_OFFSET, NTP_EPOCH};
+    use chrono::{Duration, TimeZone, Utc};
+
+    /// An answer from a stratum 2 server that received the request at
+    /// `received` and answered at `sent`, both unix seconds.
+    fn answer(received: i64, sent: i64) -> [u8; 48] {
+        let mut packet = [0; 48];
+        // no leap indicator, version 4, server mode
+        packet[0] = 0b00_100_100;
+        packet[1] = 2;
+        packet[32..36].copy_from_slice(&((received + NTP_EPOCH) as u32).to_be_bytes());
+        packet[40..44].copy_from_slice(&((sent + NTP_EPOCH) as u32).to_be_bytes());
+        packet
+    }
+
+    #[test]
+    fn reads_ntp_times() {
+        let seconds = ((1_700_000_000 + NTP_EPOCH) as u32).to_be_bytes();
+        let half = (1u32 << 31).to_be_bytes();
+        let time = ntp_time(&[seconds, half].concat()).unwrap();
+        assert_eq!(time.timestamp(), 1_700_000_000);
+        assert_eq!(time.timestamp_subsec_millis(), 500);
+        assert_eq!(ntp_time(&[0; 8]).unwrap().timestamp(), -NTP_EPOCH);
+    }
+
+    #[test]
+    fn measures_the_offset() {
+        let sent = Utc.timestamp_opt(1_700_000_000, 0).unwrap();
+        let received = sent + Duration::seconds(2);
+        // the server is 10s ahead and took no time to answer
+        let packet = answer(1_700_000_011, 1_700_000_011);
+        assert_eq!(offset(&packet, sent, received), Ok(Duration::seconds(10)));
+    }
+
+    #[test]
+    fn rejects_bad_answers() {
+        let sent = Utc.timestamp_opt(1_700_000_000, 0).unwrap();
+        assert!(offset(&[0; 48], sent, sent).is_err());
+        let mut kiss = answer(1_700_000_000, 1_700_000_000);
+        kiss[1] = 0;
+        assert!(offset(&kiss, sent, sent).is_err());
+        let mut unsynced = answer(1_700_000_000, 1_700_000_000);
+        unsynced[0] |= 0b11 << 6;
+        assert!(offset(&unsynced, sent, sent).is_err());
+        let mut client = answer(1_700_000_000, 1_700_000_000);
+        client[0] = 0b00_100_011;
+        assert!(offset(&client, sent, sent).is_err());
+        let mut no_time = answer(1_700_000_000, 0);
+        no_time[40..48].copy_from_slice(&[0; 8]);
+        assert!(offset(&no_time, sent, sent).is_err());
+    }
+
+    #[test]
+    fn clamps_the_offset() {
+        let sent = Utc.timestamp_opt(1_700_000_000, 0).unwrap();
+        let packet = answer(1_800_000_000, 1_800_000_000);
+        assert_eq!(
+            offset(&packet, sent, sent),
+            Ok(Duration::seconds(MAX_OFFSET))
+        );
+    }
+}
@@ -0,0 +1,101 @@
+use crate::storage::data_dir;
+use serde::{Deserialize, Serialize};
+use std::{fs, io::ErrorKind, path::PathBuf};
+
+/// Optional `config.toml` next to the app's storage, for settings that are
+/// easier to write by hand than to click together.
+///
+/// ```toml
+/// [startup]
+/// preset = "Coding"  # name of an in-app preset or path to a preset file
+/// connect = true
+/// rotation = true    # start the preset switcher
+///
+/// [metrics]
+/// listen = "127.0.0.1:9187"  # serve Prometheus metrics on /metrics
+///
+/// [control]
+/// listen = "127.0.0.1:9188"  # HTTP control API
+/// advertise = false          # announce it on the LAN over mDNS
+///
+/// [[control.tokens]]
+/// token = "long-random-string"
+/// scope = "read"             # "read" for status only, "full" for every command
+/// commands = ["status"]      # optional allowlist on top of the scope
+/// ```
+#[derive(Deserialize, Default)]
+#[serde(default)]
+pub struct Config {
+    pub startup: Startup,
+    pub metrics: Metrics,
+    pub control: Control,
+}
+
+/// Actions run once when the app starts, in the order preset, connect, rotation.
+#[derive(Deserialize, Default)]
+#[serde(default)]
+pub struct Startup {
+    pub preset: Option<String>,
+    pub connect: bool,
+    pub rotation: bool,
+}
+
+#[derive(Deserialize, Default)]
+#[serde(default)]
+pub struct Metrics {
+    /// Address to serve metrics on, off when not set.
+    pub listen: Option<String>,
+}
+
+/// The HTTP control API, only requests with one of the tokens are accepted.
+#[derive(Deserialize, Default)]
+#[serde(default)]
+pub struct Control {
+    /// Address to listen on, off when not set.
+    pub listen: Option<String>,
+    /// Announce the API over mDNS, needs `listen` on a LAN address.
+    pub advertise: bool,
+    pub tokens: Vec<Token>,
+}
+
+#[derive(Deserialize, Clone)]
+pub struct Token {
+    pub token: String,
+    #[serde(default)]
+    pub scope: Scope,
+    /// Commands the token may use, every command of its scope when not set.
+    pub commands: Option<Vec<String>>,
+}
+
+#[derive(Serialize, Deserialize, PartialEq, Eq, Clone, Copy, Default)]
+#[serde(rename_all = "lowercase")]
+pub enum Scope {
+    /// Only reading the status.
+    #[default]
+    Read,
+    /// Every command.
+    Full,
+}
+
+impl Config {
+    pub fn path() -> Option<PathBuf> {
+        data_dir().map(|dir| dir.join("config.toml"))
+    }
+
+    /// Loads the config file, a missing file is the same as an empty one.
+    pub fn load() -> Result<Self, String> {
+        let path = match Self::path() {
+            Some(path) => path,
+            None => return Ok(Self::default()),
+        };
+        let file = match fs::read_to_string(&path) {
+            Ok(file) => file,
+            Err(err) if err.kind() == ErrorKind::NotFound => return Ok(Self::default()),
+            Err(_) => return Err(format!("Failed to read {}", path.display())),
+        };
+        match toml::from_str(&file) {
+            Ok(config) => Ok(config),
+            Err(err) => Err(format!("Invalid config: {err}")),
+        }
+    }
+}
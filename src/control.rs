@@ -0,0 +1,578 @@
+use crate::config::{Control, Scope, Token};
+use crate::goal::Change;
+use crate::overlay;
+use crate::remote::{self, SharedPairings};
+use crate::websocket::WebSocket;
+use eframe::egui::Context;
+use serde_json::{json, Value};
+use std::io::{BufRead, BufReader, Error, ErrorKind, Read, Write};
+use std::net::{Ipv4Addr, SocketAddr, TcpListener, TcpStream};
+use std::sync::atomic::{AtomicBool, AtomicUsize, Ordering};
+use std::sync::mpsc::{channel, Receiver, Sender};
+use std::sync::Arc;
+use std::thread;
+use std::time::Duration;
+
+/// Largest request body accepted.
+const MAX_BODY: usize = 64 * 1024;
+/// Longest request or header line read.
+const MAX_LINE: u64 = 8 * 1024;
+/// Most headers read from one request.
+const MAX_HEADERS: usize = 64;
+/// Connections served at once, phone remotes included. Others are turned away.
+const MAX_CONNECTIONS: usize = 16;
+/// Shortest token accepted, shorter ones are easy to guess.
+pub const MIN_TOKEN: usize = 16;
+/// In-app preset a focus session shows.
+pub const FOCUS_PRESET: &str = "Focus";
+/// Length of a focus session started without one.
+pub const FOCUS_MINUTES: u32 = 25;
+
+/// Every name `Command::name` gives, for checking allowlists.
+pub const COMMANDS: [&str; 10] = [
+    "status",
+    "presets",
+    "overlay",
+    "connect",
+    "disconnect",
+    "update",
+    "load-preset",
+    "goal",
+    "focus",
+    "set",
+];
+
+pub enum Command {
+    Status,
+    Presets,
+    /// What Discord shows, for the browser source overlay.
+    Overlay,
+    Connect,
+    Disconnect,
+    Update,
+    LoadPreset(String),
+    Goal(Change),
+    /// Shows the Focus preset counting down this many minutes, then goes
+    /// back to the presence from before.
+    FocusStart(u32),
+    FocusEnd,
+    /// Changes the texts that are given and updates the presence.
+    Set {
+        details: Option<String>,
+        state: Option<String>,
+    },
+}
+
+impl Command {
+    /// Name used in the token allowlists.
+    pub fn name(&self) -> &'static str {
+        match self {
+            Command::Status => "status",
+            Command::Presets => "presets",
+            Command::Overlay => "overlay",
+            Command::Connect => "connect",
+            Command::Disconnect => "disconnect",
+            Command::Update => "update",
+            Command::LoadPreset(_) => "load-preset",
+            Command::Goal(_) => "goal",
+            Command::FocusStart(_) | Command::FocusEnd => "focus",
+            Command::Set { .. } => "set",
+        }
+    }
+
+    /// Reads a command from a JSON message with a `type`, as sent by the
+    /// phone remote and the command line.
+    pub fn from_message(message: &Value) -> Option<Self> {
+        let text = |key: &str| message[key].as_str().map(str::to_string);
+        match message["type"].as_str()? {
+            "status" => Some(Command::Status),
+            "presets" => Some(Command::Presets),
+            "overlay" => Some(Command::Overlay),
+            "connect" => Some(Command::Connect),
+            "disconnect" => Some(Command::Disconnect),
+            "update" => Some(Command::Update),
+            "load-preset" => text("name").map(Command::LoadPreset),
+            "goal" => {
+                Change::parse(message["change"].as_str().unwrap_or_default()).map(Command::Goal)
+            }
+            "focus-start" => match &message["minutes"] {
+                Value::Null => Some(Command::FocusStart(FOCUS_MINUTES)),
+                minutes => minutes
+                    .as_u64()
+                    .filter(|minutes| (1..=1440).contains(minutes))
+                    .map(|minutes| Command::FocusStart(minutes as u32)),
+            },
+            "focus-end" => Some(Command::FocusEnd),
+            "set" => Some(Command::Set {
+                details: text("details"),
+                state: text("state"),
+            }),
+            _ => None,
+        }
+    }
+
+    pub fn allowed(&self, token: &Token) -> bool {
+        let in_scope = match token.scope {
+            Scope::Read => matches!(self, Command::Status | Command::Presets | Command::Overlay),
+            Scope::Full => true,
+        };
+        let listed = match &token.commands {
+            Some(commands) => commands.iter().any(|command| command == self.name()),
+            None => true,
+        };
+        in_scope && listed
+    }
+}
+
+/// A command waiting for the app to run it.
+pub struct Request {
+    pub command: Command,
+    pub reply: Sender<Result<Value, String>>,
+}
+
+/// HTTP control API on its own thread. Requests are handed to the app, which
+/// runs them between frames so they see the same state as the UI.
+pub struct ControlServer {
+    pub addr: SocketAddr,
+    receiver: Receiver<Request>,
+    stopped: Arc<AtomicBool>,
+}
+
+impl ControlServer {
+    pub fn start(
+        config: &Control,
+        pairings: SharedPairings,
+        ctx: Context,
+    ) -> Result<Option<Self>, String> {
+        let listen = match &config.listen {
+            Some(listen) => listen,
+            None => return Ok(None),
+        };
+        if config.tokens.is_empty() {
+            return Err("The control API needs at least one token".to_string());
+        }
+        if config
+            .tokens
+            .iter()
+            .any(|token| token.token.len() < MIN_TOKEN)
+        {
+            return Err(format!(
+                "Control API not started, tokens need at least {MIN_TOKEN} characters"
+            ));
+        }
+        let listener = match TcpListener::bind(listen) {
+            Ok(listener) => listener,
+            Err(err) => {
+                return Err(format!(
+                    "Failed to serve the control API on {listen}: {err}"
+                ))
+            }
+        };
+        let addr = match listener.local_addr() {
+            Ok(addr) => addr,
+            Err(err) => return Err(format!("Failed to serve the control API: {err}")),
+        };
+        let tokens = config.tokens.clone();
+        let (sender, receiver) = channel();
+        let stopped = Arc::new(AtomicBool::new(false));
+        let stop = stopped.clone();
+        thread::spawn(move || {
+            let serving = Arc::new(AtomicUsize::new(0));
+            for mut stream in listener.incoming().flatten() {
+                if stop.load(Ordering::Relaxed) {
+                    break;
+                }
+                if serving.fetch_add(1, Ordering::Relaxed) >= MAX_CONNECTIONS {
+                    serving.fetch_sub(1, Ordering::Relaxed);
+                    let _ = write!(
+                        stream,
+                        "HTTP/1.1 503 Service Unavailable\r\nContent-Length: 0\r\nConnection: close\r\n\r\n"
+                    );
+                    continue;
+                }
+                let tokens = tokens.clone();
+                let sender = sender.clone();
+                let pairings = pairings.clone();
+                let ctx = ctx.clone();
+                let serving = serving.clone();
+                thread::spawn(move || {
+                    let _ = serve(stream, &tokens, &pairings, &sender, &ctx);
+                    serving.fetch_sub(1, Ordering::Relaxed);
+                });
+            }
+        });
+        Ok(Some(Self {
+            addr,
+            receiver,
+            stopped,
+        }))
+    }
+
+    pub fn poll(&self) -> Option<Request> {
+        self.receiver.try_recv().ok()
+    }
+}
+
+/// Closes the port: the listener thread only sees the flag on its next
+/// connection, so it gets one.
+impl Drop for ControlServer {
+    fn drop(&mut self) {
+        self.stopped.store(true, Ordering::Relaxed);
+        let mut addr = self.addr;
+        if addr.ip().is_unspecified() {
+            addr.set_ip(Ipv4Addr::LOCALHOST.into());
+        }
+        let _ = TcpStream::connect_timeout(&addr, Duration::from_secs(1));
+    }
+}
+
+fn serve(
+    mut stream: TcpStream,
+    tokens: &[Token],
+    pairings: &SharedPairings,
+    sender: &Sender<Request>,
+    ctx: &Context,
+) -> std::io::Result<()> {
+    stream.set_read_timeout(Some(Duration::from_secs(5)))?;
+    let mut reader = BufReader::new(&stream);
+    let request_line = read_line(&mut reader)?;
+    let mut authorization = String::new();
+    let mut length = 0;
+    let mut websocket_key = None;
+    for headers in 0.. {
+        let header = read_line(&mut reader)?;
+        let header = header.trim_end();
+        if header.is_empty() {
+            break;
+        }
+        if headers == MAX_HEADERS {
+            return Err(Error::new(ErrorKind::InvalidData, "too many headers"));
+        }
+        if let Some((name, value)) = header.split_once(':') {
+            match name.trim().to_ascii_lowercase().as_str() {
+                "authorization" => authorization = value.trim().to_string(),
+                "content-length" => length = value.trim().parse().unwrap_or(0),
+                "sec-websocket-key" => websocket_key = Some(value.trim().to_string()),
+                _ => (),
+            }
+        }
+    }
+    let mut body = vec![0; length.min(MAX_BODY)];
+    reader.read_exact(&mut body)?;
+
+    let mut parts = request_line.split_whitespace();
+    let method = parts.next().unwrap_or_default();
+    let target = parts.next().unwrap_or_default();
+    let (path, query) = target.split_once('?').unwrap_or((target, ""));
+    // browser sources can't send headers, the overlay takes the token from its URL.
+    // URLs end up in OBS's settings, so only read tokens are taken from there.
+    if path.starts_with("/overlay") && authorization.is_empty() {
+        let given = query
+            .split('&')
+            .find_map(|pair| pair.strip_prefix("token="))
+            .map(decode)
+            .unwrap_or_default();
+        let read = tokens
+            .iter()
+            .any(|token| token.scope == Scope::Read && same(&token.token, &given));
+        if read {
+            authorization = format!("Bearer {given}");
+        }
+    }
+    if (method, path) == ("GET", "/overlay") {
+        let known = authorization
+            .strip_prefix("Bearer ")
+            .map_or(false, |given| {
+                tokens
+                    .iter()
+                    .any(|token| same(&token.token, given) && Command::Overlay.allowed(token))
+            });
+        let (status, page) = match known {
+            true => ("200 OK", overlay::PAGE),
+            false => ("401 Unauthorized", "Missing or unknown read token"),
+        };
+        return write!(
+            stream,
+            "HTTP/1.1 {status}\r\nContent-Type: text/html; charset=utf-8\r\nContent-Length: {}\r\nConnection: close\r\n\r\n{page}",
+            page.len()
+        );
+    }
+    // the phone remote authenticates inside the socket, not with a bearer token
+    if let ("GET", "/remote", Some(key)) = (method, path, &websocket_key) {
+        stream.set_read_timeout(Some(Duration::from_secs(remote::IDLE_TIMEOUT)))?;
+        let socket = WebSocket::accept(stream, key)?;
+        return remote::session(socket, pairings, sender, ctx);
+    }
+    let (status, response) = handle(method, path, &body, &authorization, tokens, sender, ctx);
+    let response = response.to_string();
+    write!(
+        stream,
+        "HTTP/1.1 {status}\r\nContent-Type: application/json\r\nContent-Length: {}\r\nConnection: close\r\n\r\n{response}",
+        response.len()
+    )
+}
+
+/// Reads one line of the request head, failing on lines longer than
+/// `MAX_LINE` instead of buffering them.
+fn read_line(reader: &mut impl BufRead) -> std::io::Result<String> {
+    let mut line = String::new();
+    reader.take(MAX_LINE).read_line(&mut line)?;
+    match line.ends_with('\n') {
+        true => Ok(line),
+        false => Err(Error::new(ErrorKind::InvalidData, "line too long")),
+    }
+}
+
+fn handle(
+    method: &str,
+    path: &str,
+    body: &[u8],
+    authorization: &str,
+    tokens: &[Token],
+    sender: &Sender<Request>,
+    ctx: &Context,
+) -> (&'static str, Value) {
+    let token = authorization
+        .strip_prefix("Bearer ")
+        .and_then(|given| tokens.iter().find(|token| same(&token.token, given)));
+    let token = match token {
+        Some(token) => token,
+        None => {
+            return (
+                "401 Unauthorized",
+                json!({ "error": "Missing or unknown token" }),
+            )
+        }
+    };
+    let command = match (method, path) {
+        ("GET", "/status") => Command::Status,
+        ("GET", "/presets") => Command::Presets,
+        ("GET", "/overlay.json") => Command::Overlay,
+        ("POST", "/connect") => Command::Connect,
+        ("POST", "/disconnect") => Command::Disconnect,
+        ("POST", "/update") => Command::Update,
+        ("POST", "/goal") => Command::Goal(Change::Step),
+        ("POST", path) if path.starts_with("/goal/") => {
+            match Change::parse(&decode(&path["/goal/".len()..])) {
+                Some(change) => Command::Goal(change),
+                None => return ("400 Bad Request", json!({ "error": "Invalid goal change" })),
+            }
+        }
+        ("POST", "/focus/start") => Command::FocusStart(FOCUS_MINUTES),
+        ("POST", path) if path.starts_with("/focus/start/") => {
+            match path["/focus/start/".len()..].parse() {
+                Ok(minutes @ 1..=1440) => Command::FocusStart(minutes),
+                _ => return ("400 Bad Request", json!({ "error": "Invalid minutes" })),
+            }
+        }
+        ("POST", "/focus/end") => Command::FocusEnd,
+        ("POST", "/set") => {
+            let body: Value = serde_json::from_slice(body).unwrap_or_default();
+            let text = |key: &str| body[key].as_str().map(str::to_string);
+            Command::Set {
+                details: text("details"),
+                state: text("state"),
+            }
+        }
+        ("POST", path) if path.starts_with("/preset/") => {
+            Command::LoadPreset(decode(&path["/preset/".len()..]))
+        }
+        _ => return ("404 Not Found", json!({ "error": "Unknown command" })),
+    };
+    if !command.allowed(token) {
+        return (
+            "403 Forbidden",
+            json!({ "error": format!("Token may not use {}", command.name()) }),
+        );
+    }
+
+    match ask(command, sender, ctx) {
+        Ok(value) => ("200 OK", value),
+        Err((status, error)) => (status, json!({ "error": error })),
+    }
+}
+
+/// Hands the command to the app and waits for its answer. Failures come
+/// with the HTTP status that fits them.
+pub fn ask(
+    command: Command,
+    sender: &Sender<Request>,
+    ctx: &Context,
+) -> Result<Value, (&'static str, String)> {
+    let (reply, answer) = channel();
+    if sender.send(Request { command, reply }).is_err() {
+        return Err(("503 Service Unavailable", "App is closing".to_string()));
+    }
+    ctx.request_repaint();
+    match answer.recv_timeout(Duration::from_secs(10)) {
+        Ok(Ok(value)) => Ok(value),
+        Ok(Err(error)) => Err(("409 Conflict", error)),
+        Err(_) => Err(("504 Gateway Timeout", "App didn't answer".to_string())),
+    }
+}
+
+/// Compares tokens without stopping at the first difference.
+pub fn same(expected: &str, given: &str) -> bool {
+    expected.len() == given.len()
+        && expected
+            .bytes()
+            .zip(given.bytes())
+            .fold(0, |diff, (a, b)| diff | (a ^ b))
+            == 0
+}
+
+/// Decodes the %XX escapes of a preset name in the path.
+fn decode(text: &str) -> String {
+    let mut bytes = Vec::with_capacity(text.len());
+    let mut chars = text.bytes();
+    while let Some(byte) = chars.next() {
+        match byte {
+            b'%' => {
+                let hex: Vec<u8> = chars.by_ref().take(2).collect();
+                // a cut off escape stays as it was written
+                match std::str::from_utf8(&hex)
+                    .ok()
+                    .filter(|hex| hex.len() == 2 && hex.bytes().all(|c| c.is_ascii_hexdigit()))
+                    .and_then(|hex| u8::from_str_radix(hex, 16).ok())
+                {
+                    Some(byte) => bytes.push(byte),
+                    None => {
+                        bytes.push(b'%');
+                        bytes.extend_from_slice(&hex);
+                    }
+                }
+            }
+            byte => bytes.push(byte),
+        }
+    }
+    String::from_utf8_lossy(&bytes).to_string()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{decode, handle, Command, Request};
+    use crate::config::{Scope, Token};
+    use crate::goal::Change;
+    use eframe::egui::Context;
+    use serde_json::{json, Value};
+    use std::sync::mpsc::channel;
+    use std::thread;
+
+    const FULL: &str = "full-token-0123456789";
+    const READ: &str = "read-token-0123456789";
+
+    fn tokens() -> Vec<Token> {
+        vec![
+            Token {
+                token: FULL.to_string(),
+                scope: Scope::Full,
+                commands: None,
+            },
+            Token {
+                token: READ.to_string(),
+                scope: Scope::Read,
+                commands: None,
+            },
+        ]
+    }
+
+    /// Runs one request against an app that answers with what it was asked.
+    fn call(method: &str, path: &str, body: &str, token: &str) -> (&'static str, Value) {
+        let (sender, receiver) = channel::<Request>();
+        let app = thread::spawn(move || {
+            if let Ok(request) = receiver.recv() {
+                let detail = match &request.command {
+                    Command::Goal(Change::Step) => json!("step"),
+                    Command::Goal(Change::Add(amount)) => json!(amount),
+                    Command::FocusStart(minutes) => json!(minutes),
+                    Command::LoadPreset(name) => json!(name),
+                    Command::Set { details, state } => json!([details, state]),
+                    _ => Value::Null,
+                };
+                let answer = json!({ "ran": request.command.name(), "detail": detail });
+                let _ = request.reply.send(Ok(answer));
+            }
+        });
+        let authorization = format!("Bearer {token}");
+        let tokens = tokens();
+        let result = handle(
+            method,
+            path,
+            body.as_bytes(),
+            &authorization,
+            &tokens,
+            &sender,
+            &Context::default(),
+        );
+        drop(sender);
+        app.join().unwrap();
+        result
+    }
+
+    #[test]
+    fn runs_commands() {
+        let (status, value) = call("POST", "/preset/Late%20night", "", FULL);
+        assert_eq!(status, "200 OK");
+        assert_eq!(
+            value,
+            json!({ "ran": "load-preset", "detail": "Late night" })
+        );
+        let (_, value) = call("POST", "/goal", "", FULL);
+        assert_eq!(value["detail"], "step");
+        let (_, value) = call("POST", "/goal/+2", "", FULL);
+        assert_eq!(value["detail"], 2.);
+        let (_, value) = call("POST", "/focus/start/50", "", FULL);
+        assert_eq!(value["detail"], 50);
+        let (_, value) = call("POST", "/set", r#"{"details": "Coding"}"#, FULL);
+        assert_eq!(value["detail"], json!(["Coding", null]));
+    }
+
+    #[test]
+    fn rejects_unknown_tokens_and_paths() {
+        assert_eq!(call("GET", "/status", "", "guessed").0, "401 Unauthorized");
+        assert_eq!(call("GET", "/status", "", "").0, "401 Unauthorized");
+        assert_eq!(call("POST", "/goalanything", "", FULL).0, "404 Not Found");
+        assert_eq!(call("POST", "/focus/startle", "", FULL).0, "404 Not Found");
+        assert_eq!(call("POST", "/goal/two", "", FULL).0, "400 Bad Request");
+        assert_eq!(
+            call("POST", "/focus/start/0", "", FULL).0,
+            "400 Bad Request"
+        );
+        assert_eq!(call("DELETE", "/status", "", FULL).0, "404 Not Found");
+    }
+
+    #[test]
+    fn keeps_read_tokens_to_reading() {
+        assert_eq!(call("GET", "/status", "", READ).0, "200 OK");
+        assert_eq!(call("POST", "/connect", "", READ).0, "403 Forbidden");
+        assert_eq!(call("POST", "/preset/Coding", "", READ).0, "403 Forbidden");
+    }
+
+    #[test]
+    fn checks_allowlists() {
+        let token = Token {
+            token: FULL.to_string(),
+            scope: Scope::Full,
+            commands: Some(vec!["goal".to_string()]),
+        };
+        assert!(Command::Goal(Change::Step).allowed(&token));
+        assert!(!Command::Connect.allowed(&token));
+        assert!(!Command::Status.allowed(&token));
+        let read = Token {
+            scope: Scope::Read,
+            commands: Some(vec!["connect".to_string()]),
+            ..token
+        };
+        assert!(!Command::Connect.allowed(&read));
+    }
+
+    #[test]
+    fn decodes_escapes() {
+        assert_eq!(decode("Late%20night"), "Late night");
+        assert_eq!(decode("caf%C3%A9"), "café");
+        assert_eq!(decode("100%"), "100%");
+        assert_eq!(decode("%2"), "%2");
+        assert_eq!(decode("%zz"), "%zz");
+        assert_eq!(decode("%+1x"), "%+1x");
+    }
+}
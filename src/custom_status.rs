@@ -0,0 +1,52 @@
+use eframe::egui::{self, Context, RichText};
+use eframe::epaint::Color32;
+
+/// Companion window for the Discord custom status.
+///
+/// Discord's RPC has no command that sets a user's custom status, so it can't
+/// be applied for the user. The window composes it next to the presence and
+/// copies it so it only has to be pasted into Discord.
+#[derive(Default)]
+pub struct CustomStatus {
+    pub enabled: bool,
+    pub emoji: String,
+    pub text: String,
+}
+
+impl CustomStatus {
+    /// Shows the window, read only while the display lock is on so the
+    /// composed status stays in view.
+    pub fn run(&mut self, ctx: &Context, locked: bool) {
+        egui::containers::Window::new("Custom Status")
+            .open(&mut self.enabled)
+            .enabled(!locked)
+            .resizable(false)
+            .show(ctx, |ui| {
+                ui.label(
+                    RichText::new(
+                        "Discord doesn't allow apps to set your custom status.\nCopy it and paste it into Discord instead.",
+                    )
+                    .color(Color32::from_rgb(163, 163, 163)),
+                );
+                ui.add_space(5.);
+                ui.horizontal(|ui| {
+                    ui.label("Emoji");
+                    ui.add(egui::TextEdit::singleline(&mut self.emoji).desired_width(30.));
+                    ui.label("Text");
+                    ui.text_edit_singleline(&mut self.text);
+                });
+                ui.add_space(5.);
+                if ui
+                    .add_enabled(
+                        !self.text.is_empty() || !self.emoji.is_empty(),
+                        egui::Button::new("Copy to clipboard"),
+                    )
+                    .clicked()
+                {
+                    ui.output().copied_text = format!("{} {}", self.emoji, self.text)
+                        .trim()
+                        .to_string();
+                }
+            });
+    }
+}
@@ -0,0 +1,100 @@
+use eframe::egui::{self, Align2, Context, RichText};
+use std::collections::VecDeque;
+use std::time::{Duration, Instant};
+
+/// Frames the numbers are taken over.
+const SAMPLES: usize = 120;
+
+struct Sample {
+    /// Time since the frame before.
+    interval: Duration,
+    /// Time spent in the app's update.
+    update: Duration,
+    /// Part of the update spent on background work.
+    tick: Duration,
+}
+
+/// Hidden overlay with frame timings and the worker's queue, for reports of
+/// the app using too much CPU. Toggled with Ctrl + Shift + D.
+#[derive(Default)]
+pub struct DebugOverlay {
+    pub open: bool,
+    samples: VecDeque<Sample>,
+    last_frame: Option<Instant>,
+}
+
+impl DebugOverlay {
+    /// Records the frame that just ran.
+    pub fn frame(&mut self, update: Duration, tick: Duration) {
+        let now = Instant::now();
+        if let Some(last) = self.last_frame {
+            self.samples.push_back(Sample {
+                interval: now - last,
+                update,
+                tick,
+            });
+            if self.samples.len() > SAMPLES {
+                self.samples.pop_front();
+            }
+        }
+        self.last_frame = Some(now);
+    }
+
+    /// Shows the overlay. `jobs` are the labels of the worker's jobs not
+    /// answered yet, `sending` the activities waiting for Discord.
+    pub fn run(&mut self, ctx: &Context, jobs: &[&str], sending: usize) {
+        let toggle = {
+            let input = ctx.input();
+            input.modifiers.command && input.modifiers.shift && input.key_pressed(egui::Key::D)
+        };
+        if toggle {
+            self.open = !self.open;
+        }
+        if !self.open {
+            return;
+        }
+        egui::Area::new("debug_overlay")
+            .anchor(Align2::RIGHT_BOTTOM, [-10., -40.])
+            .interactable(false)
+            .show(ctx, |ui| {
+                egui::Frame::popup(ui.style()).show(ui, |ui| {
+                    for line in self.lines(jobs, sending) {
+                        ui.label(RichText::new(line).monospace().small());
+                    }
+                });
+            });
+        // slow enough not to show up in the numbers itself
+        ctx.request_repaint_after(Duration::from_secs(1));
+    }
+
+    fn lines(&self, jobs: &[&str], sending: usize) -> Vec<String> {
+        let count = self.samples.len().max(1) as u32;
+        let total = |get: fn(&Sample) -> Duration| self.samples.iter().map(get).sum::<Duration>();
+        let max =
+            |get: fn(&Sample) -> Duration| self.samples.iter().map(get).max().unwrap_or_default();
+        let interval = total(|sample| sample.interval) / count;
+        let fps = match interval.as_secs_f32() {
+            seconds if seconds > 0. => 1. / seconds,
+            _ => 0.,
+        };
+        vec![
+            format!("frames    {fps:>6.1}/s  every {:>7.1} ms", ms(interval)),
+            format!(
+                "update    {:>6.2} ms avg  {:>7.2} ms max",
+                ms(total(|sample| sample.update) / count),
+                ms(max(|sample| sample.update))
+            ),
+            format!(
+                "  tick    {:>6.2} ms avg  {:>7.2} ms max",
+                ms(total(|sample| sample.tick) / count),
+                ms(max(|sample| sample.tick))
+            ),
+            format!("worker    {} queued  {}", jobs.len(), jobs.join(", ")),
+            format!("sending   {sending}"),
+        ]
+    }
+}
+
+fn ms(duration: Duration) -> f32 {
+    duration.as_secs_f32() * 1000.
+}
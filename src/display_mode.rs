@@ -0,0 +1,162 @@
+use crate::control::{same, Command};
+use crate::remote::random_hex;
+use eframe::egui::{self, Context, Layout, RichText};
+use eframe::emath::Align;
+use eframe::epaint::Color32;
+use sha2::{Digest, Sha256};
+
+/// Times the passphrase is hashed, so guessing it from the settings file is slow.
+const ROUNDS: u32 = 100_000;
+
+/// Read-only mode for shared machines: the presence keeps running but the
+/// form, menus, hotkeys and control API can't change it until unlocked
+/// (optionally with a passphrase).
+#[derive(Default)]
+pub struct DisplayMode {
+    pub locked: bool,
+    /// `salt:hash` of the passphrase, empty when unlocking needs none.
+    pub passphrase_hash: String,
+    pub lock_menu: bool,
+    pub unlock_menu: bool,
+    pub passphrase: String,
+    pub wrong_passphrase: bool,
+}
+
+impl DisplayMode {
+    pub fn run(&mut self, ctx: &Context) {
+        egui::containers::Window::new("Display Mode")
+            .open(&mut self.lock_menu)
+            .resizable(false)
+            .show(ctx, |ui| {
+                ui.with_layout(Layout::top_down(Align::Center), |ui| {
+                    ui.label("Lock the app so the presence can be viewed but not edited.");
+                    ui.add_space(5.);
+                    ui.label("Passphrase (optional)");
+                    ui.add(egui::TextEdit::singleline(&mut self.passphrase).password(true));
+                    ui.add_space(5.);
+                    if ui.button("Lock").clicked() {
+                        self.passphrase_hash = match self.passphrase.is_empty() {
+                            true => String::new(),
+                            false => salted_hash(&self.passphrase, &random_hex(16)),
+                        };
+                        self.passphrase = String::new();
+                        self.locked = true;
+                    }
+                });
+            });
+        if self.locked {
+            self.lock_menu = false;
+        }
+
+        egui::containers::Window::new("Unlock")
+            .open(&mut self.unlock_menu)
+            .resizable(false)
+            .show(ctx, |ui| {
+                ui.with_layout(Layout::top_down(Align::Center), |ui| {
+                    ui.label("Passphrase");
+                    ui.add(egui::TextEdit::singleline(&mut self.passphrase).password(true));
+                    if self.wrong_passphrase {
+                        ui.label(RichText::new("Wrong passphrase").color(Color32::LIGHT_RED));
+                    }
+                    ui.add_space(5.);
+                    if ui.button("Unlock").clicked() {
+                        self.wrong_passphrase = !matches(&self.passphrase, &self.passphrase_hash);
+                        if !self.wrong_passphrase {
+                            self.locked = false;
+                        }
+                        self.passphrase = String::new();
+                    }
+                });
+            });
+        if !self.locked {
+            self.unlock_menu = false;
+        }
+    }
+
+    /// Refuses commands from the control API and the command line that
+    /// change the presence while locked, reading it is still allowed.
+    pub fn check(&self, command: &Command) -> Result<(), String> {
+        let reads = matches!(
+            command,
+            Command::Status | Command::Overlay | Command::Presets
+        );
+        match self.locked && !reads {
+            true => Err("The app is locked, unlock it to change the presence".to_string()),
+            false => Ok(()),
+        }
+    }
+
+    /// Unlocks right away when there is no passphrase, asks for it otherwise.
+    pub fn unlock(&mut self) {
+        match self.passphrase_hash.is_empty() {
+            true => self.locked = false,
+            false => {
+                self.wrong_passphrase = false;
+                self.unlock_menu = true;
+            }
+        }
+    }
+}
+
+/// Whether the passphrase is the one `stored` was made from. Locks from
+/// before the salt hold a plain sha256.
+fn matches(passphrase: &str, stored: &str) -> bool {
+    match stored.split_once(':') {
+        Some((salt, _)) => same(stored, &salted_hash(passphrase, salt)),
+        None => same(stored, &hex(&Sha256::digest(passphrase.as_bytes()))),
+    }
+}
+
+fn salted_hash(passphrase: &str, salt: &str) -> String {
+    let mut hash = Sha256::digest(format!("{salt}{passphrase}").as_bytes());
+    for _ in 1..ROUNDS {
+        let mut hasher = Sha256::new();
+        hasher.update(hash);
+        hasher.update(passphrase.as_bytes());
+        hash = hasher.finalize();
+    }
+    format!("{salt}:{}", hex(&hash))
+}
+
+fn hex(bytes: &[u8]) -> String {
+    bytes.iter().map(|byte| format!("{byte:02x}")).collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{matches, salted_hash, DisplayMode};
+    use crate::control::Command;
+
+    #[test]
+    fn locking_rejects_edits() {
+        let mut display_mode = DisplayMode::default();
+        let set = || Command::Set {
+            details: Some("Edited".to_string()),
+            state: None,
+        };
+        assert!(display_mode.check(&set()).is_ok());
+        display_mode.locked = true;
+        assert!(display_mode.check(&set()).is_err());
+        assert!(display_mode
+            .check(&Command::LoadPreset("Coding".to_string()))
+            .is_err());
+        assert!(display_mode.check(&Command::Disconnect).is_err());
+        assert!(display_mode.check(&Command::Status).is_ok());
+    }
+
+    #[test]
+    fn checks_the_passphrase() {
+        let stored = salted_hash("hunter2", "00ff");
+        assert!(stored.starts_with("00ff:"));
+        assert!(matches("hunter2", &stored));
+        assert!(!matches("hunter3", &stored));
+        assert_ne!(stored, salted_hash("hunter2", "ff00"));
+    }
+
+    #[test]
+    fn checks_locks_from_before_the_salt() {
+        let stored = "f52fbd32b2b3b86ff88ef6c490628285f482af15ddcb29541f94bcf526a3f6c7";
+        assert!(matches("hunter2", stored));
+        assert!(!matches("hunter3", stored));
+    }
+}
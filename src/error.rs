@@ -0,0 +1,21 @@
+use std::fmt;
+
+/// Failures talking to Discord. None of them are fatal: they are shown in the
+/// error bar and the app stays usable so the user can retry.
+pub enum Error {
+    Connect(String),
+    AutoConnect(String),
+    Update(String),
+    Disconnect(String),
+}
+
+impl fmt::Display for Error {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Error::Connect(reason) => write!(f, "Failed to connect to discord: {reason}"),
+            Error::AutoConnect(reason) => write!(f, "Failed to connect. (AutoConnect) {reason}"),
+            Error::Update(reason) => write!(f, "Failed to set activity: {reason}"),
+            Error::Disconnect(reason) => write!(f, "Failed to disconnect cleanly: {reason}"),
+        }
+    }
+}
@@ -1,3 +1,4 @@
+use crate::error_report;
 use chrono::{DateTime, TimeZone, Utc};
 use eframe::{
     egui::{self, Color32, Context, Layout, RichText},
@@ -7,6 +8,10 @@ use eframe::{
 #[derive(Default)]
 pub struct ErrorBar {
     pub error: Option<String>,
+    /// The last errors with the time they happened, oldest first.
+    pub history: Vec<String>,
+    /// The message is a notice, not an error.
+    notice: bool,
     time_til_end: Option<DateTime<Utc>>,
 }
 
@@ -16,9 +21,13 @@ impl ErrorBar {
             if let Some(error) = &self.error {
                 egui::TopBottomPanel::bottom("error_bar").show(ctx, |ui| {
                     ui.with_layout(Layout::right_to_left(Align::default()), |ui| {
-                        ui.label(
-                            RichText::new("Error: ".to_string() + error).color(Color32::LIGHT_RED),
-                        );
+                        match self.notice {
+                            true => ui.label(error),
+                            false => ui.label(
+                                RichText::new("Error: ".to_string() + error)
+                                    .color(Color32::LIGHT_RED),
+                            ),
+                        };
                     })
                 });
             }
@@ -31,6 +40,20 @@ impl ErrorBar {
 
     pub fn new_error(&mut self, error: String) {
         self.time_til_end = Some(Utc.timestamp(Utc::now().timestamp() + 3, 0));
+        if self.history.len() == 20 {
+            self.history.remove(0);
+        }
+        self.history
+            .push(format!("{} {error}", Utc::now().format("%H:%M:%S")));
+        error_report::report("error", &error);
+        self.notice = false;
         self.error = Some(error);
     }
+
+    /// Shows a message the same way as an error, without treating it as one.
+    pub fn new_notice(&mut self, notice: String) {
+        self.time_til_end = Some(Utc.timestamp(Utc::now().timestamp() + 3, 0));
+        self.notice = true;
+        self.error = Some(notice);
+    }
 }
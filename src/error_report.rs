@@ -0,0 +1,109 @@
+use crate::redact::redact;
+use eframe::egui::{self, Context, Layout, RichText};
+use eframe::emath::Align;
+use eframe::epaint::Color32;
+use serde_json::json;
+use std::sync::Mutex;
+use std::time::Duration;
+use std::{env, panic, thread};
+
+/// Where reports go, `None` while reporting is off.
+static ENDPOINT: Mutex<Option<String>> = Mutex::new(None);
+
+/// Opt-in error reporting to a self-hosted endpoint. Nothing is sent until
+/// the user enables it in the consent window and gives an endpoint.
+#[derive(Default)]
+pub struct ErrorReporting {
+    pub enabled: bool,
+    pub endpoint: String,
+    pub consent_menu: bool,
+    pub invalid_endpoint: Option<String>,
+}
+
+impl ErrorReporting {
+    pub fn run(&mut self, ctx: &Context) {
+        egui::containers::Window::new("Error reports")
+            .open(&mut self.consent_menu)
+            .resizable(false)
+            .show(ctx, |ui| {
+                ui.with_layout(Layout::top_down(Align::Center), |ui| {
+                    ui.label("Send a report to the endpoint below whenever an error is shown or the app crashes.");
+                    ui.label("A report contains the app version, the OS and the error message, redacted as set under Settings → Redaction. Presets aren't sent, but an error can quote a text of the presence or a preset name, e.g. when Discord rejects an update.");
+                    ui.add_space(5.);
+                    ui.label("Endpoint");
+                    ui.add_enabled(!self.enabled, egui::TextEdit::singleline(&mut self.endpoint));
+                    if let Some(error) = &self.invalid_endpoint {
+                        ui.label(RichText::new(error).small().color(Color32::LIGHT_RED));
+                    }
+                    ui.add_space(5.);
+                    match self.enabled {
+                        true => {
+                            if ui.button("Stop sending reports").clicked() {
+                                self.enabled = false;
+                                set_endpoint(None);
+                            }
+                        }
+                        false => {
+                            if ui.button("I agree, send reports").clicked() {
+                                let valid = self.endpoint.starts_with("https://")
+                                    || self.endpoint.starts_with("http://");
+                                self.invalid_endpoint = match valid {
+                                    true => None,
+                                    false => Some("Endpoint must be an http(s) URL".to_string()),
+                                };
+                                if valid {
+                                    self.enabled = true;
+                                    set_endpoint(Some(self.endpoint.clone()));
+                                }
+                            }
+                        }
+                    }
+                });
+            });
+    }
+}
+
+pub fn set_endpoint(endpoint: Option<String>) {
+    if let Ok(mut current) = ENDPOINT.lock() {
+        *current = endpoint;
+    }
+}
+
+/// Sends a report in the background if reporting is on.
+pub fn report(kind: &'static str, message: &str) {
+    if let Some(endpoint) = endpoint() {
+        let message = message.to_string();
+        thread::spawn(move || send(&endpoint, kind, &message));
+    }
+}
+
+/// Reports panics before the default hook prints them.
+pub fn install_panic_hook() {
+    let default_hook = panic::take_hook();
+    panic::set_hook(Box::new(move |info| {
+        // the process is going down, so send from this thread
+        if let Some(endpoint) = endpoint() {
+            send(&endpoint, "panic", &info.to_string());
+        }
+        default_hook(info);
+    }));
+}
+
+fn endpoint() -> Option<String> {
+    ENDPOINT.lock().ok().and_then(|endpoint| endpoint.clone())
+}
+
+fn send(endpoint: &str, kind: &str, message: &str) {
+    let report = json!({
+        "version": env!("CARGO_PKG_VERSION"),
+        "os": env::consts::OS,
+        "arch": env::consts::ARCH,
+        "kind": kind,
+        "message": redact(message),
+    });
+    // a report that can't be delivered is dropped, reporting it would loop
+    let _ = ureq::post(endpoint)
+        .timeout(Duration::from_secs(5))
+        .set("Content-Type", "application/json")
+        .send_string(&report.to_string());
+}
@@ -0,0 +1,43 @@
+use chrono::{DateTime, Local};
+use eframe::egui::{RichText, ScrollArea, TextStyle, Ui};
+use eframe::epaint::Color32;
+use std::collections::VecDeque;
+
+/// Entries kept, older ones are dropped.
+const MAX_ENTRIES: usize = 200;
+
+/// What happened on the connection to Discord, shown by the developer layout.
+#[derive(Default)]
+pub struct EventLog {
+    pub entries: VecDeque<(DateTime<Local>, String)>,
+}
+
+impl EventLog {
+    pub fn add(&mut self, entry: impl Into<String>) {
+        self.entries.push_back((Local::now(), entry.into()));
+        if self.entries.len() > MAX_ENTRIES {
+            self.entries.pop_front();
+        }
+    }
+
+    pub fn run(&self, ui: &mut Ui) {
+        ScrollArea::vertical()
+            .id_source("event_log")
+            .stick_to_bottom(true)
+            .show(ui, |ui| {
+                if self.entries.is_empty() {
+                    ui.label("Nothing happened yet");
+                }
+                for (time, entry) in self.entries.iter() {
+                    ui.horizontal_wrapped(|ui| {
+                        ui.label(
+                            RichText::new(time.format("%H:%M:%S").to_string())
+                                .text_style(TextStyle::Monospace)
+                                .color(Color32::from_rgb(163, 163, 163)),
+                        );
+                        ui.label(entry);
+                    });
+                }
+            });
+    }
+}
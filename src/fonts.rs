@@ -0,0 +1,114 @@
+use eframe::egui::{
+    self, style::default_text_styles, FontData, FontDefinitions, FontFamily, FontId,
+};
+use std::fs;
+use std::sync::OnceLock;
+
+/// Body text size egui uses by default, a custom size scales every text style from it.
+pub const DEFAULT_FONT_SIZE: f32 = 14.0;
+
+/// System fonts tried as fallbacks for scripts egui's bundled fonts don't
+/// cover (CJK, Arabic, ...). Every font that exists is added, in this order.
+#[cfg(target_os = "windows")]
+const FALLBACK_FONTS: &[&str] = &[
+    "C:\\Windows\\Fonts\\msyh.ttc",
+    "C:\\Windows\\Fonts\\YuGothM.ttc",
+    "C:\\Windows\\Fonts\\malgun.ttf",
+    "C:\\Windows\\Fonts\\arial.ttf",
+    "C:\\Windows\\Fonts\\seguisym.ttf",
+];
+#[cfg(target_os = "macos")]
+const FALLBACK_FONTS: &[&str] = &[
+    "/System/Library/Fonts/PingFang.ttc",
+    "/System/Library/Fonts/Supplemental/Arial Unicode.ttf",
+    "/System/Library/Fonts/GeezaPro.ttc",
+];
+#[cfg(not(any(target_os = "windows", target_os = "macos")))]
+const FALLBACK_FONTS: &[&str] = &[
+    "/usr/share/fonts/opentype/noto/NotoSansCJK-Regular.ttc",
+    "/usr/share/fonts/noto-cjk/NotoSansCJK-Regular.ttc",
+    "/usr/share/fonts/google-noto-cjk/NotoSansCJK-Regular.ttc",
+    "/usr/share/fonts/truetype/wqy/wqy-microhei.ttc",
+    "/usr/share/fonts/truetype/noto/NotoSansArabic-Regular.ttf",
+    "/usr/share/fonts/noto/NotoSansArabic-Regular.ttf",
+    "/usr/share/fonts/truetype/dejavu/DejaVuSans.ttf",
+    "/usr/share/fonts/TTF/DejaVuSans.ttf",
+];
+
+/// The fallback fonts that exist, read once since CJK fonts are tens of
+/// megabytes and the fonts are set up again when a custom one is picked.
+fn fallback_fonts() -> &'static [(&'static str, Vec<u8>)] {
+    static FONTS: OnceLock<Vec<(&'static str, Vec<u8>)>> = OnceLock::new();
+    FONTS.get_or_init(|| {
+        FALLBACK_FONTS
+            .iter()
+            .filter_map(|path| fs::read(path).ok().map(|bytes| (*path, bytes)))
+            .collect()
+    })
+}
+
+/// Installs the fonts, with `custom_font` (a font file path) used before the
+/// bundled ones when set. Falls back to the defaults if it can't be read.
+pub fn setup_fonts(ctx: &egui::Context, custom_font: &str) -> Result<(), String> {
+    let mut fonts = FontDefinitions::default();
+    fonts.font_data.insert(
+        "Emoji".to_owned(),
+        FontData::from_static(include_bytes!("../assets/fonts/TwitterColorEmoji.ttf")),
+    );
+    fonts
+        .families
+        .entry(FontFamily::Proportional)
+        .or_default()
+        .insert(0, "Emoji".to_owned());
+
+    for (path, bytes) in fallback_fonts() {
+        let name = path.to_string();
+        fonts
+            .font_data
+            .insert(name.clone(), FontData::from_static(bytes));
+        for family in [FontFamily::Proportional, FontFamily::Monospace] {
+            fonts.families.entry(family).or_default().push(name.clone());
+        }
+    }
+
+    let mut result = Ok(());
+    if !custom_font.is_empty() {
+        match fs::read(custom_font) {
+            // egui panics on files that aren't fonts, so check the magic number first
+            Ok(bytes) if looks_like_font(&bytes) => {
+                fonts
+                    .font_data
+                    .insert("Custom".to_owned(), FontData::from_owned(bytes));
+                fonts
+                    .families
+                    .entry(FontFamily::Proportional)
+                    .or_default()
+                    .insert(1, "Custom".to_owned());
+            }
+            _ => result = Err(format!("Failed to load font {custom_font}")),
+        }
+    }
+
+    ctx.set_fonts(fonts);
+    result
+}
+
+fn looks_like_font(bytes: &[u8]) -> bool {
+    matches!(
+        bytes.get(..4),
+        Some([0, 1, 0, 0]) | Some(b"OTTO") | Some(b"true") | Some(b"ttcf")
+    )
+}
+
+/// Scales all text styles so body text has the given size.
+pub fn set_font_size(ctx: &egui::Context, size: f32) {
+    let mut style = (*ctx.style()).clone();
+    style.text_styles = default_text_styles()
+        .into_iter()
+        .map(|(text_style, font)| {
+            let size = font.size * size / DEFAULT_FONT_SIZE;
+            (text_style, FontId::new(size, font.family))
+        })
+        .collect();
+    ctx.set_style(style);
+}
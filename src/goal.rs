@@ -0,0 +1,160 @@
+use derivative::Derivative;
+use eframe::egui::{self, Context, DragValue, Key};
+use serde::{Deserialize, Serialize};
+
+/// A change to the goal's value, from the hotkeys or the command line.
+pub enum Change {
+    Step,
+    Add(f64),
+    Set(f64),
+}
+
+impl Change {
+    /// Reads `+2` or `-1` as a change by that much, `37` as the new value and
+    /// nothing as one step. `inf` and `NaN` aren't numbers a goal can reach.
+    pub fn parse(text: &str) -> Option<Self> {
+        let text = text.trim();
+        let number = |text: &str| text.parse().ok().filter(|number: &f64| number.is_finite());
+        match text.chars().next() {
+            None => Some(Change::Step),
+            Some('+') => number(&text[1..]).map(Change::Add),
+            Some('-') => number(text).map(Change::Add),
+            Some(_) => number(text).map(Change::Set),
+        }
+    }
+}
+
+/// Progress towards a goal like "hours practiced: 37/100", shown through the
+/// `{goal}`, `{goal_pct}` and `{goal_bar}` placeholders.
+#[derive(Serialize, Deserialize, Derivative, Clone)]
+#[derivative(Default)]
+#[serde(default)]
+pub struct Goal {
+    #[serde(skip)]
+    pub open: bool,
+    pub name: String,
+    pub current: f64,
+    #[derivative(Default(value = "100."))]
+    pub target: f64,
+    /// How much the hotkeys change the value by.
+    #[derivative(Default(value = "1."))]
+    pub step: f64,
+    /// Set when the value changed, so the presence can be updated.
+    #[serde(skip)]
+    pub changed: bool,
+}
+
+impl Goal {
+    pub fn apply(&mut self, change: Change) {
+        self.current = match change {
+            Change::Step => self.current + self.step,
+            Change::Add(amount) => self.current + amount,
+            Change::Set(value) => value,
+        }
+        .max(0.);
+        self.changed = true;
+    }
+
+    /// `37/100`, to two decimals at most so steps like 0.1 don't add up to
+    /// 0.30000000000000004.
+    pub fn progress(&self) -> String {
+        format!("{}/{}", number(self.current), number(self.target))
+    }
+
+    pub fn percent(&self) -> f64 {
+        match self.target > 0. {
+            true => (self.current / self.target * 100.).min(100.),
+            false => 0.,
+        }
+    }
+
+    /// Shows the window, `locked` while the display lock is on keeps the
+    /// hotkeys from changing the value.
+    pub fn run(&mut self, ctx: &Context, locked: bool) {
+        let mut changed = false;
+        let mut open = self.open;
+        egui::containers::Window::new("Goal")
+            .open(&mut open)
+            .resizable(false)
+            .show(ctx, |ui| {
+                egui::Grid::new("goal").show(ui, |ui| {
+                    ui.label("Name");
+                    ui.text_edit_singleline(&mut self.name);
+                    ui.end_row();
+                    ui.label("Progress");
+                    ui.horizontal(|ui| {
+                        changed |= ui
+                            .add(DragValue::new(&mut self.current).clamp_range(0..=u32::MAX))
+                            .changed();
+                        ui.label("of");
+                        changed |= ui
+                            .add(DragValue::new(&mut self.target).clamp_range(1..=u32::MAX))
+                            .changed();
+                    });
+                    ui.end_row();
+                    ui.label("Step");
+                    ui.add(DragValue::new(&mut self.step).clamp_range(0.01..=1000.));
+                    ui.end_row();
+                });
+                ui.label(format!("{:.0}% done", self.percent()));
+                ui.small("Ctrl + ↑ and Ctrl + ↓ change it by one step");
+            });
+        self.open = open;
+        if changed {
+            self.changed = true;
+        }
+
+        // the arrows move the cursor while typing
+        if locked || ctx.wants_keyboard_input() {
+            return;
+        }
+        let input = ctx.input();
+        let (up, down) = (
+            input.modifiers.ctrl && input.key_pressed(Key::ArrowUp),
+            input.modifiers.ctrl && input.key_pressed(Key::ArrowDown),
+        );
+        drop(input);
+        if up {
+            self.apply(Change::Step);
+        }
+        if down {
+            self.apply(Change::Add(-self.step));
+        }
+    }
+}
+
+fn number(value: f64) -> String {
+    let text = format!("{value:.2}");
+    text.trim_end_matches('0').trim_end_matches('.').to_string()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{number, Change};
+
+    #[test]
+    fn parses_changes() {
+        assert!(matches!(Change::parse(""), Some(Change::Step)));
+        assert!(matches!(Change::parse("  "), Some(Change::Step)));
+        assert!(matches!(Change::parse("+2"), Some(Change::Add(amount)) if amount == 2.));
+        assert!(matches!(Change::parse("-1.5"), Some(Change::Add(amount)) if amount == -1.5));
+        assert!(matches!(Change::parse(" 37 "), Some(Change::Set(value)) if value == 37.));
+    }
+
+    #[test]
+    fn rejects_what_isnt_a_number() {
+        assert!(Change::parse("two").is_none());
+        assert!(Change::parse("+").is_none());
+        assert!(Change::parse("inf").is_none());
+        assert!(Change::parse("+inf").is_none());
+        assert!(Change::parse("NaN").is_none());
+    }
+
+    #[test]
+    fn trims_trailing_zeros() {
+        assert_eq!(number(0.), "0");
+        assert_eq!(number(100.), "100");
+        assert_eq!(number(10.5), "10.5");
+        assert_eq!(number(1. / 3.), "0.33");
+    }
+}
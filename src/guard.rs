@@ -0,0 +1,126 @@
+use crate::rules::Field;
+use derivative::Derivative;
+use eframe::egui::{TextEdit, Ui};
+use regex::Regex;
+use serde_json::Value;
+
+const EMAIL: &str = r"[\w.+-]+@[\w-]+\.[\w.-]*\w";
+const PHONE: &str = r"\+?\(?\d[\d\s().-]{7,}\d";
+/// Dates have as many digits as a phone number, they're taken out first.
+const DATE: &str = r"\d{4}-\d{2}-\d{2}|\d{1,2}[./]\d{1,2}[./]\d{2,4}";
+
+/// Warns before personal information or flagged words go out to everyone
+/// on the friends list. Unlike the validation rules it doesn't block.
+#[derive(Derivative)]
+#[derivative(Default)]
+pub struct Guard {
+    #[derivative(Default(value = "true"))]
+    pub enabled: bool,
+    /// One word or phrase per line, matched regardless of case.
+    pub flagged_words: String,
+    #[derivative(Default(value = "Regex::new(EMAIL).unwrap()"))]
+    email: Regex,
+    #[derivative(Default(value = "Regex::new(PHONE).unwrap()"))]
+    phone: Regex,
+    #[derivative(Default(value = "Regex::new(DATE).unwrap()"))]
+    date: Regex,
+}
+
+impl Guard {
+    pub fn new(enabled: bool, flagged_words: String) -> Self {
+        Self {
+            enabled,
+            flagged_words,
+            ..Default::default()
+        }
+    }
+
+    pub fn run(&mut self, ui: &mut Ui) {
+        ui.checkbox(&mut self.enabled, "Warn about emails and phone numbers");
+        ui.label("Also warn about, one per line");
+        ui.add(
+            TextEdit::multiline(&mut self.flagged_words)
+                .password(true)
+                .desired_rows(3),
+        );
+    }
+
+    /// What in the payload looks private, empty when nothing does.
+    pub fn check(&self, payload: &Value) -> Vec<String> {
+        if !self.enabled {
+            return Vec::new();
+        }
+        let mut warnings = vec![];
+        let texts = [
+            Field::Details,
+            Field::State,
+            Field::ImageLabels,
+            Field::ButtonLabels,
+        ]
+        .into_iter()
+        .flat_map(|field| field.texts(payload));
+        for (name, text) in texts {
+            if self.email.is_match(&text) {
+                warnings.push(format!("{name} looks like it contains an email address"));
+            }
+            let without_dates = self.date.replace_all(&text, " ");
+            let phone = self.phone.find_iter(&without_dates).any(|found| {
+                let digits = found.as_str().chars().filter(char::is_ascii_digit).count();
+                (9..=15).contains(&digits)
+            });
+            if phone {
+                warnings.push(format!("{name} looks like it contains a phone number"));
+            }
+            let lower = text.to_lowercase();
+            let flagged = self
+                .flagged_words
+                .lines()
+                .map(str::trim)
+                .any(|word| !word.is_empty() && lower.contains(&word.to_lowercase()));
+            if flagged {
+                warnings.push(format!("{name} contains a flagged word"));
+            }
+        }
+        warnings
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json::json;
+
+    fn warnings(details: &str) -> Vec<String> {
+        Guard::new(true, "secret project\n".to_string()).check(&json!({ "details": details }))
+    }
+
+    #[test]
+    fn warns_about_emails_and_phone_numbers() {
+        assert_eq!(
+            warnings("Mail me at jane.doe+dp@example.co.uk"),
+            ["Details looks like it contains an email address"]
+        );
+        assert_eq!(
+            warnings("Call +1 (555) 123-4567"),
+            ["Details looks like it contains a phone number"]
+        );
+    }
+
+    #[test]
+    fn leaves_dates_and_short_numbers_alone() {
+        assert!(warnings("Tournament on 2024-05-01, round 12 of 64").is_empty());
+        assert!(warnings("Since 01.05.2024 at 18:00").is_empty());
+    }
+
+    #[test]
+    fn warns_about_flagged_words() {
+        assert_eq!(
+            warnings("Working on the Secret Project"),
+            ["Details contains a flagged word"]
+        );
+        let off = Guard::new(false, "secret project".to_string());
+        assert!(off
+            .check(&json!({ "details": "secret project" }))
+            .is_empty());
+    }
+}
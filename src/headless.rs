@@ -0,0 +1,64 @@
+use crate::ipc;
+use crate::preset_manager;
+use crate::storage::{saved, Storage};
+use crate::App;
+use eframe::egui;
+use serde_json::from_str;
+use std::thread;
+use std::time::Duration;
+
+/// How often the app state is checked, the same work a frame does.
+const TICK: Duration = Duration::from_millis(250);
+
+/// Runs without a window: loads the saved settings, connects and sets the
+/// saved presence, then keeps it up until the process is stopped.
+/// Errors and connection changes are printed instead of shown.
+pub fn run() {
+    let saved = saved();
+    let storage: Storage = match saved.get("settings").map(|settings| from_str(settings)) {
+        Some(Ok(storage)) => storage,
+        _ => Storage::default(),
+    };
+    if storage.id.is_empty() {
+        eprintln!("No application ID saved, set one in the app first");
+        return;
+    }
+    let presets = match preset_manager::load() {
+        Some((presets, errors)) => {
+            for error in errors {
+                eprintln!("{error}");
+            }
+            presets
+        }
+        None => saved.get("presets").cloned().unwrap_or_default(),
+    };
+    let mut app = App::from_storage(storage, presets, false);
+    let ctx = egui::Context::default();
+    app.apply_config(&ctx);
+    if !app.connected && !app.worker.connecting() && !app.waiting_for_discord {
+        match ipc::discord_running() {
+            true => app.connect(),
+            false => {
+                println!("Waiting for Discord to start");
+                app.waiting_for_discord = true;
+            }
+        }
+    }
+
+    let mut connected = false;
+    loop {
+        app.tick(&ctx);
+        for error in app.error_bar.history.drain(..) {
+            eprintln!("{error}");
+        }
+        if app.connected != connected {
+            connected = app.connected;
+            match (connected, &app.user) {
+                (true, Some(user)) => println!("Connected as {user}"),
+                (true, None) => println!("Connected"),
+                (false, _) => println!("Disconnected"),
+            }
+        }
+        thread::sleep(TICK);
+    }
+}
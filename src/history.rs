@@ -0,0 +1,28 @@
+use crate::preset::Preset;
+
+/// Texts remembered per field.
+const MAX_ENTRIES: usize = 50;
+
+/// Details and states sent before, most recent first, offered while typing.
+#[derive(Default)]
+pub struct History {
+    pub details: Vec<String>,
+    pub state: Vec<String>,
+}
+
+impl History {
+    /// Remembers the texts of a presence Discord accepted, with their
+    /// placeholders so they stay up to date when picked again.
+    pub fn add(&mut self, sent: &Preset) {
+        for (list, text) in [
+            (&mut self.details, &sent.Details),
+            (&mut self.state, &sent.State),
+        ] {
+            if let Some(text) = text.as_deref().filter(|text| !text.is_empty()) {
+                list.retain(|entry| entry != text);
+                list.insert(0, text.to_string());
+                list.truncate(MAX_ENTRIES);
+            }
+        }
+    }
+}
@@ -0,0 +1,105 @@
+use crate::platform::spawn_detached;
+use eframe::egui::{self, Context, RichText, TextEdit};
+use eframe::epaint::Color32;
+use std::collections::HashMap;
+use std::process::Command;
+
+#[derive(Clone, Copy, PartialEq, Eq)]
+pub enum Hook {
+    Connected,
+    Disconnected,
+    UpdateSent,
+    UpdateFailed,
+    PresetApplied,
+}
+
+const HOOKS: [Hook; 5] = [
+    Hook::Connected,
+    Hook::Disconnected,
+    Hook::UpdateSent,
+    Hook::UpdateFailed,
+    Hook::PresetApplied,
+];
+
+impl Hook {
+    /// Name in the settings and in `DISCORD_PRESENCE_EVENT`.
+    pub fn name(self) -> &'static str {
+        match self {
+            Hook::Connected => "connected",
+            Hook::Disconnected => "disconnected",
+            Hook::UpdateSent => "update-sent",
+            Hook::UpdateFailed => "update-failed",
+            Hook::PresetApplied => "preset-applied",
+        }
+    }
+}
+
+/// Commands the user wants run when something happens on the connection,
+/// for side effects the app has no integration for.
+#[derive(Default)]
+pub struct Hooks {
+    pub open: bool,
+    /// Shell command by hook name, empty ones are skipped.
+    pub commands: HashMap<String, String>,
+}
+
+impl Hooks {
+    pub fn new(commands: HashMap<String, String>) -> Self {
+        Self {
+            commands,
+            ..Default::default()
+        }
+    }
+
+    pub fn run(&mut self, ctx: &Context) {
+        let mut open = self.open;
+        egui::containers::Window::new("Hooks")
+            .open(&mut open)
+            .resizable(false)
+            .show(ctx, |ui| {
+                ui.label("Run a command when");
+                egui::Grid::new("hooks").show(ui, |ui| {
+                    for hook in HOOKS {
+                        ui.label(hook.name());
+                        let command = self.commands.entry(hook.name().to_string()).or_default();
+                        ui.add(TextEdit::singleline(command).desired_width(250.));
+                        ui.end_row();
+                    }
+                });
+                ui.label(
+                    RichText::new(
+                        "Commands get DISCORD_PRESENCE_EVENT, _DETAILS, _STATE and _ERROR \
+                         in their environment",
+                    )
+                    .small()
+                    .color(Color32::from_rgb(163, 163, 163)),
+                );
+            });
+        self.open = open;
+    }
+
+    /// Starts the hook's command without waiting for it. `values` are put
+    /// in the environment as `DISCORD_PRESENCE_<NAME>`.
+    pub fn fire(&self, hook: Hook, values: &[(&str, &str)]) -> Result<(), String> {
+        let command = match self.commands.get(hook.name()).map(|command| command.trim()) {
+            Some(command) if !command.is_empty() => command,
+            _ => return Ok(()),
+        };
+        let mut shell = match cfg!(windows) {
+            true => Command::new("cmd"),
+            false => Command::new("sh"),
+        };
+        shell
+            .arg(match cfg!(windows) {
+                true => "/C",
+                false => "-c",
+            })
+            .arg(command)
+            .env("DISCORD_PRESENCE_EVENT", hook.name());
+        for (name, value) in values {
+            shell.env(format!("DISCORD_PRESENCE_{name}"), value);
+        }
+        spawn_detached(&mut shell)
+            .map_err(|err| format!("Failed to run the {} hook: {err}", hook.name()))
+    }
+}
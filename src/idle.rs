@@ -0,0 +1,196 @@
+use crate::preset::Preset;
+use chrono::{DateTime, Duration, Utc};
+use derivative::Derivative;
+use eframe::egui::{self, DragValue, RichText, Ui};
+use eframe::epaint::Color32;
+use std::sync::mpsc::{channel, Receiver};
+use std::thread;
+
+/// Seconds between two looks at the idle time.
+const CHECK_INTERVAL: i64 = 5;
+
+/// What changed since the last check.
+pub enum Change {
+    Away,
+    Back,
+}
+
+/// Notices when nobody used mouse or keyboard for a while. The app then
+/// clears the presence or loads the AFK preset, and restores what was shown
+/// once input comes back.
+#[derive(Derivative)]
+#[derivative(Default)]
+pub struct Idle {
+    pub enabled: bool,
+    #[derivative(Default(value = "10"))]
+    pub minutes: u32,
+    /// In-app preset loaded while away, the presence is cleared when empty.
+    pub afk_preset: String,
+    /// What was shown before going away, restored when back.
+    pub previous: Option<Preset>,
+    #[derivative(Default(value = "Utc::now()"))]
+    next_check: DateTime<Utc>,
+    /// Seconds since the last input, `None` when the system doesn't tell.
+    seconds: Option<u64>,
+    /// Whether the idle time was read once, so it's known if it can be.
+    read: bool,
+    /// The idle time being read, asking another program can take seconds.
+    reading: Option<Receiver<Option<u64>>>,
+}
+
+impl Idle {
+    pub fn new(enabled: bool, minutes: u32, afk_preset: String) -> Self {
+        Self {
+            enabled,
+            minutes,
+            afk_preset,
+            ..Default::default()
+        }
+    }
+
+    pub fn run(&mut self, ui: &mut Ui, presets: &[String]) {
+        ui.checkbox(&mut self.enabled, "Detect when I'm away");
+        ui.horizontal(|ui| {
+            ui.label("Away after");
+            ui.add(DragValue::new(&mut self.minutes).clamp_range(1..=240));
+            ui.label("minutes");
+        });
+        let selected = match self.afk_preset.is_empty() {
+            true => "Clear the presence",
+            false => self.afk_preset.as_str(),
+        };
+        egui::ComboBox::from_label("while away")
+            .selected_text(selected)
+            .show_ui(ui, |ui| {
+                ui.selectable_value(&mut self.afk_preset, String::new(), "Clear the presence");
+                for name in presets {
+                    ui.selectable_value(&mut self.afk_preset, name.clone(), name);
+                }
+            });
+        if self.enabled && self.read && self.seconds.is_none() {
+            ui.label(
+                RichText::new("The idle time can't be read on this system")
+                    .small()
+                    .color(Color32::LIGHT_RED),
+            );
+        }
+    }
+
+    /// Whether the app is showing the away state.
+    pub fn away(&self) -> bool {
+        self.previous.is_some()
+    }
+
+    /// Looks at the idle time every few seconds, says when the user left or came back.
+    pub fn poll(&mut self) -> Option<Change> {
+        if !self.enabled {
+            // turning detection off while away still brings the presence back
+            return self.away().then_some(Change::Back);
+        }
+        if self.reading.is_none() && self.next_check <= Utc::now() {
+            let (sender, receiver) = channel();
+            thread::spawn(move || sender.send(idle_seconds()));
+            self.reading = Some(receiver);
+        }
+        self.seconds = self.reading.as_ref()?.try_recv().ok()?;
+        self.reading = None;
+        self.read = true;
+        self.next_check = Utc::now() + Duration::seconds(CHECK_INTERVAL);
+        let idle = self.seconds? >= self.minutes as u64 * 60;
+        match (idle, self.away()) {
+            (true, false) => Some(Change::Away),
+            (false, true) => Some(Change::Back),
+            _ => None,
+        }
+    }
+}
+
+#[cfg(windows)]
+fn idle_seconds() -> Option<u64> {
+    #[repr(C)]
+    struct LastInputInfo {
+        size: u32,
+        time: u32,
+    }
+    #[link(name = "user32")]
+    extern "system" {
+        fn GetLastInputInfo(info: *mut LastInputInfo) -> i32;
+    }
+    #[link(name = "kernel32")]
+    extern "system" {
+        fn GetTickCount() -> u32;
+    }
+    let mut info = LastInputInfo {
+        size: std::mem::size_of::<LastInputInfo>() as u32,
+        time: 0,
+    };
+    // SAFETY: `info` is a LASTINPUTINFO with its size set, as the call expects
+    let (read, now) = unsafe { (GetLastInputInfo(&mut info), GetTickCount()) };
+    match read {
+        0 => None,
+        _ => Some(now.wrapping_sub(info.time) as u64 / 1000),
+    }
+}
+
+#[cfg(target_os = "macos")]
+fn idle_seconds() -> Option<u64> {
+    #[link(name = "CoreGraphics", kind = "framework")]
+    extern "C" {
+        fn CGEventSourceSecondsSinceLastEventType(state: i32, event_type: u32) -> f64;
+    }
+    // combined session state, any input event
+    // SAFETY: plain values in and out, the call has no preconditions
+    let seconds = unsafe { CGEventSourceSecondsSinceLastEventType(0, u32::MAX) };
+    Some(seconds as u64)
+}
+
+/// Asks `xprintidle` on X11, or GNOME's idle monitor, which also works on Wayland.
+#[cfg(all(unix, not(target_os = "macos")))]
+fn idle_seconds() -> Option<u64> {
+    use std::process::Command;
+
+    let output = |program: &str, args: &[&str]| {
+        Command::new(program)
+            .args(args)
+            .output()
+            .ok()
+            .filter(|output| output.status.success())
+            .map(|output| String::from_utf8_lossy(&output.stdout).to_string())
+    };
+    if let Some(millis) = output("xprintidle", &[]) {
+        return millis
+            .trim()
+            .parse::<u64>()
+            .ok()
+            .map(|millis| millis / 1000);
+    }
+    // answers like "(uint64 12345,)"
+    let answer = output(
+        "gdbus",
+        &[
+            "call",
+            "--session",
+            "--dest",
+            "org.gnome.Mutter.IdleMonitor",
+            "--object-path",
+            "/org/gnome/Mutter/IdleMonitor/Core",
+            "--method",
+            "org.gnome.Mutter.IdleMonitor.GetIdletime",
+            "--timeout",
+            "2",
+        ],
+    )?;
+    answer
+        .trim()
+        .trim_start_matches("(uint64")
+        .trim_end_matches(",)")
+        .trim()
+        .parse::<u64>()
+        .ok()
+        .map(|millis| millis / 1000)
+}
+
+#[cfg(not(any(unix, windows)))]
+fn idle_seconds() -> Option<u64> {
+    None
+}
@@ -1,13 +1,27 @@
-use eframe::egui::Ui;
+use crate::autocomplete::suggest;
+use crate::limits;
+use eframe::egui::{RichText, Ui};
+use eframe::epaint::Color32;
+
+/// Discord only accepts a single hover text, a second line gets appended to it.
+const LINE_SEPARATOR: &str = " — ";
+/// Discord's media proxy, links to it are sent as `mp:` keys.
+const MEDIA_PROXY: &str = "https://media.discordapp.net/";
+/// Longest image link Discord accepts.
+const MAX_URL_LEN: usize = 256;
 
 #[derive(Default)]
 pub struct Image {
     pub key: String,
     pub text: String,
+    /// Extra hover text line, the field is only shown when this is `Some`.
+    pub second_line: Option<String>,
 }
 
 impl Image {
-    pub fn run(&mut self, ui: &mut Ui, label: &str) {
+    /// Shows the fields, `assets` are the keys uploaded to the application
+    /// and are offered while typing the key.
+    pub fn run(&mut self, ui: &mut Ui, label: &str, assets: &[String]) {
         ui.vertical(|ui| {
             ui.set_max_width(200.);
             ui.horizontal(|ui| {
@@ -16,13 +30,103 @@ impl Image {
             });
             ui.horizontal(|ui| {
                 ui.label("Label");
-                ui.text_edit_singleline(&mut self.text)
+                ui.text_edit_singleline(&mut self.text);
+                if self.second_line.is_none() {
+                    limits::LARGE_TEXT.counter(ui, &self.text);
+                }
             });
+            if let Some(second_line) = &mut self.second_line {
+                ui.horizontal(|ui| {
+                    ui.label("Line 2");
+                    ui.text_edit_singleline(second_line);
+                });
+                ui.horizontal(|ui| {
+                    ui.label(
+                        RichText::new("Sent together, cut to 128")
+                            .small()
+                            .color(Color32::from_rgb(163, 163, 163)),
+                    );
+                    limits::LARGE_TEXT.counter(ui, &self.hover_text());
+                });
+            }
             ui.horizontal(|ui| {
                 ui.label("Key");
                 ui.add_space(9.);
-                ui.text_edit_singleline(&mut self.key)
-            })
+                let response = ui.text_edit_singleline(&mut self.key);
+                suggest(ui, &response, &mut self.key, assets);
+            });
+            match check_key(&self.key) {
+                Err(problem) => {
+                    ui.label(RichText::new(problem).color(Color32::LIGHT_RED).small());
+                }
+                Ok(_) if self.key.starts_with("https://") => {
+                    ui.label(
+                        RichText::new("External image, Discord proxies it")
+                            .color(Color32::from_rgb(163, 163, 163))
+                            .small(),
+                    );
+                }
+                Ok(_) => (),
+            }
         });
     }
+
+    /// Hover text as sent to Discord, both lines joined and cut to the 128
+    /// characters Discord allows.
+    pub fn hover_text(&self) -> String {
+        hover_text(&self.text, self.second_line.as_deref().unwrap_or_default())
+    }
+}
+
+/// The two lines of a hover text joined the way Discord gets them.
+pub fn hover_text(text: &str, second_line: &str) -> String {
+    let text = match (text.is_empty(), second_line.is_empty()) {
+        (_, true) => text.to_string(),
+        (true, false) => second_line.to_string(),
+        (false, false) => format!("{text}{LINE_SEPARATOR}{second_line}"),
+    };
+    text.chars().take(128).collect()
+}
+
+/// The key as it's sent to Discord. Links to Discord's media proxy become
+/// the `mp:` keys it uses for them, other https links are proxied by Discord
+/// when the presence is set and the key then takes the `mp:` form it answers
+/// with.
+pub fn send_key(key: &str) -> String {
+    match key.trim().strip_prefix(MEDIA_PROXY) {
+        Some(path) => format!("mp:{path}"),
+        None => key.trim().to_string(),
+    }
+}
+
+/// Checks an image key, asset names are checked against the application by
+/// the self-check.
+pub fn check_key(key: &str) -> Result<(), String> {
+    let key = key.trim();
+    if key.starts_with("http://") {
+        return Err("Discord only shows images from https links".to_string());
+    }
+    if key.starts_with("https://") {
+        if key.chars().any(char::is_whitespace) {
+            return Err("Image link must not contain spaces".to_string());
+        }
+        if send_key(key).chars().count() > MAX_URL_LEN {
+            return Err(format!(
+                "Image link must be at most {MAX_URL_LEN} characters"
+            ));
+        }
+        return Ok(());
+    }
+    if key.starts_with("mp:") {
+        return match key.chars().count() > MAX_URL_LEN {
+            true => Err(format!(
+                "Image link must be at most {MAX_URL_LEN} characters"
+            )),
+            false => Ok(()),
+        };
+    }
+    match key.chars().count() > 128 {
+        true => Err("Key must be at most 128 characters".to_string()),
+        false => Ok(()),
+    }
 }
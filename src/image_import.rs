@@ -0,0 +1,201 @@
+use crate::storage::data_dir;
+use eframe::egui::{self, Context, RichText};
+use eframe::epaint::Color32;
+use sha2::{Digest, Sha256};
+use std::sync::mpsc::{channel, Receiver};
+use std::time::Duration;
+use std::{fs, io::Read, path::PathBuf, thread};
+
+/// Largest image that will be downloaded, Discord rejects bigger assets anyway.
+const MAX_IMAGE_SIZE: u64 = 10 * 1024 * 1024;
+
+/// Asset keys and the files downloaded for them.
+type Cached = Vec<(String, PathBuf)>;
+
+/// Window offered after importing a preset whose image keys are external URLs.
+/// Downloads them into the local cache so they can be uploaded as assets of
+/// the user's own application.
+#[derive(Default)]
+pub struct ImageImport {
+    pub open: bool,
+    pub id: String,
+    pub urls: Vec<String>,
+    cached: Cached,
+    downloading: Option<Receiver<Result<Cached, String>>>,
+    error: Option<String>,
+    /// (url, asset key) pairs the form should swap once the assets are uploaded.
+    pub renamed: Vec<(String, String)>,
+}
+
+impl ImageImport {
+    pub fn offer(&mut self, id: Option<&String>, keys: [Option<&String>; 2]) {
+        self.urls = keys
+            .into_iter()
+            .flatten()
+            .filter(|key| key.starts_with("https://") || key.starts_with("http://"))
+            .cloned()
+            .collect();
+        self.id = id.cloned().unwrap_or_default();
+        self.cached = Vec::new();
+        self.downloading = None;
+        self.error = None;
+        self.open = !self.urls.is_empty();
+    }
+
+    pub fn run(&mut self, ctx: &Context) {
+        if let Some(Ok(done)) = self.downloading.as_ref().map(Receiver::try_recv) {
+            match done {
+                Ok(cached) => self.cached = cached,
+                Err(error) => self.error = Some(error),
+            }
+            self.downloading = None;
+        }
+        let mut open = self.open;
+        egui::containers::Window::new("Preset Images")
+            .open(&mut open)
+            .resizable(false)
+            .show(ctx, |ui| {
+                ui.label("This preset uses images from the internet:");
+                for url in self.urls.iter() {
+                    ui.label(RichText::new(url).color(Color32::from_rgb(163, 163, 163)));
+                }
+                ui.add_space(5.);
+                if self.downloading.is_some() {
+                    ui.horizontal(|ui| {
+                        ui.spinner();
+                        ui.label("Downloading…");
+                    });
+                    ctx.request_repaint();
+                } else if self.cached.is_empty() {
+                    if ui.button("Download to cache").clicked() {
+                        self.download();
+                    }
+                } else {
+                    for (key, path) in self.cached.iter() {
+                        ui.label(format!("{key} ➡ {}", path.display()));
+                    }
+                    ui.add_space(5.);
+                    ui.horizontal(|ui| {
+                        if let Some(dir) = image_dir() {
+                            if ui.button("Open folder").clicked() {
+                                ui.output().open_url(format!("file://{}", dir.display()));
+                            }
+                        }
+                        if !self.id.is_empty() && ui.button("Upload as assets").clicked() {
+                            ui.output().open_url(format!(
+                                "https://discord.com/developers/applications/{}/rich-presence/assets",
+                                self.id
+                            ));
+                        }
+                    });
+                    ui.label("Upload the files using their names as keys, then:");
+                    if ui.button("Use uploaded keys").clicked() {
+                        self.renamed = self
+                            .cached
+                            .iter()
+                            .zip(self.urls.iter())
+                            .map(|((key, _), url)| (url.clone(), key.clone()))
+                            .collect();
+                        self.open = false;
+                    }
+                }
+                if let Some(error) = &self.error {
+                    ui.label(RichText::new(error).color(Color32::LIGHT_RED));
+                }
+            });
+        self.open &= open;
+    }
+
+    /// Downloads the images on a thread, `run` picks up the files.
+    fn download(&mut self) {
+        let urls = self.urls.clone();
+        let (sender, receiver) = channel();
+        thread::spawn(move || {
+            let _ = sender.send(download(&urls));
+        });
+        self.downloading = Some(receiver);
+        self.error = None;
+    }
+}
+
+fn download(urls: &[String]) -> Result<Cached, String> {
+    let dir = image_dir().ok_or_else(|| "No cache directory available".to_string())?;
+    fs::create_dir_all(&dir).map_err(|_| "Failed to create the cache directory".to_string())?;
+    let mut cached = Vec::new();
+    for url in urls {
+        let key = asset_key(url);
+        let bytes = fetch(url)?;
+        let path = dir.join(format!("{key}.{}", extension(url)));
+        fs::write(&path, bytes).map_err(|_| format!("Failed to write {}", path.display()))?;
+        cached.push((key, path));
+    }
+    Ok(cached)
+}
+
+fn image_dir() -> Option<PathBuf> {
+    data_dir().map(|dir| dir.join("images"))
+}
+
+fn fetch(url: &str) -> Result<Vec<u8>, String> {
+    let response = match ureq::get(url).timeout(Duration::from_secs(30)).call() {
+        Ok(response) => response,
+        Err(_) => return Err(format!("Failed to download {url}")),
+    };
+    let mut bytes = Vec::new();
+    // one byte more than allowed tells a big image from one that just fits
+    match response
+        .into_reader()
+        .take(MAX_IMAGE_SIZE + 1)
+        .read_to_end(&mut bytes)
+    {
+        Ok(_) if bytes.len() as u64 > MAX_IMAGE_SIZE => Err(format!(
+            "{url} is bigger than {} MB",
+            MAX_IMAGE_SIZE / 1024 / 1024
+        )),
+        Ok(_) => Ok(bytes),
+        Err(_) => Err(format!("Failed to download {url}")),
+    }
+}
+
+/// Turns the file name in the url into something Discord accepts as asset
+/// key. A hash of the whole url tells apart files with the same name.
+fn asset_key(url: &str) -> String {
+    let name = url
+        .split(['?', '#'])
+        .next()
+        .unwrap_or_default()
+        .rsplit('/')
+        .next()
+        .unwrap_or_default();
+    let stem = name.rsplit_once('.').map_or(name, |(stem, _)| stem);
+    let key: String = stem
+        .to_lowercase()
+        .chars()
+        .map(|c| if c.is_ascii_alphanumeric() { c } else { '_' })
+        .collect();
+    let key = match key.is_empty() {
+        true => "image".to_string(),
+        false => key.chars().take(20).collect(),
+    };
+    let hash: String = Sha256::digest(url.as_bytes())
+        .iter()
+        .take(4)
+        .map(|byte| format!("{byte:02x}"))
+        .collect();
+    format!("{key}_{hash}")
+}
+
+fn extension(url: &str) -> &'static str {
+    let path = url
+        .split(['?', '#'])
+        .next()
+        .unwrap_or_default()
+        .to_lowercase();
+    if path.ends_with(".jpg") || path.ends_with(".jpeg") {
+        "jpg"
+    } else if path.ends_with(".gif") {
+        "gif"
+    } else {
+        "png"
+    }
+}
@@ -0,0 +1,88 @@
+use crate::watcher::Watcher;
+use std::sync::atomic::{AtomicU64, Ordering};
+use std::sync::Mutex;
+use std::time::Duration;
+
+/// Seconds between two polls of the sources.
+const INTERVAL: u64 = 5;
+
+/// What a music player reports as playing.
+#[derive(Clone, Default, PartialEq, Eq)]
+pub struct NowPlaying {
+    pub track: String,
+    pub artist: String,
+    pub album: String,
+    /// https link to the cover, usable as an image key.
+    pub art_url: String,
+}
+
+/// Something that tells what's playing, polled on the integrations thread.
+pub trait Source: Send {
+    fn name(&self) -> &'static str;
+    /// `Ok(None)` when nothing is playing.
+    fn now_playing(&mut self) -> Result<Option<NowPlaying>, String>;
+}
+
+static SOURCES: Mutex<Vec<Box<dyn Source>>> = Mutex::new(Vec::new());
+/// Counts the calls to `set_sources`, a poll's results are dropped when
+/// the sources were replaced while it ran.
+static GENERATION: AtomicU64 = AtomicU64::new(0);
+static NOW_PLAYING: Mutex<Option<NowPlaying>> = Mutex::new(None);
+/// Why the last poll failed, for the settings.
+static ERROR: Mutex<Option<String>> = Mutex::new(None);
+static WATCHER: Watcher = Watcher::new();
+
+/// Replaces the sources, the first one playing something wins.
+pub fn set_sources(sources: Vec<Box<dyn Source>>) {
+    let mut current = SOURCES.lock().unwrap();
+    *current = sources;
+    GENERATION.fetch_add(1, Ordering::Relaxed);
+    *NOW_PLAYING.lock().unwrap() = None;
+    *ERROR.lock().unwrap() = None;
+}
+
+/// The track playing, `None` when nothing is or before the first poll.
+/// Starts polling the sources when they aren't polled yet.
+pub fn now_playing() -> Option<NowPlaying> {
+    WATCHER.ask(Duration::from_secs(INTERVAL), poll, || {
+        // a stale track isn't shown when polling starts again
+        *NOW_PLAYING.lock().unwrap() = None;
+    });
+    NOW_PLAYING.lock().unwrap().clone()
+}
+
+pub fn error() -> Option<String> {
+    ERROR.lock().unwrap().clone()
+}
+
+/// Polls the sources taken out of `SOURCES`, since a source can take
+/// seconds to answer and `set_sources` is called from the window.
+fn poll() {
+    let (generation, mut sources) = {
+        let mut current = SOURCES.lock().unwrap();
+        (
+            GENERATION.load(Ordering::Relaxed),
+            std::mem::take(&mut *current),
+        )
+    };
+    let mut playing = None;
+    let mut error = None;
+    for source in sources.iter_mut() {
+        match source.now_playing() {
+            Ok(Some(now_playing)) => {
+                // an earlier source failing doesn't matter when a later one plays
+                playing = Some(now_playing);
+                error = None;
+                break;
+            }
+            Ok(None) => (),
+            Err(reason) => error = Some(format!("{}: {reason}", source.name())),
+        }
+    }
+    let mut current = SOURCES.lock().unwrap();
+    if GENERATION.load(Ordering::Relaxed) == generation {
+        *current = sources;
+        *NOW_PLAYING.lock().unwrap() = playing;
+        *ERROR.lock().unwrap() = error;
+    }
+}
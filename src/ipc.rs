@@ -0,0 +1,493 @@
+use crate::platform::{self, Feature};
+use serde_json::{json, Value};
+use std::collections::VecDeque;
+use std::io::{self, ErrorKind, Read, Write};
+use std::path::PathBuf;
+use std::process;
+use std::sync::Mutex;
+use std::time::Duration;
+
+#[cfg(unix)]
+type Stream = std::os::unix::net::UnixStream;
+#[cfg(windows)]
+type Stream = pipe::Pipe;
+#[cfg(not(any(unix, windows)))]
+type Stream = std::fs::File;
+
+/// How long Discord gets to answer before the connection counts as lost.
+const TIMEOUT: Duration = Duration::from_secs(10);
+/// Largest frame read from Discord, its answers are a few KiB at most.
+const MAX_FRAME: u32 = 64 * 1024;
+
+/// Pipe `discord_running` found open, kept for the connection that usually
+/// follows so Discord isn't connected to twice.
+static KEPT: Mutex<Option<(PathBuf, Stream)>> = Mutex::new(None);
+
+const OP_HANDSHAKE: u32 = 0;
+const OP_FRAME: u32 = 1;
+const OP_CLOSE: u32 = 2;
+const OP_PING: u32 = 3;
+const OP_PONG: u32 = 4;
+
+/// Connection to the Discord IPC.
+///
+/// Unlike `DiscordIpcClient` it can be pointed at a specific pipe, which is
+/// needed to choose between several running Discord clients, and it reads
+/// Discord's answers so errors and events aren't lost.
+#[derive(Default)]
+pub struct Client {
+    pub client_id: String,
+    /// Pipe to connect to, the first one that answers when `None`.
+    pub pipe: Option<PathBuf>,
+    /// Only connect to this kind of Discord client, like Canary, when no pipe is given.
+    pub flavor: Option<String>,
+    /// Pipe the client is connected on.
+    pub connected_pipe: Option<PathBuf>,
+    /// Name of the account behind the connected pipe.
+    pub user: Option<String>,
+    /// Kind of Discord client behind the connected pipe.
+    pub connected_flavor: Option<String>,
+    stream: Option<Stream>,
+    /// Events Discord sent while waiting for the answer to a command.
+    pub events: VecDeque<Value>,
+    nonce: u64,
+}
+
+impl Client {
+    pub fn new(client_id: &str, pipe: Option<PathBuf>) -> Self {
+        Self {
+            client_id: client_id.to_string(),
+            pipe,
+            ..Default::default()
+        }
+    }
+
+    pub fn connect(&mut self) -> Result<(), String> {
+        let pipes = match &self.pipe {
+            Some(pipe) => vec![pipe.clone()],
+            None => pipe_paths(),
+        };
+        for pipe in pipes {
+            let (user, flavor) = match self.open(&pipe) {
+                Ok(Some(ready)) => ready,
+                Ok(None) => continue,
+                Err(error) => return Err(error),
+            };
+            if matches!(&self.flavor, Some(wanted) if wanted != &flavor) {
+                let _ = self.close();
+                continue;
+            }
+            self.connected_pipe = Some(pipe);
+            self.user = Some(user);
+            self.connected_flavor = Some(flavor);
+            return Ok(());
+        }
+        match (platform::unavailable(Feature::Ipc), &self.flavor) {
+            (Some(reason), _) => Err(reason.to_string()),
+            (None, Some(flavor)) => Err(format!("Discord {flavor} is not running")),
+            (None, None) => Err("Discord is not running".to_string()),
+        }
+    }
+
+    pub fn is_connected(&self) -> bool {
+        self.stream.is_some()
+    }
+
+    /// Sets an activity serialized to JSON.
+    /// Sets the activity and returns it the way Discord keeps it, with
+    /// external image links in their proxied `mp:` form.
+    pub fn set_activity_value(&mut self, activity: Value) -> Result<Value, String> {
+        self.command(
+            "SET_ACTIVITY",
+            json!({ "pid": process::id(), "activity": activity }),
+        )
+    }
+
+    pub fn close(&mut self) -> Result<(), String> {
+        let result = match self.stream.is_some() {
+            true => self.send(OP_CLOSE, &json!({})),
+            false => Ok(()),
+        };
+        self.stream = None;
+        self.connected_pipe = None;
+        self.user = None;
+        self.connected_flavor = None;
+        result
+    }
+
+    /// Checks the connection is still alive.
+    pub fn ping(&mut self) -> Result<(), String> {
+        self.send(OP_PING, &json!({}))?;
+        loop {
+            let (op, frame) = self.recv()?;
+            match op {
+                OP_PONG => return Ok(()),
+                OP_CLOSE => {
+                    self.stream = None;
+                    return Err(close_message(&frame));
+                }
+                _ => self.events.push_back(frame),
+            }
+        }
+    }
+
+    /// Sends a command and waits for Discord's answer to it.
+    pub fn command(&mut self, cmd: &str, args: Value) -> Result<Value, String> {
+        self.request(json!({ "cmd": cmd, "args": args }))
+    }
+
+    /// Asks Discord to send an event, they're collected in `events`.
+    pub fn subscribe(&mut self, evt: &str) -> Result<(), String> {
+        self.request(json!({ "cmd": "SUBSCRIBE", "args": {}, "evt": evt }))
+            .map(|_| ())
+    }
+
+    fn request(&mut self, mut frame: Value) -> Result<Value, String> {
+        self.nonce += 1;
+        let nonce = format!("{}-{}", process::id(), self.nonce);
+        frame["nonce"] = json!(nonce);
+        self.send(OP_FRAME, &frame)?;
+        loop {
+            let (op, frame) = self.recv()?;
+            if op == OP_CLOSE {
+                self.stream = None;
+                return Err(close_message(&frame));
+            }
+            if frame["nonce"] != json!(nonce) {
+                self.events.push_back(frame);
+                continue;
+            }
+            return match frame["evt"].as_str() {
+                Some("ERROR") => Err(frame["data"]["message"]
+                    .as_str()
+                    .unwrap_or("Discord rejected the request")
+                    .to_string()),
+                _ => Ok(frame["data"].clone()),
+            };
+        }
+    }
+
+    /// Sends the handshake and returns the account name and the kind of
+    /// client from the READY event.
+    /// Opens `pipe` and shakes hands, `None` when nothing listens on it.
+    fn open(&mut self, pipe: &PathBuf) -> Result<Option<(String, String)>, String> {
+        let kept = match KEPT.lock().unwrap().take() {
+            Some((kept, stream)) if &kept == pipe => Some(stream),
+            _ => None,
+        };
+        // a kept pipe may have been closed by Discord since, a new one is tried then
+        if let Some(stream) = kept {
+            self.stream = Some(stream);
+            if let Ok(ready) = self.handshake() {
+                return Ok(Some(ready));
+            }
+        }
+        self.stream = match open(pipe) {
+            Ok(stream) => Some(stream),
+            Err(_) => return Ok(None),
+        };
+        match self.handshake() {
+            Ok(ready) => Ok(Some(ready)),
+            Err(error) => {
+                self.stream = None;
+                Err(error)
+            }
+        }
+    }
+
+    fn handshake(&mut self) -> Result<(String, String), String> {
+        let handshake = json!({ "v": 1, "client_id": self.client_id });
+        self.send(OP_HANDSHAKE, &handshake)?;
+        let (op, frame) = self.recv()?;
+        if op == OP_CLOSE || frame["evt"] != json!("READY") {
+            return Err(close_message(&frame));
+        }
+        Ok((
+            user_name(&frame["data"]["user"]),
+            flavor(&frame["data"]["config"]),
+        ))
+    }
+
+    fn send(&mut self, op: u32, data: &Value) -> Result<(), String> {
+        let stream = match self.stream.as_mut() {
+            Some(stream) => stream,
+            None => return Err("Not connected to discord".to_string()),
+        };
+        let data = data.to_string();
+        let mut frame = Vec::with_capacity(8 + data.len());
+        frame.extend_from_slice(&op.to_le_bytes());
+        frame.extend_from_slice(&(data.len() as u32).to_le_bytes());
+        frame.extend_from_slice(data.as_bytes());
+        match stream.write_all(&frame) {
+            Ok(_) => Ok(()),
+            Err(_) => {
+                self.stream = None;
+                Err("Lost connection to discord".to_string())
+            }
+        }
+    }
+
+    fn recv(&mut self) -> Result<(u32, Value), String> {
+        let stream = match self.stream.as_mut() {
+            Some(stream) => stream,
+            None => return Err("Not connected to discord".to_string()),
+        };
+        let mut header = [0; 8];
+        let frame = stream.read_exact(&mut header).and_then(|_| {
+            let len = u32::from_le_bytes([header[4], header[5], header[6], header[7]]);
+            if len > MAX_FRAME {
+                return Err(io::Error::new(ErrorKind::InvalidData, "frame too large"));
+            }
+            let mut data = vec![0; len as usize];
+            stream.read_exact(&mut data).map(|_| data)
+        });
+        // the rest of a frame that timed out would be read as the next one,
+        // so the connection is dropped either way
+        let data = match frame {
+            Ok(data) => data,
+            Err(err) if matches!(err.kind(), ErrorKind::WouldBlock | ErrorKind::TimedOut) => {
+                self.stream = None;
+                return Err(format!(
+                    "Discord didn't answer in {} seconds",
+                    TIMEOUT.as_secs()
+                ));
+            }
+            Err(err) if err.kind() == ErrorKind::InvalidData => {
+                self.stream = None;
+                return Err("Discord sent a frame too large to read".to_string());
+            }
+            Err(_) => {
+                self.stream = None;
+                return Err("Lost connection to discord".to_string());
+            }
+        };
+        let op = u32::from_le_bytes([header[0], header[1], header[2], header[3]]);
+        match serde_json::from_slice(&data) {
+            Ok(frame) => Ok((op, frame)),
+            Err(_) => Err("Discord sent an invalid answer".to_string()),
+        }
+    }
+}
+
+/// Kinds of Discord client, each can run next to the others on its own pipe.
+pub const FLAVORS: [&str; 3] = ["Stable", "PTB", "Canary"];
+
+/// A Discord client found on one of the IPC pipes.
+#[derive(Clone)]
+pub struct PipeUser {
+    pub pipe: PathBuf,
+    /// Empty when the client didn't accept the handshake, the pipe is still listed.
+    pub user: String,
+    pub flavor: String,
+}
+
+/// Connects to every pipe to find out which account and kind of client is behind it.
+pub fn scan(client_id: &str) -> Vec<PipeUser> {
+    pipe_paths()
+        .into_iter()
+        .filter_map(|pipe| {
+            let mut client = Client::new(client_id, Some(pipe.clone()));
+            let (user, flavor) = match client.open(&pipe) {
+                Ok(Some(ready)) => ready,
+                Ok(None) => return None,
+                Err(_) => Default::default(),
+            };
+            let _ = client.close();
+            Some(PipeUser { pipe, user, flavor })
+        })
+        .collect()
+}
+
+/// Connects to every pipe except `skip`, for mirroring the presence to all
+/// running Discord clients.
+pub fn connect_all(client_id: &str, skip: Option<&PathBuf>) -> Vec<Client> {
+    pipe_paths()
+        .into_iter()
+        .filter(|pipe| Some(pipe) != skip)
+        .filter_map(|pipe| {
+            let mut client = Client::new(client_id, Some(pipe));
+            client.connect().ok().map(|_| client)
+        })
+        .collect()
+}
+
+/// Whether any Discord client is listening, without talking to it. The pipe
+/// that answered stays open for the next `Client::connect`.
+pub fn discord_running() -> bool {
+    for pipe in pipe_paths() {
+        if let Ok(stream) = open(&pipe) {
+            *KEPT.lock().unwrap() = Some((pipe, stream));
+            return true;
+        }
+    }
+    false
+}
+
+/// Pipes a Discord client may listen on, in the order Discord numbers them.
+#[cfg(windows)]
+pub fn pipe_paths() -> Vec<PathBuf> {
+    (0..10)
+        .map(|i| PathBuf::from(format!(r"\\?\pipe\discord-ipc-{i}")))
+        .collect()
+}
+
+/// Pipes a Discord client may listen on, in the order Discord numbers them.
+#[cfg(unix)]
+pub fn pipe_paths() -> Vec<PathBuf> {
+    let mut dirs: Vec<PathBuf> = ["XDG_RUNTIME_DIR", "TMPDIR", "TMP", "TEMP"]
+        .iter()
+        .filter_map(|key| std::env::var_os(key).map(PathBuf::from))
+        .collect();
+    dirs.push(PathBuf::from("/tmp"));
+    let mut paths = Vec::new();
+    for i in 0..10 {
+        for dir in dirs.iter() {
+            for sub in ["", "app/com.discordapp.Discord", "snap.discord"] {
+                let path = dir.join(sub).join(format!("discord-ipc-{i}"));
+                if path.exists() && !paths.contains(&path) {
+                    paths.push(path);
+                }
+            }
+        }
+    }
+    paths
+}
+
+#[cfg(unix)]
+fn open(pipe: &PathBuf) -> io::Result<Stream> {
+    let stream = Stream::connect(pipe)?;
+    stream.set_read_timeout(Some(TIMEOUT))?;
+    stream.set_write_timeout(Some(TIMEOUT))?;
+    Ok(stream)
+}
+
+#[cfg(windows)]
+fn open(pipe: &PathBuf) -> io::Result<Stream> {
+    let file = std::fs::OpenOptions::new()
+        .read(true)
+        .write(true)
+        .open(pipe)?;
+    pipe::Pipe::new(file)
+}
+
+/// Named pipes opened as files can't time out a read, so reads wait for
+/// data with `PeekNamedPipe` first. A reading thread won't do, the pipe's
+/// writes would wait for its read.
+#[cfg(windows)]
+mod pipe {
+    use super::TIMEOUT;
+    use std::ffi::c_void;
+    use std::fs::File;
+    use std::io::{self, ErrorKind, Read, Write};
+    use std::os::windows::io::AsRawHandle;
+    use std::ptr;
+    use std::thread;
+    use std::time::{Duration, Instant};
+
+    #[link(name = "kernel32")]
+    extern "system" {
+        fn PeekNamedPipe(
+            pipe: *mut c_void,
+            buffer: *mut c_void,
+            size: u32,
+            read: *mut u32,
+            available: *mut u32,
+            left: *mut u32,
+        ) -> i32;
+    }
+
+    pub struct Pipe {
+        file: File,
+    }
+
+    impl Pipe {
+        pub fn new(file: File) -> io::Result<Self> {
+            Ok(Self { file })
+        }
+
+        /// Bytes that can be read without waiting.
+        fn available(&self) -> io::Result<usize> {
+            let mut available = 0;
+            let peeked = unsafe {
+                PeekNamedPipe(
+                    self.file.as_raw_handle(),
+                    ptr::null_mut(),
+                    0,
+                    ptr::null_mut(),
+                    &mut available,
+                    ptr::null_mut(),
+                )
+            };
+            match peeked {
+                0 => Err(io::Error::last_os_error()),
+                _ => Ok(available as usize),
+            }
+        }
+    }
+
+    impl Read for Pipe {
+        fn read(&mut self, buf: &mut [u8]) -> io::Result<usize> {
+            let start = Instant::now();
+            loop {
+                let available = self.available()?;
+                if available > 0 {
+                    let read = buf.len().min(available);
+                    return self.file.read(&mut buf[..read]);
+                }
+                if start.elapsed() > TIMEOUT {
+                    return Err(ErrorKind::TimedOut.into());
+                }
+                thread::sleep(Duration::from_millis(10));
+            }
+        }
+    }
+
+    impl Write for Pipe {
+        fn write(&mut self, buf: &[u8]) -> io::Result<usize> {
+            self.file.write(buf)
+        }
+
+        fn flush(&mut self) -> io::Result<()> {
+            self.file.flush()
+        }
+    }
+}
+
+#[cfg(not(any(unix, windows)))]
+pub fn pipe_paths() -> Vec<PathBuf> {
+    Vec::new()
+}
+
+#[cfg(not(any(unix, windows)))]
+fn open(_: &PathBuf) -> io::Result<Stream> {
+    Err(ErrorKind::Unsupported.into())
+}
+
+fn user_name(user: &Value) -> String {
+    let name = user["global_name"]
+        .as_str()
+        .or_else(|| user["username"].as_str())
+        .unwrap_or("Unknown");
+    match user["discriminator"].as_str() {
+        Some(discriminator) if discriminator != "0" => format!("{name}#{discriminator}"),
+        _ => name.to_string(),
+    }
+}
+
+/// Tells the clients apart by the API they talk to, other clients count as Stable.
+fn flavor(config: &Value) -> String {
+    let endpoint = config["api_endpoint"].as_str().unwrap_or_default();
+    let flavor = match endpoint {
+        _ if endpoint.contains("canary.") => "Canary",
+        _ if endpoint.contains("ptb.") => "PTB",
+        _ => "Stable",
+    };
+    flavor.to_string()
+}
+
+fn close_message(frame: &Value) -> String {
+    match frame["message"].as_str() {
+        Some(message) => format!("Discord closed the connection: {message}"),
+        None => "Discord closed the connection".to_string(),
+    }
+}
@@ -0,0 +1,75 @@
+use chrono::{DateTime, Duration, Utc};
+use eframe::egui::{self, Context};
+use serde_json::Value;
+
+/// Seconds Discord keeps a join request open.
+const EXPIRES_AFTER: i64 = 30;
+
+pub struct JoinRequest {
+    pub user_id: String,
+    pub name: String,
+    pub received: DateTime<Utc>,
+}
+
+/// People asking to join through the presence's Ask to Join button, each
+/// with accept and deny until Discord lets the request expire.
+#[derive(Default)]
+pub struct JoinRequests {
+    pub pending: Vec<JoinRequest>,
+}
+
+impl JoinRequests {
+    /// Adds the request from an `ACTIVITY_JOIN_REQUEST` event.
+    pub fn add(&mut self, event: &Value) {
+        let user = &event["data"]["user"];
+        let user_id = match user["id"].as_str() {
+            Some(id) => id.to_string(),
+            None => return,
+        };
+        let name = user["global_name"]
+            .as_str()
+            .or_else(|| user["username"].as_str())
+            .unwrap_or("Someone")
+            .to_string();
+        self.pending.retain(|request| request.user_id != user_id);
+        self.pending.push(JoinRequest {
+            user_id,
+            name,
+            received: Utc::now(),
+        });
+    }
+
+    /// Shows the open requests and returns the user answered and whether
+    /// they were accepted.
+    pub fn run(&mut self, ctx: &Context) -> Option<(String, bool)> {
+        let now = Utc::now();
+        self.pending
+            .retain(|request| now - request.received < Duration::seconds(EXPIRES_AFTER));
+        if self.pending.is_empty() {
+            return None;
+        }
+        let mut answer = None;
+        egui::containers::Window::new("Join requests")
+            .resizable(false)
+            .collapsible(false)
+            .show(ctx, |ui| {
+                for request in self.pending.iter() {
+                    let left = EXPIRES_AFTER - (now - request.received).num_seconds();
+                    ui.horizontal(|ui| {
+                        ui.label(format!("{} wants to join ({left}s)", request.name));
+                        if ui.button("Accept").clicked() {
+                            answer = Some((request.user_id.clone(), true));
+                        }
+                        if ui.button("Deny").clicked() {
+                            answer = Some((request.user_id.clone(), false));
+                        }
+                    });
+                }
+            });
+        if let Some((user_id, _)) = &answer {
+            self.pending.retain(|request| &request.user_id != user_id);
+        }
+        ctx.request_repaint_after(std::time::Duration::from_secs(1));
+        answer
+    }
+}
@@ -0,0 +1,85 @@
+use crate::event_log::EventLog;
+use eframe::egui::{self, RichText, ScrollArea, TextStyle, Ui};
+use eframe::epaint::Color32;
+use serde::{Deserialize, Serialize};
+use serde_json::Value;
+
+#[derive(Serialize, Deserialize, PartialEq, Eq, Clone, Copy, Default)]
+pub enum UiLayout {
+    /// Every field of the form.
+    #[default]
+    Full,
+    /// Only the ID, details and state, for a small window next to other work.
+    Compact,
+    /// The full form with the payload inspector and the event log beside it.
+    Developer,
+}
+
+pub const LAYOUTS: [UiLayout; 3] = [UiLayout::Full, UiLayout::Compact, UiLayout::Developer];
+
+impl UiLayout {
+    pub fn name(self) -> &'static str {
+        match self {
+            UiLayout::Full => "Full",
+            UiLayout::Compact => "Compact",
+            UiLayout::Developer => "Developer",
+        }
+    }
+
+    pub fn run(&mut self, ui: &mut Ui) {
+        for layout in LAYOUTS {
+            if ui.radio_value(self, layout, layout.name()).clicked() {
+                ui.close_menu();
+            }
+        }
+    }
+}
+
+/// The developer panel: the payload the next update sends next to the one
+/// Discord shows, and what went over the connection.
+pub fn developer_panel(
+    ctx: &egui::Context,
+    next: Result<Value, String>,
+    sent: Option<&Value>,
+    log: &mut EventLog,
+) {
+    egui::SidePanel::right("developer")
+        .resizable(true)
+        .default_width(420.)
+        .show(ctx, |ui| {
+            ui.columns(2, |columns| {
+                columns[0].heading("Payload");
+                ScrollArea::vertical()
+                    .id_source("payload")
+                    .show(&mut columns[0], |ui| {
+                        ui.label(RichText::new("Next update").strong());
+                        match &next {
+                            Ok(payload) => json(ui, payload),
+                            Err(error) => {
+                                ui.label(RichText::new(error).color(Color32::LIGHT_RED));
+                            }
+                        }
+                        ui.add_space(10.);
+                        ui.label(RichText::new("Shown on Discord").strong());
+                        match sent {
+                            Some(payload) => json(ui, payload),
+                            None => {
+                                ui.label("Nothing sent yet");
+                            }
+                        }
+                    });
+                columns[1].horizontal(|ui| {
+                    ui.heading("Events");
+                    if ui.small_button("Clear").clicked() {
+                        log.entries.clear();
+                    }
+                });
+                log.run(&mut columns[1]);
+            });
+        });
+}
+
+fn json(ui: &mut Ui, value: &Value) {
+    let text = serde_json::to_string_pretty(value).unwrap_or_default();
+    ui.label(RichText::new(text).text_style(TextStyle::Monospace).small());
+}
@@ -0,0 +1,72 @@
+use eframe::egui::{RichText, Ui};
+use eframe::epaint::Color32;
+
+/// Length Discord accepts for a text of the activity. Empty texts aren't
+/// sent, so they're always fine.
+pub struct Limit {
+    pub field: &'static str,
+    pub min: usize,
+    pub max: usize,
+}
+
+pub const DETAILS: Limit = Limit {
+    field: "Details",
+    min: 2,
+    max: 128,
+};
+pub const STATE: Limit = Limit {
+    field: "State",
+    min: 2,
+    max: 128,
+};
+pub const LARGE_TEXT: Limit = Limit {
+    field: "Large image label",
+    min: 2,
+    max: 128,
+};
+pub const SMALL_TEXT: Limit = Limit {
+    field: "Small image label",
+    min: 2,
+    max: 128,
+};
+pub const BUTTON_LABEL: Limit = Limit {
+    field: "Button label",
+    min: 1,
+    max: 32,
+};
+pub const BUTTON_URL: Limit = Limit {
+    field: "Button URL",
+    min: 1,
+    max: 512,
+};
+
+impl Limit {
+    pub fn check(&self, text: &str) -> Result<(), String> {
+        let len = text.chars().count();
+        match len == 0 || (self.min..=self.max).contains(&len) {
+            true => Ok(()),
+            false if self.min > 1 => Err(format!(
+                "{} must be between {} and {} characters",
+                self.field, self.min, self.max
+            )),
+            false => Err(format!(
+                "{} must be at most {} characters",
+                self.field, self.max
+            )),
+        }
+    }
+
+    /// Shows how much of the limit the text uses, red when Discord won't take it.
+    pub fn counter(&self, ui: &mut Ui, text: &str) {
+        let len = text.chars().count();
+        let color = match self.check(text) {
+            Ok(_) => Color32::from_rgb(163, 163, 163),
+            Err(_) => Color32::LIGHT_RED,
+        };
+        ui.label(
+            RichText::new(format!("{len}/{}", self.max))
+                .small()
+                .color(color),
+        );
+    }
+}
@@ -1,38 +1,192 @@
 #![cfg_attr(not(debug_assertions), windows_subsystem = "windows")] // hide console window on Windows in release
 #![feature(drain_filter)]
 
+mod accounts;
+mod animation;
+mod app_cache;
+mod asset_check;
+mod autocomplete;
+mod automation;
+mod bug_report;
+mod check_config;
+mod cli;
+mod clock;
+mod config;
+mod control;
+mod custom_status;
+mod debug_overlay;
+mod display_mode;
+mod error;
 mod error_bar;
+mod error_report;
+mod event_log;
+mod fonts;
+mod goal;
+mod guard;
+mod headless;
+mod history;
+mod hooks;
+mod idle;
 mod image;
+mod image_import;
+mod integrations;
+mod ipc;
+mod join_requests;
+mod layouts;
+mod limits;
+mod mdns;
+mod media;
 mod menu_bar;
+mod metrics;
+mod obs;
+mod overlay;
+mod party;
+mod placeholders;
+mod platform;
+mod pomodoro;
 mod presence_button;
 mod preset;
+mod preset_manager;
+mod preview_card;
+mod process_rules;
+mod profiles;
+mod reconnect;
+mod redact;
+mod remote;
+mod renderer;
+mod retention;
+mod rotation;
+mod rules;
+mod rundown;
+mod scheduler;
+mod secrets;
+mod self_check;
+mod session;
+mod setup;
+mod slots;
+mod sounds;
+mod spotify;
+mod status_file;
 mod storage;
+mod temporary;
+mod throttle;
+mod time_tracking;
 mod timestamp;
+mod twitch;
+mod watcher;
+mod webhook;
+mod websocket;
+mod window;
+mod window_title;
+mod worker;
 
+use accounts::AccountPicker;
+use animation::Animation;
+use app_cache::AppCache;
+use automation::Automation;
+use cli::LocalServer;
+use clock::Clock;
+use config::{Config, Startup};
+use control::{Command, ControlServer, FOCUS_PRESET};
+use custom_status::CustomStatus;
+use debug_overlay::DebugOverlay;
+use display_mode::DisplayMode;
+use error::Error;
 use error_bar::ErrorBar;
+use error_report::ErrorReporting;
+use event_log::EventLog;
+use guard::Guard;
+use history::History;
+use hooks::{Hook, Hooks};
+use idle::{Change, Idle};
 use image::Image;
+use join_requests::JoinRequests;
+use layouts::UiLayout;
+use obs::Obs;
+use party::PartySecrets;
+use platform::Feature;
+use pomodoro::{Pomodoro, Step};
 use presence_button::PresenceButton;
 use preset::{InAppPreset, Preset};
+use preview_card::PreviewCard;
+use process_rules::ProcessRules;
+use profiles::{ProfileFont, Profiles};
+use reconnect::Backoff;
+use redact::RedactionPolicy;
+use remote::Remote;
+use retention::Retention;
+use rotation::Rotation;
+use rules::Rules;
+use rundown::Rundown;
+use scheduler::Scheduler;
+use self_check::SelfCheck;
+use session::{LastSession, Session};
+use setup::Setup;
+use slots::Slots;
+use spotify::{Spotify, SpotifyLogin};
+use status_file::StatusFile;
 use storage::Storage;
+use throttle::Throttle;
+use time_tracking::TimeTracking;
 use timestamp::{Timestamp, TimestampEnum};
+use window::WindowSettings;
+use worker::{Job, Origin, Reply, Worker};
 
-use std::time::Duration;
+use std::collections::VecDeque;
+use std::path::Path;
+use std::time::{Duration, Instant};
 use std::{fs, vec};
 
 use serde_json::{from_str, to_string};
 
-use discord_rich_presence::activity::{Activity, Assets, Button, Party, Timestamps};
-use discord_rich_presence::{DiscordIpc, DiscordIpcClient};
+use discord_rich_presence::activity::Timestamps;
 
-use chrono::{DateTime, Local, Utc};
+use chrono::{DateTime, Local, NaiveDateTime, Utc};
 
-use eframe::egui::{self, Layout, Vec2};
+use eframe::egui::{self, Color32, Layout, RichText, Vec2};
 use eframe::emath::Align;
 use eframe::{run_native, NativeOptions};
 
+/// Seconds between checks that the connection to Discord is still alive.
+const PING_INTERVAL: i64 = 10;
+/// Seconds between checks whether placeholders like `{time}` expand to something new.
+const REFRESH_INTERVAL: i64 = 15;
+/// Seconds between checks whether Discord started, while autoconnect waits for it.
+const DISCORD_POLL_INTERVAL: i64 = 5;
+/// Seconds between those checks while the window is minimized.
+const LOW_POWER_REFRESH_INTERVAL: i64 = 60;
+
 fn main() {
-    let options = NativeOptions {
-        decorated: true,
+    error_report::install_panic_hook();
+    let args: Vec<String> = std::env::args().collect();
+    if let Some(code) = cli::run(&args) {
+        std::process::exit(code);
+    }
+    let safe_mode = args.iter().any(|arg| arg == "--safe-mode");
+    if args.iter().any(|arg| arg == "--headless") {
+        if safe_mode {
+            eprintln!("Safe mode needs the window to fix the settings in");
+            std::process::exit(2);
+        }
+        headless::run();
+        return;
+    }
+    if let Some(reason) = platform::unavailable(Feature::Window) {
+        eprintln!("{reason}");
+        std::process::exit(2);
+    }
+    let backend = match renderer::pick(&args) {
+        Ok(backend) => backend,
+        Err(error) => {
+            eprintln!("{error}");
+            std::process::exit(2);
+        }
+    };
+    let saved: Option<Storage> = storage::saved()
+        .get("settings")
+        .and_then(|settings| from_str(settings).ok());
+    let mut options = NativeOptions {
+        decorated: saved.map_or(true, |saved| saved.window_decorations),
         drag_and_drop_support: true,
         icon_data: None,
         initial_window_size: Some(Vec2::new(600.0, 650.0)),
@@ -41,29 +195,101 @@ fn main() {
         follow_system_theme: true,
         ..Default::default()
     };
+    backend.apply(&mut options);
     run_native(
         "Discord Presence",
         options,
-        Box::new(|cc| Box::new(App::new(cc))),
+        Box::new(move |cc| {
+            renderer::works(backend);
+            Box::new(App::new(cc, safe_mode))
+        }),
     );
 }
 pub struct App {
     error_bar: ErrorBar,
     menu_bar: menu_bar::MenuBar,
+    automation: Automation,
+    throttle: Throttle,
+    self_check: SelfCheck,
+    preview_card: PreviewCard,
+    join_requests: JoinRequests,
+    event_log: EventLog,
+    last_session: LastSession,
+    debug_overlay: DebugOverlay,
+    control: Option<ControlServer>,
+    /// Channel the command line subcommands reach the app on.
+    local: Option<LocalServer>,
+    /// Set when the command line channel couldn't start.
+    control_failed: bool,
+    /// The metrics endpoint and the control API's mDNS announcement, they
+    /// stop with the other channels.
+    metrics: Option<metrics::Metrics>,
+    mdns: Option<mdns::Advertiser>,
+    remote: Remote,
+    /// The A/B presences flipped between.
+    slots: Slots,
+    setup: Setup,
     first_btn: presence_button::PresenceButton,
     second_btn: presence_button::PresenceButton,
     first_img: image::Image,
     second_img: image::Image,
     id: String,
+    /// Keeps a stray keystroke from changing the ID, which reconnects on the next update.
+    id_locked: bool,
     details: String,
     state: String,
     party: u8,
     party_of: u8,
+    party_secrets: PartySecrets,
     timestamp: timestamp::Timestamp,
-    client: DiscordIpcClient,
+    worker: Worker,
+    /// Application ID of the current connection.
+    connected_id: String,
+    /// ID agreed to switch to while connected with another, presets switch without asking.
+    switch_to: Option<String>,
+    /// Set while asking whether to switch applications.
+    switch_prompt: bool,
+    /// Account Discord reported when connecting.
+    user: Option<String>,
+    /// Activities handed to the worker and not answered yet, with the form
+    /// state they were made from. Restored activities have none.
+    sending: VecDeque<(serde_json::Value, Option<Preset>)>,
+    accounts: AccountPicker,
+    profiles: Profiles,
+    history: History,
+    app_cache: AppCache,
     connected: bool,
     started: DateTime<Utc>,
     last_update: DateTime<Utc>,
+    /// Form state of the last presence Discord accepted.
+    last_sent: Option<Preset>,
+    /// In-app preset to switch to when the countdown ends.
+    next_preset: Option<String>,
+    /// Color tag of the loaded preset, empty when it has none.
+    color: String,
+    /// The form as the tagged preset left it, the tag goes once it's edited.
+    colored: Option<Preset>,
+    /// Presence from before a temporary preset, restored at the time.
+    revert: Option<(DateTime<Utc>, Preset)>,
+    /// An update was asked for while Discord couldn't be reached.
+    pending_update: bool,
+    /// The connection dropped and is being restored.
+    reconnecting: bool,
+    backoff: Backoff,
+    next_ping: DateTime<Utc>,
+    /// Autoconnect found Discord closed and connects once its pipe shows up.
+    waiting_for_discord: bool,
+    next_discord_check: DateTime<Utc>,
+    /// Activity Discord last accepted, sent again after reconnecting.
+    last_activity: Option<serde_json::Value>,
+    /// `problems()` of the form as last drawn, worked out again when the form
+    /// changes or after a second, for placeholders that change over time.
+    shown_problems: Option<(Preset, Instant, Vec<String>)>,
+    next_refresh: DateTime<Utc>,
+    /// Set while the window is minimized, background work slows down then.
+    low_power: bool,
+    /// Started with `--safe-mode`, nothing connects, runs or listens by itself.
+    safe_mode: bool,
 }
 
 impl Default for App {
@@ -71,42 +297,69 @@ impl Default for App {
         Self {
             error_bar: ErrorBar::default(),
             menu_bar: menu_bar::MenuBar::default(),
+            automation: Automation::default(),
+            throttle: Throttle::default(),
+            self_check: SelfCheck::default(),
+            preview_card: PreviewCard::default(),
+            join_requests: JoinRequests::default(),
+            event_log: EventLog::default(),
+            last_session: LastSession::default(),
+            debug_overlay: DebugOverlay::default(),
+            control: None,
+            local: None,
+            control_failed: false,
+            metrics: None,
+            mdns: None,
+            remote: Remote::default(),
+            slots: Slots::default(),
+            setup: Setup::default(),
             first_btn: PresenceButton::default(),
             second_btn: PresenceButton::default(),
             first_img: Image::default(),
             second_img: Image::default(),
             id: String::new(),
+            id_locked: false,
             details: String::new(),
             party: 0,
             party_of: 0,
+            party_secrets: PartySecrets::default(),
             state: String::new(),
             timestamp: Timestamp::default(),
-            client: DiscordIpcClient::new("0").unwrap(),
+            worker: Worker::default(),
+            connected_id: String::new(),
+            switch_to: None,
+            switch_prompt: false,
+            user: None,
+            sending: VecDeque::new(),
+            accounts: AccountPicker::default(),
+            profiles: Profiles::default(),
+            history: History::default(),
+            app_cache: AppCache::default(),
             connected: false,
             started: Utc::now(),
             last_update: Utc::now(),
+            last_sent: None,
+            next_preset: None,
+            color: String::new(),
+            colored: None,
+            revert: None,
+            pending_update: false,
+            reconnecting: false,
+            backoff: Backoff::default(),
+            next_ping: Utc::now(),
+            waiting_for_discord: false,
+            next_discord_check: Utc::now(),
+            last_activity: None,
+            shown_problems: None,
+            next_refresh: Utc::now(),
+            low_power: false,
+            safe_mode: false,
         }
     }
 }
 
-fn setup_font(ctx: &egui::Context) {
-    let mut fonts = egui::FontDefinitions::default();
-    fonts.font_data.insert(
-        "Emoji".to_owned(),
-        egui::FontData::from_static(include_bytes!("../assets/fonts/TwitterColorEmoji.ttf")),
-    );
-    fonts
-        .families
-        .entry(egui::FontFamily::Proportional)
-        .or_default()
-        .insert(0, "Emoji".to_owned());
-
-    ctx.set_fonts(fonts);
-}
-
 impl App {
-    fn new(cc: &eframe::CreationContext<'_>) -> Self {
-        setup_font(&cc.egui_ctx);
+    fn new(cc: &eframe::CreationContext<'_>, safe_mode: bool) -> Self {
         let storage = match cc.storage.unwrap().get_string("settings") {
             None => "".to_string(),
             Some(value) => value,
@@ -115,15 +368,25 @@ impl App {
             "" => true,
             _ => false,
         };
-        let presets = match cc.storage.unwrap().get_string("presets") {
-            None => "".to_string(),
-            Some(value) => value,
+        // presets from before the presets folder are moved there on the first save
+        let (presets, preset_errors) = match preset_manager::load() {
+            Some(loaded) => loaded,
+            None => (
+                cc.storage
+                    .unwrap()
+                    .get_string("presets")
+                    .unwrap_or_default(),
+                Vec::new(),
+            ),
         };
         let storage: Storage = match from_str(&storage) {
             Ok(storage) => storage,
             Err(_) => Storage::default(),
         };
 
+        let font_error = fonts::setup_fonts(&cc.egui_ctx, &storage.font_path).err();
+        fonts::set_font_size(&cc.egui_ctx, storage.font_size);
+
         if !default_theme {
             match storage.darkmode {
                 true => cc.egui_ctx.set_visuals(egui::Visuals::dark()),
@@ -131,62 +394,670 @@ impl App {
             }
         }
 
-        let mut client =
-            DiscordIpcClient::new(storage.id).expect("No reason for this  to failed either");
-        let error = match storage.autoconnect {
-            true => match client.connect() {
-                Ok(_) => "",
-                Err(_) => "Failed to connect. (AutoConnect)",
-            },
-            false => "",
+        let mut app = App::from_storage(storage, presets, safe_mode);
+        if let Some(error) = font_error {
+            app.error_bar.new_error(error);
+        }
+        for error in preset_errors {
+            app.error_bar.new_error(error);
+        }
+        app.apply_config(&cc.egui_ctx);
+        let keys = [app.first_img.key.as_str(), app.second_img.key.as_str()];
+        app.self_check.start(&app.id, keys, true);
+        app
+    }
+
+    /// Starts the command line channel and what `config.toml` asks for:
+    /// metrics, the control API and the startup actions.
+    fn apply_config(&mut self, ctx: &egui::Context) {
+        // nothing listens or runs startup actions, the config may be what needs fixing
+        if self.safe_mode {
+            return;
+        }
+        let config = Config::load();
+        if self.menu_bar.control {
+            self.start_control(ctx, config.as_ref().ok());
+        }
+        match config {
+            Ok(config) => self.run_startup(&config.startup),
+            Err(error) => self.error_bar.new_error(error),
+        }
+    }
+
+    /// Starts the command line channel, and the control API, its mDNS
+    /// announcement and the metrics when configured.
+    fn start_control(&mut self, ctx: &egui::Context, config: Option<&Config>) {
+        match LocalServer::start(ctx.clone()) {
+            Ok(local) => self.local = Some(local),
+            Err(error) => {
+                // not retried every frame, only after switching control off and on
+                self.control_failed = true;
+                self.error_bar.new_error(error)
+            }
+        }
+        let config = match config {
+            Some(config) => config,
+            None => return,
         };
+        if let Some(listen) = &config.metrics.listen {
+            match metrics::serve(listen) {
+                Ok(metrics) => self.metrics = Some(metrics),
+                Err(error) => self.error_bar.new_error(error),
+            }
+        }
+        match ControlServer::start(&config.control, self.remote.pairings(), ctx.clone()) {
+            Ok(control) => self.control = control,
+            Err(error) => self.error_bar.new_error(error),
+        }
+        if let (Some(control), true) = (&self.control, config.control.advertise) {
+            match mdns::advertise(control.addr) {
+                Ok(mdns) => self.mdns = Some(mdns),
+                Err(error) => self.error_bar.new_error(error),
+            }
+        }
+    }
+
+    /// Starts or stops listening when control from other programs was switched.
+    fn sync_control(&mut self, ctx: &egui::Context) {
+        let listening = self.local.is_some() || self.control.is_some();
+        match (self.menu_bar.control, listening) {
+            (true, false) if !self.safe_mode && !self.control_failed => {
+                let config = Config::load();
+                self.start_control(ctx, config.as_ref().ok());
+            }
+            (false, _) => {
+                self.local = None;
+                self.control = None;
+                self.metrics = None;
+                self.mdns = None;
+                self.control_failed = false;
+            }
+            _ => (),
+        }
+    }
+
+    /// Builds the app state from saved settings, connecting when autoconnect is on.
+    fn from_storage(storage: Storage, presets: String, safe_mode: bool) -> Self {
+        let redaction = RedactionPolicy {
+            client_ids: storage.redact_client_ids,
+            urls: storage.redact_urls,
+        };
+        redact::set_policy(redaction);
+        if storage.error_reporting {
+            error_report::set_endpoint(Some(storage.error_report_endpoint.clone()));
+        }
+        let accounts = AccountPicker {
+            pipe: storage.pipe,
+            flavor: storage.pipe_flavor,
+            ..Default::default()
+        };
+        let autoconnect = storage.autoconnect;
+        let profiles = Profiles::load(storage.profiles, &storage.id);
         let mut app = App {
-            id: storage.id.to_owned(),
-            details: storage.details.to_owned(),
-            state: storage.state.to_owned(),
+            id: storage.id,
+            id_locked: storage.id_locked,
+            details: storage.details,
+            color: storage.color,
+            state: storage.state,
             party: storage.party,
             party_of: storage.party_of,
+            party_secrets: PartySecrets {
+                id: storage.party_id,
+                join: storage.join_secret,
+                match_secret: storage.match_secret,
+            },
             timestamp: timestamp::Timestamp {
                 timestamp: storage.timestamp,
-                date: Utc::now().date(),
+                timezone: storage.timezone.clone(),
+                start: storage
+                    .timestamp_start
+                    .and_then(|start| NaiveDateTime::from_timestamp_opt(start, 0))
+                    .map_or_else(Utc::now, |start| DateTime::from_utc(start, Utc)),
+                relative_start: storage
+                    .timestamp_relative_start
+                    .and_then(|start| NaiveDateTime::from_timestamp_opt(start, 0))
+                    .map(|start| DateTime::from_utc(start, Utc)),
+                end: storage
+                    .timestamp_end
+                    .and_then(|end| NaiveDateTime::from_timestamp_opt(end, 0))
+                    .map(|end| DateTime::from_utc(end, Utc)),
+                ..Default::default()
             },
             first_btn: presence_button::PresenceButton {
-                label: storage.first_btn_label.to_owned(),
-                url: storage.first_btn_url.to_owned(),
+                label: storage.first_btn_label,
+                url: storage.first_btn_url,
             },
             second_btn: presence_button::PresenceButton {
-                label: storage.second_btn_label.to_owned(),
-                url: storage.second_btn_url.to_owned(),
+                label: storage.second_btn_label,
+                url: storage.second_btn_url,
             },
             first_img: image::Image {
-                key: storage.large_image_key.to_owned(),
-                text: storage.large_image_label.to_owned(),
+                key: storage.large_image_key,
+                text: storage.large_image_label,
+                second_line: Some(storage.large_image_label_2),
             },
             second_img: image::Image {
-                key: storage.small_image_key.to_owned(),
-                text: storage.small_image_label.to_owned(),
+                key: storage.small_image_key,
+                text: storage.small_image_label,
+                second_line: None,
             },
             menu_bar: menu_bar::MenuBar {
                 autoconnect: storage.autoconnect,
+                automation: storage.automation,
+                mirror: storage.mirror,
+                webhook_url: storage.webhook_url.clone(),
+                redaction,
+                goal: storage.goal.clone(),
+                clock: Clock {
+                    correct: storage.clock_correct,
+                    round: storage.clock_round,
+                    ..Default::default()
+                },
+                scheduler: Scheduler::new(storage.scheduler, storage.schedule),
+                process_rules: ProcessRules {
+                    rules: storage.process_rules,
+                    ..Default::default()
+                },
+                rotation: Rotation {
+                    presets: storage.rotation.clone(),
+                    minutes: storage.rotation_minutes,
+                    animation: Animation {
+                        style: storage.animation_style,
+                        text: storage.animation_text.clone(),
+                        interval: storage.animation_interval,
+                        ..Default::default()
+                    },
+                    ..Default::default()
+                },
+                rundown: Rundown {
+                    steps: storage.rundown.clone(),
+                    auto_advance: storage.rundown_auto_advance,
+                    ..Default::default()
+                },
+                error_reporting: ErrorReporting {
+                    enabled: storage.error_reporting,
+                    endpoint: storage.error_report_endpoint.clone(),
+                    ..Default::default()
+                },
                 darkmode: storage.darkmode,
+                control: storage.control,
                 presets,
                 preset_switch_1: storage.preset_switch_1,
                 preset_switch_2: storage.preset_switch_2,
                 preset_switch_time: storage.preset_switch_time,
+                spotify: SpotifyLogin::new(spotify::Account::load()),
+                local_media: storage.local_media,
+                hooks: Hooks::new(storage.hooks),
+                sounds: storage.sounds,
+                twitch: storage.twitch.load(),
+                pomodoro: Pomodoro::new(storage.pomodoro),
+                obs: Obs::new(storage.obs),
+                time_tracking: TimeTracking::new(
+                    storage.time_tracking,
+                    storage.time_tracking_service,
+                    storage.time_tracking_api_key,
+                    storage.time_tracking_workspace,
+                ),
+                custom_status: CustomStatus {
+                    enabled: storage.custom_status,
+                    emoji: storage.custom_status_emoji,
+                    text: storage.custom_status_text,
+                },
+                font_path: storage.font_path,
+                font_size: storage.font_size,
+                layout: storage.layout,
+                rules: Rules::new(storage.banned_words, storage.patterns),
+                guard: Guard::new(storage.guard, storage.flagged_words),
+                status_file: StatusFile::new(storage.status_file),
+                idle: Idle::new(storage.idle, storage.idle_minutes, storage.afk_preset),
+                retention: Retention {
+                    warn_on_close: storage.warn_on_close,
+                    ..Default::default()
+                },
+                window: WindowSettings {
+                    decorations: storage.window_decorations,
+                    decorated: storage.window_decorations,
+                    scale: storage.window_scale,
+                    ..Default::default()
+                },
+                display_mode: DisplayMode {
+                    locked: storage.locked,
+                    passphrase_hash: storage.passphrase_hash,
+                    ..Default::default()
+                },
                 ..Default::default()
             },
-            client,
-            ..Default::default()
+            accounts,
+            profiles,
+            last_session: LastSession {
+                session: storage.session,
+                dismissed: safe_mode,
+            },
+            history: History {
+                details: storage.details_history,
+                state: storage.state_history,
+            },
+            app_cache: AppCache::load(),
+            remote: Remote::new(storage.remote_devices),
+            slots: Slots {
+                a: storage.slot_a,
+                b: storage.slot_b,
+                current: storage.slot,
+            },
+            setup: Setup::default(),
+            safe_mode,
+            // spelled out, `..Default::default()` would build a whole second
+            // App, its menu bar and remote included, only to drop most of it
+            error_bar: ErrorBar::default(),
+            automation: Automation::default(),
+            throttle: Throttle::default(),
+            self_check: SelfCheck::default(),
+            preview_card: PreviewCard::default(),
+            join_requests: JoinRequests::default(),
+            event_log: EventLog::default(),
+            debug_overlay: DebugOverlay::default(),
+            control: None,
+            local: None,
+            control_failed: false,
+            metrics: None,
+            mdns: None,
+            worker: Worker::default(),
+            connected_id: String::new(),
+            switch_to: None,
+            switch_prompt: false,
+            user: None,
+            sending: VecDeque::new(),
+            connected: false,
+            started: Utc::now(),
+            last_update: Utc::now(),
+            last_sent: None,
+            next_preset: None,
+            colored: None,
+            revert: None,
+            pending_update: false,
+            reconnecting: false,
+            backoff: Backoff::default(),
+            next_ping: Utc::now(),
+            waiting_for_discord: false,
+            next_discord_check: Utc::now(),
+            last_activity: None,
+            shown_problems: None,
+            next_refresh: Utc::now(),
+            low_power: false,
         };
-        if !error.is_empty() {
-            app.error_bar.new_error(error.to_string());
-        }
-        if storage.autoconnect && error.is_empty() {
-            app.set_presence();
-            app.connected = true;
+        app.colored = Some(Preset::from_app(&app)).filter(|_| !app.color.is_empty());
+        integrations::set_sources(app.sources());
+        if autoconnect && !safe_mode {
+            match ipc::discord_running() {
+                true => app.start_connect(Origin::Startup),
+                false => app.waiting_for_discord = true,
+            }
         }
         app
     }
+
+    fn connect(&mut self) {
+        self.start_connect(Origin::User);
+    }
+
+    /// The music integrations that are set up, in the order they're asked.
+    fn sources(&self) -> Vec<Box<dyn integrations::Source>> {
+        let mut sources: Vec<Box<dyn integrations::Source>> = Vec::new();
+        if self.safe_mode {
+            return sources;
+        }
+        if self.menu_bar.local_media && media::AVAILABLE {
+            sources.push(Box::new(media::LocalPlayers));
+        }
+        // read from disk, Spotify replaces the token the app started with
+        if let (true, Some(account)) = (
+            self.menu_bar.spotify.account.is_some(),
+            spotify::Account::load(),
+        ) {
+            sources.push(Box::new(Spotify::new(account)));
+        }
+        sources
+    }
+
+    /// Fills the form with what was sent last session and reconnects when
+    /// the app was connected then.
+    fn restore(&mut self, session: Session) {
+        if let Some(id) = session.preset.ID.as_ref() {
+            self.id = id.clone();
+        }
+        self.menu_bar.loaded_preset = Some(session.preset);
+        if session.connected && !self.connected && !self.worker.connecting() && !self.id.is_empty()
+        {
+            self.connect();
+        }
+    }
+
+    /// Asks the worker for a new connection, `poll_worker` handles the answer.
+    fn start_connect(&mut self, origin: Origin) {
+        self.waiting_for_discord = false;
+        self.connected_id = self.id.clone();
+        self.worker.send(Job::Connect {
+            id: self.id.clone(),
+            pipe: self.accounts.selected(),
+            flavor: self.accounts.flavor(),
+            mirror: self.menu_bar.mirror,
+            origin,
+        });
+    }
+
+    /// Starts reconnecting after a ping found the connection dropped.
+    fn lost_connection(&mut self, reason: String) {
+        // a disconnect may have been asked for while the ping ran
+        if !self.connected {
+            return;
+        }
+        self.hook(Hook::Disconnected, &[("ERROR", &reason)]);
+        self.connected = false;
+        self.reconnecting = true;
+        self.backoff.reset();
+    }
+
+    /// Applies what the worker finished since the last frame.
+    fn poll_worker(&mut self) {
+        while let Some(reason) = self.worker.lost() {
+            self.event_log.add(format!("Ping failed: {reason}"));
+            self.lost_connection(reason);
+        }
+        while let Some(event) = self.worker.event() {
+            self.event_log.add(format!("Event {}", event["evt"]));
+            if event["evt"] == "ACTIVITY_JOIN_REQUEST" {
+                self.join_requests.add(&event);
+            }
+        }
+        while let Some(reply) = self.worker.poll() {
+            // pings run every few seconds and would bury everything else
+            if !matches!(reply, Reply::Pinged(Ok(_))) {
+                self.event_log.add(reply.summary());
+            }
+            match reply {
+                Reply::Connected {
+                    result: Ok(user), ..
+                } => {
+                    let name = user.clone().unwrap_or_default();
+                    self.hook(Hook::Connected, &[("USER", &name)]);
+                    self.user = user;
+                    self.connected = true;
+                    self.backoff.reset();
+                    match self.reconnecting && !self.pending_update {
+                        // show what Discord showed before the connection dropped
+                        true => {
+                            self.reconnecting = false;
+                            if let Some(activity) = self.last_activity.clone() {
+                                // the rules may have changed since it was sent
+                                match self.menu_bar.rules.check(&activity).into_iter().next() {
+                                    Some(problem) => self
+                                        .error_bar
+                                        .new_error(format!("Presence not sent again: {problem}")),
+                                    None => {
+                                        self.sending.push_back((activity.clone(), None));
+                                        self.worker.send(Job::SetActivity(activity));
+                                    }
+                                }
+                            }
+                        }
+                        false => {
+                            self.last_update = Utc::now();
+                            self.set_presence(false);
+                        }
+                    }
+                }
+                Reply::Connected {
+                    origin,
+                    result: Err(reason),
+                } => {
+                    self.connected = false;
+                    match origin {
+                        Origin::User => {
+                            self.error_bar.new_error(Error::Connect(reason).to_string())
+                        }
+                        // Discord closed again before the connection was made
+                        Origin::Startup if !ipc::discord_running() => {
+                            self.waiting_for_discord = true
+                        }
+                        Origin::Startup => self
+                            .error_bar
+                            .new_error(Error::AutoConnect(reason).to_string()),
+                        Origin::Retry => self.backoff.failed(),
+                    }
+                }
+                Reply::ActivitySet { result, connected } => self.activity_set(result, connected),
+                Reply::JoinAnswered(Err(reason)) => self
+                    .error_bar
+                    .new_error(format!("Failed to answer the join request: {reason}")),
+                Reply::JoinAnswered(Ok(_)) => (),
+                // a disconnect may have been asked for while the ping ran
+                Reply::Pinged(Err(reason)) => self.lost_connection(reason),
+                Reply::Pinged(_) | Reply::Closed(Ok(_)) => (),
+                // the connection is dropped either way, a failure only means Discord wasn't told
+                Reply::Closed(Err(reason)) => self
+                    .error_bar
+                    .new_error(Error::Disconnect(reason).to_string()),
+            }
+        }
+    }
+
+    /// Watches the connection and restores it with backoff when it drops,
+    /// or when an update is waiting for Discord.
+    fn watchdog(&mut self, ctx: &egui::Context) {
+        // the answer to the last job decides what comes next
+        if self.worker.busy().is_some() {
+            ctx.request_repaint_after(Duration::from_millis(100));
+            return;
+        }
+        if self.connected {
+            if self.next_ping <= Utc::now() {
+                self.next_ping = Utc::now() + chrono::Duration::seconds(PING_INTERVAL);
+                self.worker.send(Job::Ping);
+            }
+            ctx.request_repaint_after(Duration::from_secs(PING_INTERVAL as u64));
+            return;
+        }
+        if self.waiting_for_discord && !self.id.is_empty() {
+            if self.next_discord_check <= Utc::now() {
+                self.next_discord_check =
+                    Utc::now() + chrono::Duration::seconds(DISCORD_POLL_INTERVAL);
+                if ipc::discord_running() {
+                    self.waiting_for_discord = false;
+                    self.start_connect(Origin::Startup);
+                }
+            }
+            ctx.request_repaint_after(Duration::from_secs(DISCORD_POLL_INTERVAL as u64));
+            return;
+        }
+        if !(self.pending_update || self.reconnecting) || self.id.is_empty() {
+            return;
+        }
+        if self.backoff.ready() {
+            // a pending update sends the form, otherwise what Discord showed before
+            self.start_connect(Origin::Retry);
+        }
+        ctx.request_repaint_after(Duration::from_secs(1));
+    }
+
+    fn disconnect(&mut self) {
+        if self.connected {
+            self.hook(Hook::Disconnected, &[]);
+        }
+        self.waiting_for_discord = false;
+        self.worker.send(Job::Close);
+        self.connected = false;
+        self.user = None;
+        self.reconnecting = false;
+        self.pending_update = false;
+    }
+
+    /// Runs the commands that came in through the control API and the command line.
+    fn run_control(&mut self) {
+        loop {
+            let request = match self.control.as_ref().and_then(ControlServer::poll) {
+                Some(request) => request,
+                None => match self.local.as_ref().and_then(LocalServer::poll) {
+                    Some(request) => request,
+                    None => break,
+                },
+            };
+            let result = self.run_command(request.command);
+            let _ = request.reply.send(result);
+        }
+    }
+
+    fn run_command(&mut self, command: Command) -> Result<serde_json::Value, String> {
+        self.menu_bar.display_mode.check(&command)?;
+        match command {
+            Command::Status => {}
+            Command::Overlay => return Ok(self.overlay()),
+            Command::Presets => {
+                let presets: Vec<InAppPreset> =
+                    from_str(&self.menu_bar.presets).unwrap_or_default();
+                let names: Vec<String> = presets.into_iter().map(|preset| preset.name).collect();
+                return Ok(serde_json::json!({ "presets": names }));
+            }
+            Command::Connect if self.connected || self.worker.connecting() => {}
+            Command::Connect => self.connect(),
+            Command::Disconnect if self.connected => self.disconnect(),
+            Command::Disconnect => {}
+            Command::Update => {
+                self.last_update = Utc::now();
+                match self.connected {
+                    true => self.set_presence(false),
+                    false => self.pending_update = true,
+                }
+            }
+            Command::Goal(change) => self.menu_bar.goal.apply(change),
+            Command::FocusStart(minutes) => {
+                let presets: Vec<InAppPreset> =
+                    from_str(&self.menu_bar.presets).unwrap_or_default();
+                let mut preset = match presets
+                    .into_iter()
+                    .find(|preset| preset.name == FOCUS_PRESET)
+                {
+                    Some(preset) => Preset::from_in_app(preset),
+                    None => {
+                        return Err(format!("Save an in-app preset named {FOCUS_PRESET} first"))
+                    }
+                };
+                preset.EndAfterMinutes = Some(minutes as i64);
+                preset.NextPreset = None;
+                if let Err(problems) = preset.validate() {
+                    return Err(format!("Invalid preset: {problems}"));
+                }
+                let end = Utc::now() + chrono::Duration::minutes(minutes as i64);
+                self.menu_bar.revert_at = Some(end);
+                self.menu_bar.loaded_preset = Some(preset);
+                self.load_preset();
+            }
+            Command::FocusEnd => {
+                if let Some((_, previous)) = self.revert.take() {
+                    self.menu_bar.loaded_preset = Some(previous);
+                    self.load_preset();
+                }
+            }
+            Command::Set { details, state } => {
+                if let Some(details) = details {
+                    self.details = details;
+                }
+                if let Some(state) = state {
+                    self.state = state;
+                }
+                // scripts may set texts faster than Discord accepts them
+                match self.connected {
+                    true => self.throttle.queue("Set".to_string()),
+                    false => self.pending_update = true,
+                }
+            }
+            Command::LoadPreset(name) => {
+                let presets: Vec<InAppPreset> = match from_str(&self.menu_bar.presets) {
+                    Ok(presets) => presets,
+                    Err(_) => Vec::new(),
+                };
+                let preset = match presets.into_iter().find(|preset| preset.name == name) {
+                    Some(preset) => Preset::from_in_app(preset),
+                    None => return Err(format!("No preset named {name}")),
+                };
+                // answered here so the caller learns why it wasn't applied
+                if let Err(problems) = preset.validate() {
+                    return Err(format!("Invalid preset: {problems}"));
+                }
+                self.menu_bar.loaded_preset = Some(preset);
+                self.load_preset();
+            }
+        }
+        Ok(serde_json::json!({
+            "connected": self.connected,
+            "user": self.user,
+            "id": self.id,
+            "details": self.details,
+            "state": self.state,
+            "pending": self.pending_update,
+            "busy": self.worker.busy(),
+            "goal": {
+                "current": self.menu_bar.goal.current,
+                "target": self.menu_bar.goal.target,
+            },
+        }))
+    }
+
+    /// What Discord shows, with image URLs and times in seconds, for the overlay.
+    fn overlay(&self) -> serde_json::Value {
+        let activity = match (&self.last_activity, self.connected) {
+            (Some(activity), true) => activity,
+            _ => return serde_json::json!({ "shown": false }),
+        };
+        let app = self.app_cache.get(&self.connected_id);
+        let assets = &activity["assets"];
+        let text = |value: &serde_json::Value| value.as_str().unwrap_or_default().to_string();
+        // raw timestamps may be given in milliseconds
+        let seconds = |value: &serde_json::Value| {
+            value.as_i64().map(|time| match time > 100_000_000_000 {
+                true => time / 1000,
+                false => time,
+            })
+        };
+        serde_json::json!({
+            "shown": true,
+            "name": app.map(|app| app.name.clone()).unwrap_or_default(),
+            "details": text(&activity["details"]),
+            "state": text(&activity["state"]),
+            "large_image": preview_card::image_url(&assets["large_image"], app),
+            "large_text": text(&assets["large_text"]),
+            "small_image": preview_card::image_url(&assets["small_image"], app),
+            "small_text": text(&assets["small_text"]),
+            "start": seconds(&activity["timestamps"]["start"]),
+            "end": seconds(&activity["timestamps"]["end"]),
+        })
+    }
+
+    /// Runs the `[startup]` actions from the config file.
+    fn run_startup(&mut self, startup: &Startup) {
+        if let Some(name) = &startup.preset {
+            let presets: Vec<InAppPreset> = match from_str(&self.menu_bar.presets) {
+                Ok(presets) => presets,
+                Err(_) => Vec::new(),
+            };
+            let preset = match presets.iter().find(|preset| &preset.name == name) {
+                Some(preset) => Ok(Preset::from_in_app(preset.clone())),
+                None => Preset::from_file(Path::new(name), &presets),
+            };
+            match preset.and_then(|preset| preset.validate().map(|_| preset)) {
+                Ok(preset) => {
+                    self.menu_bar.loaded_preset = Some(preset);
+                    self.load_preset();
+                }
+                Err(error) => self.error_bar.new_error(format!("Startup preset: {error}")),
+            }
+        }
+        if startup.connect && !self.connected && !self.id.is_empty() {
+            self.connect();
+        }
+        if startup.rotation {
+            self.menu_bar.run_switcher = true;
+        }
+    }
 }
 
 impl eframe::App for App {
@@ -194,27 +1065,7 @@ impl eframe::App for App {
         false
     }
     fn save(&mut self, storage: &mut dyn eframe::Storage) {
-        let save = Storage {
-            id: &self.id,
-            details: &self.details,
-            state: &self.state,
-            party: self.party,
-            party_of: self.party_of,
-            timestamp: self.timestamp.timestamp,
-            large_image_key: &self.first_img.key,
-            small_image_key: &self.second_img.key,
-            large_image_label: &self.first_img.text,
-            small_image_label: &self.second_img.text,
-            first_btn_label: &self.first_btn.label,
-            second_btn_label: &self.second_btn.label,
-            first_btn_url: &self.first_btn.url,
-            second_btn_url: &self.second_btn.url,
-            autoconnect: self.menu_bar.autoconnect,
-            darkmode: self.menu_bar.darkmode,
-            preset_switch_1: self.menu_bar.preset_switch_1.clone(),
-            preset_switch_2: self.menu_bar.preset_switch_2.clone(),
-            preset_switch_time: self.menu_bar.preset_switch_time,
-        };
+        let save = Storage::from_app(self);
         let storage_string = match to_string(&save) {
             Ok(save) => save,
             Err(_) => "".to_string(),
@@ -224,214 +1075,1158 @@ impl eframe::App for App {
             return;
         }
         storage.set_string("settings", storage_string);
-        storage.set_string("presets", self.menu_bar.presets.clone());
+        if let Err(error) = self.menu_bar.preset_manager.save(&self.menu_bar.presets) {
+            self.error_bar.new_error(error);
+        }
+        for (name, secret) in [
+            (obs::SECRET, &self.menu_bar.obs.settings.password),
+            (twitch::SECRET, &self.menu_bar.twitch.client_secret),
+            (time_tracking::SECRET, &self.menu_bar.time_tracking.api_key),
+        ] {
+            if let Err(error) = secrets::set(name, secret) {
+                self.error_bar.new_error(error);
+            }
+        }
+    }
+    fn on_close_event(&mut self) -> bool {
+        let shown = self.connected && self.last_activity.is_some();
+        let close = self.menu_bar.retention.allow_close(shown);
+        if close {
+            // nothing can show the error anymore
+            let _ = self.menu_bar.time_tracking.finish();
+        }
+        close
     }
     fn auto_save_interval(&self) -> std::time::Duration {
         Duration::from_secs(5)
     }
-    fn update(&mut self, ctx: &egui::Context, _frame: &mut eframe::Frame) {
+    fn update(&mut self, ctx: &egui::Context, frame: &mut eframe::Frame) {
+        let started = Instant::now();
+        // a minimized window loses the focus everywhere, on Windows it also has no size
+        self.low_power = {
+            let raw = &ctx.input().raw;
+            !raw.has_focus || raw.screen_rect.is_none()
+        };
+        self.automation.low_power = self.low_power;
         self.menu_bar.run(ctx);
+        self.menu_bar
+            .window
+            .apply(ctx, frame.info().native_pixels_per_point);
+        if self.menu_bar.window.drag {
+            frame.drag_window();
+        }
+        if self.menu_bar.fonts_changed || self.menu_bar.font_size_changed {
+            // reading the font files is slow, a new size only restyles the text
+            if self.menu_bar.fonts_changed {
+                if let Err(error) = fonts::setup_fonts(ctx, &self.menu_bar.font_path) {
+                    self.error_bar.new_error(error);
+                }
+            }
+            fonts::set_font_size(ctx, self.menu_bar.font_size);
+            self.menu_bar.fonts_changed = false;
+            self.menu_bar.font_size_changed = false;
+            self.profiles.set_font(
+                &self.id,
+                ProfileFont {
+                    path: self.menu_bar.font_path.clone(),
+                    size: self.menu_bar.font_size,
+                },
+            );
+        }
+        let layout = self.menu_bar.layout;
+        if layout == UiLayout::Developer {
+            let next = self.activity().map_err(|error| error.to_string());
+            layouts::developer_panel(ctx, next, self.last_activity.as_ref(), &mut self.event_log);
+        }
+        let live = self
+            .last_sent
+            .as_ref()
+            .filter(|_| self.connected && self.last_activity.is_some())
+            .and_then(|sent| sent.Color.as_deref())
+            .and_then(preset::parse_color);
+        if let Some([red, green, blue]) = live {
+            egui::TopBottomPanel::bottom("preset_color")
+                .resizable(false)
+                .height_range(4.0..=4.0)
+                .frame(egui::Frame::none().fill(Color32::from_rgb(red, green, blue)))
+                .show(ctx, |_| ());
+        }
         egui::CentralPanel::default().show(ctx, |ui| {
+            ui.set_enabled(!self.menu_bar.display_mode.locked);
             ui.with_layout(Layout::top_down_justified(Align::Center), |ui| {
                 ui.heading("Discord Presence");
             });
+            if self.last_sent.is_none() {
+                if let Some(session) = self.last_session.run(ui) {
+                    self.restore(session);
+                }
+            }
             ui.horizontal(|ui| {
                 ui.add_space(60.);
                 ui.label("ID");
-                ui.text_edit_singleline(&mut self.id);
+                let id = ui.add_enabled(
+                    !self.id_locked,
+                    egui::TextEdit::singleline(&mut self.id).desired_width(180.),
+                );
+                if let Some(app) = self.app_cache.get(&self.id) {
+                    id.on_hover_text(&app.name)
+                        .on_disabled_hover_text(&app.name);
+                }
+                let lock = match self.id_locked {
+                    true => "🔒",
+                    false => "🔓",
+                };
+                ui.toggle_value(&mut self.id_locked, lock)
+                    .on_hover_text("Lock the ID so it can't be changed by accident");
+                ui.add_enabled_ui(!self.id_locked, |ui| {
+                    if let Some(id) = self.profiles.run(ui, &self.id) {
+                        self.id = id;
+                        self.profile_font();
+                    }
+                    ui.menu_button("🕘", |ui| {
+                        for app in self.app_cache.recent() {
+                            if ui.button(format!("{} ({})", app.name, app.id)).clicked() {
+                                self.id = app.id.clone();
+                                ui.close_menu();
+                            }
+                        }
+                    });
+                });
                 ui.add_space(10.);
                 if ui
-                    .add_enabled(!self.connected, egui::Button::new("Connect"))
+                    .add_enabled(
+                        !self.connected
+                            && !self.worker.connecting()
+                            && app_cache::check_id(&self.id).is_ok(),
+                        egui::Button::new("Connect"),
+                    )
                     .clicked()
-                    && !self.id.is_empty()
                 {
-                    self.client = DiscordIpcClient::new(&self.id)
-                        .expect("Theres no reason for this function to fail bruh");
-                    let error = match self.client.connect() {
-                        Ok(_) => "",
-                        Err(_) => "Failed to connect to discord",
-                    };
-                    if error.is_empty() {
-                        self.last_update = Utc::now();
-                        self.set_presence();
-                        self.connected = true;
-                    } else {
-                        self.error_bar.new_error(error.to_string());
-                    }
+                    self.connect();
                 }
                 ui.add_space(10.);
                 if ui
                     .add_enabled(self.connected, egui::Button::new("Disconnect"))
                     .clicked()
                 {
-                    self.client
-                        .close()
-                        .expect("Theres no reason for this function to fail bruh");
-                    self.connected = false;
+                    self.disconnect();
                 }
             });
+            self.application(ui);
+            if layout != UiLayout::Compact {
+                ui.add_space(5.);
+                ui.horizontal(|ui| {
+                    ui.add_space(14.);
+                    ui.label("Account");
+                    self.accounts.run(ui, &self.id, self.connected);
+                    if let (true, Some(user)) = (self.connected, &self.user) {
+                        ui.label(format!("Connected as {user}"));
+                    }
+                });
+            }
             ui.add_space(5.);
             ui.horizontal(|ui| {
                 ui.add_space(34.);
                 ui.label("Details");
-                ui.text_edit_singleline(&mut self.details);
+                let details = ui.text_edit_singleline(&mut self.details);
+                autocomplete::suggest(ui, &details, &mut self.details, &self.history.details);
+                let details = placeholders::expand(&self.details, &self.menu_bar.goal);
+                limits::DETAILS.counter(ui, &details);
             });
+            self.preview(ui, &self.details, 80., &limits::DETAILS);
             ui.add_space(5.);
             ui.horizontal(|ui| {
                 ui.add_space(42.);
                 ui.label("State");
-                ui.text_edit_singleline(&mut self.state);
+                let state = ui.text_edit_singleline(&mut self.state);
+                autocomplete::suggest(ui, &state, &mut self.state, &self.history.state);
+                let state = placeholders::expand(&self.state, &self.menu_bar.goal);
+                limits::STATE.counter(ui, &state);
+                if layout == UiLayout::Compact {
+                    return;
+                }
                 ui.label("Party");
                 ui.add(egui::DragValue::new(&mut self.party_of).clamp_range(1..=32));
                 ui.label("of");
                 ui.add(egui::DragValue::new(&mut self.party).clamp_range(0..=32));
             });
-            ui.add_space(15.);
-            self.timestamp.run(ui);
-            ui.add_space(15.);
-            ui.horizontal(|ui| {
-                ui.add_space(75.);
-                self.first_img.run(ui, "Large Image");
-                self.second_img.run(ui, "Small Image");
-            });
-            ui.horizontal(|ui| {
-                ui.add_space(75.);
-                self.first_btn.run(ui, "Button 1");
-                self.second_btn.run(ui, "Button 2");
+            self.preview(ui, &self.state, 80., &limits::STATE);
+            if layout != UiLayout::Compact {
+                self.form_rest(ui);
+            }
+            ui.add_space(match layout {
+                UiLayout::Compact => 15.,
+                _ => 50.,
             });
-            ui.add_space(50.);
+            let problems = self.shown_problems();
             ui.with_layout(Layout::top_down(Align::Center), |ui| {
+                match self.slots.run(ui, self.menu_bar.display_mode.locked) {
+                    Some(slots::Action::Load(preset)) => self.menu_bar.loaded_preset = Some(*preset),
+                    Some(slots::Action::Save(slot)) => {
+                        self.slots.save(slot, Preset::from_app(self))
+                    }
+                    None => (),
+                }
                 if ui
                     .add_enabled(
-                        self.connected,
+                        !self.id.is_empty() && problems.is_empty(),
                         egui::widgets::Button::new("Update Presence"),
                     )
+                    .on_disabled_hover_text("The presence can't be sent, see below")
                     .clicked()
                 {
                     self.last_update = Utc::now();
-                    self.set_presence()
+                    match self.connected {
+                        true => self.set_presence(true),
+                        // sent by the watchdog once Discord can be reached
+                        false => self.pending_update = true,
+                    }
+                }
+                ui.toggle_value(&mut self.preview_card.open, "Preview");
+                for problem in problems.iter() {
+                    ui.label(RichText::new(problem).small().color(Color32::LIGHT_RED));
+                }
+                if let Ok(payload) = self.activity() {
+                    for warning in self.menu_bar.guard.check(&payload) {
+                        ui.label(
+                            RichText::new(warning)
+                                .small()
+                                .color(Color32::from_rgb(230, 160, 60)),
+                        );
+                    }
+                }
+                if self.safe_mode {
+                    ui.label(
+                        RichText::new(
+                            "Safe mode: autoconnect, automation, the webhook and remote control are off",
+                        )
+                        .small()
+                        .color(Color32::from_rgb(230, 160, 60)),
+                    );
+                }
+                if let Some(busy) = self.worker.busy() {
+                    ui.horizontal(|ui| {
+                        ui.spinner();
+                        ui.label(
+                            RichText::new(busy)
+                                .small()
+                                .color(Color32::from_rgb(163, 163, 163)),
+                        );
+                    });
+                } else if self.pending_update || self.reconnecting {
+                    let text = match self.pending_update {
+                        true => "Update pending, waiting for Discord",
+                        false => "Connection lost, reconnecting",
+                    };
+                    ui.horizontal(|ui| {
+                        ui.label(
+                            RichText::new(format!("{text} ({}s)", self.backoff.seconds_left()))
+                                .small()
+                                .color(Color32::from_rgb(163, 163, 163)),
+                        );
+                        if ui.small_button("Retry now").clicked() {
+                            self.backoff.retry_now();
+                        }
+                    });
+                } else if self.waiting_for_discord {
+                    ui.horizontal(|ui| {
+                        ui.label(
+                            RichText::new("Waiting for Discord to start, connecting then")
+                                .small()
+                                .color(Color32::from_rgb(163, 163, 163)),
+                        );
+                        if ui.small_button("Stop waiting").clicked() {
+                            self.waiting_for_discord = false;
+                        }
+                    });
+                } else {
+                    retention::note(ui, self.connected, self.last_activity.is_some());
+                }
+                if self.connected {
+                    self.changes(ui);
+                }
+                self.throttle.run(ui);
+                if let Some((until, _)) = &self.revert {
+                    let until = until.with_timezone(&Local).format("%H:%M");
+                    let mut keep = false;
+                    ui.horizontal(|ui| {
+                        ui.label(format!("Reverting at {until}"));
+                        keep = ui.small_button("Keep").clicked();
+                    });
+                    if keep {
+                        self.revert = None;
+                    }
                 }
             });
         });
 
-        //preset stuff
-        self.load_preset();
+        let tick_started = Instant::now();
+        self.tick(ctx);
+        let tick = tick_started.elapsed();
         self.save_preset();
         self.save_preset_in_app();
 
+        if self.menu_bar.setup {
+            self.menu_bar.setup = false;
+            self.setup.start();
+        }
+        if self.menu_bar.display_mode.locked {
+            self.setup.open = false;
+            self.self_check.open = false;
+            self.remote.open = false;
+        }
+        if let Some(app) = self.setup.run(ctx) {
+            self.id = app.id.clone();
+            self.app_cache.insert(app);
+        }
+        if self.menu_bar.self_check {
+            self.menu_bar.self_check = false;
+            let keys = [self.first_img.key.as_str(), self.second_img.key.as_str()];
+            self.self_check.start(&self.id, keys, false);
+        }
+        let keys = [self.first_img.key.as_str(), self.second_img.key.as_str()];
+        self.self_check.run(ctx, &self.id, keys);
+        if self.menu_bar.remote {
+            self.menu_bar.remote = false;
+            self.remote.open = true;
+        }
+        self.remote
+            .run(ctx, self.control.as_ref().map(|control| control.addr));
+        self.switch_prompt(ctx);
+        if self.menu_bar.retention.run(ctx) {
+            frame.close();
+        }
+        if let Some((user_id, accept)) = self.join_requests.run(ctx) {
+            self.worker.send(Job::AnswerJoin { user_id, accept });
+        }
+        if self.preview_card.open && !self.low_power {
+            if let Ok(activity) = self.activity() {
+                self.preview_card
+                    .run(ctx, &activity, self.app_cache.get(&self.id));
+            }
+        }
+
         //Error bar
         self.error_bar.run(ctx);
+        if self.menu_bar.bug_report.open {
+            self.menu_bar.bug_report.errors = self.error_bar.history.clone();
+        }
+        self.debug_overlay.frame(started.elapsed(), tick);
+        self.debug_overlay
+            .run(ctx, self.worker.queued(), self.sending.len());
     }
 }
 impl App {
-    fn set_presence(&mut self) {
-        if self.id != self.client.client_id {
-            self.client.close().expect("No Reason for this to fail...");
-            self.client = DiscordIpcClient::new(&self.id).expect("No Reason for this to fail...");
-            let error = match self.client.connect() {
-                Ok(_) => "",
-                Err(_) => "Failed to connect to discord",
+    /// Whether something shows its own presence for now, the automatic
+    /// preset drivers wait until it's done.
+    fn drivers_paused(&self) -> bool {
+        self.menu_bar.idle.away() || self.menu_bar.pomodoro.running() || self.menu_bar.obs.live
+    }
+
+    /// The work done every frame besides drawing, also run by headless mode.
+    fn tick(&mut self, ctx: &egui::Context) {
+        self.sync_control(ctx);
+        self.menu_bar.spotify.poll();
+        if !self.safe_mode {
+            twitch::configure(&self.menu_bar.twitch);
+        }
+        if self.menu_bar.spotify.changed || self.menu_bar.sources_changed {
+            self.menu_bar.spotify.changed = false;
+            self.menu_bar.sources_changed = false;
+            integrations::set_sources(self.sources());
+        }
+        let paused = self.drivers_paused();
+        if self.menu_bar.automation && !self.safe_mode && !paused {
+            match self
+                .automation
+                .run(&self.menu_bar.presets, &self.menu_bar.process_rules.rules)
+            {
+                Some(automation::Switch::Load(preset)) => {
+                    if self.automation.previous.is_none() {
+                        let previous = match &self.last_sent {
+                            Some(sent) => sent.clone(),
+                            None => Preset::from_app(self),
+                        };
+                        self.automation.previous = Some(previous);
+                    }
+                    self.menu_bar.loaded_preset = Some(preset.to_preset());
+                }
+                Some(automation::Switch::Restore) => {
+                    if let Some(previous) = self.automation.previous.take() {
+                        self.menu_bar.loaded_preset = Some(previous);
+                    }
+                }
+                None => (),
+            }
+            let wait = match self.low_power {
+                true => automation::LOW_POWER_INTERVAL as u64,
+                false => 1,
             };
-            if !error.is_empty() {
+            ctx.request_repaint_after(Duration::from_secs(wait));
+        }
+        if self.menu_bar.goal.changed {
+            self.menu_bar.goal.changed = false;
+            if self.connected {
+                self.throttle.queue("Goal".to_string());
+            }
+        }
+        if !paused && !self.safe_mode {
+            self.drive(ctx);
+        }
+        self.pomodoro(ctx);
+        self.worker.wake(ctx);
+        // join requests are the only events read, they need a join secret
+        self.worker
+            .listen(!self.low_power && !self.party_secrets.join.trim().is_empty());
+        self.poll_worker();
+        self.run_control();
+        self.watchdog(ctx);
+        if !self.menu_bar.clock.poll() {
+            ctx.request_repaint_after(Duration::from_millis(500));
+        }
+        self.app_cache.refresh(&self.id);
+        if !self.app_cache.poll() {
+            ctx.request_repaint_after(Duration::from_millis(500));
+        }
+        self.chain_preset(ctx);
+        self.revert(ctx);
+        match self.menu_bar.obs.poll(!self.safe_mode) {
+            Some(true) => self.go_live(),
+            Some(false) => {
+                if let Some(previous) = self.menu_bar.obs.previous.take() {
+                    self.menu_bar.loaded_preset = Some(previous);
+                }
+            }
+            None => (),
+        }
+        if self.menu_bar.obs.settings.enabled {
+            ctx.request_repaint_after(Duration::from_secs(1));
+        }
+        if !self.safe_mode {
+            match self.menu_bar.idle.poll() {
+                Some(Change::Away) => self.go_away(),
+                Some(Change::Back) => self.come_back(),
+                None => (),
+            }
+            if self.menu_bar.idle.enabled {
+                ctx.request_repaint_after(Duration::from_secs(1));
+            }
+        }
+        self.refresh_placeholders(ctx);
+        let shown = self.last_activity.as_ref().filter(|_| self.connected);
+        if let Err(error) = self.menu_bar.status_file.sync(shown) {
+            self.error_bar.new_error(error);
+        }
+        let session = format!("{}\n{}\n{}", self.id, self.details, self.state);
+        if !self.safe_mode {
+            if let Err(error) = self
+                .menu_bar
+                .time_tracking
+                .sync(shown.map(|activity| (session, activity)))
+            {
+                self.error_bar.new_error(error);
+            }
+        }
+        for error in self.menu_bar.sounds.errors() {
+            self.error_bar.new_error(error);
+        }
+        self.load_preset();
+        self.drop_stale_color();
+        if let Some(wait) = self.throttle.wait() {
+            match wait.to_std() {
+                Ok(wait) if !wait.is_zero() => ctx.request_repaint_after(wait),
+                _ if self.connected => self.set_presence(false),
+                _ => self.throttle.sent(),
+            }
+        }
+    }
+
+    /// Warns about buttons or images that are the same, Discord shows those
+    /// oddly, and offers to clear the second one.
+    fn duplicates(&mut self, ui: &mut egui::Ui) {
+        let same = |first: &str, second: &str| !first.is_empty() && first == second;
+        // the last field says whether the fix clears the button or the image
+        let warnings = [
+            (
+                same(&self.first_btn.label, &self.second_btn.label),
+                "Both buttons have the same label",
+                true,
+            ),
+            (
+                same(&self.first_btn.url, &self.second_btn.url),
+                "Both buttons open the same URL",
+                true,
+            ),
+            (
+                same(&self.first_img.key, &self.second_img.key),
+                "The large and small image are the same",
+                false,
+            ),
+        ];
+        for (_, warning, button) in warnings.into_iter().filter(|(duplicate, _, _)| *duplicate) {
+            let mut clear = false;
+            ui.horizontal(|ui| {
+                ui.add_space(75.);
+                ui.label(
+                    RichText::new(warning)
+                        .small()
+                        .color(Color32::from_rgb(230, 160, 60)),
+                );
+                let fix = match button {
+                    true => "Clear button 2",
+                    false => "Clear small image",
+                };
+                clear = ui.small_button(fix).clicked();
+            });
+            match (clear, button) {
+                (true, true) => self.second_btn = PresenceButton::default(),
+                (true, false) => {
+                    self.second_img.key.clear();
+                    self.second_img.text.clear();
+                }
+                (false, _) => (),
+            }
+        }
+    }
+
+    /// Asks before the next update switches to another application.
+    fn switch_prompt(&mut self, ctx: &egui::Context) {
+        // changing the ID back answers the question as well
+        if self.id == self.connected_id || !self.connected {
+            self.switch_prompt = false;
+        }
+        if !self.switch_prompt {
+            return;
+        }
+        let name = |id: &str| match self.app_cache.get(id) {
+            Some(app) => app.name.clone(),
+            None => id.to_string(),
+        };
+        let (from, to) = (name(&self.connected_id), name(&self.id));
+        let (mut switch, mut keep) = (false, false);
+        egui::containers::Window::new("Switch application?")
+            .resizable(false)
+            .collapsible(false)
+            .show(ctx, |ui| {
+                ui.label(format!(
+                    "Switching from {from} to {to} will briefly clear your presence while Discord reconnects."
+                ));
+                ui.horizontal(|ui| {
+                    switch = ui.button("Switch").clicked();
+                    keep = ui.button(format!("Keep {from}")).clicked();
+                });
+            });
+        if switch {
+            self.switch_prompt = false;
+            self.switch_to = Some(self.id.clone());
+            self.last_update = Utc::now();
+            self.set_presence(true);
+        }
+        if keep {
+            self.switch_prompt = false;
+            self.id = self.connected_id.clone();
+        }
+    }
+
+    /// Switches to the font saved with the ID's profile.
+    fn profile_font(&mut self) {
+        let font = match self.profiles.font(&self.id) {
+            Some(font) => font.clone(),
+            None => return,
+        };
+        self.menu_bar.fonts_changed = font.path != self.menu_bar.font_path;
+        self.menu_bar.font_size_changed = font.size != self.menu_bar.font_size;
+        self.menu_bar.font_path = font.path;
+        self.menu_bar.font_size = font.size;
+    }
+
+    /// Shows the name of the application the ID belongs to, or why it can't be one.
+    fn application(&mut self, ui: &mut egui::Ui) {
+        if self.id.is_empty() {
+            ui.horizontal(|ui| {
+                ui.add_space(80.);
+                if ui.small_button("No application yet? Set one up").clicked() {
+                    self.setup.start();
+                }
+            });
+            return;
+        }
+        let (text, color) = match (app_cache::check_id(&self.id), self.app_cache.get(&self.id)) {
+            (Err(problem), _) => (problem, Color32::LIGHT_RED),
+            (Ok(_), Some(app)) => (
+                format!("Application: {}", app.name),
+                Color32::from_rgb(163, 163, 163),
+            ),
+            (Ok(_), None) if self.app_cache.unknown(&self.id) => (
+                "Discord has no application with this ID".to_string(),
+                Color32::LIGHT_RED,
+            ),
+            (Ok(_), None) if self.app_cache.looking_up(&self.id) => (
+                "Looking up the application…".to_string(),
+                Color32::from_rgb(163, 163, 163),
+            ),
+            (Ok(_), None) => return,
+        };
+        ui.horizontal(|ui| {
+            ui.add_space(80.);
+            ui.label(RichText::new(text).small().color(color));
+        });
+    }
+
+    /// The form below details and state, left out by the compact layout.
+    fn form_rest(&mut self, ui: &mut egui::Ui) {
+        let has_size = self.party != 0 && !self.state.is_empty();
+        let has_buttons = [&self.first_btn, &self.second_btn]
+            .iter()
+            .any(|button| !button.label.is_empty() && !button.url.is_empty());
+        ui.horizontal(|ui| {
+            ui.add_space(75.);
+            self.party_secrets.run(ui, has_size, has_buttons);
+        });
+        ui.add_space(15.);
+        self.timestamp.run(ui);
+        ui.add_space(15.);
+        ui.horizontal(|ui| {
+            ui.add_space(75.);
+            let assets = self
+                .app_cache
+                .get(&self.id)
+                .map(|app| app.assets.clone())
+                .unwrap_or_default();
+            self.first_img.run(ui, "Large Image", &assets);
+            self.second_img.run(ui, "Small Image", &assets);
+        });
+        ui.horizontal(|ui| {
+            ui.add_space(75.);
+            self.first_btn.run(ui, "Button 1");
+            self.second_btn.run(ui, "Button 2");
+        });
+        self.duplicates(ui);
+    }
+
+    /// Why the presence can't be sent: texts Discord would reject over
+    /// their length and the user's own validation rules.
+    fn problems(&self) -> Vec<String> {
+        let mut problems = self.limit_problems();
+        if let Ok(payload) = self.activity() {
+            problems.extend(self.menu_bar.rules.check(&payload));
+        }
+        problems
+    }
+
+    /// `problems()` for the form, cached between frames.
+    fn shown_problems(&mut self) -> Vec<String> {
+        let form = Preset::from_app(self);
+        match &self.shown_problems {
+            Some((cached, at, problems))
+                if *cached == form && at.elapsed() < Duration::from_secs(1) =>
+            {
+                problems.clone()
+            }
+            _ => {
+                let problems = self.problems();
+                self.shown_problems = Some((form, Instant::now(), problems.clone()));
+                problems
+            }
+        }
+    }
+
+    /// Texts Discord would reject over their length, checked as they'd be sent.
+    fn limit_problems(&self) -> Vec<String> {
+        let goal = &self.menu_bar.goal;
+        let mut checks = vec![
+            (limits::DETAILS, placeholders::expand(&self.details, goal)),
+            (limits::STATE, placeholders::expand(&self.state, goal)),
+            (
+                limits::LARGE_TEXT,
+                placeholders::expand(&self.first_img.hover_text(), goal),
+            ),
+            (
+                limits::SMALL_TEXT,
+                placeholders::expand(&self.second_img.text, goal),
+            ),
+        ];
+        for button in [&self.first_btn, &self.second_btn] {
+            if !button.label.is_empty() && !button.url.is_empty() {
+                checks.push((limits::BUTTON_LABEL, button.label.clone()));
+                checks.push((limits::BUTTON_URL, button.url.clone()));
+            }
+        }
+        checks
+            .iter()
+            .filter_map(|(limit, text)| limit.check(text).err())
+            .collect()
+    }
+
+    /// Shows what a text with placeholders will look like on Discord and why
+    /// Discord would reject it.
+    fn preview(&self, ui: &mut egui::Ui, text: &str, indent: f32, limit: &limits::Limit) {
+        let expanded = placeholders::expand(text, &self.menu_bar.goal);
+        if expanded != text {
+            ui.horizontal(|ui| {
+                ui.add_space(indent);
+                ui.label(
+                    RichText::new(format!("→ {expanded}"))
+                        .small()
+                        .color(Color32::from_rgb(163, 163, 163)),
+                );
+            });
+        }
+        if let Err(problem) = limit.check(&expanded) {
+            ui.horizontal(|ui| {
+                ui.add_space(indent);
+                ui.label(RichText::new(problem).small().color(Color32::LIGHT_RED));
+            });
+        }
+    }
+
+    /// Lists what the next update would change compared to what Discord shows.
+    fn changes(&self, ui: &mut egui::Ui) {
+        let changed = match &self.last_sent {
+            Some(last_sent) => Preset::from_app(self).diff(last_sent),
+            None => return,
+        };
+        let text = match changed.is_empty() {
+            true => "No changes".to_string(),
+            false => format!("Changed: {}", changed.join(", ")),
+        };
+        ui.label(RichText::new(text).small());
+    }
+
+    /// Sends the form to Discord. `ask` is set when the user pressed the
+    /// button, only then switching applications asks first; updates from
+    /// the control API, the drivers and headless mode switch right away.
+    fn set_presence(&mut self, ask: bool) {
+        // the queued update is this one, whether it gets sent or not
+        self.throttle.cancel();
+        if self.id != self.connected_id {
+            if ask && self.connected && self.switch_to.as_ref() != Some(&self.id) {
+                // the presence is gone until the new application is connected
+                self.switch_prompt = true;
+                return;
+            }
+            // sent once the connection for the new ID is up
+            self.pending_update = true;
+            self.start_connect(Origin::User);
+            return;
+        }
+        let payload = match self.activity() {
+            Ok(payload) => payload,
+            Err(error) => {
                 self.error_bar.new_error(error.to_string());
                 return;
             }
+        };
+        // automation and the control API send without the button, they're checked here
+        if let Some(problem) = self.problems().into_iter().next() {
+            self.error_bar
+                .new_error(format!("Presence not sent: {problem}"));
+            return;
         }
-        let first_btn = Button::new(&self.first_btn.label, &self.first_btn.url);
-        let second_btn = Button::new(&self.second_btn.label, &self.second_btn.url);
-        let mut buttons = vec![];
+        self.sending
+            .push_back((payload.clone(), Some(Preset::from_app(self))));
+        self.worker.send(Job::SetActivity(payload));
+        self.throttle.sent();
+    }
+
+    /// The activity as it's sent to Discord, with the placeholders filled in.
+    fn activity(&self) -> Result<serde_json::Value, Error> {
+        // moments taken from the local clock are corrected, the ones picked by the user aren't
+        let clock = &self.menu_bar.clock;
         let timestamp = match self.timestamp.timestamp {
             TimestampEnum::LocalTime => {
-                let hour = Local::now()
-                    .format("%H")
-                    .to_string()
-                    .parse::<i64>()
-                    .unwrap()
-                    * 3_600;
-                let minute = Local::now()
-                    .format("%M")
-                    .to_string()
-                    .parse::<i64>()
-                    .unwrap()
-                    * 60;
-                let second = Local::now()
-                    .format("%S")
-                    .to_string()
-                    .parse::<i64>()
-                    .unwrap();
-                let local_time = Utc::now().timestamp() - (hour + minute + second);
-                Timestamps::new().start(local_time)
-            }
-            TimestampEnum::CustomTimeStamp => Timestamps::new()
-                .start(self.timestamp.date.naive_utc().and_hms(0, 0, 0).timestamp()),
-            TimestampEnum::SinceStart => Timestamps::new().start(self.started.timestamp()),
-            TimestampEnum::SinceLastUpdate => Timestamps::new().start(self.last_update.timestamp()),
+                Timestamps::new().start(clock.adjust(self.timestamp.day_start()).timestamp())
+            }
+            TimestampEnum::CustomTimeStamp => match self.timestamp.relative_start {
+                Some(start) => Timestamps::new().start(clock.adjust(start).timestamp()),
+                None => Timestamps::new().start(self.timestamp.start.timestamp()),
+            },
+            TimestampEnum::SinceStart => {
+                Timestamps::new().start(clock.adjust(self.started).timestamp())
+            }
+            TimestampEnum::SinceLastUpdate => {
+                Timestamps::new().start(clock.adjust(self.last_update).timestamp())
+            }
             _ => Timestamps::new(),
         };
-        let assets = Assets::new();
-        let assets = match self.first_img.key.as_str() {
-            "" => assets,
-            _ => assets.large_image(&self.first_img.key),
+        let timestamp = match self.timestamp.end {
+            Some(end) => timestamp.end(end.timestamp()),
+            None => timestamp,
         };
-        let assets = match self.first_img.text.as_str() {
-            "" => assets,
-            _ => assets.large_text(&self.first_img.text),
-        };
-        let assets = match self.second_img.key.as_str() {
-            "" => assets,
-            _ => assets.small_image(&self.second_img.key),
+        Preset::from_app(self).activity(timestamp, Some(&self.menu_bar.goal))
+    }
+
+    /// Handles Discord's answer to an activity from `sending`.
+    fn activity_set(&mut self, result: Result<serde_json::Value, String>, connected: bool) {
+        let (payload, sent) = match self.sending.pop_front() {
+            Some(sending) => sending,
+            None => return,
         };
-        let assets = match self.second_img.text.as_str() {
-            "" => assets,
-            _ => assets.small_text(&self.second_img.text),
+        if !connected && self.connected {
+            // the connection is gone, keep the update for the next one
+            self.connected = false;
+            match sent.is_some() {
+                true => self.pending_update = true,
+                false => self.reconnecting = true,
+            }
+        }
+        let (accepted, mut sent) = match (result, sent) {
+            (Ok(accepted), sent) => (accepted, sent),
+            // restoring is retried quietly by the watchdog
+            (Err(_), None) => {
+                self.backoff.failed();
+                return;
+            }
+            (Err(reason), Some(_)) => {
+                self.hook(Hook::UpdateFailed, &[("ERROR", &reason)]);
+                self.menu_bar.bug_report.payload = serde_json::to_string_pretty(&payload).ok();
+                self.error_bar.new_error(Error::Update(reason).to_string());
+                return;
+            }
         };
-        let activity = Activity::new().timestamps(timestamp).assets(assets);
+        self.pending_update = false;
+        self.reconnecting = false;
+        // Discord answers with the proxied form of external image links, the
+        // form takes it so later updates don't have Discord fetch them again
+        for (image, kind) in [
+            (&mut self.first_img, "large_image"),
+            (&mut self.second_img, "small_image"),
+        ] {
+            let link = image.key.trim();
+            let proxied = accepted["assets"][kind].as_str().unwrap_or_default();
+            if link.starts_with("https://")
+                && payload["assets"][kind] == link
+                && proxied.starts_with("mp:")
+            {
+                if let Some(sent) = sent.as_mut() {
+                    for key in [&mut sent.LargeKey, &mut sent.SmallKey] {
+                        if key.as_deref() == Some(image.key.as_str()) {
+                            *key = Some(proxied.to_string());
+                        }
+                    }
+                }
+                image.key = proxied.to_string();
+            }
+        }
+        let text = |key: &str| payload[key].as_str().unwrap_or_default().to_string();
+        let (details, state) = (text("details"), text("state"));
+        self.hook(
+            Hook::UpdateSent,
+            &[("DETAILS", &details), ("STATE", &state)],
+        );
+        if let Some(sent) = sent {
+            if !self.menu_bar.webhook_url.is_empty()
+                && !self.safe_mode
+                && self.last_sent.as_ref() != Some(&sent)
+            {
+                webhook::post(&self.menu_bar.webhook_url, payload.clone());
+            }
+            self.history.add(&sent);
+            self.last_sent = Some(sent);
+        }
+        // a cleared presence is sent as null
+        self.last_activity = Some(payload).filter(|payload| !payload.is_null());
+    }
+
+    /// Plays the hook's sound and runs the user's command for it, errors go
+    /// to the error bar.
+    fn hook(&mut self, hook: Hook, values: &[(&str, &str)]) {
+        self.menu_bar.sounds.play(hook);
+        if self.safe_mode {
+            return;
+        }
+        if let Err(error) = self.menu_bar.hooks.fire(hook, values) {
+            self.error_bar.new_error(error);
+        }
+    }
 
-        let activity = match self.details.as_str() {
-            "" => activity,
-            _ => activity.details(&self.details),
+    /// Clears the presence or loads the AFK preset, remembering what was shown.
+    fn go_away(&mut self) {
+        if !self.connected || self.last_activity.is_none() {
+            return;
+        }
+        let previous = match &self.last_sent {
+            Some(sent) => sent.clone(),
+            None => Preset::from_app(self),
         };
+        self.menu_bar.idle.previous = Some(previous);
+        let presets: Vec<InAppPreset> = from_str(&self.menu_bar.presets).unwrap_or_default();
+        let afk_preset = &self.menu_bar.idle.afk_preset;
+        match presets
+            .into_iter()
+            .find(|preset| &preset.name == afk_preset)
+        {
+            Some(preset) => self.menu_bar.loaded_preset = Some(Preset::from_in_app(preset)),
+            None => {
+                if !afk_preset.is_empty() {
+                    self.error_bar.new_error(format!(
+                        "AFK preset {afk_preset} not found, clearing instead"
+                    ));
+                }
+                self.sending.push_back((serde_json::Value::Null, None));
+                self.worker.send(Job::SetActivity(serde_json::Value::Null));
+            }
+        }
+    }
 
-        let activity = match self.state.as_str() {
-            "" => activity,
-            _ => activity.state(&self.state),
+    /// Shows the Live preset with the stream's title and link, keeping
+    /// what was shown for when the stream ends.
+    fn go_live(&mut self) {
+        let previous = match &self.last_sent {
+            Some(sent) => sent.clone(),
+            None => Preset::from_app(self),
         };
-        let first_btn_label_exists = !self.first_btn.label.is_empty();
-        let first_btn_url_exists = !self.first_btn.url.is_empty();
-        if first_btn_label_exists && first_btn_url_exists {
-            buttons.push(first_btn);
+        let settings = &self.menu_bar.obs.settings;
+        let presets: Vec<InAppPreset> = from_str(&self.menu_bar.presets).unwrap_or_default();
+        let mut live = match presets
+            .into_iter()
+            .find(|preset| preset.name == settings.preset)
+        {
+            Some(preset) => Preset::from_in_app(preset),
+            None => {
+                if !settings.preset.is_empty() {
+                    self.error_bar.new_error(format!(
+                        "Live preset {} not found, using the current presence",
+                        settings.preset
+                    ));
+                }
+                Preset::from_app(self)
+            }
+        };
+        let settings = &self.menu_bar.obs.settings;
+        if !settings.title.trim().is_empty() {
+            live.Details = Some(settings.title.trim().to_string());
+        }
+        if !settings.link.trim().is_empty() {
+            live.Button1Text = Some("Watch".to_string());
+            live.Button1URL = Some(settings.link.trim().to_string());
+        }
+        self.menu_bar.obs.previous = Some(previous);
+        self.menu_bar.loaded_preset = Some(live);
+        if !self.connected && !self.id.is_empty() {
+            self.connect();
+        }
+    }
+
+    /// Lets the schedule, the rotation and the animation change the presence.
+    fn drive(&mut self, ctx: &egui::Context) {
+        match self.menu_bar.scheduler.next(&self.menu_bar.presets) {
+            Some(Ok(preset)) => self.menu_bar.loaded_preset = Some(preset),
+            Some(Err(error)) => self.error_bar.new_error(error),
+            None => (),
+        }
+        if self.menu_bar.scheduler.enabled {
+            ctx.request_repaint_after(Duration::from_secs(5));
+        }
+        match self.menu_bar.rotation.next(&self.menu_bar.presets) {
+            Some(Ok(preset)) => {
+                // loaded now so the animation's frame goes over its details
+                self.menu_bar.loaded_preset = Some(preset);
+                self.load_preset();
+            }
+            Some(Err(error)) => self.error_bar.new_error(error),
+            None => (),
+        }
+        if let Some(details) = self.menu_bar.rotation.frame() {
+            self.details = details;
+            if self.connected {
+                self.throttle.queue("Animation".to_string());
+            }
+        }
+        if let Some(wait) = self
+            .menu_bar
+            .rotation
+            .wait()
+            .and_then(|wait| wait.to_std().ok())
+        {
+            ctx.request_repaint_after(wait);
+        }
+    }
+
+    /// Shows the pomodoro's phase with a countdown to its end, keeping what
+    /// was shown for when it stops.
+    fn pomodoro(&mut self, ctx: &egui::Context) {
+        match self.menu_bar.pomodoro.next() {
+            Some(Step::Show { details, end }) => {
+                let mut preset = match &self.menu_bar.pomodoro.previous {
+                    Some(previous) => previous.clone(),
+                    None => {
+                        let previous = match &self.last_sent {
+                            Some(sent) => sent.clone(),
+                            None => Preset::from_app(self),
+                        };
+                        self.menu_bar.pomodoro.previous = Some(previous.clone());
+                        previous
+                    }
+                };
+                preset.Details = Some(details);
+                preset.StartOffsetMinutes = None;
+                preset.EndAfterMinutes = None;
+                preset.EndsAt = None;
+                preset.NextPreset = None;
+                self.menu_bar.loaded_preset = Some(preset);
+                self.load_preset();
+                // to the second, preset times only go to the minute
+                self.timestamp.end = end;
+            }
+            Some(Step::Stopped) => {
+                if let Some(previous) = self.menu_bar.pomodoro.previous.take() {
+                    self.menu_bar.loaded_preset = Some(previous);
+                }
+            }
+            None => (),
+        }
+        if let Some(wait) = self
+            .menu_bar
+            .pomodoro
+            .wait()
+            .and_then(|wait| wait.to_std().ok())
+        {
+            ctx.request_repaint_after(wait);
         }
+    }
 
-        let second_btn_label_exists = !self.second_btn.label.is_empty();
-        let second_btn_url_exists = !self.second_btn.url.is_empty();
-        if second_btn_label_exists && second_btn_url_exists {
-            buttons.push(second_btn);
+    /// Brings back what was shown before going away.
+    fn come_back(&mut self) {
+        if let Some(previous) = self.menu_bar.idle.previous.take() {
+            self.menu_bar.loaded_preset = Some(previous);
         }
+    }
 
-        let activity = match !buttons.is_empty() {
-            true => activity.buttons(buttons),
-            false => activity,
+    /// Sends the presence again when placeholders like `{time}` expand to
+    /// something new, unless there are edits that weren't sent yet.
+    fn refresh_placeholders(&mut self, ctx: &egui::Context) {
+        let texts = [
+            self.details.as_str(),
+            self.state.as_str(),
+            &self.first_img.hover_text(),
+            self.second_img.text.as_str(),
+            self.first_img.key.as_str(),
+            self.second_img.key.as_str(),
+        ]
+        .map(|text| (placeholders::changes_over_time(text), text.to_string()));
+        if !self.connected || !texts.iter().any(|(changes, _)| *changes) {
+            return;
+        }
+        // the presence was cleared while away
+        if self.menu_bar.idle.away() && self.last_activity.is_none() {
+            return;
+        }
+        let interval = match self.low_power {
+            true => LOW_POWER_REFRESH_INTERVAL,
+            false => REFRESH_INTERVAL,
+        };
+        ctx.request_repaint_after(Duration::from_secs(interval as u64));
+        if self.next_refresh > Utc::now() {
+            return;
+        }
+        self.next_refresh = Utc::now() + chrono::Duration::seconds(interval);
+        let (activity, sent) = match (&self.last_activity, &self.last_sent) {
+            (Some(activity), Some(sent)) => (activity, sent),
+            _ => return,
         };
+        if !Preset::from_app(self).diff(sent).is_empty() || self.throttle.wait().is_some() {
+            return;
+        }
+        let shown = [
+            &activity["details"],
+            &activity["state"],
+            &activity["assets"]["large_text"],
+            &activity["assets"]["small_text"],
+            &activity["assets"]["large_image"],
+            &activity["assets"]["small_image"],
+        ];
+        let changed = texts
+            .iter()
+            .zip(shown)
+            .enumerate()
+            .any(|(i, ((_, text), shown))| {
+                let expanded = placeholders::expand(text, &self.menu_bar.goal);
+                // image keys like {album_art} are compared the way they're sent
+                let expanded = match i >= 4 {
+                    true => image::send_key(&expanded),
+                    false => expanded,
+                };
+                expanded != shown.as_str().unwrap_or_default()
+            });
+        if changed {
+            self.throttle.queue("Placeholders".to_string());
+        }
+    }
+
+    /// Restores the presence from before a temporary preset once its time is up.
+    fn revert(&mut self, ctx: &egui::Context) {
+        let until = match &self.revert {
+            Some((until, _)) => *until,
+            None => return,
+        };
+        if let Ok(wait) = (until - Utc::now()).to_std() {
+            ctx.request_repaint_after(wait);
+            return;
+        }
+        let (_, previous) = self.revert.take().unwrap();
+        self.menu_bar.loaded_preset = Some(previous);
+    }
 
-        let part_exists = self.party != 0;
-        let activity = match part_exists && !self.state.is_empty() {
-            true => activity.party(Party::new().size([self.party_of as i32, self.party as i32])),
-            false => activity,
+    /// Loads the next preset once the countdown of the current one ran out.
+    fn chain_preset(&mut self, ctx: &egui::Context) {
+        let end = match (self.timestamp.end, &self.next_preset) {
+            (Some(end), Some(_)) => end,
+            _ => return,
         };
-        let error = match self.client.set_activity(activity) {
-            Ok(_) => "",
-            Err(_) => "Failed to set activity",
+        if let Ok(wait) = (end - Utc::now()).to_std() {
+            ctx.request_repaint_after(wait);
+            return;
+        }
+        let name = self.next_preset.take().unwrap();
+        let presets: Vec<InAppPreset> = match from_str(&self.menu_bar.presets) {
+            Ok(presets) => presets,
+            Err(_) => Vec::new(),
         };
-        if !error.is_empty() {
-            self.error_bar.new_error(error.to_string());
+        match presets.into_iter().find(|preset| preset.name == name) {
+            Some(preset) => {
+                self.menu_bar.loaded_preset = Some(Preset::from_in_app(preset));
+                self.error_bar
+                    .new_notice(format!("Countdown ended, switched to {name}"));
+            }
+            None => self
+                .error_bar
+                .new_error(format!("Next preset {name} doesn't exist")),
         }
     }
 
     fn load_preset(&mut self) {
+        if let Some(error) = self.menu_bar.preset_error.take() {
+            self.error_bar.new_error(error);
+        }
+        for (url, key) in self.menu_bar.image_import.renamed.drain(..) {
+            for image in [&mut self.first_img, &mut self.second_img] {
+                if image.key == url {
+                    image.key = key.clone();
+                }
+            }
+        }
+        // every way of applying a preset ends here, none skips the checks
+        if let Some(Err(problems)) = self.menu_bar.loaded_preset.as_ref().map(Preset::validate) {
+            self.menu_bar.loaded_preset = None;
+            self.menu_bar.revert_at = None;
+            self.error_bar
+                .new_error(format!("Preset not applied: {problems}"));
+            return;
+        }
         if self.menu_bar.loaded_preset.is_some() {
+            if let Some(until) = self.menu_bar.revert_at.take() {
+                // switching between temporary presets still goes back to the one before them
+                let previous = match self.revert.take() {
+                    Some((_, previous)) => previous,
+                    None => Preset::from_app(self),
+                };
+                self.revert = Some((until, previous));
+            }
             let preset = self.menu_bar.loaded_preset.as_ref().unwrap();
             if let Some(id) = preset.ID.as_ref() {
                 self.id = id.to_string();
+                self.switch_to = Some(id.to_string());
             }
             if let Some(details) = preset.Details.as_ref() {
                 self.details = details.to_string();
@@ -445,14 +2240,45 @@ impl App {
             if let Some(size) = preset.PartyMax {
                 self.party_of = size;
             }
+            // empty ones aren't saved, a preset without them has no party to join
+            for (value, field) in [
+                (&mut self.party_secrets.id, &preset.PartyId),
+                (&mut self.party_secrets.join, &preset.JoinSecret),
+                (&mut self.party_secrets.match_secret, &preset.MatchSecret),
+            ] {
+                *value = field.clone().unwrap_or_default();
+            }
 
             self.timestamp.timestamp = preset.timestamp_from_num();
+            self.timestamp.relative_start = None;
+            if let Some(start) = preset
+                .CustomTimestamp
+                .as_deref()
+                .and_then(timestamp::parse_local)
+            {
+                self.timestamp.start = start;
+            }
+            if let Some(minutes) = preset.StartOffsetMinutes {
+                self.timestamp.timestamp = TimestampEnum::CustomTimeStamp;
+                self.timestamp.relative_start =
+                    Some(Utc::now() - chrono::Duration::minutes(minutes));
+            }
+            self.timestamp.end = match (preset.EndAfterMinutes, preset.EndsAt.as_deref()) {
+                (Some(minutes), _) => Some(Utc::now() + chrono::Duration::minutes(minutes)),
+                (None, Some(end)) => timestamp::parse_local(end),
+                (None, None) => None,
+            };
+            self.next_preset = preset.NextPreset.clone();
+            // a preset without a tag doesn't keep the last one's
+            self.color = preset.Color.clone().unwrap_or_default();
             if let Some(key) = preset.LargeKey.as_ref() {
                 self.first_img.key = key.to_string();
             }
             if let Some(text) = preset.LargeText.as_ref() {
                 self.first_img.text = text.to_string();
             }
+            // saved presets leave out an empty second line
+            self.first_img.second_line = Some(preset.LargeText2.clone().unwrap_or_default());
             if let Some(key) = preset.SmallKey.as_ref() {
                 self.second_img.key = key.to_string();
             }
@@ -471,14 +2297,46 @@ impl App {
             if let Some(url) = preset.Button1URL.as_ref() {
                 self.first_btn.url = url.to_string();
             }
-            self.menu_bar.loaded_preset = None;
+            self.colored = Some(Preset::from_app(self)).filter(|_| !self.color.is_empty());
+            let preset = self.menu_bar.loaded_preset.take().unwrap();
+            let (details, state) = (self.details.clone(), self.state.clone());
+            self.hook(
+                Hook::PresetApplied,
+                &[("DETAILS", &details), ("STATE", &state)],
+            );
 
+            // presets can be switched faster than Discord accepts updates
             if self.connected {
-                self.set_presence()
+                let name = self.preset_name(preset);
+                self.throttle.queue(name);
             }
         }
     }
 
+    /// The name of the saved preset that was loaded, its details for presets
+    /// from files and the automations.
+    fn preset_name(&self, preset: Preset) -> String {
+        let presets: Vec<InAppPreset> = from_str(&self.menu_bar.presets).unwrap_or_default();
+        match presets
+            .into_iter()
+            .find(|saved| Preset::from_in_app(saved.clone()) == preset)
+        {
+            Some(saved) => saved.name,
+            None => preset.Details.unwrap_or_else(|| "Preset".to_string()),
+        }
+    }
+
+    /// Forgets the color tag once the form no longer shows the tagged preset.
+    fn drop_stale_color(&mut self) {
+        let edited = self.colored.as_ref().map_or(false, |colored| {
+            !Preset::from_app(self).diff(colored).is_empty()
+        });
+        if edited {
+            self.color.clear();
+            self.colored = None;
+        }
+    }
+
     fn save_preset(&mut self) {
         if self.menu_bar.preset_save_location.is_some() {
             let preset = Preset::from_app(self);
@@ -501,14 +2359,32 @@ impl App {
                 Ok(presets) => presets,
                 Err(_) => Vec::new(),
             };
-            presets.push(InAppPreset::from_app(
-                &self,
-                self.menu_bar.in_app_save.clone(),
-            ));
+            let name = std::mem::take(&mut self.menu_bar.in_app_save);
+            let preset = InAppPreset::from_app(self, name.clone());
+            // saving under an existing name replaces that preset in its place
+            match presets.iter_mut().find(|saved| saved.name == name) {
+                // an edited preset keeps its tag
+                Some(saved) => {
+                    let color = saved.Color.take();
+                    *saved = preset;
+                    saved.Color = saved.Color.take().or(color);
+                }
+                None => presets.push(preset),
+            }
 
             self.menu_bar.presets = to_string(&presets).unwrap();
-
-            self.menu_bar.in_app_save = String::new();
+            self.menu_bar.preset_manager.lint(
+                &self.menu_bar.presets,
+                &self.menu_bar.rules,
+                &self.menu_bar.guard,
+            );
+            match self.menu_bar.preset_manager.warnings.get(&name) {
+                Some(warnings) => self.error_bar.new_notice(format!(
+                    "Saved preset {name} with {} warnings, see Presets → Manage",
+                    warnings.len()
+                )),
+                None => self.error_bar.new_notice(format!("Saved preset {name}")),
+            }
         }
     }
 }
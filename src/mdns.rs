@@ -0,0 +1,233 @@
+use socket2::{Domain, Protocol, SockAddr, Socket, Type};
+use std::env;
+use std::io::ErrorKind;
+use std::net::{IpAddr, Ipv4Addr, SocketAddr, UdpSocket};
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::Arc;
+use std::thread;
+use std::time::Duration;
+
+const MDNS_GROUP: Ipv4Addr = Ipv4Addr::new(224, 0, 0, 251);
+const MDNS_PORT: u16 = 5353;
+const SERVICE: &str = "_discord-presence._tcp.local";
+const SERVICES: &str = "_services._dns-sd._udp.local";
+
+const TYPE_A: u16 = 1;
+const TYPE_PTR: u16 = 12;
+const TYPE_TXT: u16 = 16;
+const TYPE_SRV: u16 = 33;
+/// Class IN with the cache-flush bit, for records only this host answers.
+const CLASS_UNIQUE: u16 = 0x8001;
+const CLASS_SHARED: u16 = 1;
+
+/// The announcement, it stops answering queries when this is dropped.
+pub struct Advertiser {
+    stopped: Arc<AtomicBool>,
+}
+
+impl Drop for Advertiser {
+    fn drop(&mut self) {
+        self.stopped.store(true, Ordering::Relaxed);
+    }
+}
+
+/// Advertises the control API as `_discord-presence._tcp` over multicast DNS,
+/// so companion apps on the LAN can find it without typing the address.
+pub fn advertise(listen: SocketAddr) -> Result<Advertiser, String> {
+    let ip = match listen.ip() {
+        IpAddr::V4(ip) if ip.is_loopback() => {
+            return Err(
+                "The control API only listens on this machine, nothing to advertise".to_string(),
+            )
+        }
+        IpAddr::V4(ip) if ip.is_unspecified() => match lan_ip() {
+            Some(ip) => ip,
+            None => return Err("No network to advertise the control API on".to_string()),
+        },
+        IpAddr::V4(ip) => ip,
+        IpAddr::V6(_) => return Err("Advertising needs an IPv4 control address".to_string()),
+    };
+    let socket = match bind() {
+        Ok(socket) => socket,
+        Err(err) => return Err(format!("Failed to start mDNS: {err}")),
+    };
+    let host = host_name();
+    let instance = format!("{host}.{SERVICE}");
+    let records = records(&instance, &format!("{host}.local"), ip, listen.port());
+    let stopped = Arc::new(AtomicBool::new(false));
+    let stop = stopped.clone();
+
+    thread::spawn(move || {
+        let group = SocketAddr::from((MDNS_GROUP, MDNS_PORT));
+        // announce right away so browsers already running see the instance
+        for _ in 0..2 {
+            let _ = socket.send_to(&records, group);
+        }
+        let names = [
+            SERVICE.to_string(),
+            SERVICES.to_string(),
+            instance.to_lowercase(),
+        ];
+        let mut buffer = [0; 1500];
+        while !stop.load(Ordering::Relaxed) {
+            match socket.recv_from(&mut buffer) {
+                Ok((len, _)) if asks_for(&buffer[..len], &names) => {
+                    let _ = socket.send_to(&records, group);
+                }
+                Ok(_) => (),
+                Err(err) if matches!(err.kind(), ErrorKind::WouldBlock | ErrorKind::TimedOut) => (),
+                Err(_) => break,
+            }
+        }
+    });
+    Ok(Advertiser { stopped })
+}
+
+fn bind() -> std::io::Result<UdpSocket> {
+    let socket = Socket::new(Domain::IPV4, Type::DGRAM, Some(Protocol::UDP))?;
+    // the OS and other apps usually listen on the mDNS port too
+    socket.set_reuse_address(true)?;
+    #[cfg(unix)]
+    socket.set_reuse_port(true)?;
+    socket.bind(&SockAddr::from(SocketAddr::from((
+        Ipv4Addr::UNSPECIFIED,
+        MDNS_PORT,
+    ))))?;
+    let socket: UdpSocket = socket.into();
+    socket.join_multicast_v4(&MDNS_GROUP, &Ipv4Addr::UNSPECIFIED)?;
+    socket.set_multicast_loop_v4(true)?;
+    // wakes up now and then to see whether it was stopped
+    socket.set_read_timeout(Some(Duration::from_secs(1)))?;
+    Ok(socket)
+}
+
+/// Address of the interface used to reach other machines. Connecting a UDP
+/// socket only picks the route, nothing is sent.
+pub fn lan_ip() -> Option<Ipv4Addr> {
+    let socket = UdpSocket::bind((Ipv4Addr::UNSPECIFIED, 0)).ok()?;
+    socket.connect((Ipv4Addr::new(192, 168, 0, 1), 9)).ok()?;
+    match socket.local_addr().ok()?.ip() {
+        IpAddr::V4(ip) if !ip.is_loopback() => Some(ip),
+        _ => None,
+    }
+}
+
+fn host_name() -> String {
+    let name = env::var("COMPUTERNAME")
+        .or_else(|_| env::var("HOSTNAME"))
+        .unwrap_or_default();
+    let name: String = name
+        .chars()
+        .map(|c| match c.is_ascii_alphanumeric() {
+            true => c.to_ascii_lowercase(),
+            false => '-',
+        })
+        .collect();
+    match name.trim_matches('-') {
+        "" => "discord-presence".to_string(),
+        name => name.to_string(),
+    }
+}
+
+/// The full answer: service enumeration, PTR, SRV, TXT and A records.
+fn records(instance: &str, host: &str, ip: Ipv4Addr, port: u16) -> Vec<u8> {
+    // id 0, authoritative response, 5 answers
+    let mut packet = vec![0, 0, 0x84, 0, 0, 0, 0, 5, 0, 0, 0, 0];
+    record(
+        &mut packet,
+        SERVICES,
+        TYPE_PTR,
+        CLASS_SHARED,
+        4500,
+        &name(SERVICE),
+    );
+    record(
+        &mut packet,
+        SERVICE,
+        TYPE_PTR,
+        CLASS_SHARED,
+        4500,
+        &name(instance),
+    );
+    let mut srv = vec![0, 0, 0, 0];
+    srv.extend_from_slice(&port.to_be_bytes());
+    srv.extend(name(host));
+    record(&mut packet, instance, TYPE_SRV, CLASS_UNIQUE, 120, &srv);
+    let mut txt = Vec::new();
+    for entry in [
+        format!("version={}", env!("CARGO_PKG_VERSION")),
+        "path=/".to_string(),
+    ] {
+        txt.push(entry.len() as u8);
+        txt.extend_from_slice(entry.as_bytes());
+    }
+    record(&mut packet, instance, TYPE_TXT, CLASS_UNIQUE, 4500, &txt);
+    record(&mut packet, host, TYPE_A, CLASS_UNIQUE, 120, &ip.octets());
+    packet
+}
+
+fn record(packet: &mut Vec<u8>, owner: &str, kind: u16, class: u16, ttl: u32, data: &[u8]) {
+    packet.extend(name(owner));
+    packet.extend_from_slice(&kind.to_be_bytes());
+    packet.extend_from_slice(&class.to_be_bytes());
+    packet.extend_from_slice(&ttl.to_be_bytes());
+    packet.extend_from_slice(&(data.len() as u16).to_be_bytes());
+    packet.extend_from_slice(data);
+}
+
+fn name(name: &str) -> Vec<u8> {
+    let mut encoded = Vec::new();
+    for label in name.split('.') {
+        encoded.push(label.len() as u8);
+        encoded.extend_from_slice(label.as_bytes());
+    }
+    encoded.push(0);
+    encoded
+}
+
+/// Whether the packet is a query with a question about one of the names.
+fn asks_for(packet: &[u8], names: &[String]) -> bool {
+    if packet.len() < 12 || packet[2] & 0x80 != 0 {
+        return false;
+    }
+    let questions = u16::from_be_bytes([packet[4], packet[5]]);
+    let mut offset = 12;
+    for _ in 0..questions {
+        let (question, end) = match read_name(packet, offset) {
+            Some(read) => read,
+            None => return false,
+        };
+        if names.contains(&question.to_lowercase()) {
+            return true;
+        }
+        // skip type and class
+        offset = end + 4;
+    }
+    false
+}
+
+/// Reads a possibly compressed name, returning it and the offset after it.
+fn read_name(packet: &[u8], mut offset: usize) -> Option<(String, usize)> {
+    let mut labels = Vec::new();
+    let mut end = None;
+    // bounded so a pointer loop can't hang the responder
+    for _ in 0..128 {
+        let len = *packet.get(offset)? as usize;
+        match len {
+            0 => {
+                return Some((labels.join("."), end.unwrap_or(offset + 1)));
+            }
+            len if len & 0xC0 == 0xC0 => {
+                let pointer = u16::from_be_bytes([len as u8, *packet.get(offset + 1)?]) & 0x3FFF;
+                end.get_or_insert(offset + 2);
+                offset = pointer as usize;
+            }
+            len => {
+                let label = packet.get(offset + 1..offset + 1 + len)?;
+                labels.push(String::from_utf8_lossy(label).to_string());
+                offset += 1 + len;
+            }
+        }
+    }
+    None
+}
@@ -0,0 +1,170 @@
+use crate::integrations::{NowPlaying, Source};
+
+/// Whether this system has a way to read its media players.
+pub const AVAILABLE: bool = cfg!(any(windows, unix));
+
+/// Music players on this computer, whichever is playing: the media sessions
+/// Windows shows next to the volume flyout, Spotify and Music on macOS,
+/// MPRIS players like Spotify, Firefox or mpv on Linux.
+pub struct LocalPlayers;
+
+impl Source for LocalPlayers {
+    fn name(&self) -> &'static str {
+        "Media players"
+    }
+
+    fn now_playing(&mut self) -> Result<Option<NowPlaying>, String> {
+        now_playing()
+    }
+}
+
+/// Asks `playerctl`, which reads every MPRIS player over D-Bus.
+#[cfg(all(unix, not(target_os = "macos")))]
+fn now_playing() -> Result<Option<NowPlaying>, String> {
+    use std::io::ErrorKind;
+    use std::process::Command;
+
+    let output = Command::new("playerctl")
+        .args([
+            "--all-players",
+            "metadata",
+            "--format",
+            "{{status}}\t{{title}}\t{{artist}}\t{{album}}\t{{mpris:artUrl}}",
+        ])
+        .output();
+    let output = match output {
+        Ok(output) => output,
+        Err(err) if err.kind() == ErrorKind::NotFound => {
+            return Err("playerctl isn't installed".to_string())
+        }
+        Err(err) => return Err(format!("Failed to run playerctl: {err}")),
+    };
+    // fails when no player is running
+    if !output.status.success() {
+        return Ok(None);
+    }
+    let players = String::from_utf8_lossy(&output.stdout).to_string();
+    let playing = players
+        .lines()
+        .map(|line| line.split('\t').collect::<Vec<&str>>())
+        .find(|fields| fields.len() == 5 && fields[0] == "Playing" && !fields[1].is_empty());
+    Ok(playing.map(|fields| NowPlaying {
+        track: fields[1].to_string(),
+        artist: fields[2].to_string(),
+        album: fields[3].to_string(),
+        art_url: art_url(fields[4]),
+    }))
+}
+
+/// Asks the Global System Media Transport Controls, which every app with
+/// media keys support reports to. Their covers are streams, not links, so
+/// there's no art.
+#[cfg(windows)]
+fn now_playing() -> Result<Option<NowPlaying>, String> {
+    use windows::Media::Control::GlobalSystemMediaTransportControlsSessionManager as Manager;
+    use windows::Media::Control::GlobalSystemMediaTransportControlsSessionPlaybackStatus as Status;
+
+    let failed = |err: windows::core::Error| format!("Failed to read the media sessions: {err}");
+    let manager = Manager::RequestAsync()
+        .and_then(|request| request.get())
+        .map_err(failed)?;
+    for session in manager.GetSessions().map_err(failed)? {
+        let status = session
+            .GetPlaybackInfo()
+            .and_then(|info| info.PlaybackStatus())
+            .map_err(failed)?;
+        if status != Status::Playing {
+            continue;
+        }
+        let properties = session
+            .TryGetMediaPropertiesAsync()
+            .and_then(|request| request.get())
+            .map_err(failed)?;
+        let text = |value: windows::core::Result<windows::core::HSTRING>| {
+            value
+                .map(|value| value.to_string_lossy())
+                .unwrap_or_default()
+        };
+        let track = text(properties.Title());
+        if track.is_empty() {
+            continue;
+        }
+        return Ok(Some(NowPlaying {
+            track,
+            artist: text(properties.Artist()),
+            album: text(properties.AlbumTitle()),
+            art_url: String::new(),
+        }));
+    }
+    Ok(None)
+}
+
+/// Asks Spotify and Music over AppleScript. The system's Now Playing info
+/// is only readable by Apple's own apps since macOS 15.4, so browsers and
+/// other players can't be seen.
+#[cfg(target_os = "macos")]
+fn now_playing() -> Result<Option<NowPlaying>, String> {
+    use std::process::Command;
+
+    const SPOTIFY: &str = r#"tell application "Spotify"
+    if player state is playing then
+        set playing to current track
+        return (name of playing) & tab & (artist of playing) & tab & (album of playing) & tab & (artwork url of playing)
+    end if
+end tell"#;
+    const MUSIC: &str = r#"tell application "Music"
+    if player state is playing then
+        set playing to current track
+        return (name of playing) & tab & (artist of playing) & tab & (album of playing) & tab
+    end if
+end tell"#;
+    // a player that doesn't answer shouldn't hide the other one
+    let mut error = None;
+    for (app, script) in [("Spotify", SPOTIFY), ("Music", MUSIC)] {
+        // telling an app that isn't running would start it
+        let running = matches!(
+            Command::new("pgrep").args(["-x", app]).output(),
+            Ok(output) if output.status.success()
+        );
+        if !running {
+            continue;
+        }
+        let output = Command::new("osascript")
+            .args(["-e", script])
+            .output()
+            .map_err(|err| format!("Failed to run osascript: {err}"))?;
+        if !output.status.success() {
+            error.get_or_insert(format!("{app} didn't answer, allow the app to control it"));
+            continue;
+        }
+        let playing = String::from_utf8_lossy(&output.stdout).to_string();
+        let fields: Vec<&str> = playing.trim_end_matches('\n').split('\t').collect();
+        if fields.len() == 4 && !fields[0].is_empty() {
+            return Ok(Some(NowPlaying {
+                track: fields[0].to_string(),
+                artist: fields[1].to_string(),
+                album: fields[2].to_string(),
+                art_url: art_url(fields[3]),
+            }));
+        }
+    }
+    match error {
+        Some(error) => Err(error),
+        None => Ok(None),
+    }
+}
+
+#[cfg(not(any(windows, unix)))]
+fn now_playing() -> Result<Option<NowPlaying>, String> {
+    Ok(None)
+}
+
+/// Discord can only show covers from the internet, players that keep them
+/// in local files get none.
+#[cfg(unix)]
+fn art_url(url: &str) -> String {
+    match url.starts_with("https://") {
+        true => url.to_string(),
+        false => String::new(),
+    }
+}
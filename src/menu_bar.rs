@@ -1,5 +1,37 @@
+use crate::asset_check::AssetCheck;
+use crate::bug_report::BugReport;
+use crate::clock::Clock;
+use crate::custom_status::CustomStatus;
+use crate::display_mode::DisplayMode;
+use crate::error_report::ErrorReporting;
+use crate::fonts::DEFAULT_FONT_SIZE;
+use crate::goal::Goal;
+use crate::guard::Guard;
+use crate::hooks::Hooks;
+use crate::idle::Idle;
+use crate::image_import::ImageImport;
+use crate::layouts::UiLayout;
+use crate::media;
+use crate::obs::Obs;
+use crate::platform::{self, Feature};
+use crate::pomodoro::Pomodoro;
 use crate::preset::{InAppPreset, Preset};
-use chrono::Utc;
+use crate::preset_manager::PresetManager;
+use crate::process_rules::ProcessRules;
+use crate::redact::{set_policy, RedactionPolicy};
+use crate::retention::Retention;
+use crate::rotation::Rotation;
+use crate::rules::Rules;
+use crate::rundown::Rundown;
+use crate::scheduler::Scheduler;
+use crate::sounds::Sounds;
+use crate::spotify::SpotifyLogin;
+use crate::status_file::StatusFile;
+use crate::temporary::Temporary;
+use crate::time_tracking::TimeTracking;
+use crate::twitch::TwitchSettings;
+use crate::window::WindowSettings;
+use chrono::{DateTime, Utc};
 use derivative::Derivative;
 use eframe::{
     egui::{self, Layout, RichText, Ui},
@@ -8,7 +40,7 @@ use eframe::{
 };
 use rfd::FileDialog;
 use serde_json::{from_str, to_string};
-use std::{fs, path::PathBuf, process::exit};
+use std::{path::PathBuf, process::exit};
 
 #[derive(Derivative)]
 #[derivative(Default)]
@@ -16,17 +48,74 @@ pub struct MenuBar {
     pub run_on_startup: bool,
     pub start_minimized: bool,
     pub autoconnect: bool,
+    pub automation: bool,
+    /// Send the presence to every running Discord client, not just one.
+    pub mirror: bool,
+    /// Accept commands from the command line and the control API.
+    #[derivative(Default(value = "true"))]
+    pub control: bool,
+    /// URL every presence change is posted to, empty when off.
+    pub webhook_url: String,
     #[derivative(Default(value = "true"))]
     pub check_updates: bool,
     #[derivative(Default(value = "true"))]
     pub darkmode: bool,
     pub about_me: bool,
+    pub bug_report: BugReport,
+    /// Set when the self-check was asked for from the menu.
+    pub self_check: bool,
+    /// Set when the application setup was asked for from the menu.
+    pub setup: bool,
+    /// Set when the phone remote pairing screen was asked for.
+    pub remote: bool,
+    pub error_reporting: ErrorReporting,
+    #[derivative(Default(value = "RedactionPolicy { client_ids: true, urls: true }"))]
+    pub redaction: RedactionPolicy,
     pub loaded_preset: Option<Preset>,
+    pub temporary: Temporary,
+    /// Set with the preset picked from the menu when it's temporary.
+    pub revert_at: Option<DateTime<Utc>>,
+    pub rundown: Rundown,
+    pub rotation: Rotation,
+    pub scheduler: Scheduler,
+    pub pomodoro: Pomodoro,
+    pub process_rules: ProcessRules,
+    pub goal: Goal,
+    pub clock: Clock,
+    pub window: WindowSettings,
+    pub retention: Retention,
+    pub rules: Rules,
+    pub guard: Guard,
+    pub status_file: StatusFile,
+    pub hooks: Hooks,
+    pub sounds: Sounds,
+    pub twitch: TwitchSettings,
+    pub obs: Obs,
+    pub time_tracking: TimeTracking,
+    pub spotify: SpotifyLogin,
+    /// Read what the media players on this computer play.
+    #[derivative(Default(value = "true"))]
+    pub local_media: bool,
+    /// Set when the music sources need replacing.
+    pub sources_changed: bool,
+    pub idle: Idle,
+    pub layout: UiLayout,
+    pub preset_error: Option<String>,
+    pub image_import: ImageImport,
+    pub custom_status: CustomStatus,
+    pub font_path: String,
+    #[derivative(Default(value = "DEFAULT_FONT_SIZE"))]
+    pub font_size: f32,
+    pub fonts_changed: bool,
+    pub font_size_changed: bool,
+    pub display_mode: DisplayMode,
     pub preset_save_location: Option<PathBuf>,
     pub in_app_save: String,
     pub save_menu: bool,
     pub preset_name: String,
     pub presets: String,
+    pub preset_manager: PresetManager,
+    pub asset_check: AssetCheck,
     pub preset_switch_time: u8,
     pub preset_switch_1: Option<InAppPreset>,
     pub preset_switch_2: Option<InAppPreset>,
@@ -39,13 +128,24 @@ impl MenuBar {
     pub fn run(&mut self, ctx: &egui::Context) {
         egui::TopBottomPanel::top("menu_bar").show(ctx, |ui| {
             egui::menu::bar(ui, |ui| {
-                self.file(ui);
-                self.settings(ui);
-                self.presets(ui);
-                self.help(ui);
-                self.switcher(ui);
+                // the switcher keeps running while locked, only its menus are disabled
+                ui.add_enabled_ui(!self.display_mode.locked, |ui| {
+                    self.file(ui);
+                    self.settings(ui);
+                    self.presets(ui);
+                    self.help(ui);
+                    self.switcher(ui);
+                });
+                self.window.handle(ui);
+                if self.display_mode.locked && ui.button("🔒 Unlock").clicked() {
+                    self.display_mode.unlock();
+                }
             })
         });
+        self.display_mode.run(ctx);
+        if self.display_mode.locked {
+            self.close_tools();
+        }
 
         //about me
         egui::containers::Window::new("About")
@@ -60,26 +160,140 @@ impl MenuBar {
             });
 
         //save menu
-        egui::containers::Window::new("Save Preset")
-            .open(&mut self.save_menu)
+        let shortcut = {
+            let input = ctx.input();
+            input.modifiers.command && input.key_pressed(egui::Key::S)
+        };
+        if shortcut && !self.display_mode.locked {
+            self.save_menu = true;
+        }
+        let mut save_menu = self.save_menu;
+        egui::containers::Window::new("Save as preset")
+            .open(&mut save_menu)
             .resizable(false)
             .show(ctx, |ui| {
                 ui.with_layout(Layout::top_down(Align::Center), |ui| {
                     ui.heading("Preset Name");
 
                     ui.add_space(5.);
-                    ui.text_edit_singleline(&mut self.preset_name);
+                    let field = ui.text_edit_singleline(&mut self.preset_name);
                     ui.add_space(5.);
 
-                    if ui.button("Submit").clicked() {
-                        self.in_app_save = self.preset_name.clone();
+                    let name = self.preset_name.trim().to_string();
+                    let presets: Vec<InAppPreset> = from_str(&self.presets).unwrap_or_default();
+                    let exists = presets.iter().any(|preset| preset.name == name);
+                    if exists {
+                        ui.label(format!("Replaces the preset named {name}"));
                     }
+                    ui.horizontal(|ui| {
+                        let enter = field.lost_focus() && ui.input().key_pressed(egui::Key::Enter);
+                        let label = match exists {
+                            true => "Replace",
+                            false => "Save",
+                        };
+                        let save = ui.add_enabled(!name.is_empty(), egui::Button::new(label));
+                        if (save.clicked() || enter) && !name.is_empty() {
+                            self.in_app_save = name.clone();
+                        }
+                        if ui
+                            .add_enabled(!name.is_empty(), egui::Button::new("Save to file…"))
+                            .clicked()
+                        {
+                            self.preset_save_location = FileDialog::new()
+                                .add_filter("Preset", &["crp"])
+                                .set_title("Save Preset")
+                                .set_file_name(&format!("{name}.crp"))
+                                .save_file();
+                        }
+                    });
                 })
             });
-        if !self.in_app_save.is_empty() {
+        self.save_menu = save_menu;
+        if !self.in_app_save.is_empty() || self.preset_save_location.is_some() {
             self.save_menu = false
         }
+
+        self.image_import.run(ctx);
+        self.custom_status.run(ctx, self.display_mode.locked);
+        self.bug_report.run(ctx);
+        self.error_reporting.run(ctx);
+        self.goal.run(ctx, self.display_mode.locked);
+        self.rules.run(ctx);
+        self.hooks.run(ctx);
+        match self
+            .rundown
+            .run(ctx, &self.presets, self.display_mode.locked)
+        {
+            Some(Ok(preset)) => self.loaded_preset = Some(preset),
+            Some(Err(error)) => self.preset_error = Some(error),
+            None => {}
+        }
+        self.rotation.run(ctx, &self.presets);
+        self.scheduler.run(ctx, &self.presets);
+        self.pomodoro.run(ctx);
+        self.process_rules.run(ctx, &self.presets);
+        self.asset_check.run(ctx, &mut self.presets);
+        self.preset_manager
+            .lint(&self.presets, &self.rules, &self.guard);
+        if let Some(preset) = self.preset_manager.run(ctx, &mut self.presets) {
+            self.loaded_preset = Some(preset);
+        }
+        for (old, new) in self.preset_manager.renamed.drain(..) {
+            for step in self.rundown.steps.iter_mut() {
+                if step.preset == old {
+                    step.preset = new.clone();
+                }
+            }
+            for name in self.rotation.presets.iter_mut() {
+                if *name == old {
+                    *name = new.clone();
+                }
+            }
+            for slot in self.scheduler.slots.iter_mut() {
+                if slot.preset == old {
+                    slot.preset = new.clone();
+                }
+            }
+            for rule in self.process_rules.rules.iter_mut() {
+                if rule.preset == old {
+                    rule.preset = new.clone();
+                }
+            }
+            for preset in [&mut self.preset_switch_1, &mut self.preset_switch_2]
+                .into_iter()
+                .flatten()
+            {
+                if preset.name == old {
+                    preset.name = new.clone();
+                }
+            }
+        }
+    }
+    /// Closes every window that can change the presence or the settings, the
+    /// menus that open them are disabled while the display lock is on.
+    fn close_tools(&mut self) {
+        for open in [
+            &mut self.save_menu,
+            &mut self.setup,
+            &mut self.self_check,
+            &mut self.remote,
+            &mut self.error_reporting.consent_menu,
+            &mut self.image_import.open,
+            &mut self.rules.open,
+            &mut self.hooks.open,
+            &mut self.goal.open,
+            &mut self.rundown.open,
+            &mut self.rotation.open,
+            &mut self.scheduler.open,
+            &mut self.pomodoro.open,
+            &mut self.process_rules.open,
+            &mut self.asset_check.open,
+            &mut self.preset_manager.open,
+        ] {
+            *open = false;
+        }
     }
+
     fn file(&mut self, ui: &mut Ui) {
         ui.menu_button("File", |ui| {
             if ui.button("Load Preset | Ctrl + O").clicked() {
@@ -87,11 +301,11 @@ impl MenuBar {
                 ui.close_menu();
             }
             ui.menu_button("Save Preset", |ui| {
-                if ui.button("Save").clicked() {
+                if ui.button("Save as preset… | Ctrl + S").clicked() {
                     self.save_menu = true;
                     ui.close_menu();
                 }
-                if ui.button("Save to File | Ctrl + S").clicked() {
+                if ui.button("Save to File").clicked() {
                     self.save_preset();
                     ui.close_menu()
                 }
@@ -104,17 +318,161 @@ impl MenuBar {
     }
     fn settings(&mut self, ui: &mut Ui) {
         ui.menu_button("Settings", |ui| {
-            ui.checkbox(&mut self.run_on_startup, "Run on startup");
-            ui.checkbox(&mut self.start_minimized, "Start minimized");
+            for (feature, value, label) in [
+                (
+                    Feature::RunOnStartup,
+                    &mut self.run_on_startup,
+                    "Run on startup",
+                ),
+                (
+                    Feature::StartMinimized,
+                    &mut self.start_minimized,
+                    "Start minimized",
+                ),
+            ] {
+                match platform::unavailable(feature) {
+                    Some(reason) => {
+                        ui.add_enabled(false, egui::Checkbox::new(value, label))
+                            .on_disabled_hover_text(reason);
+                    }
+                    None => {
+                        ui.checkbox(value, label);
+                    }
+                }
+            }
             ui.checkbox(&mut self.autoconnect, "Autoconnect");
+            ui.checkbox(&mut self.automation, "Apply presets by trigger");
+            ui.checkbox(&mut self.mirror, "Mirror to all Discord clients")
+                .on_hover_text("Takes effect on the next connect");
+            ui.menu_button("Webhook", |ui| {
+                ui.label("Post every presence change to");
+                ui.text_edit_singleline(&mut self.webhook_url)
+                    .on_hover_text("Leave empty to turn off");
+            });
+            ui.menu_button("Status file", |ui| self.status_file.run(ui));
+            ui.menu_button("Sounds", |ui| self.sounds.run(ui));
+            ui.menu_button("OBS", |ui| {
+                let presets: Vec<InAppPreset> = from_str(&self.presets).unwrap_or_default();
+                let names: Vec<String> = presets.into_iter().map(|preset| preset.name).collect();
+                self.obs.run(ui, &names);
+            });
+            ui.menu_button("Integrations", |ui| {
+                let local = ui.add_enabled(
+                    media::AVAILABLE,
+                    egui::Checkbox::new(&mut self.local_media, "Players on this computer"),
+                );
+                if local.changed() {
+                    self.sources_changed = true;
+                }
+                ui.separator();
+                ui.label("Spotify");
+                self.spotify.run(ui);
+                ui.separator();
+                ui.label("Twitch");
+                self.twitch.run(ui);
+                ui.separator();
+                ui.menu_button("Time tracking", |ui| self.time_tracking.run(ui));
+            });
+            ui.menu_button("Away", |ui| {
+                let presets: Vec<InAppPreset> = from_str(&self.presets).unwrap_or_default();
+                let names: Vec<String> = presets.into_iter().map(|preset| preset.name).collect();
+                self.idle.run(ui, &names);
+            });
             ui.checkbox(&mut self.check_updates, "Check for updates");
+            ui.checkbox(&mut self.retention.warn_on_close, "Ask before closing")
+                .on_hover_text("Closing the app removes the presence from Discord");
+            ui.menu_button("Clock", |ui| self.clock.run(ui));
+            ui.menu_button("Window", |ui| self.window.run(ui));
+            ui.menu_button("Layout", |ui| self.layout.run(ui));
+            ui.checkbox(&mut self.custom_status.enabled, "Custom status companion");
+            self.font(ui);
+            ui.menu_button("Redaction", |ui| {
+                ui.label("Tokens and your name are always removed from reports");
+                let client_ids =
+                    ui.checkbox(&mut self.redaction.client_ids, "Hide application IDs");
+                let urls = ui.checkbox(&mut self.redaction.urls, "Hide URLs");
+                if client_ids.changed() || urls.changed() {
+                    set_policy(self.redaction);
+                }
+            });
+            ui.checkbox(&mut self.control, "Allow control from other programs")
+                .on_hover_text(
+                    "The command line, the control API, the phone remote and the metrics",
+                );
+            if ui.button("Phone remote").clicked() {
+                self.remote = true;
+                ui.close_menu();
+            }
+            if ui.button("Error reports").clicked() {
+                self.error_reporting.consent_menu = true;
+                ui.close_menu();
+            }
+            ui.menu_button("Privacy guard", |ui| self.guard.run(ui));
+            if ui.button("Validation rules").clicked() {
+                self.rules.open = true;
+                ui.close_menu();
+            }
+            if ui.button("Hooks").clicked() {
+                self.hooks.open = true;
+                ui.close_menu();
+            }
+            if ui.button("Display mode").clicked() {
+                self.display_mode.lock_menu = true;
+                ui.close_menu();
+            }
             egui::widgets::global_dark_light_mode_buttons(ui);
             self.darkmode = ui.ctx().style().visuals.clone().dark_mode
         });
     }
+    fn font(&mut self, ui: &mut Ui) {
+        ui.menu_button("Font", |ui| {
+            if ui.button("Pick font").clicked() {
+                let file = FileDialog::new()
+                    .add_filter("Font", &["ttf", "otf", "ttc"])
+                    .set_title("Pick font")
+                    .pick_file();
+                if let Some(file) = file {
+                    self.font_path = file.display().to_string();
+                    self.fonts_changed = true;
+                }
+                ui.close_menu();
+            }
+            if ui
+                .add_enabled(
+                    !self.font_path.is_empty(),
+                    egui::Button::new("Default font"),
+                )
+                .clicked()
+            {
+                self.font_path = String::new();
+                self.fonts_changed = true;
+            }
+            ui.horizontal(|ui| {
+                ui.label("Size");
+                if ui
+                    .add(egui::DragValue::new(&mut self.font_size).clamp_range(8..=32))
+                    .changed()
+                {
+                    self.font_size_changed = true;
+                }
+            });
+        });
+    }
     fn help(&mut self, ui: &mut Ui) {
         ui.menu_button("Help", |ui| {
             ui.hyperlink_to("Github Page", "https://github.com/Tofix26/discord_presence");
+            if ui.button("Set up an application").clicked() {
+                self.setup = true;
+                ui.close_menu();
+            }
+            if ui.button("Self-check").clicked() {
+                self.self_check = true;
+                ui.close_menu();
+            }
+            if ui.button("Report a problem").clicked() {
+                self.bug_report.open = true;
+                ui.close_menu();
+            }
             if ui.button("About").clicked() {
                 self.about_me = true;
                 ui.close_menu();
@@ -124,6 +482,41 @@ impl MenuBar {
 
     fn presets(&mut self, ui: &mut Ui) {
         ui.menu_button("Presets", |ui| {
+            if ui.button("Manage").clicked() {
+                self.preset_manager.open = true;
+                ui.close_menu();
+            }
+            if ui.button("Check image keys").clicked() {
+                self.asset_check.start(&self.presets);
+                ui.close_menu();
+            }
+            if ui.button("Rotation").clicked() {
+                self.rotation.open = true;
+                ui.close_menu();
+            }
+            if ui.button("Schedule").clicked() {
+                self.scheduler.open = true;
+                ui.close_menu();
+            }
+            if ui.button("Pomodoro").clicked() {
+                self.pomodoro.open = true;
+                ui.close_menu();
+            }
+            if ui.button("Process rules").clicked() {
+                self.process_rules.open = true;
+                ui.close_menu();
+            }
+            if ui.button("Rundown").clicked() {
+                self.rundown.open = true;
+                ui.close_menu();
+            }
+            if ui.button("Goal").clicked() {
+                self.goal.open = true;
+                ui.close_menu();
+            }
+            ui.separator();
+            self.temporary.run(ui);
+            ui.separator();
             egui::ScrollArea::new([false, true]).show(ui, |ui| {
                 let presets: Vec<InAppPreset> = match from_str(&self.presets) {
                     Ok(presets) => presets,
@@ -134,7 +527,13 @@ impl MenuBar {
                     ui.horizontal(|ui| {
                         ui.add_space(5.);
                         if ui.button(&preset.name).clicked() {
-                            self.loaded_preset = Some(Preset::from_in_app(preset.clone()));
+                            match self.temporary.until() {
+                                Ok(until) => {
+                                    self.revert_at = until;
+                                    self.loaded_preset = Some(Preset::from_in_app(preset.clone()));
+                                }
+                                Err(error) => self.preset_error = Some(error),
+                            }
                             ui.close_menu()
                         }
                         ui.add_space(5.);
@@ -204,6 +603,8 @@ impl MenuBar {
                 );
             });
             ui.checkbox(&mut self.run_switcher, "Run Switcher");
+            ui.separator();
+            self.rotation.animation.run(ui);
         });
         if (self.preset_till < Utc::now().timestamp())
             && self.run_switcher
@@ -231,9 +632,37 @@ impl MenuBar {
             .set_title("Load preset")
             .pick_file();
         if let Some(file) = file {
-            let file = fs::read_to_string(file).unwrap();
-            let xml: Preset = serde_xml_rs::from_str(&file).unwrap();
-            self.loaded_preset = Some(xml);
+            let presets: Vec<InAppPreset> = match from_str(&self.presets) {
+                Ok(presets) => presets,
+                Err(_) => Vec::new(),
+            };
+            let preset = match Preset::from_file(&file, &presets) {
+                Ok(preset) => preset,
+                Err(error) => {
+                    self.preset_error = Some(error);
+                    return;
+                }
+            };
+            if let Err(problems) = preset.validate() {
+                self.preset_error = Some(format!("Invalid preset: {problems}"));
+                return;
+            }
+            // keep presets that carry their own triggers so the rule keeps working
+            if preset.TriggerProcesses.is_some() || preset.TriggerSchedule.is_some() {
+                let name = match file.file_stem() {
+                    Some(name) => name.to_string_lossy().to_string(),
+                    None => "Imported".to_string(),
+                };
+                let mut presets = presets;
+                presets.retain(|set| set.name != name);
+                presets.push(InAppPreset::from_preset(preset.clone(), name));
+                self.presets = to_string(&presets).unwrap();
+            }
+            self.image_import.offer(
+                preset.ID.as_ref(),
+                [preset.LargeKey.as_ref(), preset.SmallKey.as_ref()],
+            );
+            self.loaded_preset = Some(preset);
         }
     }
 
@@ -0,0 +1,127 @@
+use std::io::{BufRead, BufReader, Write};
+use std::net::{Ipv4Addr, SocketAddr, TcpListener, TcpStream};
+use std::sync::atomic::{AtomicBool, AtomicU64, Ordering};
+use std::sync::Arc;
+use std::thread;
+use std::time::{Duration, Instant};
+
+pub static UPDATES: AtomicU64 = AtomicU64::new(0);
+pub static UPDATE_FAILURES: AtomicU64 = AtomicU64::new(0);
+pub static CONNECTS: AtomicU64 = AtomicU64::new(0);
+pub static CONNECT_FAILURES: AtomicU64 = AtomicU64::new(0);
+
+/// Counts the result in `ok` or `failed`.
+pub fn record<T, E>(result: &Result<T, E>, ok: &AtomicU64, failed: &AtomicU64) {
+    let counter = match result {
+        Ok(_) => ok,
+        Err(_) => failed,
+    };
+    counter.fetch_add(1, Ordering::Relaxed);
+}
+
+/// How long a scraper gets to send its request and read the answer, it's
+/// served one connection at a time.
+const TIMEOUT: Duration = Duration::from_secs(5);
+
+/// The metrics endpoint, it stops when this is dropped.
+pub struct Metrics {
+    addr: SocketAddr,
+    stopped: Arc<AtomicBool>,
+}
+
+impl Drop for Metrics {
+    fn drop(&mut self) {
+        self.stopped.store(true, Ordering::Relaxed);
+        let mut addr = self.addr;
+        if addr.ip().is_unspecified() {
+            addr.set_ip(Ipv4Addr::LOCALHOST.into());
+        }
+        // wakes the listener thread so it sees the flag
+        let _ = TcpStream::connect_timeout(&addr, Duration::from_secs(1));
+    }
+}
+
+/// Serves the counters in the Prometheus text format on `GET /metrics`.
+pub fn serve(listen: &str) -> Result<Metrics, String> {
+    let listener = match TcpListener::bind(listen) {
+        Ok(listener) => listener,
+        Err(err) => return Err(format!("Failed to serve metrics on {listen}: {err}")),
+    };
+    let addr = match listener.local_addr() {
+        Ok(addr) => addr,
+        Err(err) => return Err(format!("Failed to serve metrics: {err}")),
+    };
+    let started = Instant::now();
+    let stopped = Arc::new(AtomicBool::new(false));
+    let stop = stopped.clone();
+    thread::spawn(move || {
+        for stream in listener.incoming().flatten() {
+            if stop.load(Ordering::Relaxed) {
+                break;
+            }
+            let _ = respond(stream, started);
+        }
+    });
+    Ok(Metrics { addr, stopped })
+}
+
+fn respond(mut stream: TcpStream, started: Instant) -> std::io::Result<()> {
+    stream.set_read_timeout(Some(TIMEOUT))?;
+    stream.set_write_timeout(Some(TIMEOUT))?;
+    let mut request = String::new();
+    BufReader::new(&stream).read_line(&mut request)?;
+    let response = match request.split_whitespace().nth(1) {
+        Some("/metrics") => {
+            let body = render(started);
+            format!(
+                "HTTP/1.1 200 OK\r\nContent-Type: text/plain; version=0.0.4\r\nContent-Length: {}\r\nConnection: close\r\n\r\n{body}",
+                body.len()
+            )
+        }
+        _ => "HTTP/1.1 404 Not Found\r\nContent-Length: 0\r\nConnection: close\r\n\r\n".to_string(),
+    };
+    stream.write_all(response.as_bytes())
+}
+
+fn render(started: Instant) -> String {
+    let mut body = String::new();
+    let mut metric = |name: &str, kind: &str, help: &str, value: String| {
+        body += &format!("# HELP {name} {help}\n# TYPE {name} {kind}\n{name} {value}\n");
+    };
+    metric(
+        "discord_presence_uptime_seconds",
+        "gauge",
+        "Seconds since the app started.",
+        started.elapsed().as_secs().to_string(),
+    );
+    for (name, help, counter) in [
+        (
+            "discord_presence_updates_total",
+            "Presence updates Discord accepted.",
+            &UPDATES,
+        ),
+        (
+            "discord_presence_update_failures_total",
+            "Presence updates that failed.",
+            &UPDATE_FAILURES,
+        ),
+        (
+            "discord_presence_connects_total",
+            "Successful connections to Discord.",
+            &CONNECTS,
+        ),
+        (
+            "discord_presence_connect_failures_total",
+            "Failed connections to Discord.",
+            &CONNECT_FAILURES,
+        ),
+    ] {
+        metric(
+            name,
+            "counter",
+            help,
+            counter.load(Ordering::Relaxed).to_string(),
+        );
+    }
+    body
+}
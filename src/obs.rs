@@ -0,0 +1,290 @@
+use crate::preset::Preset;
+use crate::secrets;
+use crate::websocket::WebSocket;
+use derivative::Derivative;
+use eframe::egui::{self, RichText, TextEdit, Ui};
+use eframe::epaint::Color32;
+use serde::{Deserialize, Serialize};
+use serde_json::{json, Value};
+use sha2::{Digest, Sha256};
+use std::io::ErrorKind;
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::mpsc::{channel, Receiver, Sender};
+use std::sync::Arc;
+use std::thread;
+use std::time::Duration;
+
+/// Name of the password in the secrets file.
+pub const SECRET: &str = "obs";
+/// Seconds between two tries to reach OBS.
+const RETRY: u64 = 10;
+/// Seconds OBS gets to accept the connection and to answer the handshake.
+const CONNECT_TIMEOUT: u64 = 5;
+/// obs-websocket's event category for the stream and recording outputs.
+const OUTPUTS: u32 = 1 << 6;
+
+#[derive(Serialize, Deserialize, Derivative, Clone, PartialEq, Eq)]
+#[derivative(Default)]
+#[serde(default)]
+pub struct ObsSettings {
+    pub enabled: bool,
+    #[derivative(Default(value = "\"localhost:4455\".to_string()"))]
+    pub address: String,
+    /// Kept with the other secrets, not in the settings.
+    #[serde(skip_serializing)]
+    pub password: String,
+    /// In-app preset shown while live, the form as it is when empty.
+    pub preset: String,
+    /// Replaces the details while live, kept when empty.
+    pub title: String,
+    /// Where the Watch button leads, no button when empty.
+    pub link: String,
+}
+
+enum Event {
+    Connected,
+    Live(bool),
+    Failed(String),
+}
+
+/// The thread talking to OBS, stopped when this is dropped.
+struct Connection {
+    settings: ObsSettings,
+    stop: Arc<AtomicBool>,
+    events: Receiver<Event>,
+}
+
+impl Drop for Connection {
+    fn drop(&mut self) {
+        self.stop.store(true, Ordering::Relaxed);
+    }
+}
+
+/// Watches OBS over obs-websocket and shows a Live preset while it streams.
+#[derive(Default)]
+pub struct Obs {
+    pub settings: ObsSettings,
+    connection: Option<Connection>,
+    connected: bool,
+    pub live: bool,
+    /// What was shown before going live.
+    pub previous: Option<Preset>,
+    error: Option<String>,
+}
+
+impl Obs {
+    pub fn new(mut settings: ObsSettings) -> Self {
+        // settings from before the secrets file still have the password
+        if settings.password.is_empty() {
+            settings.password = secrets::get(SECRET);
+        }
+        Self {
+            settings,
+            ..Default::default()
+        }
+    }
+
+    pub fn run(&mut self, ui: &mut Ui, presets: &[String]) {
+        ui.checkbox(&mut self.settings.enabled, "Go live with OBS");
+        ui.label("Address of obs-websocket");
+        ui.text_edit_singleline(&mut self.settings.address);
+        ui.label("Password");
+        ui.add(TextEdit::singleline(&mut self.settings.password).password(true));
+        ui.label("Preset while live");
+        egui::ComboBox::from_id_source("obs_preset")
+            .selected_text(match self.settings.preset.as_str() {
+                "" => "The current presence",
+                name => name,
+            })
+            .show_ui(ui, |ui| {
+                ui.selectable_value(
+                    &mut self.settings.preset,
+                    String::new(),
+                    "The current presence",
+                );
+                for name in presets {
+                    ui.selectable_value(&mut self.settings.preset, name.clone(), name);
+                }
+            });
+        ui.label("Stream title");
+        ui.text_edit_singleline(&mut self.settings.title)
+            .on_hover_text("Shown as the details, leave empty to keep the preset's");
+        ui.label("Channel link");
+        ui.text_edit_singleline(&mut self.settings.link)
+            .on_hover_text("Adds a Watch button, leave empty for none");
+        let status = match (&self.error, self.connected, self.live) {
+            (Some(error), _, _) => RichText::new(error).color(Color32::LIGHT_RED),
+            (None, true, true) => RichText::new("Live").color(Color32::LIGHT_GREEN),
+            (None, true, false) => RichText::new("Connected, not streaming"),
+            (None, false, _) if self.settings.enabled => RichText::new("Connecting…"),
+            (None, false, _) => RichText::new("Off"),
+        };
+        ui.label(status.small());
+    }
+
+    /// Connects while enabled and returns whether the stream went live or
+    /// ended since the last call.
+    pub fn poll(&mut self, allowed: bool) -> Option<bool> {
+        let wanted = self.settings.enabled && allowed;
+        let current =
+            matches!(&self.connection, Some(connection) if connection.settings == self.settings);
+        if !wanted || !current {
+            self.connection = None;
+            self.connected = false;
+            self.error = None;
+        }
+        if wanted && self.connection.is_none() {
+            self.connection = Some(connect(self.settings.clone()));
+        }
+        let mut live = self.live;
+        if let Some(connection) = &self.connection {
+            for event in connection.events.try_iter() {
+                match event {
+                    Event::Connected => {
+                        self.connected = true;
+                        self.error = None;
+                    }
+                    Event::Live(streaming) => live = streaming,
+                    Event::Failed(error) => {
+                        self.connected = false;
+                        self.error = Some(error);
+                    }
+                }
+            }
+        }
+        // a stream can't be seen ending once OBS is out of reach
+        if self.connection.is_none() || !self.connected {
+            live = false;
+        }
+        match live != self.live {
+            true => {
+                self.live = live;
+                Some(live)
+            }
+            false => None,
+        }
+    }
+}
+
+fn connect(settings: ObsSettings) -> Connection {
+    let stop = Arc::new(AtomicBool::new(false));
+    let (sender, events) = channel();
+    let thread_settings = settings.clone();
+    let thread_stop = stop.clone();
+    thread::spawn(move || {
+        while !thread_stop.load(Ordering::Relaxed) {
+            if let Err(error) = watch(&thread_settings, &thread_stop, &sender) {
+                if sender.send(Event::Failed(error)).is_err() {
+                    return;
+                }
+            }
+            for _ in 0..RETRY {
+                if thread_stop.load(Ordering::Relaxed) {
+                    return;
+                }
+                thread::sleep(Duration::from_secs(1));
+            }
+        }
+    });
+    Connection {
+        settings,
+        stop,
+        events,
+    }
+}
+
+/// Logs in to obs-websocket and reports the stream's state until the
+/// connection ends or is stopped.
+fn watch(settings: &ObsSettings, stop: &AtomicBool, events: &Sender<Event>) -> Result<(), String> {
+    let address = settings.address.trim().trim_start_matches("ws://");
+    let mut socket = WebSocket::connect(address, Duration::from_secs(CONNECT_TIMEOUT))
+        .map_err(|err| format!("Failed to reach OBS at {address}: {err}"))?;
+    let closed = || "OBS closed the connection, check the password".to_string();
+    let failed = |err: std::io::Error| format!("Lost OBS: {err}");
+    // short reads so a stop is noticed within a second
+    socket
+        .stream()
+        .set_read_timeout(Some(Duration::from_secs(1)))
+        .map_err(failed)?;
+    let hello = match next(&mut socket, stop, Some(CONNECT_TIMEOUT)).map_err(failed)? {
+        Next::Message(hello) => hello,
+        Next::Closed => return Err(closed()),
+        Next::Stopped => return Ok(()),
+        Next::TimedOut => return Err("OBS didn't answer, is it obs-websocket?".to_string()),
+    };
+    let hello: Value = serde_json::from_str(&hello).map_err(|_| closed())?;
+    let mut identify = json!({ "op": 1, "d": { "rpcVersion": 1, "eventSubscriptions": OUTPUTS } });
+    let auth = &hello["d"]["authentication"];
+    if let (Some(challenge), Some(salt)) = (auth["challenge"].as_str(), auth["salt"].as_str()) {
+        if settings.password.is_empty() {
+            return Err("OBS wants a password".to_string());
+        }
+        let secret = base64::encode(Sha256::digest(format!("{}{salt}", settings.password)));
+        identify["d"]["authentication"] = json!(base64::encode(Sha256::digest(format!(
+            "{secret}{challenge}"
+        ))));
+    }
+    let request = json!({
+        "op": 6,
+        "d": { "requestType": "GetStreamStatus", "requestId": "stream" },
+    });
+    socket.send(&identify.to_string()).map_err(failed)?;
+    socket.send(&request.to_string()).map_err(failed)?;
+    loop {
+        let message = match next(&mut socket, stop, None).map_err(failed)? {
+            Next::Message(message) => message,
+            Next::Closed => return Err(closed()),
+            Next::Stopped | Next::TimedOut => return Ok(()),
+        };
+        let message: Value = serde_json::from_str(&message).unwrap_or_default();
+        let data = &message["d"];
+        let event = match message["op"].as_u64() {
+            // identified
+            Some(2) => Some(Event::Connected),
+            // the answer to GetStreamStatus
+            Some(7) => data["responseData"]["outputActive"]
+                .as_bool()
+                .map(Event::Live),
+            Some(5) if data["eventType"] == "StreamStateChanged" => {
+                match data["eventData"]["outputState"].as_str() {
+                    Some("OBS_WEBSOCKET_OUTPUT_STARTED") => Some(Event::Live(true)),
+                    Some("OBS_WEBSOCKET_OUTPUT_STOPPED") => Some(Event::Live(false)),
+                    _ => None,
+                }
+            }
+            _ => None,
+        };
+        if let Some(event) = event {
+            if events.send(event).is_err() {
+                return Ok(());
+            }
+        }
+    }
+}
+
+enum Next {
+    Message(String),
+    Closed,
+    Stopped,
+    TimedOut,
+}
+
+/// Waits for the next message until the connection is stopped, or for at
+/// most `seconds`. The socket's reads have to time out every second.
+fn next(socket: &mut WebSocket, stop: &AtomicBool, seconds: Option<u64>) -> std::io::Result<Next> {
+    let mut waited = 0;
+    while !stop.load(Ordering::Relaxed) {
+        match socket.read() {
+            Ok(Some(message)) => return Ok(Next::Message(message)),
+            Ok(None) => return Ok(Next::Closed),
+            Err(err) if matches!(err.kind(), ErrorKind::WouldBlock | ErrorKind::TimedOut) => {
+                waited += 1;
+                if matches!(seconds, Some(seconds) if waited >= seconds) {
+                    return Ok(Next::TimedOut);
+                }
+            }
+            Err(err) => return Err(err),
+        }
+    }
+    Ok(Next::Stopped)
+}
@@ -0,0 +1,86 @@
+/// Browser source page for OBS and other streaming tools, served on
+/// `/overlay` of the control API. It polls `/overlay.json` with the token
+/// from its own URL and draws the presence like Discord's card.
+///
+/// `theme` picks `dark`, `light` or `transparent`, `accent` a hex color
+/// without the `#`.
+pub const PAGE: &str = r##"<!DOCTYPE html>
+<html>
+<head>
+<meta charset="utf-8">
+<title>Discord Presence</title>
+<style>
+  :root { --background: #232428; --text: #f2f3f5; --muted: #b5bac1; --accent: #5865f2; }
+  body.light { --background: #ffffff; --text: #060607; --muted: #4e5058; }
+  body.transparent { --background: transparent; }
+  body { margin: 0; font-family: "gg sans", "Noto Sans", Helvetica, Arial, sans-serif; background: transparent; }
+  #card { display: none; gap: 12px; align-items: center; padding: 12px; border-radius: 8px;
+          background: var(--background); color: var(--text); border-left: 4px solid var(--accent); width: max-content; }
+  #card.shown { display: flex; }
+  #images { position: relative; width: 80px; height: 80px; flex: none; }
+  #large { width: 80px; height: 80px; border-radius: 8px; object-fit: cover; }
+  #small { position: absolute; right: -4px; bottom: -4px; width: 28px; height: 28px; border-radius: 50%;
+           border: 3px solid var(--background); background: var(--background); }
+  #name { font-weight: 700; }
+  #details, #state, #time { color: var(--muted); }
+</style>
+</head>
+<body>
+<div id="card">
+  <div id="images"><img id="large"><img id="small"></div>
+  <div>
+    <div id="name"></div>
+    <div id="details"></div>
+    <div id="state"></div>
+    <div id="time"></div>
+  </div>
+</div>
+<script>
+  const params = new URLSearchParams(location.search);
+  document.body.className = params.get("theme") || "dark";
+  if (params.get("accent")) {
+    document.documentElement.style.setProperty("--accent", "#" + params.get("accent"));
+  }
+  let status = null;
+  const $ = (id) => document.getElementById(id);
+  const image = (element, url, text) => {
+    element.style.display = url ? "" : "none";
+    if (url && element.src !== url) element.src = url;
+    element.title = text || "";
+  };
+  const clock = (seconds) => {
+    const pad = (n) => String(n).padStart(2, "0");
+    const hours = Math.floor(seconds / 3600);
+    const rest = pad(Math.floor(seconds / 60) % 60) + ":" + pad(seconds % 60);
+    return hours ? hours + ":" + rest : rest;
+  };
+  const draw = () => {
+    const card = $("card");
+    card.className = status && status.shown ? "shown" : "";
+    if (!status || !status.shown) return;
+    $("name").textContent = status.name;
+    $("details").textContent = status.details;
+    $("state").textContent = status.state;
+    const now = Math.floor(Date.now() / 1000);
+    $("time").textContent = status.end ? clock(Math.max(status.end - now, 0)) + " left"
+      : status.start ? clock(Math.max(now - status.start, 0)) + " elapsed" : "";
+    image($("large"), status.large_image, status.large_text);
+    image($("small"), status.small_image, status.small_text);
+    $("images").style.display = status.large_image ? "" : "none";
+  };
+  const poll = async () => {
+    try {
+      const answer = await fetch("/overlay.json?token=" + encodeURIComponent(params.get("token") || ""));
+      status = answer.ok ? await answer.json() : null;
+    } catch (error) {
+      status = null;
+    }
+    draw();
+  };
+  poll();
+  setInterval(poll, 2000);
+  setInterval(draw, 1000);
+</script>
+</body>
+</html>
+"##;
@@ -0,0 +1,55 @@
+use eframe::egui::{self, Color32, RichText, Ui};
+
+/// Party ID and secrets. With a join secret, a party ID and a party size
+/// Discord shows "Ask to Join" on the presence.
+#[derive(Default)]
+pub struct PartySecrets {
+    pub id: String,
+    pub join: String,
+    pub match_secret: String,
+}
+
+impl PartySecrets {
+    pub fn run(&mut self, ui: &mut Ui, has_size: bool, has_buttons: bool) {
+        egui::CollapsingHeader::new("Ask to Join").show(ui, |ui| {
+            egui::Grid::new("party_secrets").show(ui, |ui| {
+                for (label, value) in [
+                    ("Party ID", &mut self.id),
+                    ("Join secret", &mut self.join),
+                    ("Match secret", &mut self.match_secret),
+                ] {
+                    ui.label(label);
+                    ui.add(egui::TextEdit::singleline(value).desired_width(250.));
+                    ui.end_row();
+                }
+            });
+            if let Some(problem) = self.problem(has_size, has_buttons) {
+                ui.label(RichText::new(problem).color(Color32::LIGHT_RED).small());
+            }
+        });
+    }
+
+    /// Why Discord won't show "Ask to Join" or the presence as set.
+    pub fn problem(&self, has_size: bool, has_buttons: bool) -> Option<String> {
+        for (field, value) in [
+            ("Party ID", &self.id),
+            ("Join secret", &self.join),
+            ("Match secret", &self.match_secret),
+        ] {
+            if value.chars().count() > 128 {
+                return Some(format!("{field} must be at most 128 characters"));
+            }
+        }
+        if self.join.is_empty() {
+            return None;
+        }
+        match (self.id.is_empty(), has_size, has_buttons) {
+            (true, _, _) => Some("Ask to Join needs a party ID".to_string()),
+            (false, false, _) => Some("Ask to Join needs a party size and a state".to_string()),
+            (false, true, true) => {
+                Some("Discord doesn't show buttons together with Ask to Join".to_string())
+            }
+            (false, true, false) => None,
+        }
+    }
+}
@@ -0,0 +1,201 @@
+use crate::goal::Goal;
+use crate::integrations::{self, NowPlaying};
+use crate::twitch;
+use crate::window_title;
+use chrono::format::{Item, StrftimeItems};
+use chrono::{Local, Utc};
+use sysinfo::{System, SystemExt};
+
+/// Cells in a progress bar.
+const BAR_WIDTH: usize = 10;
+/// Partly filled cells, by eighths.
+const EIGHTHS: [char; 8] = ['░', '▏', '▎', '▍', '▌', '▋', '▊', '▉'];
+const MOON: [char; 8] = ['🌑', '🌒', '🌓', '🌔', '🌕', '🌖', '🌗', '🌘'];
+/// Seconds each moon phase is shown for.
+const MOON_STEP: i64 = 15;
+/// Longest window title put in, what Discord allows for a whole text.
+const MAX_TITLE: usize = 128;
+
+/// Expands `{name}` and `{name:argument}` placeholders in a presence text
+/// when it's sent. Unknown placeholders are left as they are.
+///
+/// - `{bar:37}`, `{bar:37%}` or `{bar:37/100}`: a progress bar
+/// - `{moon}`: a moon that changes phase as the presence is updated
+/// - `{goal}`, `{goal_pct}`, `{goal_bar}`, `{goal_name}`: progress of the goal
+/// - `{time}`, `{date}`, `{weekday}`: the local time, `{time:%I:%M %p}` in
+///   another strftime format
+/// - `{hostname}`, `{uptime}`: name of the computer and how long it's been on
+/// - `{window}`: title of the focused window, `{window:40}` cut to 40 characters
+/// - `{track}` or `{title}`, `{artist}`, `{album}`, `{album_art}`: what the music integrations
+///   say is playing, empty when nothing is
+/// - `{stream_title}`: title of the Twitch channel set in the integrations
+pub fn expand(text: &str, goal: &Goal) -> String {
+    let mut expanded = String::with_capacity(text.len());
+    let mut rest = text;
+    while let Some(start) = rest.find('{') {
+        let after = &rest[start + 1..];
+        let end = match after.find('}') {
+            Some(end) => end,
+            None => break,
+        };
+        expanded.push_str(&rest[..start]);
+        match value(&after[..end], goal) {
+            Some(value) => expanded.push_str(&value),
+            None => expanded.push_str(&rest[start..start + end + 2]),
+        }
+        rest = &after[end + 1..];
+    }
+    expanded.push_str(rest);
+    expanded
+}
+
+fn value(placeholder: &str, goal: &Goal) -> Option<String> {
+    let (name, argument) = match placeholder.split_once(':') {
+        Some((name, argument)) => (name.trim(), Some(argument.trim())),
+        None => (placeholder.trim(), None),
+    };
+    match (name, argument) {
+        ("bar", Some(argument)) => percent(argument).map(bar),
+        ("moon", None) => Some(moon()),
+        ("goal", None) => Some(goal.progress()),
+        ("goal_pct", None) => Some(format!("{:.0}%", goal.percent())),
+        ("goal_bar", None) => Some(bar(goal.percent())),
+        ("goal_name", None) => Some(goal.name.clone()),
+        ("time", format) => local_time(format.unwrap_or("%H:%M")),
+        ("date", format) => local_time(format.unwrap_or("%Y-%m-%d")),
+        ("weekday", None) => local_time("%A"),
+        ("hostname", None) => System::new().host_name(),
+        ("uptime", None) => Some(duration(System::new().uptime())),
+        ("track" | "title", None) => Some(playing(|playing| playing.track)),
+        ("artist", None) => Some(playing(|playing| playing.artist)),
+        ("album", None) => Some(playing(|playing| playing.album)),
+        ("album_art", None) => Some(playing(|playing| playing.art_url)),
+        ("stream_title", None) => Some(twitch::title()),
+        ("window", length) => {
+            let length = length.map_or(Some(MAX_TITLE), |length| length.parse().ok())?;
+            Some(window_title::current().chars().take(length).collect())
+        }
+        _ => None,
+    }
+}
+
+/// Whether the text has placeholders that change over time, so the presence
+/// needs to be sent again now and then.
+pub fn changes_over_time(text: &str) -> bool {
+    [
+        "{moon",
+        "{time",
+        "{date",
+        "{weekday",
+        "{uptime",
+        "{window",
+        "{track",
+        "{title",
+        "{artist",
+        "{album",
+        "{stream_title",
+    ]
+    .iter()
+    .any(|placeholder| text.contains(placeholder))
+}
+
+fn playing(field: fn(NowPlaying) -> String) -> String {
+    integrations::now_playing().map(field).unwrap_or_default()
+}
+
+/// The local time in a strftime format, `None` when the format is invalid.
+fn local_time(format: &str) -> Option<String> {
+    let items: Vec<Item> = StrftimeItems::new(format).collect();
+    if items.iter().any(|item| matches!(item, Item::Error)) {
+        return None;
+    }
+    Some(
+        Local::now()
+            .format_with_items(items.into_iter())
+            .to_string(),
+    )
+}
+
+/// Seconds as `2d 4h`, `3h 12m` or `12m`.
+fn duration(seconds: u64) -> String {
+    let (days, hours, minutes) = (seconds / 86_400, seconds / 3_600 % 24, seconds / 60 % 60);
+    match (days, hours) {
+        (0, 0) => format!("{minutes}m"),
+        (0, _) => format!("{hours}h {minutes}m"),
+        _ => format!("{days}d {hours}h"),
+    }
+}
+
+/// Reads `37`, `37%` or `37/100` as a percentage.
+fn percent(text: &str) -> Option<f64> {
+    match text.split_once('/') {
+        Some((value, total)) => {
+            let value: f64 = value.trim().parse().ok()?;
+            let total: f64 = total.trim().parse().ok()?;
+            match total > 0. {
+                true => Some(value / total * 100.),
+                false => None,
+            }
+        }
+        None => text.trim_end_matches('%').trim().parse().ok(),
+    }
+}
+
+/// A bar of Unicode blocks filled to `percent`, in eighths of a cell.
+pub fn bar(percent: f64) -> String {
+    let eighths = (percent.clamp(0., 100.) / 100. * (BAR_WIDTH * 8) as f64).round() as usize;
+    let full = eighths / 8;
+    let mut bar = "█".repeat(full);
+    if full < BAR_WIDTH {
+        bar.push(EIGHTHS[eighths % 8]);
+        bar.push_str(&"░".repeat(BAR_WIDTH - full - 1));
+    }
+    bar
+}
+
+fn moon() -> String {
+    let phase = Utc::now().timestamp() / MOON_STEP;
+    MOON[phase.rem_euclid(MOON.len() as i64) as usize].to_string()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::expand;
+    use crate::goal::Goal;
+
+    fn goal() -> Goal {
+        Goal {
+            name: "Hours practiced".to_string(),
+            current: 37.,
+            target: 100.,
+            ..Default::default()
+        }
+    }
+
+    #[test]
+    fn expands_the_goal() {
+        assert_eq!(
+            expand("{goal_name}: {goal} ({goal_pct})", &goal()),
+            "Hours practiced: 37/100 (37%)"
+        );
+        assert_eq!(expand("{goal_bar}", &goal()), expand("{bar:37}", &goal()));
+    }
+
+    #[test]
+    fn draws_bars() {
+        assert_eq!(expand("{bar:50}", &goal()), "█████░░░░░");
+        assert_eq!(expand("{bar:1/2}", &goal()), "█████░░░░░");
+        assert_eq!(expand("{bar:100%}", &goal()), "██████████");
+        assert_eq!(expand("{bar:150}", &goal()), "██████████");
+    }
+
+    #[test]
+    fn keeps_what_isnt_a_placeholder() {
+        assert_eq!(expand("no placeholders", &goal()), "no placeholders");
+        assert_eq!(expand("{unknown} left", &goal()), "{unknown} left");
+        assert_eq!(expand("{bar:1/0}", &goal()), "{bar:1/0}");
+        assert_eq!(expand("{goal:extra}", &goal()), "{goal:extra}");
+        assert_eq!(expand("open {goal", &goal()), "open {goal");
+        assert_eq!(expand("{}", &goal()), "{}");
+    }
+}
@@ -0,0 +1,45 @@
+use std::process::{Command, Stdio};
+use std::thread;
+
+/// Features that depend on what the platform offers. Code that needs one
+/// asks here first, so a platform without it gets a message instead of a
+/// build failure or a panic.
+#[derive(Clone, Copy)]
+pub enum Feature {
+    /// Talking to Discord over its unix socket or named pipe.
+    Ipc,
+    /// The window, which needs the system's graphics drivers.
+    Window,
+    /// Not supported by the app on any platform so far.
+    RunOnStartup,
+    StartMinimized,
+}
+
+/// Why the feature can't be used here, `None` when it can.
+pub fn unavailable(feature: Feature) -> Option<&'static str> {
+    match feature {
+        Feature::Ipc if !cfg!(any(unix, windows)) => {
+            Some("Discord's IPC isn't available on this platform")
+        }
+        // a static binary can't load the graphics drivers at runtime
+        Feature::Window if cfg!(all(target_env = "musl", target_feature = "crt-static")) => {
+            Some("Static musl builds can't open a window, use --headless or the command line")
+        }
+        Feature::RunOnStartup | Feature::StartMinimized => {
+            Some("Not supported by this version of the app")
+        }
+        _ => None,
+    }
+}
+
+/// Starts `command` without its output and without waiting for it. It's
+/// waited on from a thread of its own so it doesn't linger as a zombie.
+pub fn spawn_detached(command: &mut Command) -> std::io::Result<()> {
+    let mut child = command
+        .stdin(Stdio::null())
+        .stdout(Stdio::null())
+        .stderr(Stdio::null())
+        .spawn()?;
+    thread::spawn(move || child.wait());
+    Ok(())
+}
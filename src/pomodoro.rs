@@ -0,0 +1,265 @@
+use crate::platform::spawn_detached;
+use crate::preset::Preset;
+use chrono::{DateTime, Duration, Utc};
+use derivative::Derivative;
+use eframe::egui::{self, Context, DragValue};
+use serde::{Deserialize, Serialize};
+use std::process::Command;
+
+#[derive(Serialize, Deserialize, Derivative, Clone)]
+#[derivative(Default)]
+#[serde(default)]
+pub struct PomodoroSettings {
+    #[derivative(Default(value = "25"))]
+    pub work_minutes: u32,
+    #[derivative(Default(value = "5"))]
+    pub break_minutes: u32,
+    #[derivative(Default(value = "\"Focusing\".to_string()"))]
+    pub work_text: String,
+    #[derivative(Default(value = "\"On a break\".to_string()"))]
+    pub break_text: String,
+    #[derivative(Default(value = "true"))]
+    pub notify: bool,
+}
+
+#[derive(Clone, Copy, PartialEq, Eq)]
+enum Phase {
+    Work,
+    Break,
+}
+
+/// What the presence should change to.
+pub enum Step {
+    /// Shows the details, with a countdown to `end` unless paused.
+    Show {
+        details: String,
+        end: Option<DateTime<Utc>>,
+    },
+    /// The pomodoro ended, what was shown before comes back.
+    Stopped,
+}
+
+/// Work and break phases that drive the presence, like "Focusing" with the
+/// time left and then "On a break".
+#[derive(Default)]
+pub struct Pomodoro {
+    pub open: bool,
+    pub settings: PomodoroSettings,
+    /// What was shown before the first phase.
+    pub previous: Option<Preset>,
+    phase: Option<Phase>,
+    ends: Option<DateTime<Utc>>,
+    /// Time left in the phase while paused.
+    paused: Option<Duration>,
+    /// Work phases finished so far.
+    done: u32,
+    pending: Option<Step>,
+}
+
+impl Pomodoro {
+    pub fn new(settings: PomodoroSettings) -> Self {
+        Self {
+            settings,
+            ..Default::default()
+        }
+    }
+
+    pub fn run(&mut self, ctx: &Context) {
+        let mut open = self.open;
+        egui::containers::Window::new("Pomodoro")
+            .open(&mut open)
+            .resizable(false)
+            .show(ctx, |ui| {
+                match (self.phase, self.paused, self.ends) {
+                    (None, _, _) => ui.label("Not running"),
+                    (Some(phase), Some(left), _) => ui.label(format!(
+                        "{}, paused with {} left",
+                        self.text(phase),
+                        clock(left)
+                    )),
+                    (Some(phase), None, Some(ends)) => {
+                        ctx.request_repaint_after(std::time::Duration::from_secs(1));
+                        ui.label(format!(
+                            "{}, {} left",
+                            self.text(phase),
+                            clock(ends - Utc::now())
+                        ))
+                    }
+                    (Some(phase), None, None) => ui.label(self.text(phase)),
+                };
+                ui.label(format!("{} pomodoros done", self.done));
+                ui.horizontal(|ui| match (self.phase, self.paused) {
+                    (None, _) => {
+                        if ui.button("Start").clicked() {
+                            self.done = 0;
+                            self.begin(Phase::Work);
+                        }
+                    }
+                    (Some(phase), paused) => {
+                        match paused {
+                            Some(left) => {
+                                if ui.button("Resume").clicked() {
+                                    self.paused = None;
+                                    self.ends = Some(Utc::now() + left);
+                                    self.show(phase);
+                                }
+                            }
+                            None => {
+                                if ui.button("Pause").clicked() {
+                                    let left = self.ends.map(|ends| ends - Utc::now());
+                                    self.paused = Some(left.unwrap_or_else(Duration::zero));
+                                    self.pending = Some(Step::Show {
+                                        details: format!("{} (paused)", self.text(phase)),
+                                        end: None,
+                                    });
+                                }
+                            }
+                        }
+                        if ui.button("Skip").clicked() {
+                            self.advance(false);
+                        }
+                        if ui.button("Stop").clicked() {
+                            self.phase = None;
+                            self.paused = None;
+                            self.pending = Some(Step::Stopped);
+                        }
+                    }
+                });
+                ui.separator();
+                egui::Grid::new("pomodoro").show(ui, |ui| {
+                    ui.label("Work");
+                    ui.add(
+                        DragValue::new(&mut self.settings.work_minutes)
+                            .clamp_range(1..=240)
+                            .suffix(" min"),
+                    );
+                    ui.text_edit_singleline(&mut self.settings.work_text);
+                    ui.end_row();
+                    ui.label("Break");
+                    ui.add(
+                        DragValue::new(&mut self.settings.break_minutes)
+                            .clamp_range(1..=120)
+                            .suffix(" min"),
+                    );
+                    ui.text_edit_singleline(&mut self.settings.break_text);
+                    ui.end_row();
+                });
+                ui.checkbox(&mut self.settings.notify, "Notify when a phase ends");
+            });
+        self.open = open;
+    }
+
+    /// The change to make to the presence, when a button was pressed or a
+    /// phase ran out.
+    pub fn next(&mut self) -> Option<Step> {
+        if let (Some(_), None, Some(ends)) = (self.phase, self.paused, self.ends) {
+            if ends <= Utc::now() {
+                self.advance(true);
+            }
+        }
+        self.pending.take()
+    }
+
+    /// Whether a phase is going, also while paused.
+    pub fn running(&self) -> bool {
+        self.phase.is_some()
+    }
+
+    /// How long until the phase runs out.
+    pub fn wait(&self) -> Option<Duration> {
+        match (self.phase, self.paused) {
+            (Some(_), None) => self.ends.map(|ends| ends - Utc::now()),
+            _ => None,
+        }
+    }
+
+    /// Starts the next phase, `ran_out` when the last one ended on its own
+    /// rather than being skipped, only then there's a notification.
+    fn advance(&mut self, ran_out: bool) {
+        let next = match self.phase {
+            Some(Phase::Work) => {
+                self.done += 1;
+                Phase::Break
+            }
+            _ => Phase::Work,
+        };
+        if ran_out && self.settings.notify {
+            let body = match next {
+                Phase::Work => format!("Back to work for {} minutes", self.settings.work_minutes),
+                Phase::Break => format!("Take {} minutes off", self.settings.break_minutes),
+            };
+            notify(self.text(next), &body);
+        }
+        self.begin(next);
+    }
+
+    fn begin(&mut self, phase: Phase) {
+        let minutes = match phase {
+            Phase::Work => self.settings.work_minutes,
+            Phase::Break => self.settings.break_minutes,
+        };
+        self.paused = None;
+        self.ends = Some(Utc::now() + Duration::minutes(minutes as i64));
+        self.show(phase);
+    }
+
+    fn show(&mut self, phase: Phase) {
+        self.phase = Some(phase);
+        self.pending = Some(Step::Show {
+            details: self.text(phase).to_string(),
+            end: self.ends,
+        });
+    }
+
+    fn text(&self, phase: Phase) -> &str {
+        match phase {
+            Phase::Work => &self.settings.work_text,
+            Phase::Break => &self.settings.break_text,
+        }
+    }
+}
+
+/// `18:00` for 18 minutes.
+fn clock(left: Duration) -> String {
+    let seconds = left.num_seconds().max(0);
+    format!("{:02}:{:02}", seconds / 60, seconds % 60)
+}
+
+/// Shows a desktop notification without waiting for it, failures are
+/// ignored since the presence already tells.
+fn notify(title: &str, body: &str) {
+    let mut command = match std::env::consts::OS {
+        "windows" => {
+            let script = format!(
+                "[Windows.UI.Notifications.ToastNotificationManager, Windows.UI.Notifications, ContentType = WindowsRuntime] > $null; \
+                 $xml = [Windows.UI.Notifications.ToastNotificationManager]::GetTemplateContent([Windows.UI.Notifications.ToastTemplateType]::ToastText02); \
+                 $texts = $xml.GetElementsByTagName('text'); \
+                 $texts.Item(0).AppendChild($xml.CreateTextNode('{}')) > $null; \
+                 $texts.Item(1).AppendChild($xml.CreateTextNode('{}')) > $null; \
+                 [Windows.UI.Notifications.ToastNotificationManager]::CreateToastNotifier('{{1AC14E77-02E7-4E5D-B744-2EB1AE5198B7}}\\WindowsPowerShell\\v1.0\\powershell.exe').Show([Windows.UI.Notifications.ToastNotification]::new($xml))",
+                title.replace('\'', "''"),
+                body.replace('\'', "''"),
+            );
+            let mut command = Command::new("powershell");
+            command.args(["-NoProfile", "-Command", &script]);
+            command
+        }
+        "macos" => {
+            let quote = |text: &str| text.replace('\\', "\\\\").replace('"', "\\\"");
+            let script = format!(
+                "display notification \"{}\" with title \"{}\"",
+                quote(body),
+                quote(title)
+            );
+            let mut command = Command::new("osascript");
+            command.args(["-e", &script]);
+            command
+        }
+        _ => {
+            let mut command = Command::new("notify-send");
+            command.args(["--app-name=Discord Presence", title, body]);
+            command
+        }
+    };
+    let _ = spawn_detached(&mut command);
+}
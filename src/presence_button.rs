@@ -1,4 +1,6 @@
-use eframe::egui::Ui;
+use crate::limits;
+use eframe::egui::{RichText, Ui};
+use eframe::epaint::Color32;
 
 #[derive(Default)]
 pub struct PresenceButton {
@@ -17,14 +19,74 @@ impl PresenceButton {
             ui.add_space(2.);
             ui.horizontal(|ui| {
                 ui.label("Label");
-                ui.text_edit_singleline(&mut self.label)
+                ui.text_edit_singleline(&mut self.label);
+                limits::BUTTON_LABEL.counter(ui, &self.label);
             });
             ui.add_space(2.);
             ui.horizontal(|ui| {
                 ui.label("URL");
                 ui.add_space(7.);
                 ui.text_edit_singleline(&mut self.url)
-            })
+            });
+            if let Err(problem) = check_url(&self.url) {
+                if !self.url.is_empty() {
+                    ui.label(RichText::new(problem).color(Color32::LIGHT_RED).small());
+                }
+            }
+            for (limit, text) in [
+                (limits::BUTTON_LABEL, &self.label),
+                (limits::BUTTON_URL, &self.url),
+            ] {
+                if let Err(problem) = limit.check(text) {
+                    ui.label(RichText::new(problem).color(Color32::LIGHT_RED).small());
+                }
+            }
         });
     }
 }
+
+/// Checks a button url against the schemes Discord opens from a presence:
+/// http(s) links and `discord://` deep links to a channel or invite.
+pub fn check_url(url: &str) -> Result<(), String> {
+    if url.chars().any(char::is_whitespace) {
+        return Err("URL must not contain spaces".to_string());
+    }
+    let (scheme, rest) = match url.split_once("://") {
+        Some(parts) => parts,
+        None => return Err("URL needs http://, https:// or discord://".to_string()),
+    };
+    let host = rest.split(['/', '?', '#']).next().unwrap_or_default();
+    match scheme {
+        "http" | "https" if !host.is_empty() => Ok(()),
+        "http" | "https" => Err("URL is missing a host".to_string()),
+        "discord" => check_deep_link(host, &rest[host.len()..]),
+        _ => Err(format!("{scheme}:// links are not supported")),
+    }
+}
+
+fn check_deep_link(host: &str, path: &str) -> Result<(), String> {
+    let host_ok = matches!(
+        host,
+        "-" | "discord.com" | "ptb.discord.com" | "canary.discord.com"
+    );
+    let mut parts = path.trim_start_matches('/').split('/');
+    let is_snowflake = |part: &str| !part.is_empty() && part.chars().all(|c| c.is_ascii_digit());
+    let path_ok = match parts.next() {
+        // channels/<server or @me>/<channel>[/<message>]
+        Some("channels") => {
+            let server = parts.next().unwrap_or_default();
+            let rest: Vec<&str> = parts.collect();
+            (server == "@me" || is_snowflake(server))
+                && (1..=2).contains(&rest.len())
+                && rest.iter().all(|part| is_snowflake(part))
+        }
+        Some("invite") => parts.next().map_or(false, |code| !code.is_empty()),
+        _ => false,
+    };
+    match host_ok && path_ok {
+        true => Ok(()),
+        false => Err(
+            "discord:// links must look like discord://-/channels/<server>/<channel>".to_string(),
+        ),
+    }
+}
@@ -1,29 +1,213 @@
 #![allow(non_snake_case)]
 
-use crate::{App, TimestampEnum};
+use crate::{
+    app_cache::check_id,
+    automation::Schedule,
+    error::Error,
+    goal::Goal,
+    image::{self, check_key},
+    limits, placeholders,
+    presence_button::check_url,
+    timestamp::{format_local, parse_local},
+    App, TimestampEnum,
+};
+use discord_rich_presence::activity::{Activity, Assets, Button, Party, Secrets, Timestamps};
 use serde::{Deserialize, Serialize};
+use serde_json::Value;
+use std::{fs, path::Path};
 
-#[derive(Serialize, Deserialize, Debug, PartialEq, Eq, Clone)]
+/// How many `Base` presets may be chained before giving up, guards against cycles.
+const MAX_BASE_DEPTH: u8 = 8;
 
+/// A preset file. Keys are written in PascalCase but snake_case and camelCase
+/// spellings are accepted as well, so hand-written files load either way.
+#[derive(Serialize, Deserialize, Debug, PartialEq, Eq, Clone)]
 pub struct Preset {
+    #[serde(alias = "id", alias = "Id")]
     pub ID: Option<String>,
+    #[serde(alias = "details")]
     pub Details: Option<String>,
+    #[serde(alias = "state")]
     pub State: Option<String>,
+    #[serde(alias = "party_size", alias = "partySize", alias = "partysize")]
     pub PartySize: Option<u8>,
+    #[serde(alias = "party_max", alias = "partyMax", alias = "partymax")]
     pub PartyMax: Option<u8>,
+    #[serde(alias = "timestamps")]
     pub Timestamps: Option<u8>,
+    #[serde(
+        alias = "custom_timestamp",
+        alias = "customTimestamp",
+        alias = "customtimestamp"
+    )]
     pub CustomTimestamp: Option<String>,
+    /// Start the timestamp this many minutes before the preset is loaded.
+    #[serde(
+        alias = "start_offset_minutes",
+        alias = "startOffsetMinutes",
+        alias = "startoffsetminutes",
+        skip_serializing_if = "Option::is_none"
+    )]
+    pub StartOffsetMinutes: Option<i64>,
+    #[serde(alias = "large_key", alias = "largeKey", alias = "largekey")]
     pub LargeKey: Option<String>,
+    #[serde(alias = "large_text", alias = "largeText", alias = "largetext")]
     pub LargeText: Option<String>,
+    #[serde(alias = "small_key", alias = "smallKey", alias = "smallkey")]
     pub SmallKey: Option<String>,
+    #[serde(alias = "small_text", alias = "smallText", alias = "smalltext")]
     pub SmallText: Option<String>,
+    #[serde(alias = "button1_text", alias = "button1Text", alias = "button1text")]
     pub Button1Text: Option<String>,
+    #[serde(
+        alias = "button1_url",
+        alias = "button1Url",
+        alias = "button1URL",
+        alias = "button1url"
+    )]
     pub Button1URL: Option<String>,
+    #[serde(alias = "button2_text", alias = "button2Text", alias = "button2text")]
     pub Button2Text: Option<String>,
+    #[serde(
+        alias = "button2_url",
+        alias = "button2Url",
+        alias = "button2URL",
+        alias = "button2url"
+    )]
     pub Button2URL: Option<String>,
+    /// Name of an in-app preset or path to a preset file (relative to this one)
+    /// whose values are used for every field this preset leaves out.
+    #[serde(alias = "base", skip_serializing_if = "Option::is_none")]
+    pub Base: Option<String>,
+    /// Comma separated process names, the preset is applied while one is running.
+    #[serde(
+        alias = "trigger_processes",
+        alias = "triggerProcesses",
+        alias = "triggerprocesses",
+        skip_serializing_if = "Option::is_none"
+    )]
+    pub TriggerProcesses: Option<String>,
+    /// Time range like `Mon-Fri 09:00-17:00` during which the preset is applied.
+    #[serde(
+        alias = "trigger_schedule",
+        alias = "triggerSchedule",
+        alias = "triggerschedule",
+        skip_serializing_if = "Option::is_none"
+    )]
+    pub TriggerSchedule: Option<String>,
+    /// Second line of the large image hover text.
+    #[serde(
+        alias = "large_text2",
+        alias = "largeText2",
+        alias = "largetext2",
+        skip_serializing_if = "Option::is_none"
+    )]
+    pub LargeText2: Option<String>,
+    /// Show a countdown ending this many minutes after the preset is loaded.
+    #[serde(
+        alias = "end_after_minutes",
+        alias = "endAfterMinutes",
+        alias = "endafterminutes",
+        skip_serializing_if = "Option::is_none"
+    )]
+    pub EndAfterMinutes: Option<i64>,
+    /// In-app preset loaded when the countdown runs out.
+    #[serde(
+        alias = "next_preset",
+        alias = "nextPreset",
+        alias = "nextpreset",
+        skip_serializing_if = "Option::is_none"
+    )]
+    pub NextPreset: Option<String>,
+    /// Show a countdown ending at this local time, `YYYY-MM-DD HH:MM`.
+    #[serde(
+        alias = "ends_at",
+        alias = "endsAt",
+        alias = "endsat",
+        skip_serializing_if = "Option::is_none"
+    )]
+    pub EndsAt: Option<String>,
+    /// Party ID, with a join secret Discord shows Ask to Join.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub PartyId: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub JoinSecret: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub MatchSecret: Option<String>,
+    /// Tag like `#5865F2` shown while the preset is live.
+    #[serde(alias = "color", skip_serializing_if = "Option::is_none")]
+    pub Color: Option<String>,
 }
 
 impl Preset {
+    /// Reads a preset file and fills in anything it leaves out from its `Base`.
+    pub fn from_file(path: &Path, in_app: &[InAppPreset]) -> Result<Self, String> {
+        Self::from_file_with_depth(path, in_app, 0)
+    }
+
+    fn from_file_with_depth(
+        path: &Path,
+        in_app: &[InAppPreset],
+        depth: u8,
+    ) -> Result<Self, String> {
+        if depth > MAX_BASE_DEPTH {
+            return Err("Base presets are nested too deep, is there a cycle?".to_string());
+        }
+        let file = match fs::read_to_string(path) {
+            Ok(file) => file,
+            Err(_) => return Err(format!("Failed to read preset {}", path.display())),
+        };
+        let preset: Preset = match serde_xml_rs::from_str(&file) {
+            Ok(preset) => preset,
+            Err(err) => return Err(format!("Invalid preset: {err}")),
+        };
+        let base = match preset.Base.as_deref() {
+            None | Some("") => return Ok(preset),
+            Some(base) => base.to_string(),
+        };
+        let base = match in_app.iter().find(|in_app| in_app.name == base) {
+            Some(in_app) => Preset::from_in_app(in_app.clone()),
+            None => {
+                let dir = path.parent().unwrap_or_else(|| Path::new(""));
+                Self::from_file_with_depth(&dir.join(base), in_app, depth + 1)?
+            }
+        };
+        Ok(preset.inherit(base))
+    }
+
+    /// Uses `base` for every field this preset doesn't set.
+    pub fn inherit(self, base: Preset) -> Self {
+        Self {
+            ID: self.ID.or(base.ID),
+            Details: self.Details.or(base.Details),
+            State: self.State.or(base.State),
+            PartySize: self.PartySize.or(base.PartySize),
+            PartyMax: self.PartyMax.or(base.PartyMax),
+            Timestamps: self.Timestamps.or(base.Timestamps),
+            CustomTimestamp: self.CustomTimestamp.or(base.CustomTimestamp),
+            StartOffsetMinutes: self.StartOffsetMinutes.or(base.StartOffsetMinutes),
+            LargeKey: self.LargeKey.or(base.LargeKey),
+            LargeText: self.LargeText.or(base.LargeText),
+            SmallKey: self.SmallKey.or(base.SmallKey),
+            SmallText: self.SmallText.or(base.SmallText),
+            Button1Text: self.Button1Text.or(base.Button1Text),
+            Button1URL: self.Button1URL.or(base.Button1URL),
+            Button2Text: self.Button2Text.or(base.Button2Text),
+            Button2URL: self.Button2URL.or(base.Button2URL),
+            Base: None,
+            TriggerProcesses: self.TriggerProcesses.or(base.TriggerProcesses),
+            TriggerSchedule: self.TriggerSchedule.or(base.TriggerSchedule),
+            LargeText2: self.LargeText2.or(base.LargeText2),
+            EndAfterMinutes: self.EndAfterMinutes.or(base.EndAfterMinutes),
+            NextPreset: self.NextPreset.or(base.NextPreset),
+            EndsAt: self.EndsAt.or(base.EndsAt),
+            PartyId: self.PartyId.or(base.PartyId),
+            JoinSecret: self.JoinSecret.or(base.JoinSecret),
+            MatchSecret: self.MatchSecret.or(base.MatchSecret),
+            Color: self.Color.or(base.Color),
+        }
+    }
+
     pub fn timestamp_from_num(&self) -> TimestampEnum {
         if self.Timestamps == None {
             return TimestampEnum::None;
@@ -33,8 +217,7 @@ impl Preset {
             1 => TimestampEnum::SinceStart,
             4 => TimestampEnum::SinceLastUpdate,
             2 => TimestampEnum::LocalTime,
-            // Custom TimeStamp
-            3 => TimestampEnum::None,
+            3 => TimestampEnum::CustomTimeStamp,
             _ => TimestampEnum::None,
         }
     }
@@ -47,7 +230,15 @@ impl Preset {
             PartySize: Some(app.party),
             PartyMax: Some(app.party_of),
             Timestamps: Some(app.timestamp.timestamp.to_num()),
-            CustomTimestamp: None,
+            CustomTimestamp: match app.timestamp.timestamp {
+                TimestampEnum::CustomTimeStamp => app
+                    .timestamp
+                    .relative_start
+                    .or(Some(app.timestamp.start))
+                    .map(format_local),
+                _ => None,
+            },
+            StartOffsetMinutes: None,
             LargeKey: Some(app.first_img.key.clone()),
             LargeText: Some(app.first_img.text.clone()),
             SmallKey: Some(app.second_img.key.clone()),
@@ -56,11 +247,284 @@ impl Preset {
             Button1URL: Some(app.first_btn.url.clone()),
             Button2Text: Some(app.second_btn.label.clone()),
             Button2URL: Some(app.second_btn.url.clone()),
+            Base: None,
+            TriggerProcesses: None,
+            TriggerSchedule: None,
+            LargeText2: app
+                .first_img
+                .second_line
+                .clone()
+                .filter(|line| !line.is_empty()),
+            EndAfterMinutes: None,
+            NextPreset: None,
+            EndsAt: app.timestamp.end.map(format_local),
+            PartyId: Some(app.party_secrets.id.clone()).filter(|id| !id.is_empty()),
+            JoinSecret: Some(app.party_secrets.join.clone()).filter(|join| !join.is_empty()),
+            MatchSecret: Some(app.party_secrets.match_secret.clone())
+                .filter(|secret| !secret.is_empty()),
+            Color: Some(app.color.clone()).filter(|color| !color.is_empty()),
         }
     }
 
+    /// Names the parts of the presence that differ between the two presets.
+    pub fn diff(&self, other: &Preset) -> Vec<&'static str> {
+        let mut changed = vec![];
+        if self.ID != other.ID {
+            changed.push("application");
+        }
+        if self.Details != other.Details {
+            changed.push("details");
+        }
+        if self.State != other.State {
+            changed.push("state");
+        }
+        if (self.PartySize, self.PartyMax, &self.PartyId)
+            != (other.PartySize, other.PartyMax, &other.PartyId)
+        {
+            changed.push("party");
+        }
+        if (&self.JoinSecret, &self.MatchSecret) != (&other.JoinSecret, &other.MatchSecret) {
+            changed.push("secrets");
+        }
+        if (self.Timestamps, &self.CustomTimestamp, &self.EndsAt)
+            != (other.Timestamps, &other.CustomTimestamp, &other.EndsAt)
+        {
+            changed.push("timestamp");
+        }
+        if (&self.LargeKey, &self.LargeText, &self.LargeText2)
+            != (&other.LargeKey, &other.LargeText, &other.LargeText2)
+        {
+            changed.push("large image");
+        }
+        if (&self.SmallKey, &self.SmallText) != (&other.SmallKey, &other.SmallText) {
+            changed.push("small image");
+        }
+        if (&self.Button1Text, &self.Button1URL) != (&other.Button1Text, &other.Button1URL) {
+            changed.push("button 1");
+        }
+        if (&self.Button2Text, &self.Button2URL) != (&other.Button2Text, &other.Button2URL) {
+            changed.push("button 2");
+        }
+        changed
+    }
+
+    /// The activity sent to Discord for this preset, with the placeholders
+    /// filled in unless `goal` is `None`. The form goes through here too, so
+    /// what's checked before sending is what's sent.
+    pub fn activity(&self, timestamps: Timestamps, goal: Option<&Goal>) -> Result<Value, Error> {
+        let text = |text: &Option<String>| text.clone().unwrap_or_default();
+        let fill = |text: &str| match goal {
+            Some(goal) => placeholders::expand(text, goal),
+            None => text.to_string(),
+        };
+        let expand = |text: &Option<String>| fill(text.as_deref().unwrap_or_default());
+        let large_key = image::send_key(&expand(&self.LargeKey));
+        let small_key = image::send_key(&expand(&self.SmallKey));
+        let assets = Assets::new();
+        let assets = match large_key.as_str() {
+            "" => assets,
+            key => assets.large_image(key),
+        };
+        let large_text = fill(&image::hover_text(
+            &text(&self.LargeText),
+            &text(&self.LargeText2),
+        ));
+        let assets = match large_text.as_str() {
+            "" => assets,
+            _ => assets.large_text(&large_text),
+        };
+        let assets = match small_key.as_str() {
+            "" => assets,
+            key => assets.small_image(key),
+        };
+        let small_text = expand(&self.SmallText);
+        let assets = match small_text.as_str() {
+            "" => assets,
+            _ => assets.small_text(&small_text),
+        };
+        let activity = Activity::new().timestamps(timestamps).assets(assets);
+
+        let details = expand(&self.Details);
+        let activity = match details.as_str() {
+            "" => activity,
+            _ => activity.details(&details),
+        };
+        let state = expand(&self.State);
+        let activity = match state.as_str() {
+            "" => activity,
+            _ => activity.state(&state),
+        };
+
+        let buttons = [
+            (text(&self.Button1Text), text(&self.Button1URL)),
+            (text(&self.Button2Text), text(&self.Button2URL)),
+        ];
+        let buttons: Vec<Button> = buttons
+            .iter()
+            .filter(|(label, url)| !label.is_empty() && !url.is_empty())
+            .map(|(label, url)| Button::new(label, url))
+            .collect();
+        let activity = match !buttons.is_empty() {
+            true => activity.buttons(buttons),
+            false => activity,
+        };
+
+        let party = self.PartySize.unwrap_or_default();
+        let has_size = party != 0 && !text(&self.State).is_empty();
+        let size = [self.PartyMax.unwrap_or_default() as i32, party as i32];
+        let party_id = text(&self.PartyId);
+        let activity = match (has_size, party_id.trim()) {
+            (false, "") => activity,
+            (true, "") => activity.party(Party::new().size(size)),
+            (false, id) => activity.party(Party::new().id(id)),
+            (true, id) => activity.party(Party::new().id(id).size(size)),
+        };
+        let join = text(&self.JoinSecret);
+        let match_secret = text(&self.MatchSecret);
+        let secrets = Secrets::new();
+        let secrets = match join.trim() {
+            "" => secrets,
+            join => secrets.join(join),
+        };
+        let secrets = match match_secret.trim() {
+            "" => secrets,
+            secret => secrets.r#match(secret),
+        };
+        let activity = match join.trim().is_empty() && match_secret.trim().is_empty() {
+            true => activity,
+            false => activity.secrets(secrets),
+        };
+        serde_json::to_value(&activity)
+            .map_err(|_| Error::Update("Failed to serialize activity".to_string()))
+    }
+
+    /// Checks the preset for values Discord would reject, naming the field and
+    /// the problem so the user knows what to fix in the file.
+    pub fn validate(&self) -> Result<(), String> {
+        let mut problems = vec![];
+
+        if let Some(Err(problem)) = self.ID.as_deref().filter(|id| !id.is_empty()).map(check_id) {
+            problems.push(problem);
+        }
+        for (limit, value) in [
+            (limits::DETAILS, &self.Details),
+            (limits::STATE, &self.State),
+        ] {
+            if let Some(Err(problem)) = value.as_deref().map(|value| limit.check(value)) {
+                problems.push(problem);
+            }
+        }
+        if let (Some(size), Some(max)) = (self.PartySize, self.PartyMax) {
+            if size > max {
+                problems.push("PartySize is larger than PartyMax".to_string());
+            }
+        }
+        if let Some(timestamps) = self.Timestamps {
+            if timestamps > 4 {
+                problems.push(format!("Timestamps has unknown value {timestamps}"));
+            }
+        }
+        if let Some(minutes) = self.StartOffsetMinutes {
+            if minutes < 0 {
+                problems.push("StartOffsetMinutes must not be negative".to_string());
+            }
+        }
+        if let Some(minutes) = self.EndAfterMinutes {
+            if minutes <= 0 {
+                problems.push("EndAfterMinutes must be positive".to_string());
+            }
+        }
+        for (field, value) in [
+            ("CustomTimestamp", &self.CustomTimestamp),
+            ("EndsAt", &self.EndsAt),
+        ] {
+            if let Some(time) = value.as_deref() {
+                if !time.is_empty() && parse_local(time).is_none() {
+                    problems.push(format!("{field} {time} isn't a time like 2024-05-01 18:00"));
+                }
+            }
+        }
+        if self.NextPreset.is_some() && self.EndAfterMinutes.is_none() && self.EndsAt.is_none() {
+            problems.push("NextPreset needs EndAfterMinutes or EndsAt".to_string());
+        }
+        if let Some(schedule) = self.TriggerSchedule.as_deref() {
+            if let Err(problem) = Schedule::parse(schedule) {
+                problems.push(format!("TriggerSchedule {problem}"));
+            }
+        }
+        for (field, value) in [("LargeKey", &self.LargeKey), ("SmallKey", &self.SmallKey)] {
+            if let Some(Err(problem)) = value.as_deref().map(check_key) {
+                problems.push(format!("{field}: {problem}"));
+            }
+        }
+        for (field, value) in [
+            ("LargeText", &self.LargeText),
+            ("SmallText", &self.SmallText),
+            ("PartyId", &self.PartyId),
+            ("JoinSecret", &self.JoinSecret),
+            ("MatchSecret", &self.MatchSecret),
+        ] {
+            if let Some(value) = value {
+                if value.chars().count() > 128 {
+                    problems.push(format!("{field} is longer than 128 characters"));
+                }
+            }
+        }
+        for (text_field, text, url_field, url) in [
+            (
+                "Button1Text",
+                &self.Button1Text,
+                "Button1URL",
+                &self.Button1URL,
+            ),
+            (
+                "Button2Text",
+                &self.Button2Text,
+                "Button2URL",
+                &self.Button2URL,
+            ),
+        ] {
+            let text = text.as_deref().unwrap_or_default();
+            let url = url.as_deref().unwrap_or_default();
+            if text.chars().count() > 32 {
+                problems.push(format!("{text_field} is longer than 32 characters"));
+            }
+            if let Err(problem) = check_url(url) {
+                if !url.is_empty() {
+                    problems.push(format!("{url_field}: {problem}"));
+                }
+            }
+            if text.is_empty() != url.is_empty() {
+                let missing = if text.is_empty() {
+                    text_field
+                } else {
+                    url_field
+                };
+                problems.push(format!("{missing} is missing"));
+            }
+        }
+
+        if let Some(color) = self.Color.as_deref() {
+            if parse_color(color).is_none() {
+                problems.push(format!("Color {color} isn't a color like #5865F2"));
+            }
+        }
+
+        match problems.is_empty() {
+            true => Ok(()),
+            false => Err(problems.join(", ")),
+        }
+    }
+
+    /// The preset as a file to share, without the secrets that let others
+    /// join the live party.
     pub fn to_xml(&self) -> String {
-        let xml = serde_xml_rs::to_string(self).unwrap();
+        let preset = Self {
+            JoinSecret: None,
+            MatchSecret: None,
+            ..self.clone()
+        };
+        let xml = serde_xml_rs::to_string(&preset).unwrap();
 
         xml.replace("<CustomTimestamp></CustomTimestamp>", "")
     }
@@ -74,6 +538,7 @@ impl Preset {
             PartyMax: preset.PartyMax,
             Timestamps: preset.Timestamps,
             CustomTimestamp: preset.CustomTimestamp,
+            StartOffsetMinutes: preset.StartOffsetMinutes,
             LargeKey: preset.LargeKey,
             LargeText: preset.LargeText,
             SmallKey: preset.SmallKey,
@@ -82,6 +547,17 @@ impl Preset {
             Button1URL: preset.Button1URL,
             Button2Text: preset.Button2Text,
             Button2URL: preset.Button2URL,
+            Base: None,
+            TriggerProcesses: preset.TriggerProcesses,
+            TriggerSchedule: preset.TriggerSchedule,
+            LargeText2: preset.LargeText2,
+            EndAfterMinutes: preset.EndAfterMinutes,
+            NextPreset: preset.NextPreset,
+            EndsAt: preset.EndsAt,
+            PartyId: preset.PartyId,
+            JoinSecret: preset.JoinSecret,
+            MatchSecret: preset.MatchSecret,
+            Color: preset.Color,
         }
     }
 }
@@ -97,6 +573,7 @@ pub struct InAppPreset {
     pub PartyMax: Option<u8>,
     pub Timestamps: Option<u8>,
     pub CustomTimestamp: Option<String>,
+    pub StartOffsetMinutes: Option<i64>,
     pub LargeKey: Option<String>,
     pub LargeText: Option<String>,
     pub SmallKey: Option<String>,
@@ -105,6 +582,16 @@ pub struct InAppPreset {
     pub Button1URL: Option<String>,
     pub Button2Text: Option<String>,
     pub Button2URL: Option<String>,
+    pub TriggerProcesses: Option<String>,
+    pub TriggerSchedule: Option<String>,
+    pub LargeText2: Option<String>,
+    pub EndAfterMinutes: Option<i64>,
+    pub NextPreset: Option<String>,
+    pub EndsAt: Option<String>,
+    pub PartyId: Option<String>,
+    pub JoinSecret: Option<String>,
+    pub MatchSecret: Option<String>,
+    pub Color: Option<String>,
 }
 
 impl InAppPreset {
@@ -117,7 +604,15 @@ impl InAppPreset {
             PartySize: Some(app.party),
             PartyMax: Some(app.party_of),
             Timestamps: Some(app.timestamp.timestamp.to_num()),
-            CustomTimestamp: None,
+            CustomTimestamp: match app.timestamp.timestamp {
+                TimestampEnum::CustomTimeStamp => app
+                    .timestamp
+                    .relative_start
+                    .or(Some(app.timestamp.start))
+                    .map(format_local),
+                _ => None,
+            },
+            StartOffsetMinutes: None,
             LargeKey: Some(app.first_img.key.clone()),
             LargeText: Some(app.first_img.text.clone()),
             SmallKey: Some(app.second_img.key.clone()),
@@ -126,6 +621,52 @@ impl InAppPreset {
             Button1URL: Some(app.first_btn.url.clone()),
             Button2Text: Some(app.second_btn.label.clone()),
             Button2URL: Some(app.second_btn.url.clone()),
+            TriggerProcesses: None,
+            TriggerSchedule: None,
+            LargeText2: app
+                .first_img
+                .second_line
+                .clone()
+                .filter(|line| !line.is_empty()),
+            EndAfterMinutes: None,
+            NextPreset: None,
+            EndsAt: app.timestamp.end.map(format_local),
+            PartyId: Some(app.party_secrets.id.clone()).filter(|id| !id.is_empty()),
+            JoinSecret: Some(app.party_secrets.join.clone()).filter(|join| !join.is_empty()),
+            MatchSecret: Some(app.party_secrets.match_secret.clone())
+                .filter(|secret| !secret.is_empty()),
+            Color: Some(app.color.clone()).filter(|color| !color.is_empty()),
+        }
+    }
+    pub fn from_preset(preset: Preset, name: String) -> Self {
+        Self {
+            name,
+            ID: preset.ID,
+            Details: preset.Details,
+            State: preset.State,
+            PartySize: preset.PartySize,
+            PartyMax: preset.PartyMax,
+            Timestamps: preset.Timestamps,
+            CustomTimestamp: preset.CustomTimestamp,
+            StartOffsetMinutes: preset.StartOffsetMinutes,
+            LargeKey: preset.LargeKey,
+            LargeText: preset.LargeText,
+            SmallKey: preset.SmallKey,
+            SmallText: preset.SmallText,
+            Button1Text: preset.Button1Text,
+            Button1URL: preset.Button1URL,
+            Button2Text: preset.Button2Text,
+            Button2URL: preset.Button2URL,
+            TriggerProcesses: preset.TriggerProcesses,
+            TriggerSchedule: preset.TriggerSchedule,
+            LargeText2: preset.LargeText2,
+            EndAfterMinutes: preset.EndAfterMinutes,
+            NextPreset: preset.NextPreset,
+            EndsAt: preset.EndsAt,
+            PartyId: preset.PartyId,
+            JoinSecret: preset.JoinSecret,
+            MatchSecret: preset.MatchSecret,
+            Color: preset.Color,
         }
     }
     pub fn to_preset(self) -> Preset {
@@ -137,6 +678,7 @@ impl InAppPreset {
             PartyMax: self.PartyMax,
             Timestamps: self.Timestamps,
             CustomTimestamp: self.CustomTimestamp,
+            StartOffsetMinutes: self.StartOffsetMinutes,
             LargeKey: self.LargeKey,
             LargeText: self.LargeText,
             SmallKey: self.SmallKey,
@@ -145,6 +687,54 @@ impl InAppPreset {
             Button1URL: self.Button1URL,
             Button2Text: self.Button2Text,
             Button2URL: self.Button2URL,
+            Base: None,
+            TriggerProcesses: self.TriggerProcesses,
+            TriggerSchedule: self.TriggerSchedule,
+            LargeText2: self.LargeText2,
+            EndAfterMinutes: self.EndAfterMinutes,
+            NextPreset: self.NextPreset,
+            EndsAt: self.EndsAt,
+            PartyId: self.PartyId,
+            JoinSecret: self.JoinSecret,
+            MatchSecret: self.MatchSecret,
+            Color: self.Color,
         }
     }
 }
+
+/// Reads a color tag like `#5865F2`.
+pub fn parse_color(color: &str) -> Option<[u8; 3]> {
+    let hex = color.trim().strip_prefix('#')?;
+    // from_str_radix would also take a sign
+    if hex.len() != 6 || !hex.chars().all(|c| c.is_ascii_hexdigit()) {
+        return None;
+    }
+    let channel = |i: usize| u8::from_str_radix(hex.get(i..i + 2)?, 16).ok();
+    Some([channel(0)?, channel(2)?, channel(4)?])
+}
+
+pub fn format_color([red, green, blue]: [u8; 3]) -> String {
+    format!("#{red:02X}{green:02X}{blue:02X}")
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{format_color, parse_color};
+
+    #[test]
+    fn parses_hex_colors() {
+        assert_eq!(parse_color("#5865F2"), Some([0x58, 0x65, 0xF2]));
+        assert_eq!(parse_color(" #ff0000 "), Some([255, 0, 0]));
+        assert_eq!(format_color([0x58, 0x65, 0xF2]), "#5865F2");
+    }
+
+    #[test]
+    fn rejects_other_colors() {
+        assert_eq!(parse_color(""), None);
+        assert_eq!(parse_color("5865F2"), None);
+        assert_eq!(parse_color("#FFF"), None);
+        assert_eq!(parse_color("#GG0000"), None);
+        assert_eq!(parse_color("#+1+1+1"), None);
+        assert_eq!(parse_color("#ééé"), None);
+    }
+}
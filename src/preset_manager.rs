@@ -0,0 +1,306 @@
+use crate::guard::Guard;
+use crate::preset::{format_color, parse_color, InAppPreset, Preset};
+use crate::rules::Rules;
+use crate::storage::data_dir;
+use discord_rich_presence::activity::Timestamps;
+use eframe::egui::{self, Context, RichText, TextEdit};
+use serde_json::{from_str, to_string};
+use std::collections::HashMap;
+use std::fs;
+use std::path::{Path, PathBuf};
+
+/// Lists the in-app presets with rename, duplicate, delete, reorder and
+/// apply actions. They're kept as one JSON file each in the presets folder,
+/// numbered to keep their order.
+#[derive(Default)]
+pub struct PresetManager {
+    pub open: bool,
+    /// Index of the preset being renamed and its new name.
+    pub renaming: Option<(usize, String)>,
+    pub error: Option<String>,
+    /// Presets the folder was last written with, it's only rewritten when they change.
+    pub saved: String,
+    /// Old and new names of presets renamed since this was last checked.
+    pub renamed: Vec<(String, String)>,
+    /// What the validators found in each preset, by name.
+    pub warnings: HashMap<String, Vec<String>>,
+    /// Presets, validation rules and flagged words the warnings were found with.
+    linted: String,
+}
+
+impl PresetManager {
+    /// Shows the manager and returns the preset to apply.
+    pub fn run(&mut self, ctx: &Context, presets: &mut String) -> Option<Preset> {
+        let mut list: Vec<InAppPreset> = from_str(presets).unwrap_or_default();
+        let mut apply = None;
+        let mut changed = false;
+        let mut open = self.open;
+        egui::containers::Window::new("Preset manager")
+            .open(&mut open)
+            .resizable(false)
+            .show(ctx, |ui| {
+                if list.is_empty() {
+                    ui.label("No presets yet, save one under File → Save Preset");
+                }
+                let mut remove = None;
+                let mut swap = None;
+                let mut duplicate = None;
+                let mut rename = None;
+                let mut recolor = None;
+                let last = list.len().saturating_sub(1);
+                egui::Grid::new("preset_manager").show(ui, |ui| {
+                    for (i, preset) in list.iter().enumerate() {
+                        match self.warnings.get(&preset.name) {
+                            Some(warnings) => {
+                                ui.label(RichText::new("⚠").color(egui::Color32::YELLOW))
+                                    .on_hover_text(warnings.join("\n"));
+                            }
+                            None => {
+                                ui.label("");
+                            }
+                        }
+                        match &mut self.renaming {
+                            Some((renaming, name)) if *renaming == i => {
+                                let response =
+                                    ui.add(TextEdit::singleline(name).desired_width(150.));
+                                if response.lost_focus() || ui.button("✔").clicked() {
+                                    rename = Some((i, name.trim().to_string()));
+                                }
+                            }
+                            _ => {
+                                ui.label(&preset.name);
+                                if ui.small_button("✏").on_hover_text("Rename").clicked() {
+                                    self.renaming = Some((i, preset.name.clone()));
+                                }
+                            }
+                        }
+                        let color = preset.Color.as_deref().and_then(parse_color);
+                        let mut picked = color.unwrap_or([88, 101, 242]);
+                        let response = match color {
+                            Some(_) => ui
+                                .color_edit_button_srgb(&mut picked)
+                                .on_hover_text("Color tag, right click to remove"),
+                            None => ui.small_button("◻").on_hover_text("Add a color tag"),
+                        };
+                        if response.secondary_clicked() {
+                            recolor = Some((i, None));
+                        } else if (color.is_none() && response.clicked()) || color != Some(picked) {
+                            recolor = Some((i, Some(format_color(picked))));
+                        }
+                        if ui.button("Apply").clicked() {
+                            apply = Some(Preset::from_in_app(preset.clone()));
+                        }
+                        if ui.button("⧉").on_hover_text("Duplicate").clicked() {
+                            duplicate = Some(i);
+                        }
+                        if ui.add_enabled(i > 0, egui::Button::new("⬆")).clicked() {
+                            swap = Some(i - 1);
+                        }
+                        if ui.add_enabled(i < last, egui::Button::new("⬇")).clicked() {
+                            swap = Some(i);
+                        }
+                        if ui.button("❌").clicked() {
+                            remove = Some(i);
+                        }
+                        ui.end_row();
+                    }
+                });
+                if let Some((i, name)) = rename {
+                    self.renaming = None;
+                    if name != list[i].name {
+                        match rename_error(&list, &name) {
+                            Some(error) => self.error = Some(error),
+                            None => {
+                                self.renamed.push((list[i].name.clone(), name.clone()));
+                                list[i].name = name;
+                                changed = true;
+                            }
+                        }
+                    }
+                }
+                if let Some((i, color)) = recolor {
+                    list[i].Color = color;
+                    changed = true;
+                }
+                if let Some(i) = duplicate {
+                    let mut copy = list[i].clone();
+                    copy.name = copy_name(&list, &copy.name);
+                    list.insert(i + 1, copy);
+                    changed = true;
+                }
+                if let Some(i) = swap {
+                    list.swap(i, i + 1);
+                    changed = true;
+                }
+                if let Some(i) = remove {
+                    list.remove(i);
+                    self.renaming = None;
+                    changed = true;
+                }
+                if let Some(error) = &self.error {
+                    ui.colored_label(egui::Color32::LIGHT_RED, error);
+                }
+                if let Some(dir) = presets_dir() {
+                    ui.small(format!("Stored in {}", dir.display()));
+                }
+            });
+        self.open = open;
+        if changed {
+            self.error = None;
+            *presets = to_string(&list).unwrap();
+        }
+        apply
+    }
+
+    /// Runs every validator over the presets when they changed since the
+    /// last time, so broken ones show before they're applied.
+    pub fn lint(&mut self, presets: &str, rules: &Rules, guard: &Guard) {
+        let linted = format!(
+            "{presets}\n{}\n{}\n{}\n{}",
+            rules.banned_words,
+            to_string(&rules.patterns).unwrap(),
+            guard.enabled,
+            guard.flagged_words
+        );
+        if linted == self.linted {
+            return;
+        }
+        let list: Vec<InAppPreset> = from_str(presets).unwrap_or_default();
+        self.warnings = list
+            .into_iter()
+            .map(|preset| (preset.name.clone(), lint(preset, rules, guard)))
+            .filter(|(_, warnings)| !warnings.is_empty())
+            .collect();
+        self.linted = linted;
+    }
+
+    /// Writes the presets to the folder when they changed since the last time.
+    pub fn save(&mut self, presets: &str) -> Result<(), String> {
+        if presets == self.saved {
+            return Ok(());
+        }
+        let dir = match presets_dir() {
+            Some(dir) => dir,
+            None => return Err("No data folder for the presets".to_string()),
+        };
+        // an empty list would empty the folder
+        let list: Vec<InAppPreset> = from_str(presets)
+            .map_err(|err| format!("Not saving the presets, they don't parse: {err}"))?;
+        match write(&dir, &list) {
+            Ok(_) => {
+                self.saved = presets.to_string();
+                Ok(())
+            }
+            Err(err) => Err(format!("Failed to save the presets: {err}")),
+        }
+    }
+}
+
+/// What the preset's own checks, the validation rules and the guard find
+/// in it. Placeholders are checked as written.
+fn lint(preset: InAppPreset, rules: &Rules, guard: &Guard) -> Vec<String> {
+    let preset = Preset::from_in_app(preset);
+    let mut warnings = vec![];
+    if let Err(problems) = preset.validate() {
+        warnings.push(problems);
+    }
+    // times depend on when the preset is applied, they're left out
+    if let Ok(payload) = preset.activity(Timestamps::new(), None) {
+        warnings.extend(rules.check(&payload));
+        warnings.extend(guard.check(&payload));
+    }
+    warnings
+}
+
+/// Reads the presets folder, `None` when there's none yet. Files that aren't
+/// presets are skipped and named in the errors.
+pub fn load() -> Option<(String, Vec<String>)> {
+    let dir = presets_dir()?;
+    let mut errors = vec![];
+    let list: Vec<InAppPreset> = preset_files(&dir)
+        .ok()?
+        .into_iter()
+        .filter_map(|path| match read(&path) {
+            Some(preset) => Some(preset),
+            None => {
+                errors.push(format!("{} isn't a valid preset", path.display()));
+                None
+            }
+        })
+        .collect();
+    Some((to_string(&list).unwrap(), errors))
+}
+
+pub fn presets_dir() -> Option<PathBuf> {
+    data_dir().map(|dir| dir.join("presets"))
+}
+
+/// Writes every preset next to its file and moves it in place, then removes
+/// the files of presets that are gone. A failure leaves the old files.
+fn write(dir: &Path, list: &[InAppPreset]) -> std::io::Result<()> {
+    fs::create_dir_all(dir)?;
+    let mut written = vec![];
+    for (i, preset) in list.iter().enumerate() {
+        let path = dir.join(format!("{:03}-{}.json", i + 1, file_name(&preset.name)));
+        let temporary = path.with_extension("tmp");
+        fs::write(&temporary, serde_json::to_string_pretty(preset).unwrap())?;
+        fs::rename(&temporary, &path)?;
+        written.push(path);
+    }
+    // files that aren't presets were put there by hand and are left alone
+    for path in preset_files(dir)? {
+        if !written.contains(&path) && read(&path).is_some() {
+            fs::remove_file(path)?;
+        }
+    }
+    Ok(())
+}
+
+/// The JSON files in the folder, in the order their names sort in.
+fn preset_files(dir: &Path) -> std::io::Result<Vec<PathBuf>> {
+    let mut files: Vec<PathBuf> = fs::read_dir(dir)?
+        .flatten()
+        .map(|entry| entry.path())
+        .filter(|path| {
+            path.extension()
+                .map_or(false, |extension| extension == "json")
+        })
+        .collect();
+    files.sort();
+    Ok(files)
+}
+
+fn read(path: &Path) -> Option<InAppPreset> {
+    from_str(&fs::read_to_string(path).ok()?).ok()
+}
+
+/// The name with anything a file system might not accept replaced.
+fn file_name(name: &str) -> String {
+    name.chars()
+        .take(40)
+        .map(|c| match c.is_alphanumeric() || c == '-' || c == ' ' {
+            true => c,
+            false => '_',
+        })
+        .collect()
+}
+
+fn rename_error(list: &[InAppPreset], name: &str) -> Option<String> {
+    if name.is_empty() {
+        return Some("A preset needs a name".to_string());
+    }
+    match list.iter().any(|preset| preset.name == name) {
+        true => Some(format!("There already is a preset named {name}")),
+        false => None,
+    }
+}
+
+fn copy_name(list: &[InAppPreset], name: &str) -> String {
+    let mut copy = format!("{name} copy");
+    let mut n = 2;
+    while list.iter().any(|preset| preset.name == copy) {
+        copy = format!("{name} copy {n}");
+        n += 1;
+    }
+    copy
+}
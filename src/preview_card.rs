@@ -0,0 +1,250 @@
+use crate::app_cache::CachedApp;
+use chrono::Utc;
+use eframe::egui::{
+    self, Color32, ColorImage, Context, Pos2, Rect, RichText, Sense, Shape, TextureFilter,
+    TextureHandle, Ui, Vec2,
+};
+use serde_json::Value;
+use std::collections::HashMap;
+use std::io::Read;
+use std::sync::mpsc::{channel, Receiver, TryRecvError};
+use std::thread;
+use std::time::Duration;
+
+/// Largest image downloaded for a thumbnail.
+const MAX_IMAGE_BYTES: u64 = 8 * 1024 * 1024;
+/// Thumbnails kept before they're all fetched again.
+const MAX_THUMBNAILS: usize = 50;
+const LARGE: f32 = 80.;
+const SMALL: f32 = 26.;
+const GRAY: Color32 = Color32::from_rgb(163, 163, 163);
+
+enum Thumbnail {
+    Loading(Receiver<Result<ColorImage, String>>),
+    Loaded(TextureHandle),
+    Failed,
+}
+
+/// Shows the activity roughly the way Discord shows it on a profile, with
+/// the images fetched from Discord or the URL they point to.
+#[derive(Default)]
+pub struct PreviewCard {
+    pub open: bool,
+    /// By image URL.
+    thumbnails: HashMap<String, Thumbnail>,
+}
+
+impl PreviewCard {
+    pub fn run(&mut self, ctx: &Context, activity: &Value, app: Option<&CachedApp>) {
+        let assets = &activity["assets"];
+        // without a large image Discord shows the small one in its place
+        let (large_key, large_text, small_key, small_text) = match assets["large_image"] {
+            Value::String(_) => (
+                &assets["large_image"],
+                &assets["large_text"],
+                &assets["small_image"],
+                &assets["small_text"],
+            ),
+            _ => (
+                &assets["small_image"],
+                &assets["small_text"],
+                &Value::Null,
+                &Value::Null,
+            ),
+        };
+        let large = self.texture(ctx, image_url(large_key, app));
+        let small = self.texture(ctx, image_url(small_key, app));
+        let mut open = self.open;
+        egui::containers::Window::new("Preview")
+            .open(&mut open)
+            .resizable(false)
+            .show(ctx, |ui| {
+                egui::Frame::none()
+                    .fill(Color32::from_rgb(35, 36, 40))
+                    .rounding(8.)
+                    .inner_margin(12.)
+                    .show(ui, |ui| {
+                        ui.set_width(300.);
+                        ui.label(RichText::new("PLAYING A GAME").small().strong().color(GRAY));
+                        ui.add_space(4.);
+                        ui.horizontal(|ui| {
+                            if large_key.is_string() {
+                                images(
+                                    ui,
+                                    (large.as_ref(), large_text.as_str()),
+                                    small_key
+                                        .is_string()
+                                        .then(|| (small.as_ref(), small_text.as_str())),
+                                );
+                            }
+                            ui.vertical(|ui| lines(ui, activity, app));
+                        });
+                        if let Some(buttons) = activity["buttons"].as_array() {
+                            ui.add_space(8.);
+                            for button in buttons {
+                                let label = button["label"].as_str().unwrap_or_default();
+                                ui.add_sized(
+                                    [ui.available_width(), 28.],
+                                    egui::Button::new(RichText::new(label).color(Color32::WHITE))
+                                        .fill(Color32::from_rgb(78, 80, 88)),
+                                )
+                                .on_hover_text(button["url"].as_str().unwrap_or_default());
+                            }
+                        }
+                    });
+                ui.small("Roughly how Discord shows it, before it's sent");
+            });
+        self.open = open;
+        if self.open && activity["timestamps"].is_object() {
+            ctx.request_repaint_after(Duration::from_secs(1));
+        }
+    }
+
+    /// The thumbnail for the URL, fetched in the background the first time.
+    fn texture(&mut self, ctx: &Context, url: Option<String>) -> Option<TextureHandle> {
+        let url = url?;
+        if self.thumbnails.len() > MAX_THUMBNAILS {
+            self.thumbnails.clear();
+        }
+        let thumbnail = self.thumbnails.entry(url.clone()).or_insert_with(|| {
+            let (sender, receiver) = channel();
+            let ctx = ctx.clone();
+            let url = url.clone();
+            thread::spawn(move || {
+                let _ = sender.send(fetch(&url));
+                ctx.request_repaint();
+            });
+            Thumbnail::Loading(receiver)
+        });
+        let fetched = match thumbnail {
+            Thumbnail::Loading(receiver) => receiver.try_recv(),
+            _ => Err(TryRecvError::Empty),
+        };
+        match fetched {
+            Ok(Ok(image)) => {
+                *thumbnail = Thumbnail::Loaded(ctx.load_texture(&url, image, TextureFilter::Linear))
+            }
+            Ok(Err(_)) | Err(TryRecvError::Disconnected) => *thumbnail = Thumbnail::Failed,
+            Err(TryRecvError::Empty) => (),
+        }
+        match thumbnail {
+            Thumbnail::Loaded(texture) => Some(texture.clone()),
+            _ => None,
+        }
+    }
+}
+
+/// The large image with the small one in its corner, their texts on hover.
+fn images(
+    ui: &mut Ui,
+    large: (Option<&TextureHandle>, Option<&str>),
+    small: Option<(Option<&TextureHandle>, Option<&str>)>,
+) {
+    let (rect, response) = ui.allocate_exact_size(Vec2::splat(LARGE), Sense::hover());
+    image(ui, rect, large.0, 8.);
+    if let Some(text) = large.1 {
+        response.on_hover_text(text);
+    }
+    if let Some((texture, text)) = small {
+        let rect = Rect::from_min_size(rect.max - Vec2::splat(SMALL - 4.), Vec2::splat(SMALL));
+        ui.painter().circle_filled(
+            rect.center(),
+            SMALL / 2. + 2.,
+            Color32::from_rgb(35, 36, 40),
+        );
+        image(ui, rect, texture, SMALL / 2.);
+        let response = ui.interact(rect, ui.id().with("small_image"), Sense::hover());
+        if let Some(text) = text {
+            response.on_hover_text(text);
+        }
+    }
+}
+
+/// Draws the thumbnail, or a gray box while it's loading or when it failed.
+fn image(ui: &Ui, rect: Rect, texture: Option<&TextureHandle>, rounding: f32) {
+    match texture {
+        Some(texture) => {
+            ui.painter().add(Shape::image(
+                texture.id(),
+                rect,
+                Rect::from_min_max(Pos2::ZERO, Pos2::new(1., 1.)),
+                Color32::WHITE,
+            ));
+        }
+        None => ui
+            .painter()
+            .rect_filled(rect, rounding, Color32::from_gray(60)),
+    }
+}
+
+/// Name, details, state with the party size and the time.
+fn lines(ui: &mut Ui, activity: &Value, app: Option<&CachedApp>) {
+    let name = app.map_or("Application", |app| app.name.as_str());
+    ui.label(RichText::new(name).strong().color(Color32::WHITE));
+    if let Some(details) = activity["details"].as_str() {
+        ui.label(RichText::new(details).color(Color32::WHITE));
+    }
+    if let Some(state) = activity["state"].as_str() {
+        let party = match activity["party"]["size"].as_array().map(Vec::as_slice) {
+            Some([size, max]) => format!(" ({size} of {max})"),
+            _ => String::new(),
+        };
+        ui.label(RichText::new(format!("{state}{party}")).color(Color32::WHITE));
+    }
+    let now = Utc::now().timestamp();
+    let timestamps = &activity["timestamps"];
+    let time = match (timestamps["start"].as_i64(), timestamps["end"].as_i64()) {
+        (_, Some(end)) => Some(format!("{} left", clock(end - now))),
+        (Some(start), None) => Some(format!("{} elapsed", clock(now - start))),
+        _ => None,
+    };
+    if let Some(time) = time {
+        ui.label(RichText::new(time).color(Color32::WHITE));
+    }
+}
+
+/// Seconds as Discord shows them, `05:07` or `1:05:07`.
+fn clock(seconds: i64) -> String {
+    let seconds = seconds.max(0);
+    match seconds / 3600 {
+        0 => format!("{:02}:{:02}", seconds / 60, seconds % 60),
+        hours => format!("{hours}:{:02}:{:02}", seconds / 60 % 60, seconds % 60),
+    }
+}
+
+/// Where Discord gets the image for a key from: an uploaded asset, an
+/// external URL or one Discord already proxies.
+pub fn image_url(key: &Value, app: Option<&CachedApp>) -> Option<String> {
+    let key = key.as_str()?;
+    if key.starts_with("https://") || key.starts_with("http://") {
+        return Some(key.to_string());
+    }
+    if let Some(path) = key.strip_prefix("mp:") {
+        return Some(format!("https://media.discordapp.net/{path}"));
+    }
+    let app = app?;
+    let asset = app.asset_ids.get(key)?;
+    Some(format!(
+        "https://cdn.discordapp.com/app-assets/{}/{asset}.png",
+        app.id
+    ))
+}
+
+fn fetch(url: &str) -> Result<ColorImage, String> {
+    let response = ureq::get(url)
+        .timeout(Duration::from_secs(10))
+        .call()
+        .map_err(|_| format!("Failed to fetch {url}"))?;
+    let mut bytes = vec![];
+    response
+        .into_reader()
+        .take(MAX_IMAGE_BYTES)
+        .read_to_end(&mut bytes)
+        .map_err(|_| format!("Failed to fetch {url}"))?;
+    let image = image::load_from_memory(&bytes).map_err(|_| format!("{url} isn't an image"))?;
+    let image = image.thumbnail(128, 128).to_rgba8();
+    Ok(ColorImage::from_rgba_unmultiplied(
+        [image.width() as usize, image.height() as usize],
+        image.as_raw(),
+    ))
+}
@@ -0,0 +1,82 @@
+use crate::preset::InAppPreset;
+use eframe::egui::{self, Context};
+use serde::{Deserialize, Serialize};
+use serde_json::from_str;
+
+/// Loads a preset while a program runs.
+#[derive(Serialize, Deserialize, Clone, Default)]
+pub struct ProcessRule {
+    /// Executable name like `blender.exe`, or several separated by commas.
+    pub process: String,
+    pub preset: String,
+}
+
+/// Editor for the process rules. They're checked by the automation before
+/// the triggers inside presets, the highest rule whose program runs wins.
+#[derive(Default)]
+pub struct ProcessRules {
+    pub open: bool,
+    pub rules: Vec<ProcessRule>,
+}
+
+impl ProcessRules {
+    pub fn run(&mut self, ctx: &Context, presets: &str) {
+        let presets: Vec<InAppPreset> = from_str(presets).unwrap_or_default();
+        let mut open = self.open;
+        egui::containers::Window::new("Process rules")
+            .open(&mut open)
+            .resizable(false)
+            .show(ctx, |ui| {
+                let mut remove = None;
+                let mut swap = None;
+                let last = self.rules.len().saturating_sub(1);
+                for (i, rule) in self.rules.iter_mut().enumerate() {
+                    ui.horizontal(|ui| {
+                        ui.label(format!("{}.", i + 1));
+                        ui.add(
+                            egui::TextEdit::singleline(&mut rule.process)
+                                .hint_text("blender.exe")
+                                .desired_width(120.),
+                        );
+                        ui.label("loads");
+                        egui::ComboBox::from_id_source(("process_rule_preset", i))
+                            .selected_text(rule.preset.as_str())
+                            .show_ui(ui, |ui| {
+                                for preset in presets.iter() {
+                                    ui.selectable_value(
+                                        &mut rule.preset,
+                                        preset.name.clone(),
+                                        &preset.name,
+                                    );
+                                }
+                            });
+                        if ui.add_enabled(i > 0, egui::Button::new("⬆")).clicked() {
+                            swap = Some(i - 1);
+                        }
+                        if ui.add_enabled(i < last, egui::Button::new("⬇")).clicked() {
+                            swap = Some(i);
+                        }
+                        if ui.button("❌").clicked() {
+                            remove = Some(i);
+                        }
+                    });
+                }
+                if let Some(i) = swap {
+                    self.rules.swap(i, i + 1);
+                }
+                if let Some(i) = remove {
+                    self.rules.remove(i);
+                }
+                if ui.button("Add rule").clicked() {
+                    self.rules.push(ProcessRule::default());
+                }
+                ui.label(
+                    egui::RichText::new(
+                        "Higher rules win, they apply while \"Apply presets by trigger\" is on",
+                    )
+                    .small(),
+                );
+            });
+        self.open = open;
+    }
+}
@@ -0,0 +1,135 @@
+use eframe::egui::{self, Color32, RichText, Ui};
+use serde::{Deserialize, Serialize};
+
+#[derive(Serialize, Deserialize, Clone, Default)]
+pub struct Profile {
+    pub name: String,
+    pub id: String,
+    /// Set once the font was changed while the profile was in use.
+    #[serde(default)]
+    pub font: Option<ProfileFont>,
+}
+
+/// The font of a profile, applied when the profile is picked.
+#[derive(Serialize, Deserialize, Clone, PartialEq)]
+pub struct ProfileFont {
+    /// Font file, the default font when empty.
+    pub path: String,
+    pub size: f32,
+}
+
+/// Application IDs saved under a name, picked from the menu next to the ID field.
+#[derive(Default)]
+pub struct Profiles {
+    pub profiles: Vec<Profile>,
+    /// Name typed for saving the current ID.
+    pub new_name: String,
+    pub error: Option<String>,
+}
+
+impl Profiles {
+    /// Saves from before profiles existed only had the one ID, it becomes
+    /// the first profile. A list emptied by hand stays empty.
+    pub fn load(profiles: Option<Vec<Profile>>, id: &str) -> Self {
+        let profiles = match (profiles, id.is_empty()) {
+            (Some(profiles), _) => profiles,
+            (None, false) => vec![Profile {
+                name: "Default".to_string(),
+                id: id.to_string(),
+                font: None,
+            }],
+            (None, true) => Vec::new(),
+        };
+        Self {
+            profiles,
+            ..Default::default()
+        }
+    }
+
+    /// Shows the menu and returns the ID of the profile picked.
+    pub fn run(&mut self, ui: &mut Ui, id: &str) -> Option<String> {
+        let current = self
+            .profiles
+            .iter()
+            .find(|profile| profile.id == id)
+            .map_or("Profile", |profile| profile.name.as_str());
+        let mut picked = None;
+        ui.menu_button(format!("{current} ⏷"), |ui| {
+            let mut remove = None;
+            for (i, profile) in self.profiles.iter().enumerate() {
+                ui.horizontal(|ui| {
+                    if ui
+                        .selectable_label(profile.id == id, &profile.name)
+                        .on_hover_text(&profile.id)
+                        .clicked()
+                    {
+                        picked = Some(profile.id.clone());
+                        ui.close_menu();
+                    }
+                    if ui.small_button("❌").on_hover_text("Remove").clicked() {
+                        remove = Some(i);
+                    }
+                });
+            }
+            if let Some(i) = remove {
+                self.profiles.remove(i);
+            }
+            if !self.profiles.is_empty() {
+                ui.separator();
+            }
+            ui.horizontal(|ui| {
+                ui.add(egui::TextEdit::singleline(&mut self.new_name).hint_text("Name"));
+                if ui
+                    .add_enabled(!id.is_empty(), egui::Button::new("Save ID"))
+                    .clicked()
+                {
+                    self.save(id);
+                }
+            });
+            if let Some(error) = &self.error {
+                ui.label(RichText::new(error).color(Color32::LIGHT_RED).small());
+            }
+        });
+        picked
+    }
+
+    /// Saves the ID under the typed name, replacing a profile with that name.
+    fn save(&mut self, id: &str) {
+        let name = self.new_name.trim().to_string();
+        if name.is_empty() {
+            self.error = Some("A profile needs a name".to_string());
+            return;
+        }
+        match self
+            .profiles
+            .iter_mut()
+            .find(|profile| profile.name == name)
+        {
+            Some(saved) => saved.id = id.to_string(),
+            None => self.profiles.push(Profile {
+                name,
+                id: id.to_string(),
+                font: None,
+            }),
+        }
+        self.new_name.clear();
+        self.error = None;
+    }
+
+    /// The font of the ID's profile, `None` when it has no profile or the
+    /// font was never changed for it.
+    pub fn font(&self, id: &str) -> Option<&ProfileFont> {
+        self.profiles
+            .iter()
+            .find(|profile| profile.id == id)?
+            .font
+            .as_ref()
+    }
+
+    /// Remembers the font for the profiles of the ID.
+    pub fn set_font(&mut self, id: &str, font: ProfileFont) {
+        for profile in self.profiles.iter_mut().filter(|profile| profile.id == id) {
+            profile.font = Some(font.clone());
+        }
+    }
+}
@@ -0,0 +1,79 @@
+use chrono::{DateTime, Duration, Utc};
+
+/// Longest wait between two attempts, in seconds.
+const MAX_DELAY: i64 = 60;
+
+/// Exponential backoff for reconnecting: 1s, 2s, 4s, ... up to a minute.
+pub struct Backoff {
+    attempts: u32,
+    retry_at: DateTime<Utc>,
+}
+
+impl Default for Backoff {
+    fn default() -> Self {
+        Self {
+            attempts: 0,
+            retry_at: Utc::now(),
+        }
+    }
+}
+
+impl Backoff {
+    pub fn ready(&self) -> bool {
+        self.retry_at <= Utc::now()
+    }
+
+    /// Schedules the next attempt after a failed one.
+    pub fn failed(&mut self) {
+        let delay = 2_i64.saturating_pow(self.attempts).min(MAX_DELAY);
+        self.attempts += 1;
+        self.retry_at = Utc::now() + Duration::seconds(delay);
+    }
+
+    /// Starts over after the connection worked.
+    pub fn reset(&mut self) {
+        self.attempts = 0;
+        self.retry_at = Utc::now();
+    }
+
+    /// Skips the wait, keeping the delay for the attempts after this one.
+    pub fn retry_now(&mut self) {
+        self.retry_at = Utc::now();
+    }
+
+    pub fn seconds_left(&self) -> i64 {
+        (self.retry_at - Utc::now()).num_seconds().max(0)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn doubles_the_delay_up_to_a_minute() {
+        let mut backoff = Backoff::default();
+        assert!(backoff.ready());
+        for expected in [1, 2, 4, 8, 16, 32, 60, 60] {
+            backoff.failed();
+            assert!(!backoff.ready());
+            // a second may tick over between scheduling and checking
+            assert!((expected - 1..=expected).contains(&backoff.seconds_left()));
+        }
+    }
+
+    #[test]
+    fn retrying_now_keeps_the_delay() {
+        let mut backoff = Backoff::default();
+        for _ in 0..3 {
+            backoff.failed();
+        }
+        backoff.retry_now();
+        assert!(backoff.ready());
+        backoff.failed();
+        assert!(backoff.seconds_left() >= 7);
+        backoff.reset();
+        backoff.failed();
+        assert!(backoff.seconds_left() <= 1);
+    }
+}
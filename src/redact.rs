@@ -0,0 +1,141 @@
+use std::env;
+use std::sync::Mutex;
+
+/// What gets removed from reports and diagnostics besides tokens and the
+/// user's name and home folder, which are always removed.
+#[derive(Clone, Copy)]
+pub struct RedactionPolicy {
+    pub client_ids: bool,
+    pub urls: bool,
+}
+
+static POLICY: Mutex<RedactionPolicy> = Mutex::new(RedactionPolicy {
+    client_ids: true,
+    urls: true,
+});
+
+pub fn set_policy(policy: RedactionPolicy) {
+    if let Ok(mut current) = POLICY.lock() {
+        *current = policy;
+    }
+}
+
+/// Removes private data from text that leaves the machine.
+pub fn redact(text: &str) -> String {
+    let policy = match POLICY.lock() {
+        Ok(policy) => *policy,
+        // redact everything rather than nothing
+        Err(_) => RedactionPolicy {
+            client_ids: true,
+            urls: true,
+        },
+    };
+    let mut text = text.to_string();
+    for key in ["HOME", "USERPROFILE"] {
+        if let Some(home) = env::var(key).ok().filter(|home| home.len() > 1) {
+            text = text.replace(&home, "~");
+        }
+    }
+    for key in ["USER", "USERNAME"] {
+        if let Some(user) = env::var(key).ok().filter(|user| user.len() > 2) {
+            text = text.replace(&user, "<user>");
+        }
+    }
+
+    let mut redacted = String::with_capacity(text.len());
+    let mut word = String::new();
+    for c in text.chars() {
+        if c.is_whitespace() || "\"'`,()<>[]{}".contains(c) {
+            redacted += redact_word(&word, policy);
+            word.clear();
+            redacted.push(c);
+        } else {
+            word.push(c);
+        }
+    }
+    redacted += redact_word(&word, policy);
+    redacted
+}
+
+fn redact_word(word: &str, policy: RedactionPolicy) -> &str {
+    if policy.urls && word.contains("://") {
+        return "<url>";
+    }
+    if policy.client_ids
+        && (17..=20).contains(&word.len())
+        && word.chars().all(|c| c.is_ascii_digit())
+    {
+        return "<id>";
+    }
+    if looks_like_token(word) {
+        return "<token>";
+    }
+    word
+}
+
+/// Discord tokens are three base64 parts joined by dots, other secrets are
+/// long runs of letters and digits.
+fn looks_like_token(word: &str) -> bool {
+    let value = word.rsplit(['=', ':']).next().unwrap_or(word);
+    if value.len() < 24 || value.contains("://") {
+        return false;
+    }
+    let base64 = value
+        .chars()
+        .all(|c| c.is_ascii_alphanumeric() || "-_.".contains(c));
+    let mixed =
+        value.chars().any(|c| c.is_ascii_digit()) && value.chars().any(|c| c.is_ascii_alphabetic());
+    base64 && (value.matches('.').count() == 2 || (mixed && !value.contains('.')))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const ALL: RedactionPolicy = RedactionPolicy {
+        client_ids: true,
+        urls: true,
+    };
+    const NONE: RedactionPolicy = RedactionPolicy {
+        client_ids: false,
+        urls: false,
+    };
+
+    #[test]
+    fn redacts_by_policy() {
+        assert_eq!(redact_word("123456789012345678", ALL), "<id>");
+        assert_eq!(redact_word("https://example.com/a", ALL), "<url>");
+        assert_eq!(
+            redact_word("123456789012345678", NONE),
+            "123456789012345678"
+        );
+        assert_eq!(
+            redact_word("https://example.com/a", NONE),
+            "https://example.com/a"
+        );
+        // too short for an ID
+        assert_eq!(redact_word("1234", ALL), "1234");
+    }
+
+    #[test]
+    fn always_redacts_tokens() {
+        let discord = "MTIzNDU2Nzg5MDEyMzQ1Njc4.GabcDE.abcdefghijklmnopqrstuvwxyz0123";
+        assert_eq!(redact_word(discord, NONE), "<token>");
+        assert_eq!(
+            redact_word("token=a1b2c3d4e5f6a7b8c9d0e1f2a3b4", NONE),
+            "<token>"
+        );
+        assert_eq!(
+            redact_word("Failed_to_connect_to_Discord_again", NONE),
+            "Failed_to_connect_to_Discord_again"
+        );
+    }
+
+    #[test]
+    fn keeps_the_text_around_redacted_words() {
+        assert_eq!(
+            redact("(id 123456789012345678, key=a1b2c3d4e5f6a7b8c9d0e1f2a3b4)"),
+            "(id <id>, <token>)"
+        );
+    }
+}
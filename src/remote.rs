@@ -0,0 +1,430 @@
+use crate::config::{Scope, Token};
+use crate::control::{ask, same, Command, Request};
+use crate::mdns::lan_ip;
+use crate::websocket::WebSocket;
+use chrono::{DateTime, Duration, NaiveDateTime, Utc};
+use eframe::egui::{self, Color32, Context, Rect, Sense, Vec2};
+use qrcode::{Color, QrCode};
+use serde::{Deserialize, Serialize};
+use serde_json::{from_str, json, Value};
+use sha2::{Digest, Sha256};
+use std::collections::HashMap;
+use std::io::ErrorKind;
+use std::net::{IpAddr, SocketAddr};
+use std::sync::mpsc::Sender;
+use std::sync::{Arc, Mutex};
+
+/// How long a pairing code shown on screen stays valid, in seconds.
+const PAIRING_TTL: i64 = 120;
+/// Wrong pairing codes or tokens from one address before it's locked out.
+const MAX_FAILURES: u8 = 3;
+/// Seconds an address stays locked out after its last wrong attempt.
+const LOCKOUT: i64 = 300;
+/// Seconds a session may stay silent before it's closed, a ping counts.
+pub const IDLE_TIMEOUT: u64 = 300;
+/// Size of one QR module in points.
+const MODULE: f32 = 4.;
+
+/// A phone allowed to control the app. Only a hash of its token is kept.
+#[derive(Serialize, Deserialize, Clone)]
+pub struct Device {
+    pub name: String,
+    pub token_hash: String,
+    /// Unix time the phone was paired at.
+    pub paired: i64,
+    /// What the phone may do, like a control API token's scope. Phones
+    /// paired before scopes existed can only read until given more.
+    #[serde(default)]
+    pub scope: Scope,
+}
+
+/// State shared with the sessions running on the control API's threads.
+#[derive(Default)]
+pub struct Pairings {
+    /// Secret from the QR code currently shown, and when it stops working.
+    code: Option<(String, DateTime<Utc>)>,
+    /// Scope the next paired phone gets.
+    scope: Scope,
+    devices: Vec<Device>,
+    /// Wrong attempts per address, and when the last one was. Kept across
+    /// sessions so reconnecting doesn't start the count over.
+    failures: HashMap<IpAddr, (u8, DateTime<Utc>)>,
+}
+
+impl Pairings {
+    /// Whether `peer` got too many codes or tokens wrong lately.
+    fn locked_out(&self, peer: IpAddr) -> bool {
+        matches!(self.failures.get(&peer), Some((count, last))
+            if *count >= MAX_FAILURES && Utc::now() - *last < Duration::seconds(LOCKOUT))
+    }
+
+    fn failed(&mut self, peer: IpAddr) {
+        let now = Utc::now();
+        self.failures
+            .retain(|_, (_, last)| now - *last < Duration::seconds(LOCKOUT));
+        let (count, last) = self.failures.entry(peer).or_insert((0, now));
+        *count = count.saturating_add(1);
+        *last = now;
+    }
+
+    fn succeeded(&mut self, peer: IpAddr) {
+        self.failures.remove(&peer);
+    }
+}
+
+pub type SharedPairings = Arc<Mutex<Pairings>>;
+
+/// Pairing screen for the phone remote, which talks to the app over a
+/// WebSocket on the control API's `/remote` path.
+#[derive(Default)]
+pub struct Remote {
+    pub open: bool,
+    pairings: SharedPairings,
+    /// The QR code for the current secret, rebuilt when it changes.
+    qr: Option<(String, QrCode)>,
+}
+
+impl Remote {
+    pub fn new(devices: Vec<Device>) -> Self {
+        let pairings = Pairings {
+            code: None,
+            scope: Scope::Read,
+            devices,
+            failures: HashMap::new(),
+        };
+        Self {
+            pairings: Arc::new(Mutex::new(pairings)),
+            ..Default::default()
+        }
+    }
+
+    pub fn pairings(&self) -> SharedPairings {
+        self.pairings.clone()
+    }
+
+    pub fn devices(&self) -> Vec<Device> {
+        self.pairings.lock().unwrap().devices.clone()
+    }
+
+    pub fn run(&mut self, ctx: &Context, addr: Option<SocketAddr>) {
+        let mut open = self.open;
+        egui::Window::new("Phone remote")
+            .open(&mut open)
+            .resizable(false)
+            .show(ctx, |ui| {
+                let addr = match addr {
+                    Some(addr) => addr,
+                    None => {
+                        ui.label("The phone remote needs the control API.");
+                        ui.label("Set [control] listen in config.toml to a LAN address.");
+                        return;
+                    }
+                };
+                let host = match addr.ip() {
+                    ip if ip.is_unspecified() => lan_ip().map(IpAddr::V4),
+                    ip if ip.is_loopback() => None,
+                    ip => Some(ip),
+                };
+                let host = match host {
+                    Some(host) => host,
+                    None => {
+                        ui.label("The control API only listens on this machine.");
+                        ui.label("Phones can't reach it, listen on a LAN address instead.");
+                        return;
+                    }
+                };
+                self.pairing(ui, host, addr.port());
+                ui.separator();
+                self.device_list(ui);
+            });
+        self.open = open;
+        if !self.open {
+            self.pairings.lock().unwrap().code = None;
+        }
+    }
+
+    fn pairing(&mut self, ui: &mut egui::Ui, host: IpAddr, port: u16) {
+        let code = self.pairings.lock().unwrap().code.clone();
+        let (secret, expires) = match code {
+            Some((secret, expires)) if expires > Utc::now() => (secret, expires),
+            _ => {
+                let mut pairings = self.pairings.lock().unwrap();
+                scope_picker(ui, "new_phone_scope", &mut pairings.scope);
+                drop(pairings);
+                if ui.button("Pair a phone").clicked() {
+                    self.pairings.lock().unwrap().code =
+                        Some((random_hex(16), Utc::now() + Duration::seconds(PAIRING_TTL)));
+                }
+                return;
+            }
+        };
+        let url = format!("discord-presence://pair?host={host}&port={port}&secret={secret}");
+        if self
+            .qr
+            .as_ref()
+            .map(|(shown, _)| shown != &url)
+            .unwrap_or(true)
+        {
+            self.qr = QrCode::new(url.as_bytes()).ok().map(|qr| (url.clone(), qr));
+        }
+        ui.label("Scan this with the remote app");
+        if let Some((_, qr)) = &self.qr {
+            draw_qr(ui, qr);
+        }
+        ui.horizontal(|ui| {
+            ui.label(format!("or enter {host}:{port} and"));
+            ui.monospace(&secret);
+        });
+        let left = (expires - Utc::now()).num_seconds();
+        ui.label(format!("Expires in {left}s"));
+        if ui.button("Cancel").clicked() {
+            self.pairings.lock().unwrap().code = None;
+        }
+        ui.ctx()
+            .request_repaint_after(std::time::Duration::from_secs(1));
+    }
+
+    fn device_list(&mut self, ui: &mut egui::Ui) {
+        let mut pairings = self.pairings.lock().unwrap();
+        if pairings.devices.is_empty() {
+            ui.label("No paired phones");
+            return;
+        }
+        let mut forget = None;
+        egui::Grid::new("remote_devices").show(ui, |ui| {
+            for (i, device) in pairings.devices.iter_mut().enumerate() {
+                ui.label(&device.name);
+                if let Some(paired) = NaiveDateTime::from_timestamp_opt(device.paired, 0) {
+                    ui.label(paired.format("%Y-%m-%d").to_string());
+                }
+                scope_picker(ui, ("remote_device_scope", i), &mut device.scope);
+                if ui.small_button("Forget").clicked() {
+                    forget = Some(i);
+                }
+                ui.end_row();
+            }
+        });
+        if let Some(i) = forget {
+            pairings.devices.remove(i);
+        }
+    }
+}
+
+fn scope_picker(ui: &mut egui::Ui, id: impl std::hash::Hash, scope: &mut Scope) {
+    let name = |scope: Scope| match scope {
+        Scope::Read => "Read only",
+        Scope::Full => "Full control",
+    };
+    egui::ComboBox::from_id_source(id)
+        .selected_text(name(*scope))
+        .show_ui(ui, |ui| {
+            for option in [Scope::Read, Scope::Full] {
+                ui.selectable_value(scope, option, name(option));
+            }
+        });
+}
+
+fn draw_qr(ui: &mut egui::Ui, qr: &QrCode) {
+    let width = qr.width();
+    // four modules of quiet zone on each side, scanners need it
+    let side = (width + 8) as f32 * MODULE;
+    let (response, painter) = ui.allocate_painter(Vec2::splat(side), Sense::hover());
+    painter.rect_filled(response.rect, 0., Color32::WHITE);
+    for (i, color) in qr.to_colors().into_iter().enumerate() {
+        if color == Color::Dark {
+            let x = (i % width + 4) as f32 * MODULE;
+            let y = (i / width + 4) as f32 * MODULE;
+            let min = response.rect.min + Vec2::new(x, y);
+            painter.rect_filled(
+                Rect::from_min_size(min, Vec2::splat(MODULE)),
+                0.,
+                Color32::BLACK,
+            );
+        }
+    }
+}
+
+/// Runs the remote protocol on an upgraded control API connection.
+///
+/// Every message is a JSON object with a `type`. A new phone sends
+/// `{"type": "pair", "secret", "device"}` and gets back
+/// `{"type": "paired", "token"}`, a known one sends `{"type": "auth", "token"}`.
+/// After that `status`, `presets`, `connect`, `disconnect`, `update`,
+/// `load-preset` (with `name`) and `set` (with `details`, `state`) are answered with `{"type": "status", ...}`
+/// or `{"type": "error", "error"}`, commands outside the phone's scope with
+/// the latter. An `id` in a request is echoed back.
+/// A session silent for `IDLE_TIMEOUT` seconds gets an error and is closed.
+pub fn session(
+    mut socket: WebSocket,
+    pairings: &SharedPairings,
+    sender: &Sender<Request>,
+    ctx: &Context,
+) -> std::io::Result<()> {
+    let peer = socket.stream().peer_addr()?.ip();
+    let mut token_hash = None;
+    loop {
+        let text = match socket.read() {
+            Ok(Some(text)) => text,
+            Ok(None) => break,
+            Err(err) if matches!(err.kind(), ErrorKind::WouldBlock | ErrorKind::TimedOut) => {
+                let error = json!({ "type": "error", "error": "Closed for being idle" });
+                let _ = socket.send(&error.to_string());
+                return Err(err);
+            }
+            Err(err) => return Err(err),
+        };
+        let message: Value = from_str(&text).unwrap_or_default();
+        let kind = message["type"].as_str().unwrap_or_default();
+        if matches!(kind, "pair" | "auth") && pairings.lock().unwrap().locked_out(peer) {
+            let error =
+                json!({ "type": "error", "error": "Too many wrong attempts, try again later" });
+            let _ = socket.send(&error.to_string());
+            return Ok(());
+        }
+        let mut reply = match (&token_hash, kind) {
+            (_, "pair") => match pair(&message, pairings) {
+                Ok((token, hash)) => {
+                    pairings.lock().unwrap().succeeded(peer);
+                    token_hash = Some(hash);
+                    json!({ "type": "paired", "token": token })
+                }
+                Err(error) => {
+                    pairings.lock().unwrap().failed(peer);
+                    json!({ "type": "error", "error": error })
+                }
+            },
+            (_, "auth") => {
+                let hash = hash(message["token"].as_str().unwrap_or_default());
+                match scope(pairings, &hash) {
+                    Some(_) => {
+                        pairings.lock().unwrap().succeeded(peer);
+                        token_hash = Some(hash);
+                        json!({ "type": "authenticated" })
+                    }
+                    None => {
+                        pairings.lock().unwrap().failed(peer);
+                        json!({ "type": "error", "error": "Unknown token" })
+                    }
+                }
+            }
+            (None, _) => json!({ "type": "error", "error": "Pair or authenticate first" }),
+            (Some(hash), _) => {
+                // the phone may have been forgotten or limited while connected
+                let scope = match scope(pairings, hash) {
+                    Some(scope) => scope,
+                    None => {
+                        let error = json!({ "type": "error", "error": "Unpaired" });
+                        let _ = socket.send(&error.to_string());
+                        return Ok(());
+                    }
+                };
+                let token = Token {
+                    token: String::new(),
+                    scope,
+                    commands: None,
+                };
+                match Command::from_message(&message) {
+                    Some(command) if !command.allowed(&token) => {
+                        json!({ "type": "error", "error": "Not allowed for this phone" })
+                    }
+                    Some(command) => match ask(command, sender, ctx) {
+                        Ok(mut value) => {
+                            value["type"] = json!("status");
+                            value
+                        }
+                        Err((_, error)) => json!({ "type": "error", "error": error }),
+                    },
+                    None => json!({ "type": "error", "error": "Unknown message" }),
+                }
+            }
+        };
+        if !message["id"].is_null() {
+            reply["id"] = message["id"].clone();
+        }
+        socket.send(&reply.to_string())?;
+        if matches!(kind, "pair" | "auth") && pairings.lock().unwrap().locked_out(peer) {
+            break;
+        }
+    }
+    Ok(())
+}
+
+/// Checks the pairing secret and remembers the device, returning its new
+/// token and the hash kept of it.
+fn pair(message: &Value, pairings: &SharedPairings) -> Result<(String, String), &'static str> {
+    let mut pairings = pairings.lock().unwrap();
+    let secret = message["secret"].as_str().unwrap_or_default();
+    match &pairings.code {
+        Some((code, expires)) if *expires > Utc::now() && same(code, secret) => (),
+        _ => return Err("Wrong or expired pairing code"),
+    }
+    // a code pairs one phone only
+    pairings.code = None;
+    let name = match message["device"].as_str().map(str::trim) {
+        Some(name) if !name.is_empty() => name.chars().take(64).collect(),
+        _ => "Phone".to_string(),
+    };
+    let token = random_hex(32);
+    let token_hash = hash(&token);
+    let scope = pairings.scope;
+    pairings.devices.push(Device {
+        name,
+        token_hash: token_hash.clone(),
+        paired: Utc::now().timestamp(),
+        scope,
+    });
+    Ok((token, token_hash))
+}
+
+/// The scope of the paired phone with this token, `None` for unknown ones.
+fn scope(pairings: &SharedPairings, token_hash: &str) -> Option<Scope> {
+    pairings
+        .lock()
+        .unwrap()
+        .devices
+        .iter()
+        .find(|device| same(&device.token_hash, token_hash))
+        .map(|device| device.scope)
+}
+
+pub fn random_hex(len: usize) -> String {
+    let mut bytes = vec![0; len];
+    getrandom::getrandom(&mut bytes).expect("no random source");
+    bytes.iter().map(|byte| format!("{byte:02x}")).collect()
+}
+
+fn hash(token: &str) -> String {
+    Sha256::digest(token.as_bytes())
+        .iter()
+        .map(|byte| format!("{byte:02x}"))
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn locks_out_after_repeated_failures() {
+        let mut pairings = Pairings::default();
+        let peer = IpAddr::from([192, 168, 1, 20]);
+        for _ in 0..MAX_FAILURES {
+            assert!(!pairings.locked_out(peer));
+            pairings.failed(peer);
+        }
+        assert!(pairings.locked_out(peer));
+        // other addresses aren't affected
+        assert!(!pairings.locked_out(IpAddr::from([192, 168, 1, 21])));
+    }
+
+    #[test]
+    fn lockout_wears_off() {
+        let mut pairings = Pairings::default();
+        let peer = IpAddr::from([192, 168, 1, 20]);
+        for _ in 0..MAX_FAILURES {
+            pairings.failed(peer);
+        }
+        pairings.failures.get_mut(&peer).unwrap().1 -= Duration::seconds(LOCKOUT);
+        assert!(!pairings.locked_out(peer));
+    }
+}
@@ -0,0 +1,170 @@
+use crate::storage::data_dir;
+use eframe::{HardwareAcceleration, NativeOptions, Renderer};
+use std::fs;
+
+/// Remembers which backend was tried last and whether it opened the window.
+const STATE_FILE: &str = "renderer";
+
+/// Ways to draw the window, tried in this order until one of them works.
+const BACKENDS: [Backend; 3] = [Backend::Glow, Backend::Wgpu, Backend::Software];
+
+#[derive(Clone, Copy, PartialEq, Eq)]
+pub enum Backend {
+    Glow,
+    Wgpu,
+    /// OpenGL drawn on the CPU, for VMs and broken graphics drivers.
+    Software,
+}
+
+impl Backend {
+    pub fn name(self) -> &'static str {
+        match self {
+            Backend::Glow => "glow",
+            Backend::Wgpu => "wgpu",
+            Backend::Software => "software",
+        }
+    }
+
+    fn from_name(name: &str) -> Option<Self> {
+        BACKENDS.into_iter().find(|backend| backend.name() == name)
+    }
+
+    pub fn apply(self, options: &mut NativeOptions) {
+        match self {
+            Backend::Glow => options.renderer = Renderer::Glow,
+            Backend::Wgpu => options.renderer = Renderer::Wgpu,
+            Backend::Software => {
+                options.renderer = Renderer::Glow;
+                options.hardware_acceleration = HardwareAcceleration::Off;
+                // Mesa only falls back to llvmpipe when told to
+                std::env::set_var("LIBGL_ALWAYS_SOFTWARE", "1");
+            }
+        }
+    }
+}
+
+/// What the state file says about the last launch.
+struct State {
+    backend: Backend,
+    /// Whether it opened the window.
+    works: bool,
+    /// Version the first backend was tried again with after a fallback, it's
+    /// tried once per version so a driver update gets it back.
+    retried: Option<String>,
+}
+
+/// The backend to open the window with. `--renderer <name>` picks one,
+/// otherwise the last one that worked is used. When the last launch never
+/// got a window the next one in line is tried, and after falling back the
+/// first one is tried once more on the next start.
+pub fn pick(args: &[String]) -> Result<Backend, String> {
+    let picked = match args.iter().position(|arg| arg == "--renderer") {
+        Some(i) => match args.get(i + 1).map(String::as_str) {
+            Some(name) => match Backend::from_name(name) {
+                Some(backend) => Some(backend),
+                None => {
+                    return Err(format!(
+                        "Unknown renderer {name}, use glow, wgpu or software"
+                    ))
+                }
+            },
+            None => return Err("--renderer needs glow, wgpu or software".to_string()),
+        },
+        None => None,
+    };
+    let state = read();
+    let mut retried = state.as_ref().and_then(|state| state.retried.clone());
+    let version = env!("CARGO_PKG_VERSION");
+    let backend = match (picked, state) {
+        (Some(backend), _) => backend,
+        (
+            None,
+            Some(State {
+                backend,
+                works: true,
+                ..
+            }),
+        ) if backend != BACKENDS[0] && retried.as_deref() != Some(version) => {
+            retried = Some(version.to_string());
+            eprintln!(
+                "Trying the {} renderer again, {} is used since it failed",
+                BACKENDS[0].name(),
+                backend.name()
+            );
+            BACKENDS[0]
+        }
+        (
+            None,
+            Some(State {
+                backend,
+                works: true,
+                ..
+            }),
+        ) => backend,
+        (
+            None,
+            Some(State {
+                backend: failed,
+                works: false,
+                ..
+            }),
+        ) => {
+            let next = BACKENDS
+                .iter()
+                .position(|backend| *backend == failed)
+                .map_or(0, |i| (i + 1).min(BACKENDS.len() - 1));
+            eprintln!(
+                "The {} renderer didn't open the window last time, trying {}",
+                failed.name(),
+                BACKENDS[next].name()
+            );
+            BACKENDS[next]
+        }
+        (None, None) => BACKENDS[0],
+    };
+    write(&State {
+        backend,
+        works: false,
+        retried,
+    });
+    Ok(backend)
+}
+
+/// Remembers that the backend opened the window.
+pub fn works(backend: Backend) {
+    let retried = read().and_then(|state| state.retried);
+    write(&State {
+        backend,
+        works: true,
+        retried,
+    });
+}
+
+fn read() -> Option<State> {
+    let state = fs::read_to_string(data_dir()?.join(STATE_FILE)).ok()?;
+    let mut words = state.split_whitespace();
+    let status = words.next()?;
+    let backend = Backend::from_name(words.next()?)?;
+    Some(State {
+        backend,
+        works: status == "works",
+        retried: words.next().map(str::to_string),
+    })
+}
+
+fn write(state: &State) {
+    let dir = match data_dir() {
+        Some(dir) => dir,
+        None => return,
+    };
+    let status = match state.works {
+        true => "works",
+        false => "trying",
+    };
+    let mut text = format!("{status} {}", state.backend.name());
+    if let Some(version) = &state.retried {
+        text = format!("{text} {version}");
+    }
+    let _ = fs::create_dir_all(&dir);
+    let _ = fs::write(dir.join(STATE_FILE), text);
+}
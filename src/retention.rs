@@ -0,0 +1,71 @@
+use derivative::Derivative;
+use eframe::egui::{self, Context, RichText, Ui};
+use eframe::epaint::Color32;
+
+/// Discord removes a presence as soon as the connection that set it closes,
+/// so closing the window takes the presence with it. Headless mode keeps it
+/// up without the window.
+#[derive(Derivative)]
+#[derivative(Default)]
+pub struct Retention {
+    #[derivative(Default(value = "true"))]
+    pub warn_on_close: bool,
+    /// Set while asking whether to close.
+    pub asking: bool,
+    /// Closing was confirmed, the next close goes through.
+    pub closing: bool,
+}
+
+impl Retention {
+    /// Whether the window may close, asks first while Discord shows a presence.
+    pub fn allow_close(&mut self, shown: bool) -> bool {
+        if self.closing || !shown || !self.warn_on_close {
+            return true;
+        }
+        self.asking = true;
+        false
+    }
+
+    /// Shows the close confirmation and returns true once closing was confirmed.
+    pub fn run(&mut self, ctx: &Context) -> bool {
+        if !self.asking {
+            return false;
+        }
+        let (mut close, mut cancel) = (false, false);
+        egui::containers::Window::new("Close Discord Presence?")
+            .resizable(false)
+            .collapsible(false)
+            .show(ctx, |ui| {
+                ui.label("Closing the app will remove your presence from Discord.");
+                ui.label("Run discord_presence --headless to keep it up without the window.");
+                ui.add_space(5.);
+                ui.checkbox(&mut self.warn_on_close, "Ask before closing")
+                    .on_hover_text("Can be turned on again in Settings");
+                ui.horizontal(|ui| {
+                    close = ui.button("Close anyway").clicked();
+                    cancel = ui.button("Cancel").clicked();
+                });
+            });
+        if close {
+            self.closing = true;
+        }
+        if close || cancel {
+            self.asking = false;
+        }
+        close
+    }
+}
+
+/// Notes how long Discord keeps the shown presence.
+pub fn note(ui: &mut Ui, connected: bool, shown: bool) {
+    let text = match (connected, shown) {
+        (true, true) => "Discord shows this while the app is open, closing it removes the presence",
+        (false, true) => "Discord dropped the presence when the connection closed",
+        (_, false) => return,
+    };
+    ui.label(
+        RichText::new(text)
+            .small()
+            .color(Color32::from_rgb(163, 163, 163)),
+    );
+}
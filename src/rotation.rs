@@ -0,0 +1,172 @@
+use crate::animation::Animation;
+use crate::preset::{InAppPreset, Preset};
+use chrono::{DateTime, Duration, Utc};
+use derivative::Derivative;
+use eframe::egui::{self, Context};
+use serde_json::from_str;
+
+/// Cycles through a list of in-app presets, one every interval, until it's
+/// stopped. Unlike the rundown it loops and every preset gets the same time.
+/// It also times the animation's frames, which go on over the presets.
+#[derive(Derivative)]
+#[derivative(Default)]
+pub struct Rotation {
+    pub open: bool,
+    /// Names of the in-app presets, in the order they're shown.
+    pub presets: Vec<String>,
+    #[derivative(Default(value = "5"))]
+    pub minutes: u32,
+    pub running: bool,
+    /// Index of the preset shown next.
+    pub next: usize,
+    pub next_switch: Option<DateTime<Utc>>,
+    pub animation: Animation,
+    pub next_frame: Option<DateTime<Utc>>,
+}
+
+impl Rotation {
+    /// Shows the editor.
+    pub fn run(&mut self, ctx: &Context, presets: &str) {
+        let presets: Vec<InAppPreset> = from_str(presets).unwrap_or_default();
+        let mut open = self.open;
+        egui::containers::Window::new("Rotation")
+            .open(&mut open)
+            .resizable(false)
+            .show(ctx, |ui| {
+                let mut remove = None;
+                let mut swap = None;
+                let last = self.presets.len().saturating_sub(1);
+                for (i, name) in self.presets.iter_mut().enumerate() {
+                    ui.horizontal(|ui| {
+                        ui.label(format!("{}.", i + 1));
+                        egui::ComboBox::from_id_source(("rotation_preset", i))
+                            .selected_text(name.as_str())
+                            .show_ui(ui, |ui| {
+                                for preset in presets.iter() {
+                                    ui.selectable_value(name, preset.name.clone(), &preset.name);
+                                }
+                            });
+                        if ui.add_enabled(i > 0, egui::Button::new("⬆")).clicked() {
+                            swap = Some(i - 1);
+                        }
+                        if ui.add_enabled(i < last, egui::Button::new("⬇")).clicked() {
+                            swap = Some(i);
+                        }
+                        if ui.button("❌").clicked() {
+                            remove = Some(i);
+                        }
+                    });
+                }
+                if let Some(i) = swap {
+                    self.presets.swap(i, i + 1);
+                }
+                if let Some(i) = remove {
+                    self.presets.remove(i);
+                }
+                if ui.button("Add preset").clicked() {
+                    self.presets.push(String::new());
+                }
+                ui.add_space(5.);
+                ui.horizontal(|ui| {
+                    ui.label("Switch every");
+                    ui.add(
+                        egui::DragValue::new(&mut self.minutes)
+                            .clamp_range(1..=1440)
+                            .suffix(" min"),
+                    );
+                });
+                ui.horizontal(|ui| {
+                    match self.running {
+                        true => {
+                            if ui.button("Stop").clicked() {
+                                self.stop();
+                            }
+                        }
+                        false => {
+                            if ui
+                                .add_enabled(!self.presets.is_empty(), egui::Button::new("Start"))
+                                .clicked()
+                            {
+                                self.start();
+                            }
+                        }
+                    }
+                    if let (true, Some(next)) = (self.running, self.next_switch) {
+                        let left = (next - Utc::now()).max(Duration::zero());
+                        let name = self.presets.get(self.next).map_or("", String::as_str);
+                        ui.label(format!(
+                            "{name} in {}:{:02}",
+                            left.num_minutes(),
+                            left.num_seconds() % 60
+                        ));
+                    }
+                });
+            });
+        self.open = open;
+        if self.running {
+            ctx.request_repaint_after(std::time::Duration::from_secs(1));
+        }
+    }
+
+    pub fn start(&mut self) {
+        self.running = true;
+        self.next = 0;
+        self.next_switch = None;
+    }
+
+    pub fn stop(&mut self) {
+        self.running = false;
+        self.next_switch = None;
+    }
+
+    /// The preset to show once a switch is due. Presets that no longer exist
+    /// are skipped, the rotation stops when none of them is left.
+    pub fn next(&mut self, presets: &str) -> Option<Result<Preset, String>> {
+        if !self.running || self.next_switch.map_or(false, |next| next > Utc::now()) {
+            return None;
+        }
+        let presets: Vec<InAppPreset> = from_str(presets).unwrap_or_default();
+        for _ in 0..self.presets.len() {
+            let index = self.next % self.presets.len();
+            self.next = (index + 1) % self.presets.len();
+            if let Some(preset) = presets
+                .iter()
+                .find(|preset| preset.name == self.presets[index])
+            {
+                self.next_switch = Some(Utc::now() + Duration::minutes(self.minutes as i64));
+                // the preset brings its own details, the animation goes over them right away
+                self.next_frame = None;
+                return Some(Ok(preset.clone().to_preset()));
+            }
+        }
+        self.stop();
+        Some(Err(
+            "Rotation stopped, none of its presets exist".to_string()
+        ))
+    }
+
+    /// The details text of the animation's next frame once it's due.
+    pub fn frame(&mut self) -> Option<String> {
+        if !self.animation.running {
+            self.next_frame = None;
+            return None;
+        }
+        if self.next_frame.map_or(false, |next| next > Utc::now()) {
+            return None;
+        }
+        self.next_frame = Some(Utc::now() + Duration::seconds(self.animation.interval as i64));
+        self.animation.next()
+    }
+
+    /// Time until the next switch or frame, `None` when neither runs.
+    pub fn wait(&self) -> Option<Duration> {
+        let wait = |running: bool, next: Option<DateTime<Utc>>| match (running, next) {
+            (true, Some(next)) => Some((next - Utc::now()).max(Duration::zero())),
+            (true, None) => Some(Duration::zero()),
+            (false, _) => None,
+        };
+        let switch = wait(self.running, self.next_switch);
+        let frame = wait(self.animation.running, self.next_frame);
+        switch.into_iter().chain(frame).min()
+    }
+}
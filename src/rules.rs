@@ -0,0 +1,285 @@
+use eframe::egui::{self, Context, RichText, TextEdit};
+use eframe::epaint::Color32;
+use regex::Regex;
+use serde::{Deserialize, Serialize};
+use serde_json::Value;
+
+#[derive(Serialize, Deserialize, PartialEq, Eq, Clone, Copy, Default)]
+pub enum Field {
+    #[default]
+    Any,
+    Details,
+    State,
+    ImageLabels,
+    ButtonLabels,
+    ButtonUrls,
+}
+
+const FIELDS: [Field; 6] = [
+    Field::Any,
+    Field::Details,
+    Field::State,
+    Field::ImageLabels,
+    Field::ButtonLabels,
+    Field::ButtonUrls,
+];
+
+impl Field {
+    fn name(self) -> &'static str {
+        match self {
+            Field::Any => "Any text",
+            Field::Details => "Details",
+            Field::State => "State",
+            Field::ImageLabels => "Image labels",
+            Field::ButtonLabels => "Button labels",
+            Field::ButtonUrls => "Button URLs",
+        }
+    }
+
+    /// The field's texts in an activity payload.
+    pub fn texts(self, payload: &Value) -> Vec<(&'static str, String)> {
+        let text = |name, value: &Value| value.as_str().map(|text| (name, text.to_string()));
+        let buttons = |key: &str| -> Vec<(&'static str, String)> {
+            payload["buttons"]
+                .as_array()
+                .map(|buttons| {
+                    buttons
+                        .iter()
+                        .filter_map(|button| match key {
+                            "url" => text("Button URL", &button[key]),
+                            _ => text("Button label", &button[key]),
+                        })
+                        .collect()
+                })
+                .unwrap_or_default()
+        };
+        match self {
+            Field::Any => [
+                Field::Details,
+                Field::State,
+                Field::ImageLabels,
+                Field::ButtonLabels,
+                Field::ButtonUrls,
+            ]
+            .into_iter()
+            .flat_map(|field| field.texts(payload))
+            .collect(),
+            Field::Details => text("Details", &payload["details"]).into_iter().collect(),
+            Field::State => text("State", &payload["state"]).into_iter().collect(),
+            Field::ImageLabels => [
+                text("Large image label", &payload["assets"]["large_text"]),
+                text("Small image label", &payload["assets"]["small_text"]),
+            ]
+            .into_iter()
+            .flatten()
+            .collect(),
+            Field::ButtonLabels => buttons("label"),
+            Field::ButtonUrls => buttons("url"),
+        }
+    }
+}
+
+/// A regular expression a field must, or must not, match.
+#[derive(Serialize, Deserialize, Clone, Default)]
+pub struct Pattern {
+    pub field: Field,
+    pub pattern: String,
+    /// Texts matching the pattern are rejected instead of required.
+    pub forbid: bool,
+}
+
+/// Checks of the user's own, run on every presence before it's sent. Meant
+/// for streamers that must keep certain words off their profile.
+#[derive(Default)]
+pub struct Rules {
+    pub open: bool,
+    /// One word or phrase per line, matched in any text regardless of case.
+    pub banned_words: String,
+    pub patterns: Vec<Pattern>,
+    compiled: Vec<Result<Regex, String>>,
+}
+
+impl Rules {
+    pub fn new(banned_words: String, patterns: Vec<Pattern>) -> Self {
+        let mut rules = Self {
+            banned_words,
+            patterns,
+            ..Default::default()
+        };
+        rules.compile();
+        rules
+    }
+
+    /// Why the payload breaks the rules, empty when it doesn't.
+    pub fn check(&self, payload: &Value) -> Vec<String> {
+        let mut problems = vec![];
+        let texts = Field::Any.texts(payload);
+        for word in self.banned_words.lines().map(str::trim) {
+            if word.is_empty() {
+                continue;
+            }
+            let word = word.to_lowercase();
+            for (name, text) in texts.iter() {
+                // the word isn't repeated, the window may be on stream
+                if text.to_lowercase().contains(&word) {
+                    problems.push(format!("{name} contains a banned word"));
+                }
+            }
+        }
+        for (pattern, regex) in self.patterns.iter().zip(self.compiled.iter()) {
+            let regex = match regex {
+                Ok(regex) if !pattern.pattern.is_empty() => regex,
+                _ => continue,
+            };
+            for (name, text) in pattern.field.texts(payload) {
+                match (pattern.forbid, regex.is_match(&text)) {
+                    (true, true) => problems.push(format!("{name} matches {}", pattern.pattern)),
+                    (false, false) => {
+                        problems.push(format!("{name} doesn't match {}", pattern.pattern))
+                    }
+                    _ => (),
+                }
+            }
+        }
+        problems.dedup();
+        problems
+    }
+
+    pub fn run(&mut self, ctx: &Context) {
+        let mut open = self.open;
+        let mut changed = false;
+        egui::containers::Window::new("Validation rules")
+            .open(&mut open)
+            .resizable(false)
+            .show(ctx, |ui| {
+                ui.label("Banned words, one per line");
+                ui.add(
+                    TextEdit::multiline(&mut self.banned_words)
+                        .password(true)
+                        .desired_rows(3),
+                )
+                .on_hover_text("Hidden, in case the window is on stream");
+                ui.add_space(5.);
+                ui.label("Patterns");
+                let mut remove = None;
+                for (i, pattern) in self.patterns.iter_mut().enumerate() {
+                    ui.horizontal(|ui| {
+                        egui::ComboBox::from_id_source(("rule_field", i))
+                            .selected_text(pattern.field.name())
+                            .show_ui(ui, |ui| {
+                                for field in FIELDS {
+                                    ui.selectable_value(&mut pattern.field, field, field.name());
+                                }
+                            });
+                        egui::ComboBox::from_id_source(("rule_kind", i))
+                            .selected_text(match pattern.forbid {
+                                true => "must not match",
+                                false => "must match",
+                            })
+                            .show_ui(ui, |ui| {
+                                ui.selectable_value(&mut pattern.forbid, false, "must match");
+                                ui.selectable_value(&mut pattern.forbid, true, "must not match");
+                            });
+                        changed |= ui.text_edit_singleline(&mut pattern.pattern).changed();
+                        if ui.small_button("✖").clicked() {
+                            remove = Some(i);
+                        }
+                    });
+                    if let Some(Err(error)) = self.compiled.get(i) {
+                        ui.label(RichText::new(error).small().color(Color32::LIGHT_RED));
+                    }
+                }
+                if let Some(i) = remove {
+                    self.patterns.remove(i);
+                    changed = true;
+                }
+                if ui.button("Add pattern").clicked() {
+                    self.patterns.push(Pattern::default());
+                    changed = true;
+                }
+            });
+        self.open = open;
+        if changed {
+            self.compile();
+        }
+    }
+
+    /// Why patterns don't compile, with their position from 1.
+    pub fn invalid(&self) -> Vec<(usize, &str)> {
+        self.compiled
+            .iter()
+            .enumerate()
+            .filter_map(|(i, regex)| regex.as_ref().err().map(|error| (i + 1, error.as_str())))
+            .collect()
+    }
+
+    fn compile(&mut self) {
+        self.compiled = self
+            .patterns
+            .iter()
+            .map(|pattern| {
+                Regex::new(&pattern.pattern).map_err(|error| format!("Invalid pattern: {error}"))
+            })
+            .collect();
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json::json;
+
+    fn payload() -> Value {
+        json!({
+            "details": "Playing Ranked",
+            "state": "With friends",
+            "buttons": [{ "label": "Join", "url": "https://example.com/join" }],
+        })
+    }
+
+    fn pattern(field: Field, pattern: &str, forbid: bool) -> Pattern {
+        Pattern {
+            field,
+            pattern: pattern.to_string(),
+            forbid,
+        }
+    }
+
+    #[test]
+    fn finds_banned_words_in_any_case() {
+        let rules = Rules::new("\n ranked \n".to_string(), Vec::new());
+        assert_eq!(rules.check(&payload()), ["Details contains a banned word"]);
+        assert!(Rules::default().check(&payload()).is_empty());
+    }
+
+    #[test]
+    fn checks_required_and_forbidden_patterns() {
+        let rules = Rules::new(
+            String::new(),
+            vec![
+                pattern(Field::ButtonUrls, "^https://example\\.org/", false),
+                pattern(Field::State, "friends$", true),
+                pattern(Field::Details, "^Playing", false),
+            ],
+        );
+        assert_eq!(
+            rules.check(&payload()),
+            [
+                "Button URL doesn't match ^https://example\\.org/",
+                "State matches friends$",
+            ]
+        );
+    }
+
+    #[test]
+    fn skips_invalid_and_empty_patterns() {
+        let rules = Rules::new(
+            String::new(),
+            vec![
+                pattern(Field::Any, "(", true),
+                pattern(Field::Any, "", false),
+            ],
+        );
+        assert!(rules.check(&payload()).is_empty());
+    }
+}
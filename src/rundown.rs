@@ -0,0 +1,183 @@
+use crate::preset::{InAppPreset, Preset};
+use chrono::{DateTime, Duration, Utc};
+use eframe::egui::{self, Context, Key, RichText};
+use eframe::epaint::Color32;
+use serde::{Deserialize, Serialize};
+use serde_json::from_str;
+
+/// One step of a rundown, an in-app preset shown for a number of minutes.
+#[derive(Serialize, Deserialize, Clone, Default)]
+pub struct Step {
+    pub preset: String,
+    /// Length of the step, shown as a countdown. 0 means until advanced by hand.
+    pub minutes: u32,
+}
+
+/// Ordered list of presets for a stream (intro → gameplay → break → outro),
+/// advanced with Ctrl + → or when a step's time is up.
+#[derive(Default)]
+pub struct Rundown {
+    pub open: bool,
+    pub steps: Vec<Step>,
+    /// Go to the next step by itself when the current one's time is up.
+    pub auto_advance: bool,
+    pub current: Option<usize>,
+    pub step_end: Option<DateTime<Utc>>,
+}
+
+impl Rundown {
+    /// Shows the editor and returns the preset of the step that just started,
+    /// an error when that preset no longer exists. `locked` while the display
+    /// lock is on keeps the hotkey from advancing.
+    pub fn run(
+        &mut self,
+        ctx: &Context,
+        presets: &str,
+        locked: bool,
+    ) -> Option<Result<Preset, String>> {
+        let presets: Vec<InAppPreset> = match from_str(presets) {
+            Ok(presets) => presets,
+            Err(_) => Vec::new(),
+        };
+        let mut start = None;
+        egui::containers::Window::new("Rundown")
+            .open(&mut self.open)
+            .resizable(false)
+            .show(ctx, |ui| {
+                let mut remove = None;
+                let mut swap = None;
+                let last = self.steps.len().saturating_sub(1);
+                for (i, step) in self.steps.iter_mut().enumerate() {
+                    ui.horizontal(|ui| {
+                        let label = RichText::new(format!("{}.", i + 1));
+                        ui.label(match self.current == Some(i) {
+                            true => label.color(Color32::LIGHT_GREEN),
+                            false => label,
+                        });
+                        egui::ComboBox::from_id_source(("rundown_step", i))
+                            .selected_text(&step.preset)
+                            .show_ui(ui, |ui| {
+                                for preset in presets.iter() {
+                                    ui.selectable_value(
+                                        &mut step.preset,
+                                        preset.name.clone(),
+                                        &preset.name,
+                                    );
+                                }
+                            });
+                        ui.add(
+                            egui::DragValue::new(&mut step.minutes)
+                                .clamp_range(0..=600)
+                                .suffix(" min"),
+                        );
+                        if ui.add_enabled(i > 0, egui::Button::new("⬆")).clicked() {
+                            swap = Some(i - 1);
+                        }
+                        if ui.add_enabled(i < last, egui::Button::new("⬇")).clicked() {
+                            swap = Some(i);
+                        }
+                        if ui.button("❌").clicked() {
+                            remove = Some(i);
+                        }
+                    });
+                    if !step.preset.is_empty()
+                        && !presets.iter().any(|preset| preset.name == step.preset)
+                    {
+                        ui.label(
+                            RichText::new(format!("Preset {} doesn't exist", step.preset))
+                                .small()
+                                .color(Color32::LIGHT_RED),
+                        );
+                    }
+                }
+                if let Some(i) = swap {
+                    self.steps.swap(i, i + 1);
+                }
+                if let Some(i) = remove {
+                    self.steps.remove(i);
+                }
+                if ui.button("Add step").clicked() {
+                    self.steps.push(Step::default());
+                }
+                ui.add_space(5.);
+                ui.checkbox(&mut self.auto_advance, "Advance when a step's time is up");
+                ui.horizontal(|ui| {
+                    if ui.button("Start").clicked() {
+                        start = Some(0);
+                    }
+                    let next = self.current.map_or(0, |current| current + 1);
+                    if ui
+                        .add_enabled(next < self.steps.len(), egui::Button::new("Next"))
+                        .on_hover_text("Ctrl + →")
+                        .clicked()
+                    {
+                        start = Some(next);
+                    }
+                    if ui
+                        .add_enabled(self.current.is_some(), egui::Button::new("Stop"))
+                        .clicked()
+                    {
+                        self.current = None;
+                        self.step_end = None;
+                    }
+                });
+                if let (Some(current), Some(end)) = (self.current, self.step_end) {
+                    let left = (end - Utc::now()).max(Duration::zero());
+                    ui.label(format!(
+                        "Step {} ends in {}:{:02}",
+                        current + 1,
+                        left.num_minutes(),
+                        left.num_seconds() % 60
+                    ));
+                }
+            });
+
+        if let Some(current) = self.current {
+            let input = ctx.input();
+            if !locked && input.modifiers.ctrl && input.key_pressed(Key::ArrowRight) {
+                start = Some(current + 1);
+            }
+            drop(input);
+            if let Some(end) = self.step_end {
+                if self.auto_advance && end <= Utc::now() {
+                    start = Some(current + 1);
+                }
+                ctx.request_repaint_after(std::time::Duration::from_secs(1));
+            }
+        }
+
+        let index = start?;
+        let step = match self.steps.get(index) {
+            Some(step) => step,
+            None => {
+                // past the last step, the rundown is over
+                self.current = None;
+                self.step_end = None;
+                return None;
+            }
+        };
+        self.current = Some(index);
+        self.step_end = match step.minutes {
+            0 => None,
+            minutes => Some(Utc::now() + Duration::minutes(minutes as i64)),
+        };
+        let preset = match presets
+            .into_iter()
+            .find(|preset| preset.name == step.preset)
+        {
+            Some(preset) => preset,
+            None => {
+                return Some(Err(format!(
+                    "Rundown step {}: preset {} doesn't exist",
+                    index + 1,
+                    step.preset
+                )))
+            }
+        };
+        let mut preset = preset.to_preset();
+        if step.minutes > 0 {
+            preset.EndAfterMinutes = Some(step.minutes as i64);
+        }
+        Some(Ok(preset))
+    }
+}
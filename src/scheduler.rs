@@ -0,0 +1,177 @@
+use crate::automation::Schedule;
+use crate::preset::{InAppPreset, Preset};
+use chrono::{Local, NaiveTime, Utc, Weekday};
+use derivative::Derivative;
+use eframe::egui::{self, Context, RichText, TextEdit};
+use eframe::epaint::Color32;
+use serde::{Deserialize, Serialize};
+use serde_json::from_str;
+
+/// Seconds between two looks at the clock.
+const CHECK_INTERVAL: i64 = 5;
+
+const DAYS: [(Weekday, &str); 7] = [
+    (Weekday::Mon, "Mo"),
+    (Weekday::Tue, "Tu"),
+    (Weekday::Wed, "We"),
+    (Weekday::Thu, "Th"),
+    (Weekday::Fri, "Fr"),
+    (Weekday::Sat, "Sa"),
+    (Weekday::Sun, "Su"),
+];
+
+/// An in-app preset shown between two times on some days of the week.
+#[derive(Serialize, Deserialize, Clone, Derivative)]
+#[derivative(Default)]
+pub struct Slot {
+    pub preset: String,
+    /// Monday first.
+    #[derivative(Default(value = "[true; 7]"))]
+    pub days: [bool; 7],
+    #[derivative(Default(value = "\"09:00\".to_string()"))]
+    pub start: String,
+    #[derivative(Default(value = "\"17:00\".to_string()"))]
+    pub end: String,
+}
+
+impl Slot {
+    pub fn schedule(&self) -> Result<Schedule, String> {
+        let time = |time: &str| {
+            NaiveTime::parse_from_str(time.trim(), "%H:%M")
+                .map_err(|_| format!("\"{}\" is not a time like 09:00", time.trim()))
+        };
+        let days = DAYS
+            .iter()
+            .zip(self.days)
+            .filter(|(_, on)| *on)
+            .map(|((day, _), _)| *day)
+            .collect();
+        Ok(Schedule::new(days, time(&self.start)?, time(&self.end)?))
+    }
+}
+
+/// Switches between in-app presets by time of day and weekday. Ranges are
+/// checked top to bottom, the first one containing the current time wins,
+/// and outside of every range the presence is left as it is.
+#[derive(Default)]
+pub struct Scheduler {
+    pub open: bool,
+    pub enabled: bool,
+    pub slots: Vec<Slot>,
+    /// Index of the slot shown.
+    active: Option<usize>,
+    next_check: i64,
+}
+
+impl Scheduler {
+    pub fn new(enabled: bool, slots: Vec<Slot>) -> Self {
+        Self {
+            enabled,
+            slots,
+            ..Default::default()
+        }
+    }
+
+    /// Shows the editor.
+    pub fn run(&mut self, ctx: &Context, presets: &str) {
+        let presets: Vec<InAppPreset> = from_str(presets).unwrap_or_default();
+        let mut open = self.open;
+        let mut changed = false;
+        egui::containers::Window::new("Schedule")
+            .open(&mut open)
+            .resizable(false)
+            .show(ctx, |ui| {
+                changed |= ui
+                    .checkbox(&mut self.enabled, "Switch presets on schedule")
+                    .changed();
+                let mut remove = None;
+                let mut swap = None;
+                let last = self.slots.len().saturating_sub(1);
+                for (i, slot) in self.slots.iter_mut().enumerate() {
+                    ui.horizontal(|ui| {
+                        egui::ComboBox::from_id_source(("schedule_preset", i))
+                            .selected_text(slot.preset.as_str())
+                            .show_ui(ui, |ui| {
+                                for preset in presets.iter() {
+                                    changed |= ui
+                                        .selectable_value(
+                                            &mut slot.preset,
+                                            preset.name.clone(),
+                                            &preset.name,
+                                        )
+                                        .changed();
+                                }
+                            });
+                        for (on, (_, label)) in slot.days.iter_mut().zip(DAYS) {
+                            changed |= ui.toggle_value(on, label).changed();
+                        }
+                        for time in [&mut slot.start, &mut slot.end] {
+                            changed |= ui
+                                .add(TextEdit::singleline(time).desired_width(40.))
+                                .changed();
+                        }
+                        if ui.add_enabled(i > 0, egui::Button::new("⬆")).clicked() {
+                            swap = Some(i - 1);
+                        }
+                        if ui.add_enabled(i < last, egui::Button::new("⬇")).clicked() {
+                            swap = Some(i);
+                        }
+                        if ui.button("❌").clicked() {
+                            remove = Some(i);
+                        }
+                    });
+                    if let Err(error) = slot.schedule() {
+                        ui.label(RichText::new(error).small().color(Color32::LIGHT_RED));
+                    }
+                }
+                if let Some(i) = swap {
+                    self.slots.swap(i, i + 1);
+                    changed = true;
+                }
+                if let Some(i) = remove {
+                    self.slots.remove(i);
+                    changed = true;
+                }
+                if ui.button("Add time range").clicked() {
+                    self.slots.push(Slot::default());
+                }
+                ui.label(
+                    RichText::new("The first matching range wins, 22:00-02:00 runs past midnight")
+                        .small(),
+                );
+                if let Some(slot) = self.active.and_then(|i| self.slots.get(i)) {
+                    ui.label(format!("Showing {} until {}", slot.preset, slot.end));
+                }
+            });
+        self.open = open;
+        if changed {
+            // looked at again right away, an edited range may match now
+            self.active = None;
+            self.next_check = 0;
+        }
+    }
+
+    /// The preset to show when another range started. Outside of every range
+    /// nothing changes, presets that no longer exist are reported once.
+    pub fn next(&mut self, presets: &str) -> Option<Result<Preset, String>> {
+        if !self.enabled || self.next_check > Utc::now().timestamp() {
+            return None;
+        }
+        self.next_check = Utc::now().timestamp() + CHECK_INTERVAL;
+        let now = Local::now();
+        let matching = self
+            .slots
+            .iter()
+            .position(|slot| matches!(slot.schedule(), Ok(schedule) if schedule.contains(now)));
+        if matching == self.active {
+            return None;
+        }
+        self.active = matching;
+        let name = &self.slots[matching?].preset;
+        let presets: Vec<InAppPreset> = from_str(presets).unwrap_or_default();
+        match presets.into_iter().find(|preset| preset.name == *name) {
+            Some(preset) => Some(Ok(preset.to_preset())),
+            None => Some(Err(format!("Scheduled preset {name} doesn't exist"))),
+        }
+    }
+}
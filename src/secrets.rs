@@ -0,0 +1,49 @@
+use crate::cli::write_private;
+use crate::storage::data_dir;
+use std::collections::HashMap;
+use std::fs;
+use std::path::PathBuf;
+use std::sync::Mutex;
+
+/// The secrets file as last read or written, `None` before the first use.
+static SECRETS: Mutex<Option<HashMap<String, String>>> = Mutex::new(None);
+
+/// Passwords and keys are kept in a file only the user can read, apart
+/// from the settings, which end up in bug reports and synced dotfiles.
+fn path() -> Option<PathBuf> {
+    data_dir().map(|dir| dir.join("secrets.json"))
+}
+
+fn read() -> HashMap<String, String> {
+    path()
+        .and_then(|path| fs::read_to_string(path).ok())
+        .and_then(|file| serde_json::from_str(&file).ok())
+        .unwrap_or_default()
+}
+
+/// The secret saved under `name`, empty when there's none.
+pub fn get(name: &str) -> String {
+    let mut secrets = SECRETS.lock().unwrap();
+    secrets
+        .get_or_insert_with(read)
+        .get(name)
+        .cloned()
+        .unwrap_or_default()
+}
+
+/// Saves the secret under `name` when it changed, an empty one is removed.
+pub fn set(name: &str, secret: &str) -> Result<(), String> {
+    let mut secrets = SECRETS.lock().unwrap();
+    let secrets = secrets.get_or_insert_with(read);
+    if secrets.get(name).map_or("", String::as_str) == secret {
+        return Ok(());
+    }
+    match secret.is_empty() {
+        true => secrets.remove(name),
+        false => secrets.insert(name.to_string(), secret.to_string()),
+    };
+    let path = path().ok_or_else(|| "No data folder for the secrets".to_string())?;
+    fs::create_dir_all(path.parent().unwrap())
+        .and_then(|_| write_private(&path, &serde_json::to_string_pretty(secrets).unwrap()))
+        .map_err(|err| format!("Failed to save the secrets: {err}"))
+}
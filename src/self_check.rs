@@ -0,0 +1,172 @@
+use crate::app_cache;
+use crate::ipc;
+use eframe::egui::{self, Context, RichText};
+use eframe::epaint::Color32;
+use std::sync::mpsc::{channel, Receiver};
+use std::thread;
+use std::time::Duration;
+
+pub struct Check {
+    pub name: &'static str,
+    pub ok: bool,
+    pub detail: String,
+    /// Button label and URL that help fixing a failed check.
+    pub fix: Option<(&'static str, String)>,
+}
+
+/// Checklist of what the presence needs to work, run on launch and shown
+/// when something is missing instead of failing silently later.
+#[derive(Default)]
+pub struct SelfCheck {
+    pub open: bool,
+    pub checks: Vec<Check>,
+    running: Option<Receiver<Vec<Check>>>,
+    /// Show the panel only when a check fails, used for the launch run.
+    quiet: bool,
+}
+
+impl SelfCheck {
+    /// Runs the checks in the background for the given ID and image keys.
+    pub fn start(&mut self, id: &str, keys: [&str; 2], quiet: bool) {
+        let id = id.to_string();
+        let keys = keys.map(str::to_string);
+        let (sender, receiver) = channel();
+        thread::spawn(move || {
+            let _ = sender.send(run_checks(&id, &keys));
+        });
+        self.running = Some(receiver);
+        self.quiet = quiet;
+        self.open = self.open || !quiet;
+    }
+
+    pub fn run(&mut self, ctx: &Context, id: &str, keys: [&str; 2]) {
+        if let Some(receiver) = &self.running {
+            match receiver.try_recv() {
+                Ok(checks) => {
+                    self.open = self.open || !self.quiet || checks.iter().any(|check| !check.ok);
+                    self.checks = checks;
+                    self.running = None;
+                }
+                Err(_) => ctx.request_repaint_after(Duration::from_millis(200)),
+            }
+        }
+        let mut retry = false;
+        egui::containers::Window::new("Self-check")
+            .open(&mut self.open)
+            .resizable(false)
+            .show(ctx, |ui| {
+                if self.running.is_some() {
+                    ui.label("Checking...");
+                }
+                egui::Grid::new("self_check").show(ui, |ui| {
+                    for check in self.checks.iter() {
+                        ui.label(match check.ok {
+                            true => RichText::new("✔").color(Color32::LIGHT_GREEN),
+                            false => RichText::new("✖").color(Color32::LIGHT_RED),
+                        });
+                        ui.label(check.name);
+                        ui.label(RichText::new(&check.detail).small());
+                        match &check.fix {
+                            Some((label, url)) if !check.ok => {
+                                if ui.button(*label).clicked() {
+                                    ui.output().open_url(url);
+                                }
+                            }
+                            _ => {
+                                ui.label("");
+                            }
+                        }
+                        ui.end_row();
+                    }
+                });
+                ui.add_space(5.);
+                if ui
+                    .add_enabled(self.running.is_none(), egui::Button::new("Check again"))
+                    .clicked()
+                {
+                    retry = true;
+                }
+            });
+        if retry {
+            self.start(id, keys, false);
+        }
+    }
+}
+
+fn run_checks(id: &str, keys: &[String; 2]) -> Vec<Check> {
+    let mut checks = vec![];
+
+    let running = ipc::discord_running();
+    checks.push(Check {
+        name: "Discord",
+        ok: running,
+        detail: match running {
+            true => "Running".to_string(),
+            false => "No running Discord client found".to_string(),
+        },
+        fix: Some(("Start Discord", "discord://".to_string())),
+    });
+
+    let id_ok = (17..=20).contains(&id.len()) && id.chars().all(|c| c.is_ascii_digit());
+    checks.push(Check {
+        name: "Application ID",
+        ok: id_ok,
+        detail: match (id.is_empty(), id_ok) {
+            (true, _) => "Not set".to_string(),
+            (false, false) => "Should be 17 to 20 digits".to_string(),
+            (false, true) => id.to_string(),
+        },
+        fix: Some((
+            "Developer portal",
+            "https://discord.com/developers/applications".to_string(),
+        )),
+    });
+
+    let network = ureq::get("https://discord.com/api/v10/gateway")
+        .timeout(Duration::from_secs(10))
+        .call()
+        .is_ok();
+    checks.push(Check {
+        name: "Network",
+        ok: network,
+        detail: match network {
+            true => "Discord can be reached".to_string(),
+            false => "Discord can't be reached".to_string(),
+        },
+        fix: None,
+    });
+
+    let keys: Vec<&String> = keys
+        .iter()
+        // links and placeholders like {album_art} aren't asset names
+        .filter(|key| {
+            !key.is_empty() && !key.contains("://") && !key.starts_with("mp:") && !key.contains('{')
+        })
+        .collect();
+    if id_ok && network && !keys.is_empty() {
+        let (ok, detail) = match app_cache::fetch(id) {
+            Ok(app) => {
+                let missing: Vec<&str> = keys
+                    .iter()
+                    .filter(|key| !app.assets.contains(key))
+                    .map(|key| key.as_str())
+                    .collect();
+                match missing.is_empty() {
+                    true => (true, "All image keys exist".to_string()),
+                    false => (false, format!("Not uploaded: {}", missing.join(", "))),
+                }
+            }
+            Err(_) => (false, "The application doesn't exist".to_string()),
+        };
+        checks.push(Check {
+            name: "Assets",
+            ok,
+            detail,
+            fix: Some((
+                "Upload assets",
+                format!("https://discord.com/developers/applications/{id}/rich-presence/assets"),
+            )),
+        });
+    }
+    checks
+}
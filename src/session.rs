@@ -0,0 +1,55 @@
+use crate::preset::Preset;
+use eframe::egui::{RichText, Ui};
+use eframe::epaint::Color32;
+use serde::{Deserialize, Serialize};
+
+/// The presence Discord showed when the app was last closed.
+#[derive(Serialize, Deserialize, Clone)]
+pub struct Session {
+    pub preset: Preset,
+    /// Whether the app was connected, restoring connects again.
+    pub connected: bool,
+}
+
+/// Offers to get back to the last session on launch. Unlike autoconnect,
+/// which sends whatever the form holds, this brings back what was sent.
+#[derive(Default)]
+pub struct LastSession {
+    pub session: Option<Session>,
+    pub dismissed: bool,
+}
+
+impl LastSession {
+    /// Shows the offer and returns the session when it's restored.
+    pub fn run(&mut self, ui: &mut Ui) -> Option<Session> {
+        let session = match (&self.session, self.dismissed) {
+            (Some(session), false) => session,
+            _ => return None,
+        };
+        let mut restore = false;
+        ui.horizontal(|ui| {
+            let text = session
+                .preset
+                .Details
+                .as_deref()
+                .filter(|details| !details.is_empty())
+                .unwrap_or("your last presence");
+            ui.label(
+                RichText::new(format!("Last session: {text}"))
+                    .small()
+                    .color(Color32::from_rgb(163, 163, 163)),
+            );
+            restore = ui.small_button("Restore last session").clicked();
+            if ui.small_button("✖").on_hover_text("Dismiss").clicked() {
+                self.dismissed = true;
+            }
+        });
+        match restore {
+            true => {
+                self.dismissed = true;
+                self.session.clone()
+            }
+            false => None,
+        }
+    }
+}
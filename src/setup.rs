@@ -0,0 +1,203 @@
+use crate::app_cache::{self, CachedApp};
+use eframe::egui::{self, Context, RichText, Ui};
+use eframe::epaint::Color32;
+use std::sync::mpsc::{channel, Receiver};
+use std::thread;
+
+const PORTAL: &str = "https://discord.com/developers/applications";
+
+#[derive(PartialEq, Eq, Clone, Copy, Default)]
+enum Step {
+    #[default]
+    Create,
+    Id,
+    Art,
+    Done,
+}
+
+/// Walks through creating a Discord application for the presence. Every
+/// step is checked against Discord before the next one opens.
+#[derive(Default)]
+pub struct Setup {
+    pub open: bool,
+    step: Step,
+    id: String,
+    /// The application as Discord reported it on the last check.
+    app: Option<CachedApp>,
+    checking: Option<Receiver<Result<CachedApp, String>>>,
+    error: Option<String>,
+}
+
+impl Setup {
+    pub fn start(&mut self) {
+        *self = Self {
+            open: true,
+            ..Default::default()
+        };
+    }
+
+    /// Shows the wizard, returns the application once the user is done.
+    pub fn run(&mut self, ctx: &Context) -> Option<CachedApp> {
+        self.poll();
+        let mut open = self.open;
+        let mut finished = None;
+        egui::containers::Window::new("Set up an application")
+            .open(&mut open)
+            .resizable(false)
+            .show(ctx, |ui| {
+                match self.step {
+                    Step::Create => self.create(ui),
+                    Step::Id => self.id(ui),
+                    Step::Art => self.art(ui),
+                    Step::Done => finished = self.done(ui),
+                }
+                if self.checking.is_some() {
+                    ui.horizontal(|ui| {
+                        ui.spinner();
+                        ui.label("Asking Discord…");
+                    });
+                    ctx.request_repaint();
+                }
+                if let Some(error) = &self.error {
+                    ui.label(RichText::new(error).small().color(Color32::LIGHT_RED));
+                }
+            });
+        self.open = open && finished.is_none();
+        finished
+    }
+
+    fn create(&mut self, ui: &mut Ui) {
+        ui.label("1. Create an application");
+        ui.label(
+            "Discord shows the presence as playing an application. Open the Developer Portal, \
+             click New Application and name it what Discord should show, e.g. \"Blender\".",
+        );
+        if ui.button("Open the Developer Portal").clicked() {
+            ui.output().open_url(PORTAL);
+        }
+        ui.add_space(5.);
+        if ui.button("Next").clicked() {
+            self.step = Step::Id;
+        }
+    }
+
+    fn id(&mut self, ui: &mut Ui) {
+        ui.label("2. Copy the Application ID");
+        ui.label(
+            "It's on the application's General Information page, under the name. \
+             Click Copy and paste it here.",
+        );
+        ui.horizontal(|ui| {
+            ui.label("Application ID");
+            ui.text_edit_singleline(&mut self.id);
+        });
+        if let Some(app) = &self.app {
+            ui.label(
+                RichText::new(format!("Found {}", app.name)).color(Color32::from_rgb(87, 242, 135)),
+            );
+        }
+        ui.add_space(5.);
+        ui.horizontal(|ui| {
+            if ui.button("Back").clicked() {
+                self.step = Step::Create;
+            }
+            let checked = matches!(&self.app, Some(app) if app.id == self.id);
+            let label = match checked {
+                true => "Next",
+                false => "Check",
+            };
+            let enabled = app_cache::check_id(&self.id).is_ok() && self.checking.is_none();
+            if ui.add_enabled(enabled, egui::Button::new(label)).clicked() {
+                match checked {
+                    true => self.step = Step::Art,
+                    false => self.check(),
+                }
+            }
+        });
+        if let Err(problem) = app_cache::check_id(&self.id) {
+            if !self.id.is_empty() {
+                ui.label(RichText::new(problem).small().color(Color32::LIGHT_RED));
+            }
+        }
+    }
+
+    fn art(&mut self, ui: &mut Ui) {
+        ui.label("3. Upload images (optional)");
+        ui.label(
+            "Under Rich Presence → Art Assets, upload the images the presence should show. \
+             The name given to each is the key to type in the image fields. New images can \
+             take a few minutes to show up.",
+        );
+        if ui.button("Open Art Assets").clicked() {
+            ui.output()
+                .open_url(format!("{PORTAL}/{}/rich-presence/assets", self.id));
+        }
+        let assets = self
+            .app
+            .as_ref()
+            .map(|app| app.assets.join(", "))
+            .unwrap_or_default();
+        match assets.is_empty() {
+            true => ui.label("No images found yet"),
+            false => ui.label(format!("Found {assets}")),
+        };
+        ui.add_space(5.);
+        ui.horizontal(|ui| {
+            if ui.button("Back").clicked() {
+                self.step = Step::Id;
+            }
+            if ui
+                .add_enabled(self.checking.is_none(), egui::Button::new("Check again"))
+                .clicked()
+            {
+                self.check();
+            }
+            let label = match assets.is_empty() {
+                true => "Skip",
+                false => "Next",
+            };
+            if ui.button(label).clicked() {
+                self.step = Step::Done;
+            }
+        });
+    }
+
+    fn done(&mut self, ui: &mut Ui) -> Option<CachedApp> {
+        let app = self.app.as_ref()?;
+        ui.label(format!("{} is ready", app.name));
+        ui.label("Its ID goes into the form, connect to show the presence.");
+        ui.add_space(5.);
+        match ui.button("Use this application").clicked() {
+            true => self.app.clone(),
+            false => None,
+        }
+    }
+
+    fn check(&mut self) {
+        let (sender, receiver) = channel();
+        let id = self.id.trim().to_string();
+        thread::spawn(move || {
+            let _ = sender.send(app_cache::fetch(&id));
+        });
+        self.checking = Some(receiver);
+        self.error = None;
+    }
+
+    fn poll(&mut self) {
+        let result = match self.checking.as_ref().map(Receiver::try_recv) {
+            Some(Ok(result)) => result,
+            _ => return,
+        };
+        self.checking = None;
+        match result {
+            Ok(app) => self.app = Some(app),
+            Err(error) => {
+                // a failed look at the images keeps the application that was found
+                if self.step == Step::Id {
+                    self.app = None;
+                }
+                self.error = Some(error);
+            }
+        }
+    }
+}
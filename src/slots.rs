@@ -0,0 +1,121 @@
+use crate::preset::Preset;
+use eframe::egui::{Key, Ui};
+use serde::{Deserialize, Serialize};
+
+#[derive(Serialize, Deserialize, PartialEq, Eq, Clone, Copy, Default)]
+pub enum Slot {
+    #[default]
+    A,
+    B,
+}
+
+impl Slot {
+    fn name(self) -> &'static str {
+        match self {
+            Slot::A => "A",
+            Slot::B => "B",
+        }
+    }
+
+    fn other(self) -> Self {
+        match self {
+            Slot::A => Slot::B,
+            Slot::B => Slot::A,
+        }
+    }
+}
+
+/// What the user asked the slots for.
+pub enum Action {
+    Load(Box<Preset>),
+    /// Keep the presence as it is in the app in the slot.
+    Save(Slot),
+}
+
+/// Two presences to flip between with one click or Ctrl + Shift + F, e.g.
+/// a public-friendly one and a detailed one, without going through presets.
+#[derive(Default)]
+pub struct Slots {
+    pub a: Option<Preset>,
+    pub b: Option<Preset>,
+    /// The slot loaded last.
+    pub current: Slot,
+}
+
+impl Slots {
+    /// Shows the slots, `locked` while the display lock is on keeps the
+    /// hotkey from flipping them.
+    pub fn run(&mut self, ui: &mut Ui, locked: bool) -> Option<Action> {
+        let mut action = None;
+        ui.horizontal(|ui| {
+            for slot in [Slot::A, Slot::B] {
+                let saved = self.get(slot).is_some();
+                let button = ui
+                    .selectable_label(saved && self.current == slot, slot.name())
+                    .on_hover_text(match saved {
+                        true => "Click to load, right click to save or clear",
+                        false => "Click to keep the presence as it is here",
+                    });
+                if button.clicked() {
+                    action = match self.get(slot) {
+                        Some(_) => self.load(slot),
+                        None => Some(Action::Save(slot)),
+                    };
+                }
+                button.context_menu(|ui| {
+                    if ui.button("Save the presence here").clicked() {
+                        action = Some(Action::Save(slot));
+                        ui.close_menu();
+                    }
+                    if ui.button("Clear").clicked() {
+                        *self.get_mut(slot) = None;
+                        ui.close_menu();
+                    }
+                });
+            }
+            let flip = ui
+                .add_enabled(
+                    self.a.is_some() && self.b.is_some(),
+                    eframe::egui::Button::new("⇄"),
+                )
+                .on_hover_text("Ctrl + Shift + F");
+            let input = ui.input();
+            let hotkey = !locked
+                && input.modifiers.ctrl
+                && input.modifiers.shift
+                && input.key_pressed(Key::F);
+            drop(input);
+            if flip.clicked() || hotkey {
+                if let Some(load) = self.load(self.current.other()) {
+                    action = Some(load);
+                }
+            }
+        });
+        action
+    }
+
+    pub fn save(&mut self, slot: Slot, preset: Preset) {
+        *self.get_mut(slot) = Some(preset);
+        self.current = slot;
+    }
+
+    fn load(&mut self, slot: Slot) -> Option<Action> {
+        let preset = self.get(slot).cloned()?;
+        self.current = slot;
+        Some(Action::Load(Box::new(preset)))
+    }
+
+    fn get(&self, slot: Slot) -> Option<&Preset> {
+        match slot {
+            Slot::A => self.a.as_ref(),
+            Slot::B => self.b.as_ref(),
+        }
+    }
+
+    fn get_mut(&mut self, slot: Slot) -> &mut Option<Preset> {
+        match slot {
+            Slot::A => &mut self.a,
+            Slot::B => &mut self.b,
+        }
+    }
+}
@@ -0,0 +1,128 @@
+use crate::hooks::Hook;
+use derivative::Derivative;
+use eframe::egui::Ui;
+use rfd::FileDialog;
+use serde::{Deserialize, Serialize};
+use std::fs;
+use std::io::Cursor;
+use std::sync::{Arc, Mutex};
+use std::thread;
+
+const CONNECTED: &[u8] = include_bytes!("../assets/sounds/connected.wav");
+const DISCONNECTED: &[u8] = include_bytes!("../assets/sounds/disconnected.wav");
+const FAILED: &[u8] = include_bytes!("../assets/sounds/failed.wav");
+
+/// Sound cues for the connection, so it can be followed with the window
+/// minimized. The paths pick other sounds, the bundled ones play when empty.
+#[derive(Serialize, Deserialize, Derivative, Clone)]
+#[derivative(Default)]
+#[serde(default)]
+pub struct Sounds {
+    pub enabled: bool,
+    #[derivative(Default(value = "0.5"))]
+    pub volume: f32,
+    pub connected: String,
+    pub disconnected: String,
+    pub failed: String,
+    /// Failures from the playback threads, taken by `errors`.
+    #[serde(skip)]
+    failures: Arc<Mutex<Vec<String>>>,
+}
+
+impl Sounds {
+    pub fn run(&mut self, ui: &mut Ui) {
+        ui.checkbox(&mut self.enabled, "Play sounds");
+        ui.add(eframe::egui::Slider::new(&mut self.volume, 0.0..=1.0).text("Volume"));
+        let mut test = None;
+        for (hook, label) in [
+            (Hook::Connected, "Connected"),
+            (Hook::Disconnected, "Disconnected"),
+            (Hook::UpdateFailed, "Update failed"),
+        ] {
+            ui.label(label);
+            ui.horizontal(|ui| {
+                let path = match hook {
+                    Hook::Connected => &mut self.connected,
+                    Hook::Disconnected => &mut self.disconnected,
+                    _ => &mut self.failed,
+                };
+                ui.text_edit_singleline(path)
+                    .on_hover_text("Leave empty for the bundled sound");
+                if ui.button("…").clicked() {
+                    let file = FileDialog::new()
+                        .add_filter("Sound", &["wav", "ogg", "mp3", "flac"])
+                        .pick_file();
+                    if let Some(file) = file {
+                        *path = file.display().to_string();
+                    }
+                }
+                if ui.button("▶").on_hover_text("Play").clicked() {
+                    test = Some(hook);
+                }
+            });
+        }
+        if let Some(hook) = test {
+            self.sound(hook);
+        }
+    }
+
+    /// Plays the hook's cue in the background, when it has one. Failures
+    /// show up in `errors`.
+    pub fn play(&self, hook: Hook) {
+        if self.enabled {
+            self.sound(hook);
+        }
+    }
+
+    /// Failures since the last call, to show in the error bar.
+    pub fn errors(&self) -> Vec<String> {
+        match self.failures.lock() {
+            Ok(mut failures) => failures.drain(..).collect(),
+            Err(_) => Vec::new(),
+        }
+    }
+
+    /// Plays the hook's sound, hooks without a cue are ignored.
+    fn sound(&self, hook: Hook) {
+        let (path, bundled) = match hook {
+            Hook::Connected => (&self.connected, CONNECTED),
+            Hook::Disconnected => (&self.disconnected, DISCONNECTED),
+            Hook::UpdateFailed => (&self.failed, FAILED),
+            _ => return,
+        };
+        let path = path.trim().to_string();
+        let volume = self.volume;
+        let failures = self.failures.clone();
+        thread::spawn(move || {
+            if let Err(error) = play(&path, bundled, volume) {
+                if let Ok(mut failures) = failures.lock() {
+                    failures.push(error);
+                }
+            }
+        });
+    }
+}
+
+/// Reads, decodes and plays the sound, the bundled one when the path is
+/// empty. Runs on its own thread, the output device is only held while
+/// it plays.
+fn play(path: &str, bundled: &'static [u8], volume: f32) -> Result<(), String> {
+    let sound = match path {
+        "" => bundled.to_vec(),
+        path => fs::read(path).map_err(|err| format!("Failed to read the sound {path}: {err}"))?,
+    };
+    let name = match path {
+        "" => "the bundled sound",
+        path => path,
+    };
+    let source = rodio::Decoder::new(Cursor::new(sound))
+        .map_err(|err| format!("Failed to decode {name}: {err}"))?;
+    let (_stream, output) = rodio::OutputStream::try_default()
+        .map_err(|err| format!("No sound output for {name}: {err}"))?;
+    let sink =
+        rodio::Sink::try_new(&output).map_err(|err| format!("Failed to play {name}: {err}"))?;
+    sink.set_volume(volume);
+    sink.append(source);
+    sink.sleep_until_end();
+    Ok(())
+}
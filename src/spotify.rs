@@ -0,0 +1,354 @@
+use crate::cli::write_private;
+use crate::integrations::{NowPlaying, Source};
+use crate::remote::random_hex;
+use crate::storage::data_dir;
+use chrono::Utc;
+use eframe::egui::{self, RichText, Ui};
+use eframe::epaint::Color32;
+use serde::{Deserialize, Serialize};
+use serde_json::Value;
+use sha2::{Digest, Sha256};
+use std::fs;
+use std::io::{BufRead, BufReader, Write};
+use std::net::TcpListener;
+use std::path::PathBuf;
+use std::sync::mpsc::{channel, Receiver};
+use std::thread;
+use std::time::Duration;
+
+/// Address Spotify sends the browser back to after logging in, it has to be
+/// added to the Spotify app's redirect URIs.
+pub const REDIRECT: &str = "http://127.0.0.1:8898/callback";
+const LISTEN: &str = "127.0.0.1:8898";
+const SCOPES: &str = "user-read-currently-playing user-read-playback-state";
+const TOKEN_URL: &str = "https://accounts.spotify.com/api/token";
+/// Seconds the login waits for the browser.
+const LOGIN_TIMEOUT: i64 = 5 * 60;
+
+/// A logged in Spotify account, kept in `spotify.json` only the user can read.
+#[derive(Serialize, Deserialize, Clone)]
+pub struct Account {
+    pub client_id: String,
+    pub refresh_token: String,
+}
+
+impl Account {
+    pub fn load() -> Option<Self> {
+        let file = fs::read_to_string(account_path()?).ok()?;
+        serde_json::from_str(&file).ok()
+    }
+
+    fn save(&self) {
+        if let (Some(path), Ok(file)) = (account_path(), serde_json::to_string(self)) {
+            let _ = write_private(&path, &file);
+        }
+    }
+}
+
+fn account_path() -> Option<PathBuf> {
+    data_dir().map(|dir| dir.join("spotify.json"))
+}
+
+/// Reads the current track from the Spotify Web API, for any device the
+/// account plays on.
+pub struct Spotify {
+    account: Account,
+    /// Access token and when it expires, as unix time.
+    access: Option<(String, i64)>,
+}
+
+impl Spotify {
+    pub fn new(account: Account) -> Self {
+        Self {
+            account,
+            access: None,
+        }
+    }
+
+    fn access_token(&mut self) -> Result<String, String> {
+        if let Some((token, expires)) = &self.access {
+            if *expires > Utc::now().timestamp() {
+                return Ok(token.clone());
+            }
+        }
+        let answer = token(&[
+            ("grant_type", "refresh_token"),
+            ("refresh_token", &self.account.refresh_token),
+            ("client_id", &self.account.client_id),
+        ])?;
+        // refresh tokens are replaced on every use, the new one has to be kept
+        if let Some(refresh_token) = answer["refresh_token"].as_str() {
+            self.account.refresh_token = refresh_token.to_string();
+            self.account.save();
+        }
+        let token = answer["access_token"]
+            .as_str()
+            .unwrap_or_default()
+            .to_string();
+        let expires = Utc::now().timestamp() + answer["expires_in"].as_i64().unwrap_or(3600) - 60;
+        self.access = Some((token.clone(), expires));
+        Ok(token)
+    }
+}
+
+impl Source for Spotify {
+    fn name(&self) -> &'static str {
+        "Spotify"
+    }
+
+    fn now_playing(&mut self) -> Result<Option<NowPlaying>, String> {
+        let token = self.access_token()?;
+        let response = match ureq::get("https://api.spotify.com/v1/me/player/currently-playing")
+            .set("Authorization", &format!("Bearer {token}"))
+            .timeout(Duration::from_secs(5))
+            .call()
+        {
+            Ok(response) => response,
+            Err(ureq::Error::Status(401, _)) => {
+                self.access = None;
+                return Err("Login expired".to_string());
+            }
+            Err(ureq::Error::Status(code, _)) => return Err(format!("Spotify answered {code}")),
+            Err(_) => return Err("Failed to reach Spotify".to_string()),
+        };
+        // nothing is playing on any device
+        if response.status() == 204 {
+            return Ok(None);
+        }
+        let playing: Value = response
+            .into_string()
+            .ok()
+            .and_then(|body| serde_json::from_str(&body).ok())
+            .ok_or_else(|| "Invalid answer from Spotify".to_string())?;
+        let item = &playing["item"];
+        // ads and podcasts have no track
+        if playing["is_playing"].as_bool() != Some(true) || item["name"].as_str().is_none() {
+            return Ok(None);
+        }
+        let text = |value: &Value| value.as_str().unwrap_or_default().to_string();
+        let artists: Vec<&str> = item["artists"]
+            .as_array()
+            .map(|artists| {
+                artists
+                    .iter()
+                    .filter_map(|artist| artist["name"].as_str())
+                    .collect()
+            })
+            .unwrap_or_default();
+        Ok(Some(NowPlaying {
+            track: text(&item["name"]),
+            artist: artists.join(", "),
+            album: text(&item["album"]["name"]),
+            // the largest cover comes first
+            art_url: text(&item["album"]["images"][0]["url"]),
+        }))
+    }
+}
+
+/// Spotify login with PKCE, so no client secret is needed. The browser is
+/// sent back to a port on this machine with the code.
+#[derive(Default)]
+pub struct SpotifyLogin {
+    /// Client ID of the user's app on the Spotify developer dashboard.
+    pub client_id: String,
+    pub account: Option<Account>,
+    /// Set when the account changed and the sources need replacing.
+    pub changed: bool,
+    waiting: Option<Receiver<Result<Account, String>>>,
+    error: Option<String>,
+}
+
+impl SpotifyLogin {
+    pub fn new(account: Option<Account>) -> Self {
+        Self {
+            client_id: account
+                .as_ref()
+                .map(|account| account.client_id.clone())
+                .unwrap_or_default(),
+            account,
+            ..Default::default()
+        }
+    }
+
+    pub fn run(&mut self, ui: &mut Ui) {
+        if self.account.is_some() {
+            ui.label("Logged in, {track}, {artist}, {album} and {album_art} show what's playing");
+            if ui.button("Log out").clicked() {
+                if let Some(path) = account_path() {
+                    let _ = fs::remove_file(path);
+                }
+                self.account = None;
+                self.changed = true;
+            }
+        } else {
+            ui.label("Client ID of an app on developer.spotify.com");
+            ui.text_edit_singleline(&mut self.client_id);
+            ui.label(
+                RichText::new(format!("The app needs {REDIRECT} as a redirect URI"))
+                    .small()
+                    .color(Color32::from_rgb(163, 163, 163)),
+            );
+            match self.waiting {
+                Some(_) => {
+                    ui.horizontal(|ui| {
+                        ui.spinner();
+                        ui.label("Waiting for the browser…");
+                    });
+                    ui.ctx().request_repaint_after(Duration::from_millis(500));
+                }
+                None => {
+                    if ui
+                        .add_enabled(
+                            !self.client_id.trim().is_empty(),
+                            egui::Button::new("Log in"),
+                        )
+                        .clicked()
+                    {
+                        match self.start() {
+                            Ok(url) => ui.output().open_url(url),
+                            Err(error) => self.error = Some(error),
+                        }
+                    }
+                }
+            }
+        }
+        let error = self.error.clone().or_else(crate::integrations::error);
+        if let Some(error) = error {
+            ui.label(RichText::new(error).small().color(Color32::LIGHT_RED));
+        }
+    }
+
+    /// Starts waiting for the browser, returns the page to open.
+    fn start(&mut self) -> Result<String, String> {
+        let listener = TcpListener::bind(LISTEN)
+            .map_err(|err| format!("Failed to wait for the login on {LISTEN}: {err}"))?;
+        let client_id = self.client_id.trim().to_string();
+        let verifier = random_hex(32);
+        let challenge =
+            base64::encode_config(Sha256::digest(verifier.as_bytes()), base64::URL_SAFE_NO_PAD);
+        let state = random_hex(16);
+        let url = format!(
+            "https://accounts.spotify.com/authorize?response_type=code&client_id={client_id}\
+             &scope={}&redirect_uri={}&state={state}&code_challenge_method=S256\
+             &code_challenge={challenge}",
+            SCOPES.replace(' ', "%20"),
+            REDIRECT.replace(':', "%3A").replace('/', "%2F"),
+        );
+        let (sender, receiver) = channel();
+        thread::spawn(move || {
+            let _ = sender.send(finish(listener, client_id, &verifier, &state));
+        });
+        self.waiting = Some(receiver);
+        self.error = None;
+        Ok(url)
+    }
+
+    /// Keeps the account once the browser came back, runs every frame since
+    /// the menu may be closed by then.
+    pub fn poll(&mut self) {
+        let result = match self.waiting.as_ref().map(Receiver::try_recv) {
+            Some(Ok(result)) => result,
+            _ => return,
+        };
+        self.waiting = None;
+        match result {
+            Ok(account) => {
+                account.save();
+                self.account = Some(account);
+                self.changed = true;
+            }
+            Err(error) => self.error = Some(error),
+        }
+    }
+}
+
+/// Waits for the browser to come back with the code and trades it for tokens.
+fn finish(
+    listener: TcpListener,
+    client_id: String,
+    verifier: &str,
+    state: &str,
+) -> Result<Account, String> {
+    let failed = |err: std::io::Error| format!("Login failed: {err}");
+    listener.set_nonblocking(true).map_err(failed)?;
+    let deadline = Utc::now().timestamp() + LOGIN_TIMEOUT;
+    let stream = loop {
+        match listener.accept() {
+            Ok((stream, _)) => break stream,
+            Err(_) if Utc::now().timestamp() < deadline => {
+                thread::sleep(Duration::from_millis(200))
+            }
+            Err(_) => return Err("Login timed out".to_string()),
+        }
+    };
+    stream.set_nonblocking(false).map_err(failed)?;
+    stream
+        .set_read_timeout(Some(Duration::from_secs(5)))
+        .map_err(failed)?;
+    let mut request_line = String::new();
+    BufReader::new(&stream)
+        .read_line(&mut request_line)
+        .map_err(failed)?;
+    // GET /callback?code=…&state=… HTTP/1.1
+    let query = request_line
+        .split_whitespace()
+        .nth(1)
+        .and_then(|target| target.split_once('?'))
+        .map(|(_, query)| query)
+        .unwrap_or_default();
+    let param = |name: &str| {
+        query
+            .split('&')
+            .find_map(|pair| pair.strip_prefix(&format!("{name}=")))
+            .unwrap_or_default()
+    };
+    let result = match (param("code"), param("error")) {
+        (_, error) if !error.is_empty() => Err(format!("Spotify refused the login: {error}")),
+        (_, _) if param("state") != state => {
+            Err("Login failed, the answer wasn't ours".to_string())
+        }
+        ("", _) => Err("Login failed, Spotify sent no code".to_string()),
+        (code, _) => token(&[
+            ("grant_type", "authorization_code"),
+            ("code", code),
+            ("redirect_uri", REDIRECT),
+            ("client_id", &client_id),
+            ("code_verifier", verifier),
+        ])
+        .and_then(|answer| match answer["refresh_token"].as_str() {
+            Some(refresh_token) => Ok(Account {
+                client_id,
+                refresh_token: refresh_token.to_string(),
+            }),
+            None => Err("Spotify sent no refresh token".to_string()),
+        }),
+    };
+    let page = match &result {
+        Ok(_) => "Logged in to Spotify, this tab can be closed.".to_string(),
+        Err(error) => error.clone(),
+    };
+    let _ = write!(
+        &stream,
+        "HTTP/1.1 200 OK\r\nContent-Type: text/plain; charset=utf-8\r\nContent-Length: {}\r\nConnection: close\r\n\r\n{page}",
+        page.len()
+    );
+    result
+}
+
+fn token(form: &[(&str, &str)]) -> Result<Value, String> {
+    let response = match ureq::post(TOKEN_URL)
+        .timeout(Duration::from_secs(10))
+        .send_form(form)
+    {
+        Ok(response) => response,
+        Err(ureq::Error::Status(400, _)) => {
+            return Err("Spotify refused the login, log in again".to_string())
+        }
+        Err(_) => return Err("Failed to reach Spotify".to_string()),
+    };
+    response
+        .into_string()
+        .ok()
+        .and_then(|body| serde_json::from_str(&body).ok())
+        .ok_or_else(|| "Invalid answer from Spotify".to_string())
+}
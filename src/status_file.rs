@@ -0,0 +1,107 @@
+use eframe::egui::{RichText, Ui};
+use eframe::epaint::Color32;
+use rfd::FileDialog;
+use serde_json::{json, Value};
+use std::fs;
+use std::path::Path;
+
+/// Keeps a file with what Discord shows, for OBS text sources and other
+/// tools. A `.json` path gets the fields and the whole activity, any other
+/// path the details and state as two lines.
+#[derive(Default)]
+pub struct StatusFile {
+    /// Empty when off.
+    pub path: String,
+    /// The path being typed, used once the field is left.
+    input: String,
+    /// Why the typed path wasn't used.
+    problem: Option<String>,
+    /// What was written last, `Some(None)` when the presence was cleared.
+    written: Option<Option<Value>>,
+}
+
+impl StatusFile {
+    pub fn new(path: String) -> Self {
+        let mut file = Self {
+            input: path.clone(),
+            ..Default::default()
+        };
+        file.apply(path);
+        file
+    }
+
+    /// Uses the path for the file, only absolute ones since the app may be
+    /// started from anywhere.
+    fn apply(&mut self, path: String) {
+        let path = path.trim().to_string();
+        if !path.is_empty() && !Path::new(&path).is_absolute() {
+            self.problem = Some("Pick a full path, like the … button does".to_string());
+            return;
+        }
+        self.problem = None;
+        if path != self.path {
+            self.path = path;
+            self.written = None;
+        }
+    }
+
+    pub fn run(&mut self, ui: &mut Ui) {
+        ui.label("Write the presence to");
+        ui.horizontal(|ui| {
+            if ui
+                .text_edit_singleline(&mut self.input)
+                .on_hover_text("Leave empty to turn off, .json files get every field")
+                .lost_focus()
+            {
+                self.apply(self.input.clone());
+            }
+            if ui.button("…").clicked() {
+                let file = FileDialog::new()
+                    .add_filter("Text", &["txt"])
+                    .add_filter("JSON", &["json"])
+                    .set_file_name("presence.txt")
+                    .save_file();
+                if let Some(file) = file {
+                    self.input = file.display().to_string();
+                    self.apply(self.input.clone());
+                }
+                ui.close_menu();
+            }
+        });
+        if let Some(problem) = &self.problem {
+            ui.label(RichText::new(problem).small().color(Color32::LIGHT_RED));
+        }
+    }
+
+    /// Writes the file when what Discord shows changed, `None` when it shows nothing.
+    pub fn sync(&mut self, shown: Option<&Value>) -> Result<(), String> {
+        if self.path.is_empty() || self.written.as_ref() == Some(&shown.cloned()) {
+            return Ok(());
+        }
+        self.written = Some(shown.cloned());
+        let path = Path::new(&self.path);
+        let text = |activity: Option<&Value>, key: &str| {
+            activity
+                .and_then(|activity| activity[key].as_str())
+                .unwrap_or_default()
+                .to_string()
+        };
+        let content = match path.extension().and_then(|extension| extension.to_str()) {
+            Some("json") => {
+                let status = json!({
+                    "shown": shown.is_some(),
+                    "details": text(shown, "details"),
+                    "state": text(shown, "state"),
+                    "activity": shown,
+                });
+                serde_json::to_string_pretty(&status).unwrap_or_default()
+            }
+            _ => format!("{}\n{}\n", text(shown, "details"), text(shown, "state")),
+        };
+        // written next to it and renamed, so readers never see half a file
+        let temporary = path.with_extension("tmp");
+        fs::write(&temporary, content)
+            .and_then(|_| fs::rename(&temporary, path))
+            .map_err(|error| format!("Failed to write the status file: {error}"))
+    }
+}
@@ -1,28 +1,278 @@
 use serde::{Deserialize, Serialize};
 
-use crate::{preset::InAppPreset, timestamp::TimestampEnum};
+use crate::{
+    animation::{Style, MIN_INTERVAL},
+    fonts::DEFAULT_FONT_SIZE,
+    goal::Goal,
+    layouts::UiLayout,
+    obs::ObsSettings,
+    pomodoro::PomodoroSettings,
+    preset::{InAppPreset, Preset},
+    process_rules::ProcessRule,
+    profiles::Profile,
+    remote::Device,
+    rules::Pattern,
+    rundown::Step,
+    scheduler::Slot,
+    session::Session,
+    slots,
+    sounds::Sounds,
+    time_tracking::Service,
+    timestamp::TimestampEnum,
+    twitch::TwitchSettings,
+    App,
+};
 use derivative::Derivative;
+use directories_next::ProjectDirs;
+use std::collections::HashMap;
+use std::fs;
+use std::path::PathBuf;
+
+/// Settings persisted between sessions.
+///
+/// Every field falls back to its default when missing, so saves written by
+/// older versions keep loading after new settings are added.
 #[derive(Serialize, Deserialize, Derivative)]
 #[derivative(Default)]
-pub struct Storage<'a> {
-    pub id: &'a str,
-    pub details: &'a str,
-    pub state: &'a str,
+#[serde(default)]
+pub struct Storage {
+    pub id: String,
+    pub id_locked: bool,
+    /// Named application IDs, `None` in saves from before them that only have `id`.
+    pub profiles: Option<Vec<Profile>>,
+    pub pipe: String,
+    /// Kind of Discord client to connect to, empty for any.
+    pub pipe_flavor: String,
+    pub details: String,
+    pub state: String,
+    /// Color tag of the preset the form came from.
+    pub color: String,
+    /// Details and states sent before, most recent first.
+    pub details_history: Vec<String>,
+    pub state_history: Vec<String>,
     pub party: u8,
     pub party_of: u8,
+    pub party_id: String,
+    pub join_secret: String,
+    pub match_secret: String,
     pub timestamp: TimestampEnum,
-    pub large_image_key: &'a str,
-    pub small_image_key: &'a str,
-    pub large_image_label: &'a str,
-    pub small_image_label: &'a str,
-    pub first_btn_label: &'a str,
-    pub second_btn_label: &'a str,
-    pub first_btn_url: &'a str,
-    pub second_btn_url: &'a str,
+    /// Timezone name for the local time timestamp, the system's when empty.
+    pub timezone: String,
+    /// Start of the custom timestamp as unix time.
+    pub timestamp_start: Option<i64>,
+    /// Start a preset set relative to when it was loaded, as unix time.
+    pub timestamp_relative_start: Option<i64>,
+    /// End of the countdown as unix time.
+    pub timestamp_end: Option<i64>,
+    pub large_image_key: String,
+    pub small_image_key: String,
+    pub large_image_label: String,
+    pub large_image_label_2: String,
+    pub small_image_label: String,
+    pub first_btn_label: String,
+    pub second_btn_label: String,
+    pub first_btn_url: String,
+    pub second_btn_url: String,
     pub autoconnect: bool,
+    pub automation: bool,
+    pub mirror: bool,
+    pub webhook_url: String,
+    pub rundown: Vec<Step>,
+    pub rundown_auto_advance: bool,
+    pub rotation: Vec<String>,
+    #[derivative(Default(value = "5"))]
+    pub rotation_minutes: u32,
+    pub scheduler: bool,
+    pub schedule: Vec<Slot>,
+    pub process_rules: Vec<ProcessRule>,
+    pub slot_a: Option<Preset>,
+    pub slot_b: Option<Preset>,
+    /// The A/B slot loaded last.
+    pub slot: slots::Slot,
+    pub goal: Goal,
+    pub clock_correct: bool,
+    pub clock_round: bool,
+    pub error_reporting: bool,
+    pub error_report_endpoint: String,
+    #[derivative(Default(value = "true"))]
+    pub redact_client_ids: bool,
+    #[derivative(Default(value = "true"))]
+    pub redact_urls: bool,
+    pub remote_devices: Vec<Device>,
     #[derivative(Default(value = "true"))]
     pub darkmode: bool,
     pub preset_switch_1: Option<InAppPreset>,
     pub preset_switch_2: Option<InAppPreset>,
     pub preset_switch_time: u8,
+    pub animation_style: Style,
+    pub animation_text: String,
+    #[derivative(Default(value = "MIN_INTERVAL"))]
+    pub animation_interval: u8,
+    pub custom_status: bool,
+    pub custom_status_emoji: String,
+    pub custom_status_text: String,
+    pub font_path: String,
+    #[derivative(Default(value = "DEFAULT_FONT_SIZE"))]
+    pub font_size: f32,
+    /// Banned words, one per line.
+    pub banned_words: String,
+    pub patterns: Vec<Pattern>,
+    pub idle: bool,
+    #[derivative(Default(value = "10"))]
+    pub idle_minutes: u32,
+    pub afk_preset: String,
+    /// File the shown presence is written to, empty when off.
+    pub status_file: String,
+    #[derivative(Default(value = "true"))]
+    pub guard: bool,
+    pub flagged_words: String,
+    #[derivative(Default(value = "true"))]
+    pub warn_on_close: bool,
+    /// Accept commands from the command line and the control API.
+    #[derivative(Default(value = "true"))]
+    pub control: bool,
+    #[derivative(Default(value = "true"))]
+    pub local_media: bool,
+    /// Shell command by hook name.
+    pub hooks: HashMap<String, String>,
+    pub sounds: Sounds,
+    pub twitch: TwitchSettings,
+    pub pomodoro: PomodoroSettings,
+    pub obs: ObsSettings,
+    pub time_tracking: bool,
+    pub time_tracking_service: Service,
+    /// Kept with the other secrets, only read from older settings.
+    #[serde(skip_serializing)]
+    pub time_tracking_api_key: String,
+    pub time_tracking_workspace: String,
+    #[derivative(Default(value = "true"))]
+    pub window_decorations: bool,
+    /// Pixels per point, 0 follows the system.
+    pub window_scale: f32,
+    pub layout: UiLayout,
+    /// What was sent when the app was last closed.
+    pub session: Option<Session>,
+    pub locked: bool,
+    pub passphrase_hash: String,
+}
+
+impl Storage {
+    pub fn from_app(app: &App) -> Self {
+        Self {
+            id: app.id.clone(),
+            id_locked: app.id_locked,
+            profiles: Some(app.profiles.profiles.clone()),
+            pipe: app.accounts.pipe.clone(),
+            pipe_flavor: app.accounts.flavor.clone(),
+            details: app.details.clone(),
+            color: app.color.clone(),
+            state: app.state.clone(),
+            details_history: app.history.details.clone(),
+            state_history: app.history.state.clone(),
+            party: app.party,
+            party_of: app.party_of,
+            party_id: app.party_secrets.id.clone(),
+            join_secret: app.party_secrets.join.clone(),
+            match_secret: app.party_secrets.match_secret.clone(),
+            timestamp: app.timestamp.timestamp,
+            timezone: app.timestamp.timezone.clone(),
+            timestamp_start: Some(app.timestamp.start.timestamp()),
+            timestamp_relative_start: app.timestamp.relative_start.map(|start| start.timestamp()),
+            timestamp_end: app.timestamp.end.map(|end| end.timestamp()),
+            large_image_key: app.first_img.key.clone(),
+            small_image_key: app.second_img.key.clone(),
+            large_image_label: app.first_img.text.clone(),
+            large_image_label_2: app.first_img.second_line.clone().unwrap_or_default(),
+            small_image_label: app.second_img.text.clone(),
+            first_btn_label: app.first_btn.label.clone(),
+            second_btn_label: app.second_btn.label.clone(),
+            first_btn_url: app.first_btn.url.clone(),
+            second_btn_url: app.second_btn.url.clone(),
+            autoconnect: app.menu_bar.autoconnect,
+            automation: app.menu_bar.automation,
+            mirror: app.menu_bar.mirror,
+            webhook_url: app.menu_bar.webhook_url.clone(),
+            rundown: app.menu_bar.rundown.steps.clone(),
+            rundown_auto_advance: app.menu_bar.rundown.auto_advance,
+            rotation: app.menu_bar.rotation.presets.clone(),
+            rotation_minutes: app.menu_bar.rotation.minutes,
+            scheduler: app.menu_bar.scheduler.enabled,
+            schedule: app.menu_bar.scheduler.slots.clone(),
+            process_rules: app.menu_bar.process_rules.rules.clone(),
+            slot_a: app.slots.a.clone(),
+            slot_b: app.slots.b.clone(),
+            slot: app.slots.current,
+            goal: app.menu_bar.goal.clone(),
+            clock_correct: app.menu_bar.clock.correct,
+            clock_round: app.menu_bar.clock.round,
+            error_reporting: app.menu_bar.error_reporting.enabled,
+            error_report_endpoint: app.menu_bar.error_reporting.endpoint.clone(),
+            redact_client_ids: app.menu_bar.redaction.client_ids,
+            redact_urls: app.menu_bar.redaction.urls,
+            remote_devices: app.remote.devices(),
+            darkmode: app.menu_bar.darkmode,
+            preset_switch_1: app.menu_bar.preset_switch_1.clone(),
+            preset_switch_2: app.menu_bar.preset_switch_2.clone(),
+            preset_switch_time: app.menu_bar.preset_switch_time,
+            animation_style: app.menu_bar.rotation.animation.style,
+            animation_text: app.menu_bar.rotation.animation.text.clone(),
+            animation_interval: app.menu_bar.rotation.animation.interval,
+            custom_status: app.menu_bar.custom_status.enabled,
+            custom_status_emoji: app.menu_bar.custom_status.emoji.clone(),
+            custom_status_text: app.menu_bar.custom_status.text.clone(),
+            font_path: app.menu_bar.font_path.clone(),
+            font_size: app.menu_bar.font_size,
+            banned_words: app.menu_bar.rules.banned_words.clone(),
+            patterns: app.menu_bar.rules.patterns.clone(),
+            idle: app.menu_bar.idle.enabled,
+            idle_minutes: app.menu_bar.idle.minutes,
+            afk_preset: app.menu_bar.idle.afk_preset.clone(),
+            status_file: app.menu_bar.status_file.path.clone(),
+            guard: app.menu_bar.guard.enabled,
+            flagged_words: app.menu_bar.guard.flagged_words.clone(),
+            warn_on_close: app.menu_bar.retention.warn_on_close,
+            control: app.menu_bar.control,
+            local_media: app.menu_bar.local_media,
+            hooks: app.menu_bar.hooks.commands.clone(),
+            sounds: app.menu_bar.sounds.clone(),
+            twitch: app.menu_bar.twitch.clone(),
+            pomodoro: app.menu_bar.pomodoro.settings.clone(),
+            obs: app.menu_bar.obs.settings.clone(),
+            time_tracking: app.menu_bar.time_tracking.enabled,
+            time_tracking_service: app.menu_bar.time_tracking.service,
+            time_tracking_api_key: app.menu_bar.time_tracking.api_key.clone(),
+            time_tracking_workspace: app.menu_bar.time_tracking.workspace.clone(),
+            window_decorations: app.menu_bar.window.decorations,
+            window_scale: app.menu_bar.window.scale,
+            layout: app.menu_bar.layout,
+            // nothing sent this time keeps the session from before for the next launch
+            session: match &app.last_sent {
+                Some(preset) => Some(Session {
+                    preset: preset.clone(),
+                    connected: app.connected,
+                }),
+                None => app.last_session.session.clone(),
+            },
+            locked: app.menu_bar.display_mode.locked,
+            passphrase_hash: app.menu_bar.display_mode.passphrase_hash.clone(),
+        }
+    }
+}
+
+/// Directory eframe keeps its storage in, also used for our own files.
+pub fn data_dir() -> Option<PathBuf> {
+    ProjectDirs::from("", "", "Discord Presence").map(|dirs| dirs.data_dir().to_path_buf())
+}
+
+/// The key-value store eframe keeps the settings in, empty when missing.
+/// Only for running without a window, the app gets it from eframe.
+pub fn saved() -> HashMap<String, String> {
+    let path = match data_dir() {
+        Some(dir) => dir.join("app.ron"),
+        None => return HashMap::new(),
+    };
+    match fs::read_to_string(path) {
+        Ok(file) => ron::from_str(&file).unwrap_or_default(),
+        Err(_) => HashMap::new(),
+    }
 }
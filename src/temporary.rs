@@ -0,0 +1,72 @@
+use chrono::{DateTime, Duration, Local, NaiveTime, TimeZone, Utc};
+use derivative::Derivative;
+use eframe::egui::{DragValue, TextEdit, Ui};
+
+#[derive(PartialEq, Eq, Clone, Copy, Default)]
+pub enum Revert {
+    #[default]
+    Never,
+    After,
+    At,
+}
+
+/// Makes presets picked from the Presets menu temporary, the presence from
+/// before comes back after a while or at a time of day.
+#[derive(Derivative)]
+#[derivative(Default)]
+pub struct Temporary {
+    pub revert: Revert,
+    #[derivative(Default(value = "45"))]
+    pub minutes: u32,
+    /// Local time of day as `HH:MM`.
+    #[derivative(Default(value = "\"18:00\".to_string()"))]
+    pub at: String,
+}
+
+impl Temporary {
+    pub fn run(&mut self, ui: &mut Ui) {
+        ui.horizontal(|ui| {
+            ui.radio_value(&mut self.revert, Revert::Never, "Keep");
+            ui.radio_value(&mut self.revert, Revert::After, "Revert after");
+            ui.add_enabled(
+                self.revert == Revert::After,
+                DragValue::new(&mut self.minutes)
+                    .clamp_range(1..=1440)
+                    .suffix("min"),
+            );
+        });
+        ui.horizontal(|ui| {
+            ui.radio_value(&mut self.revert, Revert::At, "Revert at");
+            ui.add_enabled(
+                self.revert == Revert::At,
+                TextEdit::singleline(&mut self.at)
+                    .hint_text("18:00")
+                    .desired_width(50.),
+            );
+        });
+    }
+
+    /// When a preset applied now goes back, `None` when it stays.
+    pub fn until(&self) -> Result<Option<DateTime<Utc>>, String> {
+        match self.revert {
+            Revert::Never => Ok(None),
+            Revert::After => Ok(Some(Utc::now() + Duration::minutes(self.minutes as i64))),
+            Revert::At => {
+                let time = match NaiveTime::parse_from_str(self.at.trim(), "%H:%M") {
+                    Ok(time) => time,
+                    Err(_) => return Err(format!("Invalid revert time {}, use HH:MM", self.at)),
+                };
+                let today = Local::now().date_naive().and_time(time);
+                let at = match Local.from_local_datetime(&today).earliest() {
+                    Some(at) => at.with_timezone(&Utc),
+                    None => return Err(format!("{} doesn't exist today", self.at)),
+                };
+                // a time that already passed means tomorrow
+                match at > Utc::now() {
+                    true => Ok(Some(at)),
+                    false => Ok(Some(at + Duration::days(1))),
+                }
+            }
+        }
+    }
+}
@@ -0,0 +1,60 @@
+use chrono::{DateTime, Duration, Utc};
+use eframe::egui::{RichText, Ui};
+use eframe::epaint::Color32;
+
+/// Discord accepts 5 activity updates per 20 seconds, so updates are spread
+/// at least this far apart.
+const MIN_INTERVAL: i64 = 4;
+
+/// Coalesces presets applied in quick succession into one presence update
+/// per interval, keeping track of the ones that never got sent.
+#[derive(Default)]
+pub struct Throttle {
+    last_sent: Option<DateTime<Utc>>,
+    pending: Option<String>,
+    pub skipped: Vec<String>,
+}
+
+impl Throttle {
+    /// Queues an update for the preset `name`, replacing one still waiting.
+    pub fn queue(&mut self, name: String) {
+        match self.pending.replace(name) {
+            Some(previous) => self.skipped.push(previous),
+            None => self.skipped.clear(),
+        }
+    }
+
+    /// Records an update that was sent, which also covers any queued one.
+    pub fn sent(&mut self) {
+        self.last_sent = Some(Utc::now());
+        self.pending = None;
+    }
+
+    /// Drops the queued update without counting it as sent.
+    pub fn cancel(&mut self) {
+        self.pending = None;
+    }
+
+    /// Time left until the queued update may be sent, `None` if nothing is queued.
+    pub fn wait(&self) -> Option<Duration> {
+        self.pending.as_ref()?;
+        let wait = match self.last_sent {
+            Some(last_sent) => last_sent + Duration::seconds(MIN_INTERVAL) - Utc::now(),
+            None => Duration::zero(),
+        };
+        Some(wait.max(Duration::zero()))
+    }
+
+    pub fn run(&mut self, ui: &mut Ui) {
+        if let Some(wait) = self.wait() {
+            ui.label(format!("Updating in {}s", wait.num_seconds() + 1));
+        }
+        if !self.skipped.is_empty() {
+            ui.label(
+                RichText::new(format!("Skipped: {}", self.skipped.join(", ")))
+                    .small()
+                    .color(Color32::GRAY),
+            );
+        }
+    }
+}
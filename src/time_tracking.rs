@@ -0,0 +1,235 @@
+use crate::secrets;
+use chrono::{DateTime, SecondsFormat, Utc};
+use eframe::egui::{self, TextEdit, Ui};
+use serde::{Deserialize, Serialize};
+use serde_json::{json, Value};
+use std::sync::mpsc::{channel, Receiver, Sender};
+use std::thread;
+use std::time::Duration;
+
+/// Name of the API key in the secrets file.
+pub const SECRET: &str = "time_tracking";
+/// Sessions shorter than this, in seconds, aren't sent.
+const MIN_LENGTH: i64 = 60;
+
+#[derive(Serialize, Deserialize, PartialEq, Eq, Clone, Copy, Default)]
+pub enum Service {
+    #[default]
+    Toggl,
+    Clockify,
+}
+
+impl Service {
+    fn name(self) -> &'static str {
+        match self {
+            Service::Toggl => "Toggl Track",
+            Service::Clockify => "Clockify",
+        }
+    }
+}
+
+/// A presence shown without changes, from when it was sent.
+struct Session {
+    /// Details and state as typed, placeholders not expanded, so `{time}`
+    /// ticking doesn't start a new session.
+    key: String,
+    description: String,
+    start: DateTime<Utc>,
+}
+
+/// Sends finished sessions to a time tracker as time entries, for people
+/// whose status already says what they're working on.
+pub struct TimeTracking {
+    pub enabled: bool,
+    pub service: Service,
+    pub api_key: String,
+    pub workspace: String,
+    session: Option<Session>,
+    sender: Sender<String>,
+    errors: Receiver<String>,
+}
+
+impl Default for TimeTracking {
+    fn default() -> Self {
+        let (sender, errors) = channel();
+        Self {
+            enabled: false,
+            service: Service::default(),
+            api_key: String::new(),
+            workspace: String::new(),
+            session: None,
+            sender,
+            errors,
+        }
+    }
+}
+
+impl TimeTracking {
+    pub fn new(enabled: bool, service: Service, mut api_key: String, workspace: String) -> Self {
+        // settings from before the secrets file still have the key
+        if api_key.is_empty() {
+            api_key = secrets::get(SECRET);
+        }
+        Self {
+            enabled,
+            service,
+            api_key,
+            workspace,
+            ..Default::default()
+        }
+    }
+
+    pub fn run(&mut self, ui: &mut Ui) {
+        ui.checkbox(&mut self.enabled, "Send finished sessions");
+        egui::ComboBox::from_id_source("time_tracking_service")
+            .selected_text(self.service.name())
+            .show_ui(ui, |ui| {
+                for service in [Service::Toggl, Service::Clockify] {
+                    ui.selectable_value(&mut self.service, service, service.name());
+                }
+            });
+        ui.label("API key");
+        ui.add(TextEdit::singleline(&mut self.api_key).password(true));
+        ui.label("Workspace ID");
+        ui.text_edit_singleline(&mut self.workspace);
+        if let Some(session) = self.session.as_ref().filter(|_| self.enabled) {
+            ui.small(format!(
+                "Tracking {} since {}",
+                session.description,
+                session.start.with_timezone(&chrono::Local).format("%H:%M")
+            ));
+        }
+    }
+
+    /// Starts a session when the shown presence changes and sends the one
+    /// that ended. `shown` is the form's key and the activity sent.
+    pub fn sync(&mut self, shown: Option<(String, &Value)>) -> Result<(), String> {
+        let same = match (&self.session, &shown) {
+            (Some(session), Some((key, _))) => session.key == *key,
+            (None, None) => true,
+            _ => false,
+        };
+        if !same {
+            if let Some(entry) = self.finished() {
+                let sender = self.sender.clone();
+                thread::spawn(move || {
+                    if let Err(error) = entry.send() {
+                        let _ = sender.send(error);
+                    }
+                });
+            }
+            self.session = shown.map(|(key, activity)| Session {
+                key,
+                description: description(activity),
+                start: Utc::now(),
+            });
+        }
+        match self.errors.try_recv() {
+            Ok(error) => Err(error),
+            Err(_) => Ok(()),
+        }
+    }
+
+    /// Sends the running session before the app closes.
+    pub fn finish(&mut self) -> Result<(), String> {
+        match self.finished() {
+            Some(entry) => entry.send(),
+            None => Ok(()),
+        }
+    }
+
+    /// Ends the running session, returns it when it's worth sending.
+    fn finished(&mut self) -> Option<Entry> {
+        let session = self.session.take()?;
+        let end = Utc::now();
+        let configured = self.enabled && !self.api_key.trim().is_empty();
+        if !configured || (end - session.start).num_seconds() < MIN_LENGTH {
+            return None;
+        }
+        Some(Entry {
+            service: self.service,
+            api_key: self.api_key.trim().to_string(),
+            workspace: self.workspace.trim().to_string(),
+            description: session.description,
+            start: session.start,
+            end,
+        })
+    }
+}
+
+struct Entry {
+    service: Service,
+    api_key: String,
+    workspace: String,
+    description: String,
+    start: DateTime<Utc>,
+    end: DateTime<Utc>,
+}
+
+impl Entry {
+    fn send(&self) -> Result<(), String> {
+        let time = |time: DateTime<Utc>| time.to_rfc3339_opts(SecondsFormat::Secs, true);
+        let request = match self.service {
+            Service::Toggl => {
+                let workspace: i64 = self
+                    .workspace
+                    .parse()
+                    .map_err(|_| "The Toggl workspace ID is a number".to_string())?;
+                let body = json!({
+                    "description": self.description,
+                    "start": time(self.start),
+                    "stop": time(self.end),
+                    "duration": (self.end - self.start).num_seconds(),
+                    "workspace_id": workspace,
+                    "created_with": "discord_presence",
+                });
+                let auth = base64::encode(format!("{}:api_token", self.api_key));
+                ureq::post(&format!(
+                    "https://api.track.toggl.com/api/v9/workspaces/{workspace}/time_entries"
+                ))
+                .set("Authorization", &format!("Basic {auth}"))
+                .set("Content-Type", "application/json")
+                .timeout(Duration::from_secs(10))
+                .send_string(&body.to_string())
+            }
+            Service::Clockify => {
+                if self.workspace.is_empty() {
+                    return Err("The Clockify workspace ID is missing".to_string());
+                }
+                let body = json!({
+                    "description": self.description,
+                    "start": time(self.start),
+                    "end": time(self.end),
+                });
+                ureq::post(&format!(
+                    "https://api.clockify.me/api/v1/workspaces/{}/time-entries",
+                    self.workspace
+                ))
+                .set("X-Api-Key", &self.api_key)
+                .set("Content-Type", "application/json")
+                .timeout(Duration::from_secs(10))
+                .send_string(&body.to_string())
+            }
+        };
+        let service = self.service.name();
+        match request {
+            Ok(_) => Ok(()),
+            Err(ureq::Error::Status(401 | 403, _)) => Err(format!("{service} refused the API key")),
+            Err(ureq::Error::Status(code, _)) => {
+                Err(format!("{service} refused the time entry with {code}"))
+            }
+            Err(_) => Err(format!("Failed to reach {service}")),
+        }
+    }
+}
+
+/// What the time entry is called, the presence's lines like the webhook posts them.
+fn description(activity: &Value) -> String {
+    let details = activity["details"].as_str().unwrap_or_default();
+    let state = activity["state"].as_str().unwrap_or_default();
+    match (details, state) {
+        ("", "") => "Discord presence".to_string(),
+        (text, "") | ("", text) => text.to_string(),
+        (details, state) => format!("{details} — {state}"),
+    }
+}
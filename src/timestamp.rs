@@ -1,9 +1,11 @@
-use chrono::{Date, Utc};
+use chrono::{
+    DateTime, Datelike, Duration, Local, NaiveDate, NaiveDateTime, TimeZone, Timelike, Utc,
+};
+use chrono_tz::{Tz, TZ_VARIANTS};
 use eframe::{
-    egui::{Layout, Ui},
+    egui::{CollapsingHeader, ComboBox, DragValue, Grid, Layout, TextEdit, Ui},
     emath::Align,
 };
-// use egui_datepicker::DatePicker;
 use serde::{Deserialize, Serialize};
 
 //stfu rust analyzer this code compiles
@@ -31,14 +33,31 @@ impl TimestampEnum {
 
 pub struct Timestamp {
     pub timestamp: TimestampEnum,
-    pub date: Date<Utc>,
+    /// Timezone name like `Europe/Berlin` for the local time, the system's when empty.
+    pub timezone: String,
+    /// Start of a custom timestamp.
+    pub start: DateTime<Utc>,
+    /// Exact start moment set by a preset relative to when it was loaded,
+    /// takes precedence over `start` for custom timestamps.
+    pub relative_start: Option<DateTime<Utc>>,
+    /// End of the countdown Discord shows as "XX:XX left", set in the app or
+    /// by presets with an end time.
+    pub end: Option<DateTime<Utc>>,
+    /// Text of the unix time fields while they're edited.
+    pub start_epoch: String,
+    pub end_epoch: String,
 }
 
 impl Default for Timestamp {
     fn default() -> Self {
         Self {
             timestamp: TimestampEnum::default(),
-            date: Utc::now().date(),
+            timezone: String::new(),
+            start: Utc::now(),
+            relative_start: None,
+            end: None,
+            start_epoch: String::new(),
+            end_epoch: String::new(),
         }
     }
 }
@@ -67,15 +86,239 @@ impl Timestamp {
                 TimestampEnum::LocalTime,
                 "Your local time",
             );
+            if self.timestamp == TimestampEnum::LocalTime {
+                self.timezone_picker(ui);
+            }
+            ui.add_space(5.);
+            ui.radio_value(
+                &mut self.timestamp,
+                TimestampEnum::CustomTimeStamp,
+                "Custom timestamp",
+            );
+            if self.timestamp == TimestampEnum::CustomTimeStamp {
+                if let Some(start) = pick(ui, self.relative_start.unwrap_or(self.start)) {
+                    self.start = start;
+                    self.relative_start = None;
+                }
+            }
             ui.add_space(5.);
-            // ui.radio_value(
-            //     &mut self.timestamp,
-            //     TimestampEnum::CustomTimeStamp,
-            //     "Custom timestamp",
-            // );
+            self.end_picker(ui);
             ui.add_space(5.);
-            // ui.add(DatePicker::new("datepicker-unique-id", &mut self.date).movable(true));
+            ui.small(format!(
+                "Times are local, UTC{}",
+                Local::now().format("%:z")
+            ));
             ui.add_space(5.);
+            self.epoch_entry(ui);
         });
     }
+
+    /// Raw unix times for replicating an exact payload, seconds or milliseconds.
+    fn epoch_entry(&mut self, ui: &mut Ui) {
+        CollapsingHeader::new("Unix timestamps").show(ui, |ui| {
+            Grid::new("epoch").show(ui, |ui| {
+                ui.label("Start");
+                let start = match self.timestamp {
+                    TimestampEnum::CustomTimeStamp => {
+                        Some(self.relative_start.unwrap_or(self.start))
+                    }
+                    _ => None,
+                };
+                match epoch_field(ui, &mut self.start_epoch, start) {
+                    Some(Some(start)) => {
+                        self.timestamp = TimestampEnum::CustomTimeStamp;
+                        self.start = start;
+                        self.relative_start = None;
+                    }
+                    Some(None) if start.is_some() => self.timestamp = TimestampEnum::None,
+                    _ => (),
+                }
+                ui.end_row();
+                ui.label("End");
+                if let Some(end) = epoch_field(ui, &mut self.end_epoch, self.end) {
+                    self.end = end;
+                }
+                ui.end_row();
+            });
+        });
+    }
+
+    fn timezone_picker(&mut self, ui: &mut Ui) {
+        let system = format!("System (UTC{})", Local::now().format("%:z"));
+        let selected = match self.timezone.as_str() {
+            "" => system.clone(),
+            timezone => timezone.to_string(),
+        };
+        ComboBox::from_id_source("timezone")
+            .selected_text(selected)
+            .width(180.)
+            .show_ui(ui, |ui| {
+                ui.selectable_value(&mut self.timezone, String::new(), system);
+                for timezone in TZ_VARIANTS {
+                    ui.selectable_value(
+                        &mut self.timezone,
+                        timezone.name().to_string(),
+                        timezone.name(),
+                    );
+                }
+            });
+    }
+
+    /// Start of today in the chosen timezone, so the elapsed time Discord
+    /// shows reads as the time of day there.
+    pub fn day_start(&self) -> DateTime<Utc> {
+        match self.timezone.parse::<Tz>() {
+            Ok(timezone) => day_start(Utc::now().with_timezone(&timezone)),
+            Err(_) => day_start(Local::now()),
+        }
+    }
+
+    fn end_picker(&mut self, ui: &mut Ui) {
+        let mut ends = self.end.is_some();
+        if ui.checkbox(&mut ends, "Ends at").changed() {
+            self.end = match ends {
+                true => Some(Utc::now() + Duration::hours(1)),
+                false => None,
+            };
+        }
+        let end = match self.end {
+            Some(end) => end,
+            None => return,
+        };
+        if let Some(end) = pick(ui, end) {
+            self.end = Some(end);
+        }
+        match self.end.map(|end| end - Utc::now()) {
+            Some(left) if left > Duration::zero() => ui.label(format!(
+                "{}:{:02} left",
+                left.num_hours(),
+                left.num_minutes() % 60
+            )),
+            _ => ui.label("Already over"),
+        };
+    }
+}
+
+/// Date and time of day in local time, the new moment once it was changed.
+fn pick(ui: &mut Ui, time: DateTime<Utc>) -> Option<DateTime<Utc>> {
+    let time = time.with_timezone(&Local);
+    let (mut year, mut month, mut day) = (time.year(), time.month(), time.day());
+    let (mut hour, mut minute, mut second) = (time.hour(), time.minute(), time.second());
+    let mut changed = false;
+    ui.horizontal(|ui| {
+        changed |= ui
+            .add(DragValue::new(&mut year).clamp_range(1970..=9999))
+            .changed();
+        changed |= ui
+            .add(DragValue::new(&mut month).clamp_range(1..=12).prefix("-"))
+            .changed();
+        changed |= ui
+            .add(DragValue::new(&mut day).clamp_range(1..=31).prefix("-"))
+            .changed();
+    });
+    ui.horizontal(|ui| {
+        changed |= ui
+            .add(DragValue::new(&mut hour).clamp_range(0..=23))
+            .changed();
+        changed |= ui
+            .add(DragValue::new(&mut minute).clamp_range(0..=59).prefix(":"))
+            .changed();
+        changed |= ui
+            .add(DragValue::new(&mut second).clamp_range(0..=59).prefix(":"))
+            .changed();
+    });
+    if !changed {
+        return None;
+    }
+    // the 31st becomes the last day of shorter months
+    let date = (1..=day)
+        .rev()
+        .find_map(|day| NaiveDate::from_ymd_opt(year, month, day))?;
+    let time = date.and_hms_opt(hour, minute, second)?;
+    // a time skipped by a DST change has no local moment, it stays unchanged
+    let time = Local.from_local_datetime(&time).earliest()?;
+    Some(time.with_timezone(&Utc))
+}
+
+/// `now` minus the time shown on the clock. Across a DST change that isn't
+/// midnight, but it keeps the displayed time right.
+fn day_start<Z: TimeZone>(now: DateTime<Z>) -> DateTime<Utc> {
+    let shown = now.time().num_seconds_from_midnight();
+    now.with_timezone(&Utc) - Duration::seconds(shown as i64)
+}
+
+/// A unix time field that shows `current` while it isn't edited. Returns
+/// the typed time once it's valid, `Some(None)` once the field was cleared.
+fn epoch_field(
+    ui: &mut Ui,
+    text: &mut String,
+    current: Option<DateTime<Utc>>,
+) -> Option<Option<DateTime<Utc>>> {
+    let response = ui.add(
+        TextEdit::singleline(text)
+            .hint_text("1700000000")
+            .desired_width(110.),
+    );
+    if !response.has_focus() {
+        *text = current
+            .map(|time| time.timestamp().to_string())
+            .unwrap_or_default();
+        return None;
+    }
+    if !response.changed() {
+        return None;
+    }
+    let epoch = match text.trim() {
+        "" => return Some(None),
+        epoch => epoch.parse::<i64>().ok()?,
+    };
+    // anything past the year 5000 in seconds is meant as milliseconds
+    let epoch = match epoch > 100_000_000_000 {
+        true => epoch / 1000,
+        false => epoch,
+    };
+    let time = NaiveDateTime::from_timestamp_opt(epoch, 0)?;
+    Some(Some(DateTime::from_utc(time, Utc)))
+}
+
+/// Reads a local `YYYY-MM-DD HH:MM[:SS]` time, the format presets store times in.
+pub fn parse_local(text: &str) -> Option<DateTime<Utc>> {
+    let text = text.trim();
+    let time = NaiveDateTime::parse_from_str(text, "%Y-%m-%d %H:%M:%S")
+        .or_else(|_| NaiveDateTime::parse_from_str(text, "%Y-%m-%d %H:%M"))
+        .ok()?;
+    let time = Local.from_local_datetime(&time).earliest()?;
+    Some(time.with_timezone(&Utc))
+}
+
+pub fn format_local(time: DateTime<Utc>) -> String {
+    time.with_timezone(&Local)
+        .format("%Y-%m-%d %H:%M")
+        .to_string()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn parses_local_times() {
+        let time = parse_local(" 2024-05-01 18:00 ").unwrap();
+        assert_eq!(format_local(time), "2024-05-01 18:00");
+        let with_seconds = parse_local("2024-05-01 18:00:30").unwrap();
+        assert_eq!((with_seconds - time).num_seconds(), 30);
+    }
+
+    #[test]
+    fn rejects_other_formats() {
+        for text in [
+            "",
+            "18:00",
+            "2024-05-01",
+            "01.05.2024 18:00",
+            "2024-13-01 18:00",
+        ] {
+            assert!(parse_local(text).is_none(), "{text}");
+        }
+    }
 }
@@ -0,0 +1,154 @@
+use crate::secrets;
+use crate::watcher::Watcher;
+use eframe::egui::{RichText, TextEdit, Ui};
+use eframe::epaint::Color32;
+use serde::{Deserialize, Serialize};
+use serde_json::Value;
+use std::sync::Mutex;
+use std::time::Duration;
+
+/// Seconds between two reads of the title, it rarely changes.
+const INTERVAL: u64 = 60;
+/// Name of the client secret in the secrets file.
+pub const SECRET: &str = "twitch";
+
+/// A Twitch channel and the app on dev.twitch.tv used to read it.
+#[derive(Serialize, Deserialize, Clone, Default, PartialEq, Eq)]
+#[serde(default)]
+pub struct TwitchSettings {
+    pub channel: String,
+    pub client_id: String,
+    /// Kept with the other secrets, not in the settings.
+    #[serde(skip_serializing)]
+    pub client_secret: String,
+}
+
+impl TwitchSettings {
+    /// Fills in the client secret from the secrets file, settings from
+    /// before it still have the secret.
+    pub fn load(mut self) -> Self {
+        if self.client_secret.is_empty() {
+            self.client_secret = secrets::get(SECRET);
+        }
+        self
+    }
+
+    pub fn run(&mut self, ui: &mut Ui) {
+        ui.label("Channel");
+        ui.text_edit_singleline(&mut self.channel);
+        ui.label("Client ID and secret of an app on dev.twitch.tv");
+        ui.text_edit_singleline(&mut self.client_id);
+        ui.add(TextEdit::singleline(&mut self.client_secret).password(true));
+        if let Some(error) = ERROR.lock().unwrap().clone() {
+            ui.label(RichText::new(error).small().color(Color32::LIGHT_RED));
+        }
+    }
+
+    fn complete(&self) -> bool {
+        [&self.channel, &self.client_id, &self.client_secret]
+            .iter()
+            .all(|value| !value.trim().is_empty())
+    }
+}
+
+static SETTINGS: Mutex<Option<TwitchSettings>> = Mutex::new(None);
+static TITLE: Mutex<String> = Mutex::new(String::new());
+static ERROR: Mutex<Option<String>> = Mutex::new(None);
+static WATCHER: Watcher = Watcher::new();
+
+/// Replaces the channel that's read, the title is read again.
+pub fn configure(settings: &TwitchSettings) {
+    let mut current = SETTINGS.lock().unwrap();
+    if current.as_ref() == Some(settings) {
+        return;
+    }
+    *current = Some(settings.clone());
+    TITLE.lock().unwrap().clear();
+    *ERROR.lock().unwrap() = None;
+}
+
+/// Title of the channel's stream, set even while offline, empty until it
+/// was read. Starts polling Twitch when it isn't polled yet.
+pub fn title() -> String {
+    let mut token = None;
+    let poll = move || {
+        let settings = SETTINGS.lock().unwrap().clone().unwrap_or_default();
+        if !settings.complete() {
+            return;
+        }
+        match read(&settings, &mut token) {
+            Ok(title) => {
+                *TITLE.lock().unwrap() = title;
+                *ERROR.lock().unwrap() = None;
+            }
+            Err(error) => {
+                // tokens expire, a new one is asked for next time
+                token = None;
+                *ERROR.lock().unwrap() = Some(error);
+            }
+        }
+    };
+    WATCHER.ask(Duration::from_secs(INTERVAL), poll, || ());
+    TITLE.lock().unwrap().clone()
+}
+
+/// Reads the title with an app access token, which needs no login.
+fn read(settings: &TwitchSettings, token: &mut Option<String>) -> Result<String, String> {
+    let client_id = settings.client_id.trim();
+    let access = match token {
+        Some(access) => access.clone(),
+        None => {
+            let answer = request(
+                ureq::post("https://id.twitch.tv/oauth2/token")
+                    .timeout(Duration::from_secs(5))
+                    .send_form(&[
+                        ("client_id", client_id),
+                        ("client_secret", settings.client_secret.trim()),
+                        ("grant_type", "client_credentials"),
+                    ]),
+            )?;
+            let access = answer["access_token"]
+                .as_str()
+                .ok_or_else(|| "Twitch sent no token".to_string())?
+                .to_string();
+            *token = Some(access.clone());
+            access
+        }
+    };
+    let get = |url: &str, key: &str, value: &str| {
+        request(
+            ureq::get(url)
+                .query(key, value)
+                .set("Client-Id", client_id)
+                .set("Authorization", &format!("Bearer {access}"))
+                .timeout(Duration::from_secs(5))
+                .call(),
+        )
+    };
+    let channel = settings.channel.trim().to_lowercase();
+    let users = get("https://api.twitch.tv/helix/users", "login", &channel)?;
+    let id = users["data"][0]["id"]
+        .as_str()
+        .ok_or_else(|| format!("There's no Twitch channel {channel}"))?;
+    let channels = get("https://api.twitch.tv/helix/channels", "broadcaster_id", id)?;
+    Ok(channels["data"][0]["title"]
+        .as_str()
+        .unwrap_or_default()
+        .to_string())
+}
+
+fn request(response: Result<ureq::Response, ureq::Error>) -> Result<Value, String> {
+    let response = match response {
+        Ok(response) => response,
+        Err(ureq::Error::Status(400 | 401 | 403, _)) => {
+            return Err("Twitch refused the client ID or secret".to_string())
+        }
+        Err(ureq::Error::Status(code, _)) => return Err(format!("Twitch answered {code}")),
+        Err(_) => return Err("Failed to reach Twitch".to_string()),
+    };
+    response
+        .into_string()
+        .ok()
+        .and_then(|body| serde_json::from_str(&body).ok())
+        .ok_or_else(|| "Invalid answer from Twitch".to_string())
+}
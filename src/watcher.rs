@@ -0,0 +1,60 @@
+use chrono::Utc;
+use std::sync::atomic::{AtomicBool, AtomicI64, Ordering};
+use std::thread;
+use std::time::Duration;
+
+/// Seconds without being asked after which a watcher stops.
+const IDLE_AFTER: i64 = 60;
+
+/// Polls an integration on its own thread only while a placeholder or rule
+/// uses it. Asking starts the thread, which stops once nothing asked for a
+/// minute and drops whatever connection or token the poll held.
+pub struct Watcher {
+    running: AtomicBool,
+    last_asked: AtomicI64,
+}
+
+impl Watcher {
+    pub const fn new() -> Self {
+        Self {
+            running: AtomicBool::new(false),
+            last_asked: AtomicI64::new(0),
+        }
+    }
+
+    /// Notes that the integration is used and starts calling `poll` every
+    /// `interval` when it isn't running, `stopped` is called once it stops.
+    /// Returns whether this call started it.
+    pub fn ask<F>(&'static self, interval: Duration, poll: F, stopped: fn()) -> bool
+    where
+        F: FnMut() + Send + 'static,
+    {
+        self.last_asked
+            .store(Utc::now().timestamp(), Ordering::SeqCst);
+        if self.running.swap(true, Ordering::SeqCst) {
+            return false;
+        }
+        thread::spawn(move || self.watch(interval, poll, stopped));
+        true
+    }
+
+    fn watch(&self, interval: Duration, mut poll: impl FnMut(), stopped: fn()) {
+        loop {
+            while self.asked() {
+                poll();
+                thread::sleep(interval);
+            }
+            stopped();
+            self.running.store(false, Ordering::SeqCst);
+            // an ask that came in since the loop ended saw it still running
+            // and left the polling to this thread
+            if !self.asked() || self.running.swap(true, Ordering::SeqCst) {
+                break;
+            }
+        }
+    }
+
+    fn asked(&self) -> bool {
+        Utc::now().timestamp() - self.last_asked.load(Ordering::SeqCst) < IDLE_AFTER
+    }
+}
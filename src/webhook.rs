@@ -0,0 +1,25 @@
+use serde_json::{json, Value};
+use std::thread;
+use std::time::Duration;
+
+/// Posts a presence change to the user's webhook in the background.
+///
+/// The payload has a `content` line so Discord channel webhooks show it as
+/// a message, and the full `activity` for other services.
+pub fn post(url: &str, activity: Value) {
+    let details = activity["details"].as_str().unwrap_or_default();
+    let state = activity["state"].as_str().unwrap_or_default();
+    let content = match (details, state) {
+        ("", "") => "Presence updated".to_string(),
+        (text, "") | ("", text) => text.to_string(),
+        (details, state) => format!("{details} — {state}"),
+    };
+    let payload = json!({ "content": content, "activity": activity });
+    let url = url.to_string();
+    thread::spawn(move || {
+        let _ = ureq::post(&url)
+            .timeout(Duration::from_secs(5))
+            .set("Content-Type", "application/json")
+            .send_string(&payload.to_string());
+    });
+}
@@ -0,0 +1,320 @@
+use sha1::{Digest, Sha1};
+use std::io::{BufRead, BufReader, ErrorKind, Read, Write};
+use std::net::{TcpStream, ToSocketAddrs};
+use std::time::Duration;
+
+const OP_CONTINUATION: u8 = 0x0;
+const OP_TEXT: u8 = 0x1;
+const OP_CLOSE: u8 = 0x8;
+const OP_PING: u8 = 0x9;
+const OP_PONG: u8 = 0xA;
+/// Largest message accepted, remote commands are tiny.
+const MAX_MESSAGE: u64 = 64 * 1024;
+/// How long the rest of a frame may take once its first byte came in.
+const FRAME_TIMEOUT: Duration = Duration::from_secs(10);
+/// Close code for frames that break the protocol.
+const PROTOCOL_ERROR: u16 = 1002;
+
+/// One side of a WebSocket connection, text messages only.
+pub struct WebSocket {
+    stream: TcpStream,
+    /// Clients mask what they send, servers don't.
+    client: bool,
+}
+
+impl WebSocket {
+    /// Answers the HTTP upgrade request that carried `key`.
+    pub fn accept(mut stream: TcpStream, key: &str) -> std::io::Result<Self> {
+        let accept = base64::encode(Sha1::digest(
+            format!("{key}258EAFA5-E914-47DA-95CA-C5AB0DC85B11").as_bytes(),
+        ));
+        write!(
+            stream,
+            "HTTP/1.1 101 Switching Protocols\r\nUpgrade: websocket\r\nConnection: Upgrade\r\nSec-WebSocket-Accept: {accept}\r\n\r\n"
+        )?;
+        Ok(Self {
+            stream,
+            client: false,
+        })
+    }
+
+    /// Opens a connection to `ws://{address}/`, giving up on reaching the
+    /// server and on its answer after `timeout`.
+    pub fn connect(address: &str, timeout: Duration) -> std::io::Result<Self> {
+        let mut stream = connect(address, timeout)?;
+        stream.set_read_timeout(Some(timeout))?;
+        stream.set_write_timeout(Some(timeout))?;
+        let key = base64::encode(random_bytes::<16>());
+        write!(
+            stream,
+            "GET / HTTP/1.1\r\nHost: {address}\r\nUpgrade: websocket\r\nConnection: Upgrade\r\nSec-WebSocket-Key: {key}\r\nSec-WebSocket-Version: 13\r\n\r\n"
+        )?;
+        // the answer is read a byte at a time so no frame is buffered away
+        let mut reader = BufReader::with_capacity(1, &stream);
+        let mut status = String::new();
+        reader.read_line(&mut status)?;
+        if status.split_whitespace().nth(1) != Some("101") {
+            return Err(std::io::Error::new(
+                ErrorKind::ConnectionRefused,
+                format!("not a WebSocket server: {}", status.trim()),
+            ));
+        }
+        let mut line = String::new();
+        while reader.read_line(&mut line)? > 2 {
+            line.clear();
+        }
+        Ok(Self {
+            stream,
+            client: true,
+        })
+    }
+
+    pub fn stream(&self) -> &TcpStream {
+        &self.stream
+    }
+
+    /// Waits for the next text message, `None` once the other side closed.
+    /// Fragmented messages are put back together. A read timeout of the
+    /// stream comes back as the error, frames that break the protocol close
+    /// the connection with 1002.
+    pub fn read(&mut self) -> std::io::Result<Option<String>> {
+        let mut message: Option<Vec<u8>> = None;
+        loop {
+            let (fin, op, payload) = self.read_frame()?;
+            match op {
+                OP_TEXT | OP_CONTINUATION => {
+                    let text = match (op, message.take()) {
+                        (OP_TEXT, None) => payload,
+                        (OP_CONTINUATION, Some(mut text)) => {
+                            text.extend_from_slice(&payload);
+                            text
+                        }
+                        _ => return Err(self.fail("unexpected fragment")),
+                    };
+                    if text.len() as u64 > MAX_MESSAGE {
+                        return Err(self.fail("message too large"));
+                    }
+                    match fin {
+                        true => return Ok(String::from_utf8(text).ok()),
+                        false => message = Some(text),
+                    }
+                }
+                // control frames may come between fragments, but never in pieces
+                OP_PING | OP_PONG | OP_CLOSE if !fin || payload.len() > 125 => {
+                    return Err(self.fail("fragmented control frame"))
+                }
+                OP_PING => self.write_frame(OP_PONG, &payload)?,
+                OP_PONG => (),
+                OP_CLOSE => {
+                    let _ = self.write_frame(OP_CLOSE, &[]);
+                    return Ok(None);
+                }
+                _ => return Err(self.fail("unsupported frame")),
+            }
+        }
+    }
+
+    /// Closes the connection for breaking the protocol.
+    fn fail(&mut self, reason: &str) -> std::io::Error {
+        let _ = self.write_frame(OP_CLOSE, &PROTOCOL_ERROR.to_be_bytes());
+        std::io::Error::new(ErrorKind::InvalidData, reason.to_string())
+    }
+
+    pub fn send(&mut self, text: &str) -> std::io::Result<()> {
+        self.write_frame(OP_TEXT, text.as_bytes())
+    }
+
+    /// Reads a frame, only waiting for its first byte can time out. The
+    /// rest of a frame cut short by a timeout would be read as the next
+    /// one, so a frame that stops halfway fails the connection.
+    fn read_frame(&mut self) -> std::io::Result<(bool, u8, Vec<u8>)> {
+        let mut first = [0; 1];
+        self.stream.read_exact(&mut first)?;
+        let timeout = self.stream.read_timeout()?;
+        self.stream.set_read_timeout(Some(FRAME_TIMEOUT))?;
+        let frame = self.read_rest(first[0]);
+        self.stream.set_read_timeout(timeout)?;
+        frame.map_err(|err| match err.kind() {
+            ErrorKind::WouldBlock | ErrorKind::TimedOut => {
+                std::io::Error::new(ErrorKind::InvalidData, "frame cut off")
+            }
+            _ => err,
+        })
+    }
+
+    fn read_rest(&mut self, first: u8) -> std::io::Result<(bool, u8, Vec<u8>)> {
+        let mut header = [first, 0];
+        self.stream.read_exact(&mut header[1..])?;
+        let fin = header[0] & 0x80 != 0;
+        let op = header[0] & 0x0F;
+        let masked = header[1] & 0x80 != 0;
+        // clients have to mask their frames and servers must not
+        if masked == self.client {
+            return Err(self.fail("wrong masking"));
+        }
+        let len = match header[1] & 0x7F {
+            126 => {
+                let mut len = [0; 2];
+                self.stream.read_exact(&mut len)?;
+                u16::from_be_bytes(len) as u64
+            }
+            127 => {
+                let mut len = [0; 8];
+                self.stream.read_exact(&mut len)?;
+                u64::from_be_bytes(len)
+            }
+            len => len as u64,
+        };
+        if len > MAX_MESSAGE {
+            return Err(self.fail("message too large"));
+        }
+        let mut mask = [0; 4];
+        if masked {
+            self.stream.read_exact(&mut mask)?;
+        }
+        let mut payload = vec![0; len as usize];
+        self.stream.read_exact(&mut payload)?;
+        for (i, byte) in payload.iter_mut().enumerate() {
+            *byte ^= mask[i % 4];
+        }
+        Ok((fin, op, payload))
+    }
+
+    fn write_frame(&mut self, op: u8, payload: &[u8]) -> std::io::Result<()> {
+        let mut frame = vec![0x80 | op];
+        let masked = match self.client {
+            true => 0x80,
+            false => 0,
+        };
+        match payload.len() {
+            len if len < 126 => frame.push(masked | len as u8),
+            len if len <= u16::MAX as usize => {
+                frame.push(masked | 126);
+                frame.extend_from_slice(&(len as u16).to_be_bytes());
+            }
+            len => {
+                frame.push(masked | 127);
+                frame.extend_from_slice(&(len as u64).to_be_bytes());
+            }
+        }
+        match self.client {
+            true => {
+                let mask = random_bytes::<4>();
+                frame.extend_from_slice(&mask);
+                frame.extend(
+                    payload
+                        .iter()
+                        .enumerate()
+                        .map(|(i, byte)| byte ^ mask[i % 4]),
+                );
+            }
+            false => frame.extend_from_slice(payload),
+        }
+        self.stream.write_all(&frame)
+    }
+}
+
+/// Connects to the first address of the host that answers in time.
+fn connect(address: &str, timeout: Duration) -> std::io::Result<TcpStream> {
+    let mut error = std::io::Error::new(ErrorKind::NotFound, "no address for the host");
+    for addr in address.to_socket_addrs()? {
+        match TcpStream::connect_timeout(&addr, timeout) {
+            Ok(stream) => return Ok(stream),
+            Err(err) => error = err,
+        }
+    }
+    Err(error)
+}
+
+fn random_bytes<const N: usize>() -> [u8; N] {
+    let mut bytes = [0; N];
+    getrandom::getrandom(&mut bytes).expect("no random source");
+    bytes
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::net::TcpListener;
+
+    /// A server side WebSocket and the raw client stream talking to it.
+    fn pair() -> (WebSocket, TcpStream) {
+        let listener = TcpListener::bind("127.0.0.1:0").unwrap();
+        let client = TcpStream::connect(listener.local_addr().unwrap()).unwrap();
+        let (stream, _) = listener.accept().unwrap();
+        let server = WebSocket {
+            stream,
+            client: false,
+        };
+        (server, client)
+    }
+
+    /// A masked client frame.
+    fn frame(first: u8, payload: &[u8]) -> Vec<u8> {
+        let mask = [1, 2, 3, 4];
+        let mut frame = vec![first, 0x80 | payload.len() as u8];
+        frame.extend_from_slice(&mask);
+        frame.extend(payload.iter().enumerate().map(|(i, b)| b ^ mask[i % 4]));
+        frame
+    }
+
+    fn closed_with(client: &mut TcpStream) -> Vec<u8> {
+        let mut answer = [0; 4];
+        client.read_exact(&mut answer).unwrap();
+        answer.to_vec()
+    }
+
+    #[test]
+    fn reads_masked_text() {
+        let (mut server, mut client) = pair();
+        client.write_all(&frame(0x81, b"pause")).unwrap();
+        assert_eq!(server.read().unwrap().as_deref(), Some("pause"));
+    }
+
+    #[test]
+    fn reassembles_fragments() {
+        let (mut server, mut client) = pair();
+        client.write_all(&frame(0x01, b"pa")).unwrap();
+        client.write_all(&frame(0x89, b"hi")).unwrap();
+        client.write_all(&frame(0x80, b"use")).unwrap();
+        assert_eq!(server.read().unwrap().as_deref(), Some("pause"));
+        // the ping in between was answered
+        let mut pong = [0; 4];
+        client.read_exact(&mut pong).unwrap();
+        assert_eq!(pong, [0x8A, 2, b'h', b'i']);
+    }
+
+    #[test]
+    fn rejects_unmasked_frames() {
+        let (mut server, mut client) = pair();
+        client.write_all(&[0x81, 5]).unwrap();
+        client.write_all(b"pause").unwrap();
+        assert_eq!(server.read().unwrap_err().kind(), ErrorKind::InvalidData);
+        assert_eq!(closed_with(&mut client), [0x88, 2, 0x03, 0xEA]);
+    }
+
+    #[test]
+    fn rejects_stray_continuations() {
+        let (mut server, mut client) = pair();
+        client.write_all(&frame(0x80, b"pause")).unwrap();
+        assert!(server.read().is_err());
+        assert_eq!(closed_with(&mut client), [0x88, 2, 0x03, 0xEA]);
+    }
+
+    #[test]
+    fn rejects_fragmented_pings() {
+        let (mut server, mut client) = pair();
+        client.write_all(&frame(0x09, b"hi")).unwrap();
+        assert!(server.read().is_err());
+    }
+
+    #[test]
+    fn answers_a_close() {
+        let (mut server, mut client) = pair();
+        client.write_all(&frame(0x88, &[])).unwrap();
+        assert_eq!(server.read().unwrap(), None);
+        let mut close = [0; 2];
+        client.read_exact(&mut close).unwrap();
+        assert_eq!(close, [0x88, 0]);
+    }
+}
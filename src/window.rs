@@ -0,0 +1,84 @@
+use derivative::Derivative;
+use eframe::egui::{self, Context, Sense, Ui};
+
+/// How the window is drawn. Wayland compositors differ in what they do for
+/// apps, so these can be changed when the defaults look wrong there.
+#[derive(Derivative)]
+#[derivative(Default)]
+pub struct WindowSettings {
+    /// Let the system draw the title bar. Without it the menu bar gets a
+    /// handle to move the window with. Read on startup.
+    #[derivative(Default(value = "true"))]
+    pub decorations: bool,
+    /// Whether the window was opened with the title bar.
+    #[derivative(Default(value = "true"))]
+    pub decorated: bool,
+    /// Pixels per point, 0 follows the scale the system reports.
+    pub scale: f32,
+    /// Set while the handle is dragged, the app moves the window then.
+    pub drag: bool,
+}
+
+impl WindowSettings {
+    /// Shows the Settings → Window submenu.
+    pub fn run(&mut self, ui: &mut Ui) {
+        ui.checkbox(&mut self.decorations, "System title bar")
+            .on_hover_text("Takes effect after a restart");
+        let mut automatic = self.scale == 0.;
+        if ui
+            .checkbox(&mut automatic, "Scale like the system")
+            .changed()
+        {
+            self.scale = match automatic {
+                true => 0.,
+                false => ui.ctx().pixels_per_point(),
+            };
+        }
+        if !automatic {
+            ui.horizontal(|ui| {
+                ui.label("Scale");
+                ui.add(
+                    egui::DragValue::new(&mut self.scale)
+                        .clamp_range(0.5..=4.)
+                        .speed(0.05)
+                        .fixed_decimals(2),
+                )
+                .on_hover_text(match wayland() {
+                    true => {
+                        "Some Wayland compositors report a rounded scale, set the right one here"
+                    }
+                    false => "Pixels per point",
+                });
+            });
+        }
+    }
+
+    /// Shows the handle that moves an undecorated window.
+    pub fn handle(&mut self, ui: &mut Ui) {
+        if self.decorated {
+            return;
+        }
+        let handle = ui
+            .add(egui::Label::new("✥").sense(Sense::drag()))
+            .on_hover_text("Drag to move the window");
+        self.drag = handle.drag_started();
+    }
+
+    /// Keeps the scale at the one picked, or at the system's when that's
+    /// picked again.
+    pub fn apply(&self, ctx: &Context, native: Option<f32>) {
+        let scale = match (self.scale, native) {
+            (scale, _) if scale > 0. => scale,
+            (_, Some(native)) => native,
+            _ => return,
+        };
+        if ctx.pixels_per_point() != scale {
+            ctx.set_pixels_per_point(scale);
+        }
+    }
+}
+
+/// Whether the app runs under a Wayland compositor.
+pub fn wayland() -> bool {
+    cfg!(target_os = "linux") && std::env::var_os("WAYLAND_DISPLAY").is_some()
+}
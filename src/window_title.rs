@@ -0,0 +1,97 @@
+use crate::watcher::Watcher;
+use std::sync::Mutex;
+use std::time::Duration;
+
+/// Seconds between two reads of the title.
+const INTERVAL: u64 = 2;
+
+static TITLE: Mutex<String> = Mutex::new(String::new());
+static WATCHER: Watcher = Watcher::new();
+
+/// Title of the focused window, empty when it can't be read. It's read on
+/// its own thread every few seconds, which only runs while a text uses it.
+pub fn current() -> String {
+    let update = || *TITLE.lock().unwrap() = read().unwrap_or_default();
+    if WATCHER.ask(Duration::from_secs(INTERVAL), update, || ()) {
+        // read once right away, the first presence shouldn't go out without it
+        update();
+    }
+    TITLE.lock().unwrap().clone()
+}
+
+#[cfg(windows)]
+fn read() -> Option<String> {
+    #[link(name = "user32")]
+    extern "system" {
+        fn GetForegroundWindow() -> isize;
+        fn GetWindowTextW(window: isize, text: *mut u16, length: i32) -> i32;
+    }
+    let mut text = [0u16; 512];
+    // SAFETY: the buffer is as long as the length given, a null window gives 0
+    let length = unsafe {
+        let window = GetForegroundWindow();
+        GetWindowTextW(window, text.as_mut_ptr(), text.len() as i32)
+    };
+    match length {
+        0 => None,
+        length => Some(String::from_utf16_lossy(&text[..length as usize])),
+    }
+}
+
+/// Asks System Events for the front window, the app's name when the app
+/// has no accessibility permission to see window titles.
+#[cfg(target_os = "macos")]
+fn read() -> Option<String> {
+    const SCRIPT: &str = r#"tell application "System Events"
+    set frontApp to first application process whose frontmost is true
+    try
+        return name of front window of frontApp
+    on error
+        return name of frontApp
+    end try
+end tell"#;
+    output("osascript", &["-e", SCRIPT])
+}
+
+/// Asks the compositor on Hyprland and Sway, `xdotool` on X11 and XWayland.
+#[cfg(all(unix, not(target_os = "macos")))]
+fn read() -> Option<String> {
+    use serde_json::Value;
+
+    if let Some(window) = output("hyprctl", &["activewindow", "-j"]) {
+        let window: Value = serde_json::from_str(&window).unwrap_or_default();
+        if let Some(title) = window["title"].as_str() {
+            return Some(title.to_string());
+        }
+    }
+    if let Some(tree) = output("swaymsg", &["-t", "get_tree"]) {
+        fn focused(node: &Value) -> Option<String> {
+            if node["focused"].as_bool() == Some(true) {
+                return node["name"].as_str().map(str::to_string);
+            }
+            ["nodes", "floating_nodes"]
+                .iter()
+                .filter_map(|key| node[key].as_array())
+                .flatten()
+                .find_map(focused)
+        }
+        return focused(&serde_json::from_str(&tree).unwrap_or_default());
+    }
+    output("xdotool", &["getactivewindow", "getwindowname"])
+}
+
+#[cfg(not(any(unix, windows)))]
+fn read() -> Option<String> {
+    None
+}
+
+/// Trimmed standard output of a program that succeeded.
+#[cfg(unix)]
+fn output(program: &str, args: &[&str]) -> Option<String> {
+    std::process::Command::new(program)
+        .args(args)
+        .output()
+        .ok()
+        .filter(|output| output.status.success())
+        .map(|output| String::from_utf8_lossy(&output.stdout).trim().to_string())
+}
@@ -0,0 +1,331 @@
+use crate::ipc::{self, Client};
+use crate::metrics;
+use eframe::egui::Context;
+use serde_json::{json, Value};
+use std::path::PathBuf;
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::mpsc::{channel, Receiver, RecvTimeoutError, Sender};
+use std::sync::{Arc, Mutex};
+use std::thread;
+use std::time::Duration;
+
+/// How often Discord is asked for events while no job runs and the app
+/// listens for them.
+const EVENT_POLL: Duration = Duration::from_secs(2);
+/// Events the worker subscribes to after connecting.
+const EVENTS: [&str; 1] = ["ACTIVITY_JOIN_REQUEST"];
+
+/// Why a connection was started, decides how a failure is reported.
+#[derive(Clone, Copy, PartialEq, Eq)]
+pub enum Origin {
+    /// The user or the control API asked for it.
+    User,
+    /// Autoconnect on launch.
+    Startup,
+    /// The watchdog restoring a dropped connection.
+    Retry,
+}
+
+pub enum Job {
+    /// Replaces the connection, and the mirrors when `mirror` is set.
+    Connect {
+        id: String,
+        pipe: Option<PathBuf>,
+        /// Kind of Discord client to connect to when no pipe is picked.
+        flavor: Option<String>,
+        mirror: bool,
+        origin: Origin,
+    },
+    SetActivity(Value),
+    /// Accepts or denies someone's request to join.
+    AnswerJoin {
+        user_id: String,
+        accept: bool,
+    },
+    Ping,
+    Close,
+}
+
+/// Answer to a job, one for every job and in the same order.
+pub enum Reply {
+    /// The account connected as, or why connecting failed.
+    Connected {
+        origin: Origin,
+        result: Result<Option<String>, String>,
+    },
+    ActivitySet {
+        /// The activity as Discord took it.
+        result: Result<Value, String>,
+        /// Whether the connection survived, a failed update can drop it.
+        connected: bool,
+    },
+    JoinAnswered(Result<(), String>),
+    Pinged(Result<(), String>),
+    Closed(Result<(), String>),
+}
+
+impl Reply {
+    /// One line for the event log.
+    pub fn summary(&self) -> String {
+        let (what, error) = match self {
+            Reply::Connected {
+                result: Ok(Some(user)),
+                ..
+            } => return format!("Connected as {user}"),
+            Reply::Connected { result, .. } => ("Connect", result.as_ref().err()),
+            Reply::ActivitySet { result, .. } => ("SET_ACTIVITY", result.as_ref().err()),
+            Reply::JoinAnswered(result) => ("Join answer", result.as_ref().err()),
+            Reply::Pinged(result) => ("Ping", result.as_ref().err()),
+            Reply::Closed(result) => ("Disconnect", result.as_ref().err()),
+        };
+        match error {
+            None => format!("{what}: ok"),
+            Some(reason) => format!("{what} failed: {reason}"),
+        }
+    }
+}
+
+impl Job {
+    /// What the app is waiting for while the job runs.
+    fn label(&self) -> &'static str {
+        match self {
+            Job::Connect {
+                origin: Origin::Retry,
+                ..
+            } => "Reconnecting",
+            Job::Connect { .. } => "Connecting",
+            Job::SetActivity(_) => "Updating presence",
+            Job::AnswerJoin { .. } => "Answering join request",
+            Job::Ping => "Checking connection",
+            Job::Close => "Disconnecting",
+        }
+    }
+}
+
+/// Talks to Discord on its own thread so a slow or missing Discord never
+/// freezes the window. Jobs run one after another in the order sent.
+pub struct Worker {
+    jobs: Sender<Job>,
+    replies: Receiver<Reply>,
+    /// Labels of the jobs sent but not answered yet.
+    in_flight: Vec<&'static str>,
+    /// Events Discord sent, like join requests.
+    events: Receiver<Value>,
+    /// Why the connection was found dropped while asking for events.
+    lost: Receiver<String>,
+    /// Whether Discord is asked for events while no job runs.
+    listen: Arc<AtomicBool>,
+    /// Redraws the window when an event comes in.
+    waker: Arc<Mutex<Option<Context>>>,
+}
+
+impl Default for Worker {
+    fn default() -> Self {
+        let (jobs, receiver) = channel();
+        let (sender, replies) = channel();
+        let (event_sender, events) = channel();
+        let (lost_sender, lost) = channel();
+        let waker = Arc::new(Mutex::new(None));
+        let listen = Arc::new(AtomicBool::new(false));
+        let worker = Idle {
+            events: event_sender,
+            lost: lost_sender,
+            listen: listen.clone(),
+            waker: waker.clone(),
+        };
+        thread::spawn(move || work(receiver, sender, worker));
+        Self {
+            jobs,
+            replies,
+            in_flight: Vec::new(),
+            events,
+            lost,
+            listen,
+            waker,
+        }
+    }
+}
+
+impl Worker {
+    pub fn send(&mut self, job: Job) {
+        self.in_flight.push(job.label());
+        let _ = self.jobs.send(job);
+    }
+
+    pub fn poll(&mut self) -> Option<Reply> {
+        let reply = self.replies.try_recv().ok()?;
+        self.in_flight.remove(0);
+        Some(reply)
+    }
+
+    pub fn event(&mut self) -> Option<Value> {
+        self.events.try_recv().ok()
+    }
+
+    /// Why the connection dropped while the worker was idle.
+    pub fn lost(&mut self) -> Option<String> {
+        self.lost.try_recv().ok()
+    }
+
+    /// Reads events while idle only when something uses them, each read
+    /// pings Discord.
+    pub fn listen(&self, listen: bool) {
+        self.listen.store(listen, Ordering::Relaxed);
+    }
+
+    /// Lets the worker redraw the window when an event comes in.
+    pub fn wake(&self, ctx: &Context) {
+        if let Ok(mut waker) = self.waker.lock() {
+            if waker.is_none() {
+                *waker = Some(ctx.clone());
+            }
+        }
+    }
+
+    /// Label of the job running right now, `None` when idle.
+    pub fn busy(&self) -> Option<&'static str> {
+        self.in_flight.first().copied()
+    }
+
+    /// Labels of the jobs sent but not answered yet, the running one first.
+    pub fn queued(&self) -> &[&'static str] {
+        &self.in_flight
+    }
+
+    /// Whether a connect is queued or running, so another one isn't started.
+    pub fn connecting(&self) -> bool {
+        self.in_flight
+            .iter()
+            .any(|label| matches!(*label, "Connecting" | "Reconnecting"))
+    }
+}
+
+/// What the worker shares with the app for the time between jobs.
+struct Idle {
+    events: Sender<Value>,
+    lost: Sender<String>,
+    listen: Arc<AtomicBool>,
+    waker: Arc<Mutex<Option<Context>>>,
+}
+
+fn work(jobs: Receiver<Job>, replies: Sender<Reply>, idle: Idle) {
+    let mut client = Client::default();
+    let mut mirrors: Vec<Client> = Vec::new();
+    let Idle {
+        events,
+        lost,
+        listen,
+        waker,
+    } = idle;
+    loop {
+        let job = match jobs.recv_timeout(EVENT_POLL) {
+            Ok(job) => job,
+            // events only arrive while reading, a ping reads whatever came in
+            Err(RecvTimeoutError::Timeout) => {
+                if client.is_connected() && listen.load(Ordering::Relaxed) {
+                    if let Err(reason) = client.ping() {
+                        close(&mut mirrors);
+                        let _ = lost.send(reason);
+                        wake(&waker);
+                    }
+                }
+                forward(&mut client, &events, &waker);
+                continue;
+            }
+            Err(RecvTimeoutError::Disconnected) => break,
+        };
+        let reply = match job {
+            Job::Connect {
+                id,
+                pipe,
+                flavor,
+                mirror,
+                origin,
+            } => {
+                // the old connection is replaced, it doesn't matter if it was already gone
+                let _ = client.close();
+                close(&mut mirrors);
+                client = Client::new(&id, pipe);
+                client.flavor = flavor;
+                let result = client.connect();
+                metrics::record(&result, &metrics::CONNECTS, &metrics::CONNECT_FAILURES);
+                if result.is_ok() && mirror {
+                    mirrors = ipc::connect_all(&id, client.connected_pipe.as_ref());
+                }
+                if result.is_ok() {
+                    for event in EVENTS {
+                        let _ = client.subscribe(event);
+                    }
+                }
+                Reply::Connected {
+                    origin,
+                    result: result.map(|_| client.user.clone()),
+                }
+            }
+            Job::SetActivity(activity) => {
+                // a mirror that fails is dropped, its Discord client was most likely closed
+                mirrors.retain_mut(|mirror| mirror.set_activity_value(activity.clone()).is_ok());
+                let result = client.set_activity_value(activity);
+                metrics::record(&result, &metrics::UPDATES, &metrics::UPDATE_FAILURES);
+                if !client.is_connected() {
+                    close(&mut mirrors);
+                }
+                Reply::ActivitySet {
+                    result,
+                    connected: client.is_connected(),
+                }
+            }
+            Job::AnswerJoin { user_id, accept } => {
+                let cmd = match accept {
+                    true => "SEND_ACTIVITY_JOIN_INVITE",
+                    false => "CLOSE_ACTIVITY_REQUEST",
+                };
+                Reply::JoinAnswered(
+                    client
+                        .command(cmd, json!({ "user_id": user_id }))
+                        .map(|_| ()),
+                )
+            }
+            Job::Ping => {
+                let result = client.ping();
+                if result.is_err() {
+                    close(&mut mirrors);
+                }
+                Reply::Pinged(result)
+            }
+            Job::Close => {
+                close(&mut mirrors);
+                Reply::Closed(client.close())
+            }
+        };
+        forward(&mut client, &events, &waker);
+        if replies.send(reply).is_err() {
+            break;
+        }
+    }
+}
+
+/// Hands the events Discord sent to the app.
+fn forward(client: &mut Client, events: &Sender<Value>, waker: &Arc<Mutex<Option<Context>>>) {
+    if client.events.is_empty() {
+        return;
+    }
+    for event in client.events.drain(..) {
+        let _ = events.send(event);
+    }
+    wake(waker);
+}
+
+fn wake(waker: &Arc<Mutex<Option<Context>>>) {
+    if let Ok(waker) = waker.lock() {
+        if let Some(ctx) = waker.as_ref() {
+            ctx.request_repaint();
+        }
+    }
+}
+
+fn close(mirrors: &mut Vec<Client>) {
+    for mut mirror in mirrors.drain(..) {
+        let _ = mirror.close();
+    }
+}